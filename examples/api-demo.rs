@@ -1,7 +1,6 @@
 //! Demo of the new tiered fluent API and parallel processing capabilities.
 
-use std::sync::Arc;
-use aptitude::{AgentHarness, AgentType, fluent::StdoutAssertion, review::{grade_stdout_batch_async, ReviewConfig}};
+use aptitude::{AgentHarness, AgentType, fluent::StdoutAssertion, review::ReviewConfig};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -44,16 +43,19 @@ async fn main() -> anyhow::Result<()> {
             criteria: "should confirm completion".to_string(),
             threshold: 7,
             model: None,
+            prompt_template: None,
         },
         ReviewConfig {
             criteria: "should indicate success".to_string(),
             threshold: 7,
             model: None,
+            prompt_template: None,
         },
         ReviewConfig {
             criteria: "should report an error".to_string(),
             threshold: 7,
             model: None,
+            prompt_template: None,
         },
     ];
 
@@ -0,0 +1,189 @@
+//! Declarative test tables for Rust-native agent tests.
+//!
+//! [`agent_test!`](crate::agent_test) expands to a `#[test]` function that
+//! runs a prompt with [`prompt`](crate::prompt) and checks the tool calls
+//! with the fluent API, without writing a YAML file or the
+//! `prompt().run()` / `expect_tools()` boilerplate by hand.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use aptitude::agent_test;
+//!
+//! agent_test! {
+//!     reads_config,
+//!     prompt: "What port does the server listen on?",
+//!     in_dir: "fixtures/server",
+//!     asserts: [read(called), edit(not_called), bash(at_most(1))],
+//! }
+//! ```
+
+/// Define a `#[test]` that runs a prompt and asserts on its tool calls.
+///
+/// Fields come in this order; `in_dir` and `harness` are optional:
+///
+/// - `prompt`: the prompt text.
+/// - `in_dir`: working directory for the agent.
+/// - `harness`: an [`AgentHarness`](crate::AgentHarness), or an `Arc` of one, to run
+///   with instead of the default one, e.g. to replay recordings.
+/// - `asserts`: a list of `tool(check)` entries.
+///
+/// Tools are written in snake case (`read`, `bash`, `web_fetch`, `ls`, ...)
+/// and checked at compile time. Each check maps to a
+/// [`ToolAssertion`](crate::ToolAssertion) method:
+///
+/// | Check | Method |
+/// |-------|--------|
+/// | `called` | `.to_be_called()` |
+/// | `not_called` | `.not_to_be_called()` |
+/// | `once` | `.to_be_called_once()` |
+/// | `times(n)` | `.to_be_called_exactly(n)` |
+/// | `at_least(n)` | `.at_least(n).to_be_called()` |
+/// | `at_most(n)` | `.at_most(n).to_be_called()` |
+///
+/// Attributes written before the name, such as `#[ignore]`, are kept on the
+/// generated function.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use aptitude::agent_test;
+///
+/// agent_test! {
+///     #[ignore]
+///     answers_without_tools,
+///     prompt: "What is 2 + 2?",
+///     asserts: [read(not_called), bash(not_called)],
+/// }
+/// ```
+///
+/// # Panics
+///
+/// The generated test panics if the agent fails to run or any check fails.
+#[macro_export]
+macro_rules! agent_test {
+    (@tool read) => { $crate::Tool::Read };
+    (@tool write) => { $crate::Tool::Write };
+    (@tool edit) => { $crate::Tool::Edit };
+    (@tool bash) => { $crate::Tool::Bash };
+    (@tool glob) => { $crate::Tool::Glob };
+    (@tool grep) => { $crate::Tool::Grep };
+    (@tool ls) => { $crate::Tool::ListDirectory };
+    (@tool task) => { $crate::Tool::Task };
+    (@tool web_fetch) => { $crate::Tool::WebFetch };
+    (@tool web_search) => { $crate::Tool::WebSearch };
+    (@tool notebook_edit) => { $crate::Tool::NotebookEdit };
+    (@tool ask_user_question) => { $crate::Tool::AskUserQuestion };
+    (@tool todo_write) => { $crate::Tool::TodoWrite };
+    (@tool kill_shell) => { $crate::Tool::KillShell };
+    (@tool task_output) => { $crate::Tool::TaskOutput };
+    (@tool skill) => { $crate::Tool::Skill };
+
+    (@check $assertion:expr, called) => { $assertion.to_be_called() };
+    (@check $assertion:expr, not_called) => { $assertion.not_to_be_called() };
+    (@check $assertion:expr, once) => { $assertion.to_be_called_once() };
+    (@check $assertion:expr, times($n:expr)) => { $assertion.to_be_called_exactly($n) };
+    (@check $assertion:expr, at_least($n:expr)) => { $assertion.at_least($n).to_be_called() };
+    (@check $assertion:expr, at_most($n:expr)) => { $assertion.at_most($n).to_be_called() };
+
+    // Checks a list of tool calls; the generated tests go through here
+    (@asserts $calls:expr, [$($tool:ident($($check:tt)+)),* $(,)?]) => {
+        $(
+            $crate::agent_test!(
+                @check
+                $crate::expect_tools(&$calls).tool($crate::agent_test!(@tool $tool)),
+                $($check)+
+            );
+        )*
+    };
+
+    (
+        $(#[$meta:meta])*
+        $name:ident,
+        prompt: $prompt:expr,
+        $(in_dir: $dir:expr,)?
+        $(harness: $harness:expr,)?
+        asserts: [$($tool:ident($($check:tt)+)),* $(,)?]
+        $(,)?
+    ) => {
+        $(#[$meta])*
+        #[test]
+        fn $name() {
+            let builder = $crate::prompt($prompt);
+            $(let builder = builder.in_dir($dir);)?
+            $(let builder = builder.with_harness($harness);)?
+            let tool_calls = builder.run().expect("agent run failed");
+            $crate::agent_test!(@asserts tool_calls, [$($tool($($check)+)),*]);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use crate::parser::ToolCall;
+    use crate::test_util::MockAgent;
+    use crate::{AgentHarness, AgentType};
+
+    fn make_call(name: &str, params: serde_json::Value) -> ToolCall {
+        ToolCall {
+            name: name.to_string(),
+            params,
+            timestamp: String::new(),
+            result: None,
+        }
+    }
+
+    fn mock_harness() -> AgentHarness {
+        let mock = MockAgent::new()
+            .with_tool_call("Read", json!({"file_path": "README.md"}))
+            .with_tool_call("Grep", json!({"pattern": "port"}))
+            .with_tool_call("Read", json!({"file_path": "src/main.rs"}));
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(mock));
+        harness
+    }
+
+    agent_test! {
+        generated_test_runs_assertions,
+        prompt: "What port does the server listen on?",
+        harness: mock_harness(),
+        asserts: [
+            read(times(2)),
+            grep(once),
+            bash(not_called),
+            web_fetch(not_called),
+            read(at_least(1)),
+            grep(at_most(1)),
+        ],
+    }
+
+    agent_test! {
+        #[should_panic(expected = "expected Bash called")]
+        generated_test_fails_on_violation,
+        prompt: "Run the tests",
+        harness: mock_harness(),
+        asserts: [read(called), bash(called)],
+    }
+
+    #[test]
+    fn test_asserts_on_tool_calls() {
+        let calls = vec![
+            make_call("Read", json!({"file_path": "/a.txt"})),
+            make_call("LS", json!({"path": "/"})),
+        ];
+
+        agent_test!(@asserts calls, [read(called), ls(once), edit(not_called)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected at least 2 calls, got 1")]
+    fn test_asserts_count_mismatch() {
+        let calls = vec![make_call("Read", json!({"file_path": "/a.txt"}))];
+
+        agent_test!(@asserts calls, [read(at_least(2))]);
+    }
+}
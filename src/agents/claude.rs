@@ -4,11 +4,20 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
-
-use crate::parser::{parse_jsonl_file, ToolCall};
-use super::{Agent, ExecutionConfig, RawExecutionResult, ToolNameMapping};
+use serde_json::Value;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, SystemTime};
+
+use crate::error::HarnessError;
+use crate::parser::{parse_jsonl_file, parse_session_with_usage, ToolCall, UsageStats};
+use super::{
+    run_grader, run_grader_async, wait_with_timeout, Agent, ExecutionConfig, RawExecutionResult,
+    ToolNameMapping,
+};
 
 /// Claude Code agent adapter.
 pub struct ClaudeAdapter {
@@ -23,21 +32,67 @@ impl ClaudeAdapter {
             mapping: ToolNameMapping::new(),
         }
     }
-}
 
-impl Default for ClaudeAdapter {
-    fn default() -> Self {
-        Self::new()
+    /// Run `claude --print`, resuming the session logged at `resumed` if given.
+    fn run(
+        &self,
+        prompt: &str,
+        config: &ExecutionConfig,
+        resumed: Option<&Path>,
+    ) -> Result<RawExecutionResult> {
+        let sessions = SessionWatch::start(config)?;
+
+        let mut cmd = claude_command(config, resumed);
+        let child = config
+            .spawn_with_prompt(&mut cmd, prompt)
+            .context("Failed to execute claude command")?;
+        let output = wait_with_timeout(child, config.timeout, "claude")?;
+
+        sessions.finish(output, prompt, resumed)
+    }
+
+    /// Async counterpart of [`run`](Self::run), awaiting the process with
+    /// Tokio instead of blocking a thread on it.
+    async fn run_async(
+        &self,
+        prompt: &str,
+        config: &ExecutionConfig,
+        resumed: Option<&Path>,
+    ) -> Result<RawExecutionResult> {
+        let sessions = SessionWatch::start(config)?;
+        let output = config
+            .output_with_prompt_async(claude_command(config, resumed), prompt, "claude")
+            .await?;
+        sessions.finish(output, prompt, resumed)
     }
 }
 
-#[async_trait]
-impl Agent for ClaudeAdapter {
-    fn name(&self) -> &'static str {
-        "claude"
+/// Build the `claude --print` command, resuming the session logged at `resumed`.
+///
+/// The config's working directory, extra arguments and environment are
+/// applied later, when the prompt is added.
+pub(crate) fn claude_command(config: &ExecutionConfig, resumed: Option<&Path>) -> Command {
+    let mut cmd = Command::new("claude");
+    cmd.arg("--print");
+    if let Some(id) = resumed.and_then(|path| path.file_stem()) {
+        cmd.arg("--resume").arg(id);
     }
+    if let Some(text) = &config.system_prompt {
+        cmd.arg("--append-system-prompt").arg(text);
+    }
+    cmd
+}
 
-    fn execute(&self, prompt: &str, config: &ExecutionConfig) -> Result<RawExecutionResult> {
+/// Session logs in a project directory before a run, to find the run's log after.
+struct SessionWatch {
+    project_dir: PathBuf,
+    existing: Vec<PathBuf>,
+    start_time: SystemTime,
+}
+
+impl SessionWatch {
+    /// Snapshot the session logs for the config's working directory.
+    fn start(config: &ExecutionConfig) -> Result<Self> {
         // Get the claude projects directory to watch for new sessions
         let claude_dir = get_claude_projects_dir()?;
 
@@ -45,35 +100,67 @@ impl Agent for ClaudeAdapter {
         let project_dir = get_project_dir_for_workdir(&claude_dir, &config.working_dir)?;
 
         // Get list of existing sessions before running (only in this project)
-        let existing_sessions = list_session_files(&project_dir)?;
-
-        // Run claude with the prompt
-        let mut cmd = Command::new("claude");
-        cmd.arg("--print").arg(prompt).stdin(Stdio::null());
+        let existing = list_session_files(&project_dir)?;
 
-        if let Some(dir) = &config.working_dir {
-            cmd.current_dir(dir);
-        }
-
-        for arg in &config.extra_args {
-            cmd.arg(arg);
-        }
-
-        let output = cmd.output().context("Failed to execute claude command")?;
+        Ok(Self {
+            project_dir,
+            existing,
+            start_time: SystemTime::now(),
+        })
+    }
 
+    /// Build the execution result from the finished process's output.
+    ///
+    /// A new session's log starts with `prompt`; a resumed one starts with
+    /// the prompt of the session it continues.
+    fn finish(self, output: Output, prompt: &str, resumed: Option<&Path>) -> Result<RawExecutionResult> {
         // Capture stdout
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         let stdout = if stdout.is_empty() { None } else { Some(stdout) };
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let stderr = if stderr.is_empty() { None } else { Some(stderr) };
 
-        // Find the new session log file (only in this project)
-        let session_log_path = find_new_session(&project_dir, &existing_sessions)?;
+        // Find the new session log file (only in this project). A resumed
+        // session may instead be appended to its existing log.
+        let prompt = if resumed.is_none() { Some(prompt) } else { None };
+        let session_log_path = match find_new_session(&self.project_dir, &self.existing, self.start_time, prompt) {
+            Ok(path) => path,
+            Err(e) => resumed.map(Path::to_path_buf).ok_or(e)?,
+        };
 
         Ok(RawExecutionResult {
             session_log_path: Some(session_log_path),
             stdout,
+            stderr,
+            exit_code: output.status.code(),
             agent_context: None,
         })
     }
+}
+
+impl Default for ClaudeAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Agent for ClaudeAdapter {
+    fn name(&self) -> &'static str {
+        "claude"
+    }
+
+    fn execute(&self, prompt: &str, config: &ExecutionConfig) -> Result<RawExecutionResult> {
+        self.run(prompt, config, None)
+    }
+
+    async fn execute_async(
+        &self,
+        prompt: &str,
+        config: &ExecutionConfig,
+    ) -> Result<RawExecutionResult> {
+        self.run_async(prompt, config, None).await
+    }
 
     fn parse_session(&self, result: &RawExecutionResult) -> Result<Vec<ToolCall>> {
         let path = result
@@ -83,6 +170,24 @@ impl Agent for ClaudeAdapter {
         parse_jsonl_file(path)
     }
 
+    fn parse_usage(&self, result: &RawExecutionResult) -> Option<UsageStats> {
+        let path = result.session_log_path.as_ref()?;
+        parse_session_with_usage(path).ok().map(|(_, usage)| usage)
+    }
+
+    /// Read the session log once for both tool calls and usage.
+    fn parse_session_and_usage(
+        &self,
+        result: &RawExecutionResult,
+    ) -> Result<(Vec<ToolCall>, Option<UsageStats>)> {
+        let path = result
+            .session_log_path
+            .as_ref()
+            .context("Claude requires session log path")?;
+        let (tool_calls, usage) = parse_session_with_usage(path)?;
+        Ok((tool_calls, Some(usage)))
+    }
+
     fn tool_mapping(&self) -> &ToolNameMapping {
         &self.mapping
     }
@@ -91,6 +196,24 @@ impl Agent for ClaudeAdapter {
         true
     }
 
+    fn supports_resume(&self) -> bool {
+        true
+    }
+
+    fn resume(
+        &self,
+        previous: &RawExecutionResult,
+        prompt: &str,
+        config: &ExecutionConfig,
+    ) -> Result<RawExecutionResult> {
+        // Session logs are named after the session id: <id>.jsonl
+        let session = previous
+            .session_log_path
+            .as_deref()
+            .context("Claude requires the previous session log path to resume")?;
+        self.run(prompt, config, Some(session))
+    }
+
     fn is_available(&self) -> bool {
         Command::new("claude")
             .arg("--version")
@@ -102,27 +225,51 @@ impl Agent for ClaudeAdapter {
     }
 
     fn grade(&self, prompt: &str, model: Option<&str>) -> Result<String> {
-        let mut cmd = Command::new("claude");
-        cmd.arg("--print").arg(prompt).stdin(Stdio::null());
-
-        if let Some(m) = model {
-            cmd.arg("--model").arg(m);
-        }
+        run_grader(grade_command("claude", prompt, model), "claude")
+    }
 
-        let output = cmd.output().context("Failed to execute claude command for grading")?;
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    async fn grade_async(&self, prompt: &str, model: Option<&str>) -> Result<String> {
+        run_grader_async(grade_command("claude", prompt, model), "claude").await
+    }
+}
 
-        if stdout.trim().is_empty() {
-            anyhow::bail!("Grading agent returned empty response");
-        }
+/// Build the `claude --print` command that answers a grading prompt.
+fn grade_command(program: &str, prompt: &str, model: Option<&str>) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.arg("--print").arg(prompt).stdin(Stdio::null());
 
-        Ok(stdout)
+    if let Some(m) = model {
+        cmd.arg("--model").arg(m);
     }
 
+    cmd
 }
 
 /// Get the Claude projects directory.
+///
+/// Resolution order:
+/// 1. `CLAUDE_PROJECTS_DIR` environment variable (for testing and custom installs)
+/// 2. `projects` under `CLAUDE_CONFIG_DIR`, Claude Code's own config directory override
+/// 3. `~/.claude/projects`
 pub(crate) fn get_claude_projects_dir() -> Result<PathBuf> {
+    let env = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    resolve_claude_projects_dir(env("CLAUDE_PROJECTS_DIR"), env("CLAUDE_CONFIG_DIR"))
+}
+
+/// [`get_claude_projects_dir`], given the non-empty values of
+/// `CLAUDE_PROJECTS_DIR` and `CLAUDE_CONFIG_DIR`.
+fn resolve_claude_projects_dir(
+    projects_dir: Option<OsString>,
+    config_dir: Option<OsString>,
+) -> Result<PathBuf> {
+    // Allow override via environment variable
+    if let Some(path) = projects_dir {
+        return existing_projects_dir(PathBuf::from(path), "CLAUDE_PROJECTS_DIR");
+    }
+    if let Some(path) = config_dir {
+        return existing_projects_dir(PathBuf::from(path).join("projects"), "CLAUDE_CONFIG_DIR");
+    }
+
     let home = dirs::home_dir().context("Could not find home directory")?;
     let claude_dir = home.join(".claude").join("projects");
 
@@ -136,12 +283,26 @@ pub(crate) fn get_claude_projects_dir() -> Result<PathBuf> {
     Ok(claude_dir)
 }
 
+/// Check that a projects directory set through the environment variable `var` exists.
+fn existing_projects_dir(claude_dir: PathBuf, var: &str) -> Result<PathBuf> {
+    if !claude_dir.is_dir() {
+        return Err(HarnessError::SessionNotFound {
+            agent: "claude".to_string(),
+            detail: format!("projects directory not found at {} (from {})", claude_dir.display(), var),
+        }
+        .into());
+    }
+    Ok(claude_dir)
+}
+
 /// Get the specific project directory for a given working directory.
 ///
-/// Claude Code stores sessions in directories named after the working directory path,
-/// with slashes replaced by dashes. E.g., /Users/foo/bar becomes -Users-foo-bar
+/// Claude Code stores sessions in directories named after the working directory
+/// path (see [`project_dir_name`]). If no such directory exists, e.g. before the
+/// first session in a new working directory, a directory whose name differs only
+/// in case is used, and failing that the whole projects directory is searched.
 pub(crate) fn get_project_dir_for_workdir(
-    claude_dir: &PathBuf,
+    claude_dir: &Path,
     working_dir: &Option<PathBuf>,
 ) -> Result<PathBuf> {
     let workdir = match working_dir {
@@ -151,24 +312,65 @@ pub(crate) fn get_project_dir_for_workdir(
         None => std::env::current_dir().context("Failed to get current directory")?,
     };
 
-    // Convert path to Claude's project directory naming convention
-    // /Users/foo/bar -> -Users-foo-bar
-    let path_str = workdir.to_string_lossy();
-    let project_name = path_str.replace('/', "-");
-
+    let project_name = project_dir_name(&workdir.to_string_lossy());
     let project_dir = claude_dir.join(&project_name);
+    if project_dir.exists() {
+        return Ok(project_dir);
+    }
 
-    // If the specific project dir doesn't exist, fall back to searching all projects
-    if !project_dir.exists() {
-        return Ok(claude_dir.clone());
+    // Drive letters and other path components may be cased differently on
+    // case-insensitive filesystems
+    let same_name = std::fs::read_dir(claude_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .find(|entry| entry.file_name().to_string_lossy().eq_ignore_ascii_case(&project_name));
+    if let Some(entry) = same_name {
+        eprintln!(
+            "Warning: No Claude project directory named {}; using {} (differs only in case)",
+            project_name,
+            entry.path().display()
+        );
+        return Ok(entry.path());
     }
 
-    Ok(project_dir)
+    // If the specific project dir doesn't exist, fall back to searching all projects
+    eprintln!(
+        "Warning: No Claude project directory named {} yet; searching all projects in {}",
+        project_name,
+        claude_dir.display()
+    );
+    Ok(claude_dir.to_path_buf())
+}
+
+/// Encode a working directory as the name of its Claude Code project directory.
+///
+/// Claude Code replaces every character other than an ASCII letter or digit
+/// with `-` (one per UTF-16 code unit), on every platform:
+/// `/Users/foo/my.app` becomes `-Users-foo-my-app` and `C:\Users\foo` becomes
+/// `C--Users-foo`. Windows verbatim prefixes (`\\?\`), which canonicalizing
+/// adds, are dropped first. Distinct paths can share a name, such as
+/// `/a/b-c` and `/a/b/c`.
+pub(crate) fn project_dir_name(workdir: &str) -> String {
+    let workdir = match workdir.strip_prefix(r"\\?\UNC\") {
+        Some(share) => format!(r"\\{}", share),
+        None => workdir.strip_prefix(r"\\?\").unwrap_or(workdir).to_string(),
+    };
+
+    let mut name = String::with_capacity(workdir.len());
+    for c in workdir.chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c);
+        } else {
+            name.extend(std::iter::repeat_n('-', c.len_utf16()));
+        }
+    }
+    name
 }
 
 /// List all JSONL session files in the claude directory.
 /// Excludes subagent logs (files in /subagents/ directories).
-pub(crate) fn list_session_files(claude_dir: &PathBuf) -> Result<Vec<PathBuf>> {
+pub(crate) fn list_session_files(claude_dir: &Path) -> Result<Vec<PathBuf>> {
     let mut files = Vec::new();
 
     if claude_dir.exists() {
@@ -178,10 +380,10 @@ pub(crate) fn list_session_files(claude_dir: &PathBuf) -> Result<Vec<PathBuf>> {
         {
             let path = entry.path();
             // Skip subagent logs - we only want main session logs
-            if path.to_string_lossy().contains("/subagents/") {
+            if path.components().any(|c| c.as_os_str() == "subagents") {
                 continue;
             }
-            if path.extension().map_or(false, |ext| ext == "jsonl") {
+            if path.extension().is_some_and(|ext| ext == "jsonl") {
                 files.push(path.to_path_buf());
             }
         }
@@ -190,35 +392,404 @@ pub(crate) fn list_session_files(claude_dir: &PathBuf) -> Result<Vec<PathBuf>> {
     Ok(files)
 }
 
-/// Find a new session log file that wasn't in the existing list.
-pub(crate) fn find_new_session(claude_dir: &PathBuf, existing: &[PathBuf]) -> Result<PathBuf> {
-    let current = list_session_files(claude_dir)?;
+/// How much earlier than the run's start a session log's mtime may be.
+///
+/// Filesystems stamp files from a coarser clock than [`SystemTime::now`], so a
+/// log written just after the run started can appear to predate it.
+const MTIME_SLACK: Duration = Duration::from_secs(1);
+
+/// Find the session log of a run that started at `start_time`.
+///
+/// Only logs modified since the run started are candidates, so sessions
+/// that finished before it are never picked. New logs (not in `existing`)
+/// win over existing ones, which a resumed session may append to. When
+/// `prompt` is given, a log whose first user message is a different prompt
+/// belongs to another run, such as a concurrent `claude` in the same
+/// project, and is skipped. Among the remaining candidates the most
+/// recently modified wins.
+pub(crate) fn find_new_session(
+    claude_dir: &Path,
+    existing: &[PathBuf],
+    start_time: SystemTime,
+    prompt: Option<&str>,
+) -> Result<PathBuf> {
+    let since = start_time.checked_sub(MTIME_SLACK).unwrap_or(start_time);
+
+    let mut best: Option<(bool, SystemTime, PathBuf)> = None;
+    for path in list_session_files(claude_dir)? {
+        let Some(modified) = path.metadata().and_then(|m| m.modified()).ok() else {
+            continue;
+        };
+        if modified < since {
+            continue;
+        }
+        if let Some(prompt) = prompt {
+            // A log whose prompt isn't written yet may still be ours
+            if session_prompt(&path).is_some_and(|first| first.trim() != prompt.trim()) {
+                continue;
+            }
+        }
 
-    // Find files that are new or modified
-    for path in &current {
-        if !existing.contains(path) {
-            return Ok(path.clone());
+        let is_new = !existing.contains(&path);
+        if best
+            .as_ref()
+            .is_none_or(|(best_new, best_time, _)| (is_new, modified) > (*best_new, *best_time))
+        {
+            best = Some((is_new, modified, path));
         }
     }
 
-    // If no new file, find the most recently modified from the filtered list
-    let mut newest: Option<(PathBuf, std::time::SystemTime)> = None;
+    best.map(|(_, _, path)| path).ok_or_else(|| {
+        HarnessError::SessionNotFound {
+            agent: "claude".to_string(),
+            detail: format!("no session logs modified since the run started in {}", claude_dir.display()),
+        }
+        .into()
+    })
+}
 
-    for path in current {
-        if let Ok(metadata) = path.metadata() {
-            if let Ok(modified) = metadata.modified() {
-                match &newest {
-                    None => newest = Some((path, modified)),
-                    Some((_, newest_time)) if modified > *newest_time => {
-                        newest = Some((path, modified));
-                    }
-                    _ => {}
+/// The first prompt in a session log: the text of its first user message.
+///
+/// Returns `None` if the log can't be read or has no user message yet.
+fn session_prompt(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    for line in BufReader::new(file).lines() {
+        let Ok(entry) = serde_json::from_str::<Value>(&line.ok()?) else {
+            continue;
+        };
+        if entry.get("type").and_then(Value::as_str) != Some("user") {
+            continue;
+        }
+        match entry.pointer("/message/content")? {
+            Value::String(text) => return Some(text.clone()),
+            Value::Array(blocks) => {
+                let text: Vec<&str> = blocks
+                    .iter()
+                    .filter(|b| b.get("type").and_then(Value::as_str) == Some("text"))
+                    .filter_map(|b| b.get("text").and_then(Value::as_str))
+                    .collect();
+                if !text.is_empty() {
+                    return Some(text.join("\n"));
                 }
             }
+            _ => {}
         }
     }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Tests that run a `#!/bin/sh` stand-in for `claude`.
+    #[cfg(unix)]
+    mod stub_tests {
+        use super::*;
+        use crate::review::{grade_stdout, ReviewConfig};
+        use std::os::unix::fs::PermissionsExt;
+
+        /// Write an executable stand-in for `claude` that records its arguments
+        /// and prints `response`.
+        fn stub_claude(dir: &Path, response: &str) -> PathBuf {
+            let path = dir.join("claude");
+            let script = format!(
+                "#!/bin/sh\nprintf '%s\\n' \"$@\" > \"{}\"\nprintf '%s' '{}'\n",
+                dir.join("args").display(),
+                response
+            );
+            fs::write(&path, script).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        }
 
-    newest
-        .map(|(path, _)| path)
-        .context("Could not find session log file")
+        #[test]
+        fn test_grade_returns_parseable_json() {
+            let dir = tempfile::tempdir().unwrap();
+            let stub = stub_claude(
+                dir.path(),
+                r#"```json
+    {"score": 8, "reasoning": "Confirms the change"}
+    ```"#,
+            );
+            let stub = stub.to_str().unwrap();
+            let config = ReviewConfig {
+                criteria: "confirms the change".to_string(),
+                threshold: 7,
+                model: Some("haiku".to_string()),
+                prompt_template: None,
+            };
+
+            let result = grade_stdout(&Some("Done.".to_string()), &config, |prompt, model| {
+                run_grader(grade_command(stub, prompt, model), "claude")
+            })
+            .unwrap();
+            assert_eq!(result.score, 8);
+            assert!(result.passed);
+
+            let args = fs::read_to_string(dir.path().join("args")).unwrap();
+            let args: Vec<&str> = args.lines().collect();
+            assert_eq!(args[0], "--print");
+            assert!(args.contains(&"--model") && args.ends_with(&["haiku"]));
+            assert!(args.iter().any(|line| line.contains("confirms the change")));
+        }
+
+        #[test]
+        fn test_grade_async_matches_grade() {
+            let dir = tempfile::tempdir().unwrap();
+            let stub = stub_claude(dir.path(), r#"{"score": 3, "reasoning": "No"}"#);
+            let cmd = grade_command(stub.to_str().unwrap(), "Grade this", None);
+
+            let response = futures::executor::block_on(run_grader_async(cmd, "claude")).unwrap();
+            assert_eq!(response, r#"{"score": 3, "reasoning": "No"}"#);
+            let args = fs::read_to_string(dir.path().join("args")).unwrap();
+            assert_eq!(args, "--print\nGrade this\n");
+        }
+
+        #[test]
+        fn test_grade_empty_response_errors() {
+            let dir = tempfile::tempdir().unwrap();
+            let stub = stub_claude(dir.path(), "  ");
+            let cmd = grade_command(stub.to_str().unwrap(), "Grade this", None);
+
+            let err = run_grader(cmd, "claude").unwrap_err();
+            assert_eq!(err.to_string(), "Grading agent returned empty response");
+        }
+    }
+
+    #[test]
+    fn test_claude_command_appends_system_prompt() {
+        let args = |cmd: &Command| -> Vec<String> {
+            cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect()
+        };
+
+        assert_eq!(args(&claude_command(&ExecutionConfig::new(), None)), vec!["--print"]);
+
+        let config = ExecutionConfig::new()
+            .with_system_prompt("Prefer Grep over Bash")
+            .with_model("haiku");
+        let mut cmd = claude_command(&config, Some(Path::new("/sessions/abc123.jsonl")));
+        config.apply_to(&mut cmd);
+        assert_eq!(
+            args(&cmd),
+            vec![
+                "--print",
+                "--resume",
+                "abc123",
+                "--append-system-prompt",
+                "Prefer Grep over Bash",
+                "--model",
+                "haiku",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_session_and_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        fs::write(
+            &path,
+            concat!(
+                r#"{"type":"assistant","costUSD":0.01,"message":{"id":"msg_1","content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"/a.txt"}}],"usage":{"input_tokens":100,"output_tokens":20}}}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        let result = RawExecutionResult {
+            session_log_path: Some(path),
+            stdout: None,
+            stderr: None,
+            exit_code: Some(0),
+            agent_context: None,
+        };
+
+        let adapter = ClaudeAdapter::new();
+        let (calls, usage) = adapter.parse_session_and_usage(&result).unwrap();
+        assert_eq!(calls.len(), adapter.parse_session(&result).unwrap().len());
+        assert_eq!(calls[0].name, "Read");
+        assert_eq!(usage, adapter.parse_usage(&result));
+        assert_eq!(usage.unwrap().total_tokens(), 120);
+
+        let missing = RawExecutionResult {
+            session_log_path: None,
+            ..result
+        };
+        assert!(adapter.parse_session_and_usage(&missing).is_err());
+    }
+
+    /// Write a session log whose first user message is `prompt`, last
+    /// modified `age` before `now`.
+    fn write_session(dir: &Path, name: &str, prompt: &str, now: SystemTime, age: Duration) -> PathBuf {
+        let path = dir.join(name);
+        let user = serde_json::json!({ "type": "user", "message": { "content": prompt } });
+        fs::write(&path, format!("{{\"type\":\"summary\"}}\n{}\n", user)).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(now - age)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_find_new_session_ignores_logs_older_than_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let start = now - Duration::from_secs(60);
+        // Not in the snapshot, but finished long before the run started
+        write_session(dir.path(), "stale.jsonl", "list files", now, Duration::from_secs(3600));
+
+        let err = find_new_session(dir.path(), &[], start, None).unwrap_err();
+        assert!(err.to_string().contains("no session logs modified since the run started"));
+
+        let ours = write_session(dir.path(), "ours.jsonl", "list files", now, Duration::from_secs(30));
+        assert_eq!(find_new_session(dir.path(), &[], start, None).unwrap(), ours);
+    }
+
+    #[test]
+    fn test_find_new_session_prefers_new_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let start = now - Duration::from_secs(60);
+        let older = write_session(dir.path(), "older.jsonl", "fix the bug", now, Duration::from_secs(3600));
+        let existing = vec![older.clone()];
+
+        // Another run appends to a log from before the snapshot, after ours started
+        File::options().write(true).open(&older).unwrap().set_modified(now).unwrap();
+        let ours = write_session(dir.path(), "ours.jsonl", "list files", now, Duration::from_secs(30));
+        assert_eq!(find_new_session(dir.path(), &existing, start, None).unwrap(), ours);
+
+        // Without a new log, a resumed session appended to an existing one
+        fs::remove_file(&ours).unwrap();
+        assert_eq!(find_new_session(dir.path(), &existing, start, None).unwrap(), older);
+    }
+
+    #[test]
+    fn test_find_new_session_matches_prompt_among_concurrent_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let start = now - Duration::from_secs(60);
+        let ours = write_session(dir.path(), "ours.jsonl", "list files", now, Duration::from_secs(40));
+        let theirs = write_session(dir.path(), "theirs.jsonl", "fix the bug", now, Duration::from_secs(10));
+
+        // By time alone, the concurrent session started later and wins
+        assert_eq!(find_new_session(dir.path(), &[], start, None).unwrap(), theirs);
+        assert_eq!(
+            find_new_session(dir.path(), &[], start, Some("list files\n")).unwrap(),
+            ours
+        );
+
+        // A log with no prompt yet may still be ours
+        let pending = dir.path().join("pending.jsonl");
+        fs::write(&pending, "").unwrap();
+        assert_eq!(
+            find_new_session(dir.path(), &[], start, Some("list files")).unwrap(),
+            pending
+        );
+        fs::remove_file(&pending).unwrap();
+        assert!(find_new_session(dir.path(), &[], start, Some("edit it")).is_err());
+    }
+
+    #[test]
+    fn test_session_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        fs::write(
+            &path,
+            concat!(
+                r#"{"type":"summary","summary":"Listing"}"#, "\n",
+                "not json\n",
+                r#"{"type":"user","message":{"content":[{"type":"text","text":"list"},{"type":"text","text":"files"}]}}"#, "\n",
+                r#"{"type":"user","message":{"content":"thanks"}}"#, "\n",
+            ),
+        )
+        .unwrap();
+        assert_eq!(session_prompt(&path).as_deref(), Some("list\nfiles"));
+
+        fs::write(&path, r#"{"type":"assistant","message":{"content":"hi"}}"#).unwrap();
+        assert_eq!(session_prompt(&path), None);
+        assert_eq!(session_prompt(&dir.path().join("missing.jsonl")), None);
+    }
+
+    #[test]
+    fn test_claude_projects_dir_from_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        let projects = config_dir.join("projects");
+        let missing_dir = dir.path().join("missing");
+        let workdir = dir.path().join("work");
+        fs::create_dir_all(&workdir).unwrap();
+        let workdir = workdir.canonicalize().unwrap();
+        let project = projects.join(project_dir_name(&workdir.to_string_lossy()));
+        fs::create_dir_all(&project).unwrap();
+        let now = SystemTime::now();
+        let session = write_session(&project, "abc.jsonl", "list files", now, Duration::ZERO);
+
+        let from_config = resolve_claude_projects_dir(None, Some(config_dir.clone().into()));
+        let missing = resolve_claude_projects_dir(Some(missing_dir.clone().into()), Some(config_dir.into()));
+        // CLAUDE_PROJECTS_DIR takes precedence over CLAUDE_CONFIG_DIR
+        let from_projects = resolve_claude_projects_dir(Some(projects.clone().into()), Some(missing_dir.into()));
+
+        assert_eq!(from_config.unwrap(), projects);
+        let err = missing.unwrap_err().to_string();
+        assert!(err.contains("(from CLAUDE_PROJECTS_DIR)"), "{}", err);
+        let claude_dir = from_projects.unwrap();
+        assert_eq!(claude_dir, projects);
+
+        let project_dir = get_project_dir_for_workdir(&claude_dir, &Some(workdir)).unwrap();
+        assert_eq!(project_dir, project);
+        assert_eq!(
+            find_new_session(&project_dir, &[], now, Some("list files")).unwrap(),
+            session
+        );
+    }
+
+    #[test]
+    fn test_project_dir_name_unix_paths() {
+        assert_eq!(project_dir_name("/Users/foo/bar"), "-Users-foo-bar");
+        assert_eq!(project_dir_name("/home/foo/my.app"), "-home-foo-my-app");
+        assert_eq!(project_dir_name("/home/foo/.config/my_tool"), "-home-foo--config-my-tool");
+        assert_eq!(project_dir_name("/tmp/caf\u{e9} \u{1f600}"), "-tmp-caf----");
+        // The encoding is lossy: dashes and separators collide
+        assert_eq!(project_dir_name("/a/b-c"), project_dir_name("/a/b/c"));
+    }
+
+    #[test]
+    fn test_project_dir_name_windows_paths() {
+        assert_eq!(project_dir_name(r"C:\Users\foo\bar"), "C--Users-foo-bar");
+        assert_eq!(project_dir_name(r"\\?\C:\Users\foo\my.app"), "C--Users-foo-my-app");
+        assert_eq!(project_dir_name(r"\\?\UNC\server\share\proj"), "--server-share-proj");
+        assert_eq!(project_dir_name(r"\\server\share\proj"), "--server-share-proj");
+    }
+
+    #[test]
+    fn test_project_dir_for_workdir() {
+        let dir = tempfile::tempdir().unwrap();
+        let claude_dir = dir.path().join("projects");
+        let workdir = dir.path().join("my.app");
+        fs::create_dir_all(&workdir).unwrap();
+        let name = project_dir_name(&workdir.canonicalize().unwrap().to_string_lossy());
+        assert!(name.ends_with("-my-app"));
+        fs::create_dir_all(&claude_dir).unwrap();
+
+        // No project yet: search every project
+        let found = get_project_dir_for_workdir(&claude_dir, &Some(workdir.clone())).unwrap();
+        assert_eq!(found, claude_dir);
+
+        // A project differing only in case, where the filesystem tells them apart
+        let cased = claude_dir.join(name.to_uppercase());
+        let exact = claude_dir.join(&name);
+        fs::create_dir(&cased).unwrap();
+        if !exact.exists() {
+            let found = get_project_dir_for_workdir(&claude_dir, &Some(workdir.clone())).unwrap();
+            assert_eq!(found, cased);
+            fs::remove_dir(&cased).unwrap();
+            fs::create_dir(&exact).unwrap();
+        }
+        let found = get_project_dir_for_workdir(&claude_dir, &Some(workdir)).unwrap();
+        assert_eq!(found, exact);
+    }
 }
@@ -0,0 +1,535 @@
+//! Cursor agent adapter.
+//!
+//! This adapter integrates with Cursor's agent CLI (`cursor-agent --print`).
+//! Like Kiro, Cursor keeps conversations in a SQLite database rather than log
+//! files: the `cursorDiskKV` key-value table of its `state.vscdb`. Each
+//! conversation ("composer") is stored under `composerData:<composer id>`,
+//! either with its messages ("bubbles") inline or, in newer versions, with
+//! only their ids and each bubble under `bubbleId:<composer id>:<bubble id>`.
+//! The format is undocumented, so only the fields tool calls need are read.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat};
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::{run_grader, wait_with_timeout, Agent, ExecutionConfig, RawExecutionResult, ToolNameMapping};
+use crate::error::HarnessError;
+use crate::parser::ToolCall;
+
+// =========================================================================
+// Cursor JSON data structures for parsing conversations
+// =========================================================================
+
+/// A tool invocation recorded on an assistant bubble.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CursorToolFormerData {
+    name: String,
+    /// Arguments, usually as a JSON-encoded string.
+    #[serde(default)]
+    params: Value,
+    /// The arguments as the model wrote them, when `params` is missing.
+    #[serde(default)]
+    raw_args: Value,
+    #[serde(default)]
+    result: Option<String>,
+}
+
+/// One message of a conversation.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CursorBubble {
+    /// Epoch milliseconds or an RFC3339 string, depending on the version.
+    #[serde(default)]
+    created_at: Value,
+    #[serde(default)]
+    tool_former_data: Option<CursorToolFormerData>,
+}
+
+/// A reference to a bubble stored under its own key.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CursorBubbleHeader {
+    bubble_id: String,
+}
+
+/// Top-level conversation structure from Cursor's database.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CursorComposer {
+    #[serde(default)]
+    composer_id: String,
+    #[serde(default)]
+    created_at: Option<u64>,
+    #[serde(default)]
+    last_updated_at: Option<u64>,
+    /// Bubbles stored inline (older versions).
+    #[serde(default)]
+    conversation: Vec<CursorBubble>,
+    /// Bubble ids, each stored under `bubbleId:<composer id>:<bubble id>`.
+    #[serde(default)]
+    full_conversation_headers_only: Vec<CursorBubbleHeader>,
+}
+
+impl CursorComposer {
+    /// Epoch milliseconds of the last change to the conversation.
+    fn updated_at(&self) -> u64 {
+        self.last_updated_at.or(self.created_at).unwrap_or(0)
+    }
+}
+
+// =========================================================================
+// Helper functions for Cursor database access
+// =========================================================================
+
+/// Get the path to Cursor's SQLite database.
+///
+/// Resolution order:
+/// 1. `CURSOR_DB_PATH` environment variable (for testing and custom installs)
+/// 2. Platform config directory via `dirs::config_dir()`:
+///    - macOS: `~/Library/Application Support/Cursor/User/globalStorage/state.vscdb`
+///    - Linux: `$XDG_CONFIG_HOME/Cursor/User/globalStorage/state.vscdb` (or `~/.config/...`)
+///    - Windows: `{FOLDERID_RoamingAppData}/Cursor/User/globalStorage/state.vscdb`
+fn get_cursor_db_path() -> Result<PathBuf> {
+    // Allow override via environment variable
+    if let Ok(path) = std::env::var("CURSOR_DB_PATH") {
+        let db_path = PathBuf::from(path);
+        if !db_path.exists() {
+            return Err(HarnessError::SessionNotFound {
+                agent: "cursor".to_string(),
+                detail: format!("database not found at {} (from CURSOR_DB_PATH)", db_path.display()),
+            }
+            .into());
+        }
+        return Ok(db_path);
+    }
+
+    let config_dir =
+        dirs::config_dir().ok_or_else(|| anyhow!("Could not determine platform config directory"))?;
+
+    let db_path = config_dir
+        .join("Cursor")
+        .join("User")
+        .join("globalStorage")
+        .join("state.vscdb");
+
+    if !db_path.exists() {
+        return Err(HarnessError::SessionNotFound {
+            agent: "cursor".to_string(),
+            detail: format!(
+                "database not found at {}. Cursor may not be installed or has not been used yet.",
+                db_path.display()
+            ),
+        }
+        .into());
+    }
+
+    Ok(db_path)
+}
+
+/// Parse tool calls from a Cursor conversation (`composerData`) blob.
+///
+/// Bubbles stored under their own keys are looked up with `bubble`, given the
+/// bubble's key; ones it can't find are skipped.
+pub(crate) fn parse_cursor_tool_calls(
+    content: &str,
+    mut bubble: impl FnMut(&str) -> Option<String>,
+) -> Result<Vec<ToolCall>> {
+    let composer: CursorComposer =
+        serde_json::from_str(content).context("Failed to parse Cursor conversation JSON")?;
+
+    let mut bubbles = composer.conversation;
+    for header in &composer.full_conversation_headers_only {
+        let key = format!("bubbleId:{}:{}", composer.composer_id, header.bubble_id);
+        let Some(content) = bubble(&key) else {
+            continue;
+        };
+        match serde_json::from_str::<CursorBubble>(&content) {
+            Ok(parsed) => bubbles.push(parsed),
+            Err(e) => eprintln!("Warning: Failed to parse Cursor message {}: {}", key, e),
+        }
+    }
+
+    Ok(bubbles.into_iter().filter_map(bubble_tool_call).collect())
+}
+
+/// The tool call a bubble records, if any.
+fn bubble_tool_call(bubble: CursorBubble) -> Option<ToolCall> {
+    let tool = bubble.tool_former_data.filter(|t| !t.name.is_empty())?;
+
+    let params = [tool.params, tool.raw_args]
+        .into_iter()
+        .map(|args| match args {
+            // Arguments are JSON encoded as a string
+            Value::String(text) => serde_json::from_str(&text).unwrap_or(Value::String(text)),
+            args => args,
+        })
+        .find(|args| !args.is_null())
+        .unwrap_or(Value::Null);
+
+    let timestamp = match bubble.created_at {
+        Value::String(text) => text,
+        Value::Number(ms) => ms
+            .as_i64()
+            .and_then(DateTime::from_timestamp_millis)
+            .map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true))
+            .unwrap_or_default(),
+        _ => String::new(),
+    };
+
+    Some(ToolCall {
+        name: tool.name,
+        params,
+        timestamp,
+        result: tool.result.map(Value::String),
+    })
+}
+
+/// Query tool calls from the Cursor database for the conversation most
+/// recently updated since `start_time_ms`.
+///
+/// **Known limitation:** conversations aren't tied to a working directory in
+/// the database, so a concurrent Cursor session that updates a conversation
+/// after this run's may be picked instead.
+fn query_tool_calls(db_path: &Path, start_time_ms: u64) -> Result<Vec<ToolCall>> {
+    let conn = Connection::open(db_path).context("Failed to open Cursor database")?;
+
+    let mut stmt = conn
+        .prepare("SELECT value FROM cursorDiskKV WHERE key LIKE 'composerData:%'")
+        .context("Failed to prepare SQL query")?;
+
+    let mut latest: Option<(u64, String)> = None;
+    let rows = stmt
+        .query_map([], |row| read_text(row.get_ref(0)?))
+        .context("Failed to execute SQL query")?;
+    for row in rows {
+        let content = match row {
+            Ok(Some(content)) => content,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!("Warning: Failed to read database row: {}", e);
+                continue;
+            }
+        };
+        // Skip conversations that don't parse; they can't be this run's
+        let Ok(composer) = serde_json::from_str::<CursorComposer>(&content) else {
+            continue;
+        };
+        let updated_at = composer.updated_at();
+        if updated_at >= start_time_ms && latest.as_ref().is_none_or(|(newest, _)| updated_at > *newest) {
+            latest = Some((updated_at, content));
+        }
+    }
+
+    let (_, content) = latest.ok_or_else(|| HarnessError::SessionNotFound {
+        agent: "cursor".to_string(),
+        detail: format!("no conversation updated since the run started in {}", db_path.display()),
+    })?;
+
+    let mut bubbles = conn
+        .prepare("SELECT value FROM cursorDiskKV WHERE key = ?1")
+        .context("Failed to prepare SQL query")?;
+    parse_cursor_tool_calls(&content, |key| {
+        bubbles
+            .query_row([key], |row| read_text(row.get_ref(0)?))
+            .ok()
+            .flatten()
+    })
+}
+
+/// Read a `cursorDiskKV` value, which may be stored as text or a blob.
+fn read_text(value: rusqlite::types::ValueRef<'_>) -> rusqlite::Result<Option<String>> {
+    Ok(value
+        .as_bytes_or_null()?
+        .map(|bytes| String::from_utf8_lossy(bytes).into_owned()))
+}
+
+/// Agent-specific context for Cursor session recovery.
+///
+/// Stored in `RawExecutionResult::agent_context` by `execute()` and
+/// downcast in `parse_session()` to query the correct conversation.
+struct CursorSessionContext {
+    start_time_ms: u64,
+}
+
+/// Get current time in milliseconds since Unix epoch.
+fn current_time_ms() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// =========================================================================
+// Cursor adapter
+// =========================================================================
+
+/// Cursor agent adapter.
+///
+/// Only tool names are mapped; params keep Cursor's own keys. In particular
+/// `read_file` reports its path as `target_file`, not `file_path`, so
+/// assert on `target_file` for this agent's `Read` calls.
+pub struct CursorAdapter {
+    mapping: ToolNameMapping,
+}
+
+impl CursorAdapter {
+    pub fn new() -> Self {
+        // Cursor tool name mappings to canonical names
+        let mut mapping = ToolNameMapping::new();
+        mapping.add("read_file", "Read");
+        mapping.add("write", "Write");
+        mapping.add("edit_file", "Edit");
+        mapping.add("search_replace", "Edit");
+        mapping.add("run_terminal_cmd", "Bash");
+        mapping.add("glob_file_search", "Glob");
+        mapping.add("file_search", "Glob");
+        mapping.add("grep", "Grep");
+        mapping.add("grep_search", "Grep");
+        mapping.add("list_dir", "LS");
+        mapping.add("web_search", "WebSearch");
+        mapping.add("todo_write", "TodoWrite");
+        Self { mapping }
+    }
+}
+
+impl Default for CursorAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Agent for CursorAdapter {
+    fn name(&self) -> &'static str {
+        "cursor"
+    }
+
+    fn execute(&self, prompt: &str, config: &ExecutionConfig) -> Result<RawExecutionResult> {
+        config.reject_system_prompt("cursor")?;
+
+        // Record start time for filtering database queries
+        let start_time_ms = current_time_ms();
+
+        let mut cmd = Command::new("cursor-agent");
+        cmd.arg("--print").arg("--output-format").arg("text");
+        let child = config
+            .spawn_with_prompt(&mut cmd, prompt)
+            .context("Failed to execute cursor-agent command")?;
+        let output = wait_with_timeout(child, config.timeout, "cursor-agent")?;
+
+        // Capture stdout
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let stdout = if stdout.is_empty() { None } else { Some(stdout) };
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let stderr = if stderr.is_empty() { None } else { Some(stderr) };
+
+        Ok(RawExecutionResult {
+            session_log_path: None, // Cursor uses a SQLite database, not log files
+            stdout,
+            stderr,
+            exit_code: output.status.code(),
+            agent_context: Some(Box::new(CursorSessionContext { start_time_ms })),
+        })
+    }
+
+    fn parse_session(&self, result: &RawExecutionResult) -> Result<Vec<ToolCall>> {
+        let db_path = get_cursor_db_path()?;
+
+        let ctx = result
+            .agent_context
+            .as_ref()
+            .and_then(|c| c.downcast_ref::<CursorSessionContext>())
+            .ok_or_else(|| {
+                anyhow!("No Cursor session context in execution result - cannot query database")
+            })?;
+
+        query_tool_calls(&db_path, ctx.start_time_ms)
+    }
+
+    fn tool_mapping(&self) -> &ToolNameMapping {
+        &self.mapping
+    }
+
+    fn is_available(&self) -> bool {
+        Command::new("cursor-agent")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    }
+
+    fn grade(&self, prompt: &str, model: Option<&str>) -> Result<String> {
+        let mut cmd = Command::new("cursor-agent");
+        cmd.arg("--print").arg("--output-format").arg("text").arg(prompt).stdin(Stdio::null());
+
+        if let Some(m) = model {
+            cmd.arg("--model").arg(m);
+        }
+
+        run_grader(cmd, "cursor-agent")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    /// A conversation with its bubbles inline, as older Cursor versions store it.
+    const INLINE: &str = r##"{
+        "composerId": "c1",
+        "createdAt": 1760000000000,
+        "lastUpdatedAt": 1760000005000,
+        "conversation": [
+            {"type": 1, "bubbleId": "b1", "text": "Read the readme", "createdAt": 1760000000000},
+            {
+                "type": 2,
+                "bubbleId": "b2",
+                "createdAt": 1760000001500,
+                "toolFormerData": {
+                    "tool": 5,
+                    "name": "read_file",
+                    "params": "{\"target_file\":\"README.md\"}",
+                    "rawArgs": "{\"target_file\": \"README.md\"}",
+                    "status": "completed",
+                    "result": "# Aptitude"
+                }
+            },
+            {"type": 2, "bubbleId": "b3", "text": "It describes the project."},
+            {
+                "type": 2,
+                "bubbleId": "b4",
+                "createdAt": "2025-10-09T08:53:23.000Z",
+                "toolFormerData": {"name": "run_terminal_cmd", "rawArgs": "{\"command\":\"ls\"}"}
+            }
+        ]
+    }"##;
+
+    #[test]
+    fn test_parse_cursor_inline_conversation() {
+        let calls = parse_cursor_tool_calls(INLINE, |_| None).unwrap();
+        assert_eq!(calls.len(), 2);
+
+        assert_eq!(calls[0].name, "read_file");
+        assert_eq!(calls[0].params, json!({"target_file": "README.md"}));
+        assert_eq!(calls[0].timestamp, "2025-10-09T08:53:21.500Z");
+        assert_eq!(calls[0].result, Some(json!("# Aptitude")));
+
+        // Falls back to the raw arguments, and keeps string timestamps
+        assert_eq!(calls[1].name, "run_terminal_cmd");
+        assert_eq!(calls[1].params, json!({"command": "ls"}));
+        assert_eq!(calls[1].timestamp, "2025-10-09T08:53:23.000Z");
+        assert!(calls[1].time().is_some());
+        assert_eq!(calls[1].result, None);
+    }
+
+    #[test]
+    fn test_parse_cursor_bubbles_by_key() {
+        let composer = r#"{
+            "composerId": "c2",
+            "fullConversationHeadersOnly": [
+                {"bubbleId": "b1", "type": 1},
+                {"bubbleId": "b2", "type": 2},
+                {"bubbleId": "missing", "type": 2},
+                {"bubbleId": "b3", "type": 2}
+            ]
+        }"#;
+        let bubbles: HashMap<String, String> = [
+            ("bubbleId:c2:b1", json!({"type": 1, "text": "Fix it"})),
+            (
+                "bubbleId:c2:b2",
+                json!({"type": 2, "toolFormerData": {"name": "grep", "params": {"pattern": "TODO"}}}),
+            ),
+            (
+                "bubbleId:c2:b3",
+                json!({"type": 2, "toolFormerData": {"name": "edit_file", "params": "not json"}}),
+            ),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+        let calls = parse_cursor_tool_calls(composer, |key| bubbles.get(key).cloned()).unwrap();
+        let names: Vec<_> = calls.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["grep", "edit_file"]);
+        assert_eq!(calls[0].params, json!({"pattern": "TODO"}));
+        // Arguments that aren't JSON are kept as the string
+        assert_eq!(calls[1].params, json!("not json"));
+        assert_eq!(calls[1].timestamp, "");
+    }
+
+    #[test]
+    fn test_parse_cursor_malformed_json() {
+        assert!(parse_cursor_tool_calls("not valid json", |_| None).is_err());
+        assert!(parse_cursor_tool_calls("{}", |_| None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_query_picks_latest_conversation_since_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("state.vscdb");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute("CREATE TABLE cursorDiskKV (key TEXT PRIMARY KEY, value BLOB)", [])
+            .unwrap();
+        let insert = |key: &str, value: &str| {
+            conn.execute("INSERT INTO cursorDiskKV (key, value) VALUES (?1, ?2)", [key, value])
+                .unwrap();
+        };
+
+        let old = json!({
+            "composerId": "old",
+            "lastUpdatedAt": 1_000,
+            "conversation": [{"toolFormerData": {"name": "list_dir", "params": "{}"}}],
+        });
+        let new = json!({
+            "composerId": "new",
+            "lastUpdatedAt": 3_000,
+            "fullConversationHeadersOnly": [{"bubbleId": "b1"}],
+        });
+        let bubble = json!({"toolFormerData": {"name": "read_file", "params": r#"{"target_file":"a.rs"}"#}});
+        insert("composerData:old", &old.to_string());
+        insert("composerData:new", &new.to_string());
+        insert("bubbleId:new:b1", &bubble.to_string());
+        insert("composerData:other", &json!({"composerId": "other", "lastUpdatedAt": 2_000}).to_string());
+        insert("composerData:broken", "not json");
+        insert("workbench.panel", "{}");
+
+        let calls = query_tool_calls(&db_path, 1_500).unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].name, "read_file");
+        assert_eq!(calls[0].params["target_file"], "a.rs");
+
+        let calls = query_tool_calls(&db_path, 0).unwrap();
+        assert_eq!(calls[0].name, "read_file");
+
+        let err = query_tool_calls(&db_path, 5_000).unwrap_err();
+        assert!(err.to_string().contains("no conversation updated since the run started"));
+    }
+
+    #[test]
+    fn test_cursor_adapter_tool_mapping_maps_known_tools() {
+        let adapter = CursorAdapter::new();
+        let mapping = adapter.tool_mapping();
+
+        assert_eq!(adapter.name(), "cursor");
+        assert_eq!(mapping.to_canonical("read_file"), "Read");
+        assert_eq!(mapping.to_canonical("edit_file"), "Edit");
+        assert_eq!(mapping.to_canonical("search_replace"), "Edit");
+        assert_eq!(mapping.to_canonical("run_terminal_cmd"), "Bash");
+        assert_eq!(mapping.to_canonical("grep_search"), "Grep");
+        assert_eq!(mapping.to_canonical("list_dir"), "LS");
+
+        // Unmapped names pass through unchanged
+        assert_eq!(mapping.to_canonical("codebase_search"), "codebase_search");
+    }
+}
@@ -0,0 +1,314 @@
+//! Gemini CLI agent adapter.
+//!
+//! This adapter integrates with Google's Gemini CLI (`gemini --prompt`).
+//! Gemini has no session log we can tail, so tool calls are read from its
+//! `stream-json` output, which emits one JSON event per line on stdout.
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use std::process::{Command, Stdio};
+
+use super::{
+    run_grader, wait_with_timeout, Agent, ExecutionConfig, RawExecutionResult, ToolNameMapping,
+};
+use crate::parser::ToolCall;
+
+// =========================================================================
+// Gemini stream-json data structures
+// =========================================================================
+
+/// A single event from `gemini --output-format stream-json`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum GeminiEvent {
+    ToolUse {
+        tool_name: String,
+        #[serde(default)]
+        parameters: Value,
+        #[serde(default)]
+        timestamp: String,
+    },
+    Message {
+        role: String,
+        #[serde(default)]
+        content: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Tool calls and assistant text extracted from a Gemini event stream.
+#[derive(Debug, Default)]
+pub(crate) struct GeminiStream {
+    pub tool_calls: Vec<ToolCall>,
+    pub text: String,
+}
+
+/// Raw event stream captured in `execute()` for `parse_session()`.
+struct GeminiSessionContext {
+    stream: String,
+}
+
+/// Parse Gemini's stream-json output.
+///
+/// Lines that are not JSON events (e.g. warnings printed by the CLI) are skipped.
+pub(crate) fn parse_gemini_stream(output: &str) -> GeminiStream {
+    let mut stream = GeminiStream::default();
+
+    for line in output.lines() {
+        let Ok(event) = serde_json::from_str::<GeminiEvent>(line.trim()) else {
+            continue;
+        };
+
+        match event {
+            GeminiEvent::ToolUse {
+                tool_name,
+                parameters,
+                timestamp,
+            } => stream.tool_calls.push(ToolCall {
+                name: tool_name,
+                params: parameters,
+                timestamp,
+                result: None,
+            }),
+            GeminiEvent::Message { role, content } if role == "assistant" => {
+                stream.text.push_str(&content);
+            }
+            _ => {}
+        }
+    }
+
+    stream
+}
+
+// =========================================================================
+// Gemini adapter
+// =========================================================================
+
+/// Gemini CLI agent adapter.
+///
+/// Only tool names are mapped; params keep Gemini's own keys. In particular
+/// `read_file` reports its path as `absolute_path`, not `file_path`, so
+/// assert on `absolute_path` for this agent's `Read` calls.
+pub struct GeminiAdapter {
+    mapping: ToolNameMapping,
+}
+
+impl GeminiAdapter {
+    pub fn new() -> Self {
+        // Gemini tool name mappings to canonical names
+        let mut mapping = ToolNameMapping::new();
+        mapping.add("read_file", "Read");
+        mapping.add("write_file", "Write");
+        mapping.add("replace", "Edit");
+        mapping.add("run_shell_command", "Bash");
+        mapping.add("glob", "Glob");
+        mapping.add("search_file_content", "Grep");
+        mapping.add("list_directory", "LS");
+        mapping.add("web_fetch", "WebFetch");
+        mapping.add("google_web_search", "WebSearch");
+        mapping.add("write_todos", "TodoWrite");
+        Self { mapping }
+    }
+}
+
+impl Default for GeminiAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Agent for GeminiAdapter {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn execute(&self, prompt: &str, config: &ExecutionConfig) -> Result<RawExecutionResult> {
+        config.reject_system_prompt("gemini")?;
+
+        let mut cmd = Command::new("gemini");
+        cmd.arg("--prompt")
+            .arg(prompt)
+            .arg("--output-format")
+            .arg("stream-json")
+            .stdin(Stdio::null());
+
+        config.apply_to(&mut cmd);
+
+        let child = cmd
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to execute gemini command")?;
+        let output = wait_with_timeout(child, config.timeout, "gemini")?;
+        let raw = String::from_utf8_lossy(&output.stdout).to_string();
+
+        // Stdout is the event stream; surface only the assistant's text
+        let text = parse_gemini_stream(&raw).text;
+        let stdout = if text.is_empty() { None } else { Some(text) };
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let stderr = if stderr.is_empty() { None } else { Some(stderr) };
+
+        Ok(RawExecutionResult {
+            session_log_path: None, // Gemini reports tool calls on stdout, not in a log file
+            stdout,
+            stderr,
+            exit_code: output.status.code(),
+            agent_context: Some(Box::new(GeminiSessionContext { stream: raw })),
+        })
+    }
+
+    fn parse_session(&self, result: &RawExecutionResult) -> Result<Vec<ToolCall>> {
+        let ctx = result
+            .agent_context
+            .as_ref()
+            .and_then(|c| c.downcast_ref::<GeminiSessionContext>())
+            .ok_or_else(|| anyhow!("No Gemini event stream in execution result"))?;
+
+        Ok(parse_gemini_stream(&ctx.stream).tool_calls)
+    }
+
+    fn tool_mapping(&self) -> &ToolNameMapping {
+        &self.mapping
+    }
+
+    fn is_available(&self) -> bool {
+        Command::new("gemini")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    }
+
+    fn grade(&self, prompt: &str, model: Option<&str>) -> Result<String> {
+        let mut cmd = Command::new("gemini");
+        cmd.arg("--prompt").arg(prompt).stdin(Stdio::null());
+
+        if let Some(m) = model {
+            cmd.arg("--model").arg(m);
+        }
+
+        run_grader(cmd, "gemini")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const STREAM: &str = r#"{"type":"init","timestamp":"2025-10-10T12:00:00.000Z","session_id":"abc","model":"gemini-2.5-pro"}
+{"type":"message","timestamp":"2025-10-10T12:00:00.100Z","role":"user","content":"Read the config"}
+{"type":"tool_use","timestamp":"2025-10-10T12:00:01.000Z","tool_name":"read_file","tool_id":"t1","parameters":{"absolute_path":"/app/config.json"}}
+{"type":"tool_result","timestamp":"2025-10-10T12:00:01.200Z","tool_id":"t1","status":"success","output":"{}"}
+{"type":"message","timestamp":"2025-10-10T12:00:02.000Z","role":"assistant","content":"The config ","delta":true}
+{"type":"tool_use","timestamp":"2025-10-10T12:00:03.000Z","tool_name":"run_shell_command","tool_id":"t2","parameters":{"command":"ls"}}
+{"type":"message","timestamp":"2025-10-10T12:00:04.000Z","role":"assistant","content":"is empty.","delta":true}
+{"type":"result","timestamp":"2025-10-10T12:00:05.000Z","status":"success","stats":{}}
+"#;
+
+    #[test]
+    fn test_parse_gemini_stream_tool_calls() {
+        let stream = parse_gemini_stream(STREAM);
+
+        assert_eq!(stream.tool_calls.len(), 2);
+        assert_eq!(stream.tool_calls[0].name, "read_file");
+        assert_eq!(
+            stream.tool_calls[0].params,
+            json!({"absolute_path": "/app/config.json"})
+        );
+        assert_eq!(stream.tool_calls[0].timestamp, "2025-10-10T12:00:01.000Z");
+        assert_eq!(stream.tool_calls[1].name, "run_shell_command");
+    }
+
+    #[test]
+    fn test_parse_gemini_stream_assistant_text() {
+        let stream = parse_gemini_stream(STREAM);
+        assert_eq!(stream.text, "The config is empty.");
+    }
+
+    #[test]
+    fn test_parse_gemini_stream_skips_non_json_lines() {
+        let output = "Loaded cached credentials.\n\
+            {\"type\":\"tool_use\",\"tool_name\":\"glob\",\"parameters\":{\"pattern\":\"*.rs\"}}\n\
+            not json either\n";
+        let stream = parse_gemini_stream(output);
+
+        assert_eq!(stream.tool_calls.len(), 1);
+        assert_eq!(stream.tool_calls[0].name, "glob");
+        assert_eq!(stream.tool_calls[0].timestamp, "");
+    }
+
+    #[test]
+    fn test_parse_gemini_stream_empty() {
+        let stream = parse_gemini_stream("");
+        assert!(stream.tool_calls.is_empty());
+        assert!(stream.text.is_empty());
+    }
+
+    #[test]
+    fn test_parse_session_uses_agent_context() {
+        let adapter = GeminiAdapter::new();
+        let result = RawExecutionResult {
+            session_log_path: None,
+            stdout: None,
+            stderr: None,
+            exit_code: Some(0),
+            agent_context: Some(Box::new(GeminiSessionContext {
+                stream: STREAM.to_string(),
+            })),
+        };
+
+        let calls = adapter.parse_session(&result).unwrap();
+        assert_eq!(calls.len(), 2);
+
+        let missing = RawExecutionResult {
+            session_log_path: None,
+            stdout: None,
+            stderr: None,
+            exit_code: None,
+            agent_context: None,
+        };
+        assert!(adapter.parse_session(&missing).is_err());
+    }
+
+    #[test]
+    fn test_gemini_adapter_tool_mapping() {
+        let adapter = GeminiAdapter::new();
+        let mapping = adapter.tool_mapping();
+
+        assert_eq!(mapping.to_canonical("read_file"), "Read");
+        assert_eq!(mapping.to_canonical("write_file"), "Write");
+        assert_eq!(mapping.to_canonical("replace"), "Edit");
+        assert_eq!(mapping.to_canonical("run_shell_command"), "Bash");
+        assert_eq!(mapping.to_canonical("search_file_content"), "Grep");
+        assert_eq!(mapping.to_canonical("google_web_search"), "WebSearch");
+        assert_eq!(mapping.to_canonical("list_directory"), "LS");
+        // Unmapped tools pass through unchanged
+        assert_eq!(mapping.to_canonical("save_memory"), "save_memory");
+    }
+
+    #[test]
+    fn test_gemini_adapter_name() {
+        assert_eq!(GeminiAdapter::default().name(), "gemini");
+    }
+
+    #[test]
+    fn test_system_prompt_unsupported() {
+        let config = ExecutionConfig::new().with_system_prompt("Be terse");
+        let err = GeminiAdapter::new().execute("Read the config", &config).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<crate::HarnessError>(),
+            Some(crate::HarnessError::Unsupported { .. })
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Agent 'gemini' does not support appending to the system prompt"
+        );
+    }
+}
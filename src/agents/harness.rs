@@ -3,16 +3,23 @@
 //! This module provides the main entry point for executing agents
 //! and normalizing their results.
 
-use anyhow::{bail, Result};
+use anyhow::Result;
 use std::collections::HashMap;
+use serde_json::Value;
 use std::sync::Arc;
 
-use crate::parser::ToolCall;
+use crate::error::HarnessError;
+use crate::output::make_relative;
+use crate::parser::{ToolCall, UsageStats};
 use crate::streaming::{self, StreamHandle};
 use super::claude::ClaudeAdapter;
+#[cfg(feature = "cursor")]
+use super::cursor::CursorAdapter;
+#[cfg(feature = "gemini")]
+use super::gemini::GeminiAdapter;
 #[cfg(feature = "kiro")]
 use super::kiro::KiroAdapter;
-use super::{Agent, ExecutionConfig, ToolNameMapping};
+use super::{Agent, ExecutionConfig, RawExecutionResult, ToolNameMapping};
 
 /// Supported agent types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -21,15 +28,24 @@ pub enum AgentType {
     Claude,
     #[cfg(feature = "kiro")]
     Kiro,
+    #[cfg(feature = "gemini")]
+    Gemini,
+    #[cfg(feature = "cursor")]
+    Cursor,
 }
 
 impl AgentType {
     /// Parse an agent type from a string.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "claude" | "claude-code" => Some(AgentType::Claude),
             #[cfg(feature = "kiro")]
             "kiro" => Some(AgentType::Kiro),
+            #[cfg(feature = "gemini")]
+            "gemini" | "gemini-cli" => Some(AgentType::Gemini),
+            #[cfg(feature = "cursor")]
+            "cursor" | "cursor-agent" => Some(AgentType::Cursor),
             _ => None,
         }
     }
@@ -40,6 +56,10 @@ impl AgentType {
             AgentType::Claude => "claude",
             #[cfg(feature = "kiro")]
             AgentType::Kiro => "kiro",
+            #[cfg(feature = "gemini")]
+            AgentType::Gemini => "gemini",
+            #[cfg(feature = "cursor")]
+            AgentType::Cursor => "cursor",
         }
     }
 }
@@ -69,6 +89,14 @@ pub struct ExecutionOutput {
     pub session_log_path: Option<std::path::PathBuf>,
     /// Stdout captured from the agent command.
     pub stdout: Option<String>,
+    /// Stderr captured from the agent command, if non-empty.
+    pub stderr: Option<String>,
+    /// Exit code of the agent command, if it exited normally.
+    pub exit_code: Option<i32>,
+    /// Token and cost usage, for agents whose logs record it.
+    pub usage: Option<UsageStats>,
+    /// The working directory the agent ran in, if one was configured.
+    pub working_dir: Option<std::path::PathBuf>,
 }
 
 /// The main facade for agent operations.
@@ -77,6 +105,8 @@ pub struct ExecutionOutput {
 /// coding agents and normalizing their results.
 pub struct AgentHarness {
     agents: HashMap<AgentType, Arc<dyn Agent>>,
+    /// Agents registered by name, for implementations `AgentType` can't represent.
+    named_agents: HashMap<String, Arc<dyn Agent>>,
     default_agent: AgentType,
 }
 
@@ -89,13 +119,40 @@ impl AgentHarness {
         agents.insert(AgentType::Claude, Arc::new(ClaudeAdapter::new()));
         #[cfg(feature = "kiro")]
         agents.insert(AgentType::Kiro, Arc::new(KiroAdapter::new()));
+        #[cfg(feature = "gemini")]
+        agents.insert(AgentType::Gemini, Arc::new(GeminiAdapter::new()));
+        #[cfg(feature = "cursor")]
+        agents.insert(AgentType::Cursor, Arc::new(CursorAdapter::new()));
 
         Self {
             agents,
+            named_agents: HashMap::new(),
             default_agent: AgentType::Claude,
         }
     }
 
+    /// Register an agent, replacing any agent already registered for this type.
+    pub fn register(&mut self, agent_type: AgentType, agent: Arc<dyn Agent>) {
+        self.agents.insert(agent_type, agent);
+    }
+
+    /// Replace every registered agent with `wrap(agent)`, e.g. to record or
+    /// replay their runs (see [`RecordingAgent`](super::RecordingAgent)).
+    pub fn wrap_agents(&mut self, wrap: impl Fn(Arc<dyn Agent>) -> Arc<dyn Agent>) {
+        for agent in self.agents.values_mut().chain(self.named_agents.values_mut()) {
+            *agent = wrap(Arc::clone(agent));
+        }
+    }
+
+    /// Register an agent under a custom name, replacing any agent already
+    /// registered with that name.
+    ///
+    /// Use this for agents `AgentType` can't represent, then run them with
+    /// [`execute_named()`](Self::execute_named).
+    pub fn register_named(&mut self, name: String, agent: Arc<dyn Agent>) {
+        self.named_agents.insert(name, agent);
+    }
+
     /// Execute an agent and return full execution output.
     ///
     /// Tool calls are automatically converted to canonical names.
@@ -106,37 +163,121 @@ impl AgentHarness {
         agent_type: Option<AgentType>,
         prompt: &str,
         config: ExecutionConfig,
-    ) -> Result<ExecutionOutput> {
-        let agent_type = agent_type.unwrap_or(self.default_agent);
+    ) -> Result<ExecutionOutput, HarnessError> {
+        let agent = self.registered(agent_type)?;
+        self.execute_agent(agent.as_ref(), prompt, config)
+    }
 
+    /// Execute an agent looked up by name and return full execution output.
+    ///
+    /// Resolves the name with [`get_agent_named()`](Self::get_agent_named).
+    pub fn execute_named(
+        &self,
+        name: &str,
+        prompt: &str,
+        config: ExecutionConfig,
+    ) -> Result<ExecutionOutput, HarnessError> {
         let agent = self
-            .agents
-            .get(&agent_type)
-            .ok_or_else(|| anyhow::anyhow!("Agent not registered: {:?}", agent_type))?;
+            .get_agent_named(name)
+            .ok_or_else(|| HarnessError::AgentNotRegistered(name.to_string()))?;
 
-        if !agent.is_available() {
-            bail!(
-                "Agent '{}' is not available on this system",
-                agent.name()
-            );
-        }
+        self.execute_agent(agent.as_ref(), prompt, config)
+    }
+
+    fn execute_agent(
+        &self,
+        agent: &dyn Agent,
+        prompt: &str,
+        config: ExecutionConfig,
+    ) -> Result<ExecutionOutput, HarnessError> {
+        ensure_available(agent)?;
 
         // Execute the agent
-        let raw_result = agent.execute(prompt, &config)?;
+        let raw_result = agent
+            .execute(prompt, &config)
+            .map_err(|e| execution_failed(agent, e))?;
+        self.output_for(agent, &raw_result, &config)
+    }
 
-        // Parse tool calls
-        let raw_tool_calls = agent.parse_session(&raw_result)?;
+    /// Execute a prompt followed by follow-up turns in the same session.
+    ///
+    /// Each follow-up resumes the session left by the turn before it, so
+    /// only agents that [support resuming](Agent::supports_resume) can run
+    /// more than one turn — currently just Claude. Tool calls from every
+    /// turn are returned in order; stdout, exit code, and session log path
+    /// come from the last turn (the exit code from the first failing turn).
+    pub fn execute_conversation(
+        &self,
+        agent_type: Option<AgentType>,
+        turns: &[String],
+        config: ExecutionConfig,
+    ) -> Result<ExecutionOutput, HarnessError> {
+        let agent = self.registered(agent_type)?;
+
+        let Some((first, follow_ups)) = turns.split_first() else {
+            return Err(HarnessError::InvalidRequest(
+                "A conversation needs at least one prompt".to_string(),
+            ));
+        };
+        if !follow_ups.is_empty() && !agent.supports_resume() {
+            return Err(HarnessError::Unsupported {
+                agent: agent.name().to_string(),
+                feature: "follow-up turns (only claude does)".to_string(),
+            });
+        }
+        ensure_available(agent.as_ref())?;
 
-        // Normalize tool names to canonical form
-        let normalized_calls = self.normalize_tool_calls(&raw_tool_calls, agent.tool_mapping());
+        let mut raw_result = agent
+            .execute(first, &config)
+            .map_err(|e| execution_failed(agent.as_ref(), e))?;
+        let mut output = self.output_for(agent.as_ref(), &raw_result, &config)?;
+
+        for prompt in follow_ups {
+            let next = agent
+                .resume(&raw_result, prompt, &config)
+                .map_err(|e| execution_failed(agent.as_ref(), e))?;
+            let turn = self.output_for(agent.as_ref(), &next, &config)?;
+            let same_log =
+                turn.session_log_path.is_some() && turn.session_log_path == output.session_log_path;
+            output = merge_turn(output, turn, same_log);
+            raw_result = next;
+        }
+
+        Ok(output)
+    }
+
+    /// Parse and normalize a raw execution result.
+    fn output_for(
+        &self,
+        agent: &dyn Agent,
+        raw_result: &RawExecutionResult,
+        config: &ExecutionConfig,
+    ) -> Result<ExecutionOutput, HarnessError> {
+        // Parse tool calls and usage
+        let (raw_tool_calls, usage) = agent.parse_session_and_usage(raw_result).map_err(|e| {
+            HarnessError::from_agent(e, |cause| HarnessError::ParseError {
+                agent: agent.name().to_string(),
+                cause,
+            })
+        })?;
+
+        // Normalize tool names to canonical form, with any configured extras
+        let mut mapping = agent.tool_mapping().clone();
+        mapping.extend(&config.tool_mappings);
+        let mut normalized_calls = self.normalize_tool_calls(&raw_tool_calls, &mapping);
+        self.normalize_paths(&mut normalized_calls, config);
 
         Ok(ExecutionOutput {
             result: NormalizedResult {
                 tool_calls: normalized_calls,
                 agent_name: agent.name().to_string(),
             },
-            session_log_path: raw_result.session_log_path,
-            stdout: raw_result.stdout,
+            session_log_path: raw_result.session_log_path.clone(),
+            stdout: raw_result.stdout.clone(),
+            stderr: raw_result.stderr.clone(),
+            exit_code: raw_result.exit_code,
+            usage,
+            working_dir: config.working_dir.clone(),
         })
     }
 
@@ -152,10 +293,47 @@ impl AgentHarness {
                 name: mapping.to_canonical(&call.name),
                 params: call.params.clone(),
                 timestamp: call.timestamp.clone(),
+                result: call.result.clone(),
             })
             .collect()
     }
 
+    /// Rewrite tool call paths relative to the working directory, if
+    /// [`ExecutionConfig::normalize_paths`] is set.
+    ///
+    /// `file_path` and `path` parameters inside the working directory become
+    /// relative (`src/main.rs`), and so do paths anywhere in a `command`
+    /// (see [`relativize_command`]). Paths outside it are unchanged.
+    /// [`execute()`](Self::execute) applies this already; call it on tool
+    /// calls collected from [`execute_streaming()`](Self::execute_streaming).
+    pub fn normalize_paths(&self, calls: &mut [ToolCall], config: &ExecutionConfig) {
+        if !config.normalize_paths {
+            return;
+        }
+        // Agents report canonical paths (e.g. /private/tmp on macOS)
+        let workdir = match &config.working_dir {
+            Some(dir) => dir.canonicalize(),
+            None => std::env::current_dir().and_then(|dir| dir.canonicalize()),
+        };
+        let Ok(workdir) = workdir else {
+            return;
+        };
+
+        for call in calls {
+            let Some(params) = call.params.as_object_mut() else {
+                continue;
+            };
+            for key in ["file_path", "path"] {
+                if let Some(Value::String(path)) = params.get_mut(key) {
+                    *path = make_relative(path, &workdir);
+                }
+            }
+            if let Some(Value::String(command)) = params.get_mut("command") {
+                *command = relativize_command(command, &workdir.to_string_lossy());
+            }
+        }
+    }
+
     /// Execute an agent in streaming mode, returning a handle for live events.
     ///
     /// Tool calls are emitted as raw agent names through the `StreamHandle`'s
@@ -167,22 +345,18 @@ impl AgentHarness {
         agent_type: Option<AgentType>,
         prompt: &str,
         config: ExecutionConfig,
-    ) -> Result<StreamHandle> {
-        let agent_type = agent_type.unwrap_or(self.default_agent);
+    ) -> Result<StreamHandle, HarnessError> {
+        let agent = self.registered(agent_type)?;
+        ensure_available(agent.as_ref())?;
 
-        let agent = self
-            .agents
-            .get(&agent_type)
-            .ok_or_else(|| anyhow::anyhow!("Agent not registered: {:?}", agent_type))?;
-
-        if !agent.is_available() {
-            bail!(
-                "Agent '{}' is not available on this system",
-                agent.name()
-            );
-        }
+        streaming::execute_streaming(prompt, &config).map_err(|e| execution_failed(agent.as_ref(), e))
+    }
 
-        streaming::execute_streaming(prompt, &config)
+    /// Whether an agent of this type is registered and its CLI is installed.
+    pub fn is_agent_available(&self, agent_type: AgentType) -> bool {
+        self.agents
+            .get(&agent_type)
+            .is_some_and(|agent| agent.is_available())
     }
 
     /// Get an agent by type.
@@ -190,6 +364,23 @@ impl AgentHarness {
         self.agents.get(&agent_type)
     }
 
+    /// Get an agent by name.
+    ///
+    /// Checks agents registered with [`register_named()`](Self::register_named)
+    /// first, then built-in agent type names (e.g. "claude"), then the
+    /// [`Agent::name()`] of every registered agent.
+    pub fn get_agent_named(&self, name: &str) -> Option<&Arc<dyn Agent>> {
+        self.named_agents
+            .get(name)
+            .or_else(|| AgentType::from_str(name).and_then(|t| self.agents.get(&t)))
+            .or_else(|| {
+                self.agents
+                    .values()
+                    .chain(self.named_agents.values())
+                    .find(|a| a.name() == name)
+            })
+    }
+
     /// Grade content using the specified agent.
     ///
     /// Resolves the agent type and delegates to [`Agent::grade()`].
@@ -201,18 +392,8 @@ impl AgentHarness {
         prompt: &str,
         model: Option<&str>,
     ) -> Result<String> {
-        let agent_type = agent_type.unwrap_or(self.default_agent);
-        let agent = self
-            .agents
-            .get(&agent_type)
-            .ok_or_else(|| anyhow::anyhow!("Agent not registered: {:?}", agent_type))?;
-
-        if !agent.is_available() {
-            bail!(
-                "Agent '{}' is not available on this system",
-                agent.name()
-            );
-        }
+        let agent = self.registered(agent_type)?;
+        ensure_available(agent.as_ref())?;
 
         agent.grade(prompt, model)
     }
@@ -226,38 +407,170 @@ impl AgentHarness {
         prompt: &str,
         model: Option<&str>,
     ) -> Result<String> {
+        let agent = self.registered(agent_type)?;
+        ensure_available(agent.as_ref())?;
+
+        agent.grade_async(prompt, model).await
+    }
+
+    /// List all registered agents by the name they were registered under:
+    /// the agent type's name (e.g. "claude") for [`register()`](Self::register),
+    /// and the given name for [`register_named()`](Self::register_named).
+    /// Sorted by name.
+    pub fn registered_agents(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.registrations().map(|(name, _)| name).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// List the registered agents whose CLI is installed, by registered
+    /// name (see [`registered_agents()`](Self::registered_agents)), sorted.
+    ///
+    /// Each agent's [`is_available()`](Agent::is_available) typically runs
+    /// its CLI with `--version`, so this spawns one process per registered
+    /// agent. Call it once and keep the result rather than in a loop.
+    pub fn list_available(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .registrations()
+            .filter(|(_, agent)| agent.is_available())
+            .map(|(name, _)| name)
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Every registered agent with the name it was registered under.
+    fn registrations(&self) -> impl Iterator<Item = (&str, &Arc<dyn Agent>)> {
+        self.agents
+            .iter()
+            .map(|(agent_type, agent)| (agent_type.as_str(), agent))
+            .chain(self.named_agents.iter().map(|(name, agent)| (name.as_str(), agent)))
+    }
+
+    /// Look up an agent by type, falling back to the default agent.
+    fn registered(&self, agent_type: Option<AgentType>) -> Result<&Arc<dyn Agent>, HarnessError> {
         let agent_type = agent_type.unwrap_or(self.default_agent);
-        let agent = self
-            .agents
+        self.agents
             .get(&agent_type)
-            .ok_or_else(|| anyhow::anyhow!("Agent not registered: {:?}", agent_type))?;
+            .ok_or_else(|| HarnessError::AgentNotRegistered(agent_type.as_str().to_string()))
+    }
+}
 
-        if !agent.is_available() {
-            bail!(
-                "Agent '{}' is not available on this system",
-                agent.name()
-            );
-        }
+/// Make paths inside `workdir` relative wherever they appear in a shell
+/// command: `cat /proj/a.txt` becomes `cat a.txt` and `cd /proj` becomes
+/// `cd .`.
+///
+/// Only whole paths are rewritten, so `/var/proj/a.txt` and `/proj-old`
+/// are left alone for a `workdir` of `/proj`.
+fn relativize_command(command: &str, workdir: &str) -> String {
+    let workdir = workdir.trim_end_matches('/');
+    if workdir.is_empty() {
+        return command.to_string();
+    }
+    let is_path_char = |c: char| c.is_alphanumeric() || "/._-~".contains(c);
 
-        agent.grade_async(prompt, model).await
+    let mut result = String::with_capacity(command.len());
+    let mut copied = 0;
+    for (start, _) in command.match_indices(workdir) {
+        if start < copied || command[..start].ends_with(is_path_char) {
+            continue;
+        }
+        let rest = &command[start + workdir.len()..];
+        let mut chars = rest.chars();
+        let (replacement, len) = match (chars.next(), chars.next()) {
+            (Some('/'), Some(c)) if is_path_char(c) => ("", workdir.len() + 1),
+            (Some('/'), _) => (".", workdir.len() + 1),
+            (Some(c), _) if is_path_char(c) => continue,
+            _ => (".", workdir.len()),
+        };
+        result.push_str(&command[copied..start]);
+        result.push_str(replacement);
+        copied = start + len;
     }
+    result.push_str(&command[copied..]);
+    result
+}
 
-    /// List all registered agents.
-    pub fn registered_agents(&self) -> Vec<&'static str> {
-        self.agents.values().map(|a| a.name()).collect()
+/// Fail with [`HarnessError::AgentUnavailable`] if the agent's CLI is missing.
+fn ensure_available(agent: &dyn Agent) -> Result<(), HarnessError> {
+    if agent.is_available() {
+        Ok(())
+    } else {
+        Err(HarnessError::AgentUnavailable(agent.name().to_string()))
     }
 }
 
+/// Classify an error from running `agent`, keeping any typed error it returned.
+fn execution_failed(agent: &dyn Agent, err: anyhow::Error) -> HarnessError {
+    HarnessError::from_agent(err, |cause| HarnessError::ExecutionFailed {
+        agent: agent.name().to_string(),
+        cause,
+    })
+}
+
 impl Default for AgentHarness {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Fold a follow-up turn into the conversation so far.
+///
+/// A resumed session's log may hold the whole conversation (the same file
+/// appended to, or a new file starting with a copy of the history) or only
+/// the new turn. If the turn's log repeats the earlier tool calls, its calls
+/// and usage replace the running totals; otherwise they are added to them.
+fn merge_turn(so_far: ExecutionOutput, turn: ExecutionOutput, same_log: bool) -> ExecutionOutput {
+    let earlier = &so_far.result.tool_calls;
+    let calls = &turn.result.tool_calls;
+    let repeats_history = same_log
+        || (!earlier.is_empty()
+            && calls.len() >= earlier.len()
+            && earlier.iter().zip(calls).all(|(a, b)| {
+                a.name == b.name && a.params == b.params && a.timestamp == b.timestamp
+            }));
+
+    let (tool_calls, usage) = if repeats_history {
+        (turn.result.tool_calls, turn.usage)
+    } else {
+        let mut tool_calls = so_far.result.tool_calls;
+        tool_calls.extend(turn.result.tool_calls);
+        let usage = match (so_far.usage, turn.usage) {
+            (Some(a), Some(b)) => Some(UsageStats {
+                input_tokens: a.input_tokens + b.input_tokens,
+                output_tokens: a.output_tokens + b.output_tokens,
+                total_cost_usd: a.total_cost_usd + b.total_cost_usd,
+            }),
+            (a, b) => a.or(b),
+        };
+        (tool_calls, usage)
+    };
+
+    // Keep the first failure so a later successful turn doesn't hide it
+    let exit_code = match so_far.exit_code {
+        Some(0) => turn.exit_code,
+        failed => failed,
+    };
+
+    ExecutionOutput {
+        result: NormalizedResult {
+            tool_calls,
+            agent_name: turn.result.agent_name,
+        },
+        session_log_path: turn.session_log_path,
+        stdout: turn.stdout,
+        stderr: turn.stderr,
+        exit_code,
+        usage,
+        working_dir: turn.working_dir,
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::MockAgent;
+    use serde_json::json;
 
     #[test]
     fn test_agent_type_from_str_unknown() {
@@ -265,6 +578,123 @@ mod tests {
         assert_eq!(AgentType::from_str(""), None);
     }
 
+    #[test]
+    fn test_register_replaces_builtin_agent() {
+        let mut mapping = ToolNameMapping::new();
+        mapping.add("read_file", "Read");
+        let mock = MockAgent::new()
+            .with_tool_call("read_file", json!({"file_path": "/a.txt"}))
+            .with_stdout("done")
+            .with_mapping(mapping);
+
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(mock));
+
+        let output = harness
+            .execute(None, "Read a file", ExecutionConfig::new())
+            .unwrap();
+        assert_eq!(output.result.agent_name, "mock");
+        assert_eq!(output.result.tool_calls[0].name, "Read");
+        assert_eq!(output.stdout.as_deref(), Some("done"));
+    }
+
+    #[test]
+    fn test_execute_propagates_exit_code() {
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(MockAgent::new().with_exit_code(3)));
+
+        let output = harness
+            .execute(None, "Fail loudly", ExecutionConfig::new())
+            .unwrap();
+        assert_eq!(output.exit_code, Some(3));
+    }
+
+    #[test]
+    fn test_execute_propagates_usage() {
+        let usage = UsageStats {
+            input_tokens: 1200,
+            output_tokens: 300,
+            total_cost_usd: 0.02,
+        };
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(MockAgent::new().with_usage(usage)));
+
+        let output = harness
+            .execute(None, "Count tokens", ExecutionConfig::new())
+            .unwrap();
+        assert_eq!(output.usage, Some(usage));
+    }
+
+    #[test]
+    fn test_register_named_execute_end_to_end() {
+        let mock = MockAgent::new()
+            .with_tool_call("Bash", json!({"command": "ls"}))
+            .with_tool_call("Write", json!({"file_path": "/out.txt"}));
+
+        let mut harness = AgentHarness::new();
+        harness.register_named("my-agent".to_string(), Arc::new(mock));
+
+        let output = harness
+            .execute_named("my-agent", "List and write", ExecutionConfig::new())
+            .unwrap();
+        let names: Vec<&str> = output.result.tool_calls.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Bash", "Write"]);
+
+        // Resolvable by registered name and by Agent::name()
+        assert!(harness.get_agent_named("my-agent").is_some());
+        assert!(harness.get_agent_named("mock").is_some());
+        // Listed under the name it was registered with, not Agent::name()
+        assert!(harness.registered_agents().contains(&"my-agent"));
+        assert!(!harness.registered_agents().contains(&"mock"));
+        // Built-in agents are still reachable by name
+        assert_eq!(harness.get_agent_named("claude").unwrap().name(), "claude");
+
+        let err = harness
+            .execute_named("missing", "prompt", ExecutionConfig::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("Agent not registered: 'missing'"));
+    }
+
+    #[test]
+    fn test_execute_named_unavailable_agent() {
+        let mut harness = AgentHarness::new();
+        harness.register_named("offline".to_string(), Arc::new(MockAgent::new().unavailable()));
+
+        let err = harness
+            .execute_named("offline", "prompt", ExecutionConfig::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("not available"));
+    }
+
+    #[test]
+    fn test_is_agent_available() {
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(MockAgent::new()));
+        assert!(harness.is_agent_available(AgentType::Claude));
+
+        harness.register(AgentType::Claude, Arc::new(MockAgent::new().unavailable()));
+        assert!(!harness.is_agent_available(AgentType::Claude));
+    }
+
+    #[test]
+    fn test_list_available_filters_uninstalled_agents() {
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(MockAgent::new().with_name("claude").unavailable()));
+        harness.register_named("zeta".to_string(), Arc::new(MockAgent::new().with_name("zeta")));
+        harness.register_named("alpha".to_string(), Arc::new(MockAgent::new().with_name("alpha")));
+        harness.register_named("offline".to_string(), Arc::new(MockAgent::new().with_name("offline").unavailable()));
+
+        let available = harness.list_available();
+        // Built-in kiro/gemini depend on this machine, so only check the mocks
+        let mocks: Vec<&str> = available
+            .iter()
+            .copied()
+            .filter(|name| ["claude", "zeta", "alpha", "offline"].contains(name))
+            .collect();
+        assert_eq!(mocks, vec!["alpha", "zeta"]);
+        assert!(harness.registered_agents().contains(&"offline"));
+    }
+
     #[test]
     fn test_harness_registers_claude() {
         let harness = AgentHarness::new();
@@ -273,6 +703,267 @@ mod tests {
         assert!(harness.registered_agents().contains(&"claude"));
     }
 
+    fn turn(calls: &[(&str, &str)], path: Option<&str>) -> ExecutionOutput {
+        ExecutionOutput {
+            result: NormalizedResult {
+                tool_calls: calls
+                    .iter()
+                    .map(|(name, ts)| ToolCall {
+                        name: name.to_string(),
+                        params: json!({}),
+                        timestamp: ts.to_string(),
+                        result: None,
+                    })
+                    .collect(),
+                agent_name: "mock".to_string(),
+            },
+            session_log_path: path.map(std::path::PathBuf::from),
+            stdout: Some(format!("{} calls", calls.len())),
+            stderr: None,
+            exit_code: Some(0),
+            usage: Some(UsageStats {
+                input_tokens: 10,
+                output_tokens: 1,
+                total_cost_usd: 0.0,
+            }),
+            working_dir: None,
+        }
+    }
+
+    fn names(output: &ExecutionOutput) -> Vec<&str> {
+        output.result.tool_calls.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_merge_turn_appends_separate_logs() {
+        let first = turn(&[("Read", "t1")], Some("/s/a.jsonl"));
+        let second = turn(&[("Edit", "t2"), ("Bash", "t3")], Some("/s/b.jsonl"));
+
+        let merged = merge_turn(first, second, false);
+
+        assert_eq!(names(&merged), vec!["Read", "Edit", "Bash"]);
+        assert_eq!(merged.usage.unwrap().input_tokens, 20);
+        assert_eq!(merged.stdout.as_deref(), Some("2 calls"));
+        assert_eq!(merged.session_log_path.unwrap().to_str(), Some("/s/b.jsonl"));
+    }
+
+    #[test]
+    fn test_merge_turn_replaces_repeated_history() {
+        // A new log that starts with a copy of the earlier turn
+        let first = turn(&[("Read", "t1")], Some("/s/a.jsonl"));
+        let second = turn(&[("Read", "t1"), ("Edit", "t2")], Some("/s/b.jsonl"));
+        let merged = merge_turn(first, second, false);
+        assert_eq!(names(&merged), vec!["Read", "Edit"]);
+        assert_eq!(merged.usage.unwrap().input_tokens, 10);
+
+        // The same log appended to, even when the earlier turn made no calls
+        let first = turn(&[], Some("/s/a.jsonl"));
+        let second = turn(&[("Edit", "t2")], Some("/s/a.jsonl"));
+        let merged = merge_turn(first, second, true);
+        assert_eq!(names(&merged), vec!["Edit"]);
+        assert_eq!(merged.usage.unwrap().input_tokens, 10);
+    }
+
+    #[test]
+    fn test_merge_turn_keeps_first_failure() {
+        let mut first = turn(&[("Read", "t1")], None);
+        first.exit_code = Some(2);
+        let merged = merge_turn(first, turn(&[("Edit", "t2")], None), false);
+        assert_eq!(merged.exit_code, Some(2));
+    }
+
+    #[test]
+    fn test_execute_conversation_runs_each_turn() {
+        let mock = Arc::new(
+            MockAgent::new()
+                .with_tool_call("Read", json!({"file_path": "/a.txt"}))
+                .with_stdout("done"),
+        );
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, mock.clone());
+
+        let turns = vec!["Read a.txt".to_string(), "Now summarize it".to_string()];
+        let output = harness
+            .execute_conversation(None, &turns, ExecutionConfig::new())
+            .unwrap();
+
+        assert_eq!(mock.runs(), 2);
+        assert_eq!(names(&output), vec!["Read"]);
+        assert_eq!(output.stdout.as_deref(), Some("done"));
+
+        let err = harness
+            .execute_conversation(None, &[], ExecutionConfig::new())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "A conversation needs at least one prompt");
+    }
+
+    fn path_harness(workdir: &std::path::Path) -> AgentHarness {
+        let root = workdir.canonicalize().unwrap();
+        let root = root.to_str().unwrap();
+        let mock = MockAgent::new()
+            .with_tool_call("Read", json!({"file_path": format!("{}/src/main.rs", root)}))
+            .with_tool_call("Glob", json!({"pattern": "*.rs", "path": root}))
+            .with_tool_call("Bash", json!({"command": format!("cat {}/a.txt {}/b.txt", root, root)}))
+            .with_tool_call("Read", json!({"file_path": "/etc/hosts"}));
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(mock));
+        harness
+    }
+
+    #[test]
+    fn test_execute_normalizes_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ExecutionConfig::new()
+            .with_working_dir(dir.path().to_path_buf())
+            .with_normalize_paths(true);
+
+        let output = path_harness(dir.path()).execute(None, "Read", config).unwrap();
+        let params: Vec<_> = output.result.tool_calls.iter().map(|c| &c.params).collect();
+
+        assert_eq!(params[0]["file_path"], "src/main.rs");
+        assert_eq!(params[1]["path"], ".");
+        assert_eq!(params[2]["command"], "cat a.txt b.txt");
+        assert_eq!(params[3]["file_path"], "/etc/hosts");
+    }
+
+    #[test]
+    fn test_relativize_command() {
+        let cases = [
+            ("cat /tmp/x/a.txt /tmp/x/b.txt", "cat a.txt b.txt"),
+            ("cd /tmp/x && ls", "cd . && ls"),
+            ("cd /tmp/x/", "cd ."),
+            ("git -C '/tmp/x/sub' status", "git -C 'sub' status"),
+            ("cp --target=/tmp/x/out a", "cp --target=out a"),
+            // Paths that only contain the working directory are left alone
+            ("cat /var/tmp/x/foo", "cat /var/tmp/x/foo"),
+            ("ls /tmp/xy /tmp/x-old/a", "ls /tmp/xy /tmp/x-old/a"),
+            ("echo /tmp/x.bak", "echo /tmp/x.bak"),
+        ];
+        for (command, expected) in cases {
+            assert_eq!(relativize_command(command, "/tmp/x"), expected, "{}", command);
+        }
+        assert_eq!(relativize_command("ls /tmp/x/a", "/tmp/x/"), "ls a");
+    }
+
+    #[test]
+    fn test_execute_keeps_raw_paths_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let config = ExecutionConfig::new().with_working_dir(dir.path().to_path_buf());
+
+        let output = path_harness(dir.path()).execute(None, "Read", config).unwrap();
+
+        assert_eq!(
+            output.result.tool_calls[0].params["file_path"],
+            format!("{}/src/main.rs", root.to_str().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_execute_applies_configured_tool_mappings() {
+        let mock = MockAgent::new()
+            .with_tool_call("my_custom_read", json!({"file_path": "/a.txt"}))
+            .with_tool_call("Bash", json!({"command": "ls"}));
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(mock));
+
+        let config = ExecutionConfig::new().with_tool_mapping("my_custom_read", "Read");
+        let output = harness.execute(None, "Read a.txt", config).unwrap();
+        assert_eq!(names(&output), vec!["Read", "Bash"]);
+
+        let output = harness.execute(None, "Read a.txt", ExecutionConfig::new()).unwrap();
+        assert_eq!(names(&output), vec!["my_custom_read", "Bash"]);
+    }
+
+    /// An agent that fails at a chosen step.
+    struct FailingAgent {
+        step: &'static str,
+        mapping: ToolNameMapping,
+    }
+
+    impl FailingAgent {
+        fn at(step: &'static str) -> Arc<dyn Agent> {
+            Arc::new(Self { step, mapping: ToolNameMapping::new() })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Agent for FailingAgent {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn execute(&self, _prompt: &str, _config: &ExecutionConfig) -> Result<RawExecutionResult> {
+            if self.step == "execute" {
+                anyhow::bail!("process exited with 1");
+            }
+            Ok(RawExecutionResult {
+                session_log_path: None,
+                stdout: None,
+                stderr: None,
+                exit_code: Some(0),
+                agent_context: None,
+            })
+        }
+
+        fn parse_session(&self, _result: &RawExecutionResult) -> Result<Vec<ToolCall>> {
+            match self.step {
+                "session" => Err(HarnessError::SessionNotFound {
+                    agent: "failing".to_string(),
+                    detail: "no session logs".to_string(),
+                }
+                .into()),
+                _ => anyhow::bail!("unexpected end of log"),
+            }
+        }
+
+        fn tool_mapping(&self) -> &ToolNameMapping {
+            &self.mapping
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+
+        fn grade(&self, _prompt: &str, _model: Option<&str>) -> Result<String> {
+            anyhow::bail!("not a grader")
+        }
+    }
+
+    #[test]
+    fn test_execute_error_variants() {
+        let mut harness = AgentHarness::new();
+        harness.register_named("offline".to_string(), Arc::new(MockAgent::new().unavailable()));
+        harness.register_named("crashes".to_string(), FailingAgent::at("execute"));
+        harness.register_named("no-log".to_string(), FailingAgent::at("session"));
+        harness.register_named("bad-log".to_string(), FailingAgent::at("parse"));
+        let run = |name: &str| {
+            harness
+                .execute_named(name, "prompt", ExecutionConfig::new())
+                .unwrap_err()
+        };
+
+        assert!(matches!(run("missing"), HarnessError::AgentNotRegistered(name) if name == "missing"));
+        assert!(matches!(run("offline"), HarnessError::AgentUnavailable(name) if name == "mock"));
+
+        let err = run("crashes");
+        assert!(matches!(err, HarnessError::ExecutionFailed { .. }));
+        assert_eq!(err.to_string(), "failing failed to run: process exited with 1");
+
+        let err = run("no-log");
+        assert!(matches!(err, HarnessError::SessionNotFound { .. }));
+        assert_eq!(err.to_string(), "Could not find failing session log: no session logs");
+
+        let err = run("bad-log");
+        assert!(matches!(err, HarnessError::ParseError { .. }));
+        assert_eq!(err.to_string(), "Failed to parse failing session: unexpected end of log");
+
+        let err = harness
+            .execute_conversation(None, &[], ExecutionConfig::new())
+            .unwrap_err();
+        assert!(matches!(err, HarnessError::InvalidRequest(_)));
+    }
+
     #[cfg(feature = "kiro")]
     mod kiro_tests {
         use super::*;
@@ -328,5 +1019,59 @@ mod tests {
             }
         }
     }
-}
 
+    #[cfg(feature = "gemini")]
+    mod gemini_tests {
+        use super::*;
+
+        #[test]
+        fn test_agent_type_from_str_gemini() {
+            assert_eq!(AgentType::from_str("gemini"), Some(AgentType::Gemini));
+            assert_eq!(AgentType::from_str("Gemini-CLI"), Some(AgentType::Gemini));
+            assert_eq!(AgentType::Gemini.as_str(), "gemini");
+        }
+
+        #[test]
+        fn test_harness_registers_gemini() {
+            let harness = AgentHarness::new();
+            let gemini = harness.get_agent(AgentType::Gemini).expect("gemini should be registered");
+            assert_eq!(gemini.name(), "gemini");
+            assert!(harness.registered_agents().contains(&"gemini"));
+        }
+
+        #[test]
+        fn test_follow_up_turns_unsupported() {
+            let harness = AgentHarness::new();
+            let turns = vec!["First".to_string(), "Second".to_string()];
+
+            let err = harness
+                .execute_conversation(Some(AgentType::Gemini), &turns, ExecutionConfig::new())
+                .unwrap_err();
+            assert!(matches!(err, HarnessError::Unsupported { ref agent, .. } if agent == "gemini"));
+            assert_eq!(
+                err.to_string(),
+                "Agent 'gemini' does not support follow-up turns (only claude does)"
+            );
+        }
+    }
+
+    #[cfg(feature = "cursor")]
+    mod cursor_tests {
+        use super::*;
+
+        #[test]
+        fn test_agent_type_from_str_cursor() {
+            assert_eq!(AgentType::from_str("cursor"), Some(AgentType::Cursor));
+            assert_eq!(AgentType::from_str("Cursor-Agent"), Some(AgentType::Cursor));
+            assert_eq!(AgentType::Cursor.as_str(), "cursor");
+        }
+
+        #[test]
+        fn test_harness_registers_cursor() {
+            let harness = AgentHarness::new();
+            let cursor = harness.get_agent(AgentType::Cursor).expect("cursor should be registered");
+            assert_eq!(cursor.name(), "cursor");
+            assert!(harness.registered_agents().contains(&"cursor"));
+        }
+    }
+}
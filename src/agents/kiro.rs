@@ -8,11 +8,13 @@ use async_trait::async_trait;
 use rusqlite::Connection;
 use serde::Deserialize;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
-use super::{Agent, ExecutionConfig, RawExecutionResult, ToolNameMapping};
+use super::{wait_with_timeout, Agent, ExecutionConfig, RawExecutionResult, ToolNameMapping};
+use crate::error::HarnessError;
 use crate::parser::ToolCall;
 
 // =========================================================================
@@ -22,6 +24,8 @@ use crate::parser::ToolCall;
 /// Tool use entry from Kiro's JSON format.
 #[derive(Debug, Deserialize)]
 struct KiroToolUse {
+    #[serde(default)]
+    id: Option<String>,
     name: String,
     args: Value,
 }
@@ -44,10 +48,41 @@ enum KiroAssistantResponse {
     Other(Value),
 }
 
-/// User message structure to extract timestamp.
+/// Result of a tool use, sent back to the model in the next user message.
+///
+/// `content` is a list of blocks, each either `{"Text": "..."}` or
+/// `{"Json": {...}}` (e.g. `execute_bash`'s `exit_status`, `stdout` and `stderr`).
+#[derive(Debug, Deserialize)]
+struct KiroToolUseResult {
+    tool_use_id: String,
+    #[serde(default)]
+    content: Vec<Value>,
+}
+
+/// Wrapper for the tool_use_results array in Kiro's ToolUseResults content.
+#[derive(Debug, Deserialize)]
+struct KiroToolUseResultsWrapper {
+    tool_use_results: Vec<KiroToolUseResult>,
+}
+
+/// User message content - can be ToolUseResults or other types (e.g. a prompt).
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum KiroUserContent {
+    ToolUseResults {
+        #[serde(rename = "ToolUseResults")]
+        tool_use_results: KiroToolUseResultsWrapper,
+    },
+    #[allow(dead_code)]
+    Other(Value),
+}
+
+/// User message structure to extract timestamp and tool results.
 #[derive(Debug, Deserialize)]
 struct KiroUserMessage {
     timestamp: Option<String>,
+    #[serde(default)]
+    content: Option<KiroUserContent>,
 }
 
 /// A history entry containing user and assistant messages.
@@ -80,10 +115,11 @@ fn get_kiro_db_path() -> Result<PathBuf> {
     if let Ok(path) = std::env::var("KIRO_DB_PATH") {
         let db_path = PathBuf::from(path);
         if !db_path.exists() {
-            return Err(anyhow!(
-                "Kiro database not found at {} (from KIRO_DB_PATH).",
-                db_path.display()
-            ));
+            return Err(HarnessError::SessionNotFound {
+                agent: "kiro".to_string(),
+                detail: format!("database not found at {} (from KIRO_DB_PATH)", db_path.display()),
+            }
+            .into());
         }
         return Ok(db_path);
     }
@@ -94,10 +130,14 @@ fn get_kiro_db_path() -> Result<PathBuf> {
     let db_path = data_dir.join("kiro-cli").join("data.sqlite3");
 
     if !db_path.exists() {
-        return Err(anyhow!(
-            "Kiro database not found at {}. Kiro may not be installed or has not been used yet.",
-            db_path.display()
-        ));
+        return Err(HarnessError::SessionNotFound {
+            agent: "kiro".to_string(),
+            detail: format!(
+                "database not found at {}. Kiro may not be installed or has not been used yet.",
+                db_path.display()
+            ),
+        }
+        .into());
     }
 
     Ok(db_path)
@@ -105,11 +145,16 @@ fn get_kiro_db_path() -> Result<PathBuf> {
 
 /// Parse tool uses from Kiro's JSON content format.
 /// Extracts tool calls from assistant messages containing ToolUse content.
+///
+/// Tool results arrive in the `ToolUseResults` of a later user message and are
+/// matched back to their tool use by id to populate [`ToolCall::result`].
 pub(crate) fn parse_kiro_tool_uses(content: &str) -> Result<Vec<ToolCall>> {
     let conversation: KiroConversation =
         serde_json::from_str(content).context("Failed to parse Kiro conversation JSON")?;
 
     let mut tool_calls = Vec::new();
+    let mut tool_use_ids = Vec::new();
+    let mut tool_results = HashMap::new();
 
     for entry in conversation.history {
         // Get timestamp from user message for this exchange (keep full ISO format)
@@ -119,46 +164,98 @@ pub(crate) fn parse_kiro_tool_uses(content: &str) -> Result<Vec<ToolCall>> {
             .clone()
             .unwrap_or_default();
 
+        if let Some(KiroUserContent::ToolUseResults { tool_use_results }) = entry.user.content {
+            for result in tool_use_results.tool_use_results {
+                tool_results.insert(result.tool_use_id, kiro_result_value(result.content));
+            }
+        }
+
         if let Some(KiroAssistantResponse::ToolUse { tool_use }) = entry.assistant {
             for tu in tool_use.tool_uses {
+                tool_use_ids.push(tu.id);
                 tool_calls.push(ToolCall {
                     name: tu.name,
                     params: tu.args,
                     timestamp: timestamp.clone(),
+                    result: None,
                 });
             }
         }
     }
 
+    for (call, id) in tool_calls.iter_mut().zip(tool_use_ids) {
+        if let Some(id) = id {
+            call.result = tool_results.remove(&id);
+        }
+    }
+
     Ok(tool_calls)
 }
 
+/// Unwrap Kiro's `Text`/`Json` result blocks.
+///
+/// A single block becomes its inner string or JSON value; several become an
+/// array of them. Unrecognized blocks are kept as-is.
+fn kiro_result_value(content: Vec<Value>) -> Value {
+    let mut values: Vec<Value> = content
+        .into_iter()
+        .map(|block| match block {
+            Value::Object(mut map) if map.len() == 1 => {
+                match map.remove("Text").or_else(|| map.remove("Json")) {
+                    Some(inner) => inner,
+                    None => Value::Object(map),
+                }
+            }
+            other => other,
+        })
+        .collect();
+
+    if values.len() == 1 {
+        values.remove(0)
+    } else {
+        Value::Array(values)
+    }
+}
+
 /// Query tool calls from the Kiro database for a specific working directory.
-/// If start_time_ms is provided, only returns conversations updated after that time.
 ///
-/// **Known limitation (TOCTOU):** The start timestamp is recorded before spawning
-/// the Kiro process. If another Kiro session writes to the same working directory
-/// between the timestamp snapshot and execution, its data may be included.
-/// A session-ID–based approach would eliminate this race but requires upstream
-/// Kiro support.
-fn query_tool_calls(db_path: &Path, working_dir: &Path, start_time_ms: Option<u64>) -> Result<Vec<ToolCall>> {
+/// Only the most recently updated conversation whose `updated_at` falls within
+/// `start_time_ms..=end_time_ms` is read; either bound may be omitted.
+///
+/// **Known limitation (TOCTOU):** Kiro conversations are keyed by working
+/// directory, not by run. The start timestamp is recorded when the Kiro process
+/// is spawned and the end timestamp when it exits, so only another Kiro session
+/// in the same working directory that writes while this one is running can be
+/// picked up instead. Copying the database before querying would not close that
+/// window, since the other session's rows are already committed. A session-ID–based
+/// approach would eliminate this race but requires upstream Kiro support. Tests
+/// sharing a working directory never run concurrently, so in practice only Kiro
+/// sessions started outside aptitude can collide.
+fn query_tool_calls(
+    db_path: &Path,
+    working_dir: &Path,
+    start_time_ms: Option<u64>,
+    end_time_ms: Option<u64>,
+) -> Result<Vec<ToolCall>> {
     let conn = Connection::open(db_path).context("Failed to open Kiro database")?;
 
     let working_dir_str = working_dir.to_string_lossy();
 
     let mut tool_calls = Vec::new();
 
-    // Use 0 as the default start time to match all conversations
+    // Default to an unbounded window to match all conversations
     let start_ms = start_time_ms.unwrap_or(0) as i64;
+    let end_ms = end_time_ms.map_or(i64::MAX, |ms| ms as i64);
 
     let mut stmt = conn
         .prepare(
-            "SELECT value FROM conversations_v2 WHERE key = ?1 AND updated_at >= ?2 ORDER BY updated_at DESC LIMIT 1",
+            "SELECT value FROM conversations_v2 WHERE key = ?1 AND updated_at >= ?2 AND updated_at <= ?3 \
+             ORDER BY updated_at DESC LIMIT 1",
         )
         .context("Failed to prepare SQL query")?;
 
     let rows: Vec<String> = stmt
-        .query_map(rusqlite::params![&working_dir_str, start_ms], |row| {
+        .query_map(rusqlite::params![&working_dir_str, start_ms, end_ms], |row| {
             row.get::<_, String>(0)
         })
         .context("Failed to execute SQL query")?
@@ -191,6 +288,7 @@ fn query_tool_calls(db_path: &Path, working_dir: &Path, start_time_ms: Option<u6
 struct KiroSessionContext {
     working_dir: PathBuf,
     start_time_ms: u64,
+    end_time_ms: u64,
 }
 
 /// Kiro agent adapter.
@@ -234,20 +332,16 @@ impl Agent for KiroAdapter {
     }
 
     fn execute(&self, prompt: &str, config: &ExecutionConfig) -> Result<RawExecutionResult> {
-        // Record start time for filtering database queries
-        let start_time_ms = current_time_ms();
+        config.reject_system_prompt("kiro")?;
 
         let mut cmd = Command::new("kiro-cli");
         cmd.arg("chat").arg("--no-interactive");
 
-        // Pass extra args from config
-        for arg in &config.extra_args {
-            cmd.arg(arg);
-        }
+        // Pass extra args, environment and working directory from config
+        config.apply_to(&mut cmd);
 
-        // Set working directory if provided, canonicalize for database matching
+        // Canonicalize the working directory for database matching
         let working_dir = if let Some(dir) = &config.working_dir {
-            cmd.current_dir(dir);
             // Canonicalize to match how Kiro stores paths in the database
             dir.canonicalize().ok().or_else(|| Some(dir.clone()))
         } else {
@@ -260,6 +354,8 @@ impl Agent for KiroAdapter {
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
+        // Record start time for filtering database queries
+        let start_time_ms = current_time_ms();
         let mut child = cmd.spawn().context("Failed to spawn kiro command")?;
 
         // Write prompt to stdin
@@ -269,24 +365,31 @@ impl Agent for KiroAdapter {
                 .context("Failed to write prompt to kiro stdin")?;
         }
 
-        let output = child
-            .wait_with_output()
-            .context("Failed to wait for kiro command")?;
+        let output = wait_with_timeout(child, config.timeout, "kiro")?;
+
+        // Kiro has written its conversation by the time it exits, so later
+        // updates belong to other sessions
+        let end_time_ms = current_time_ms();
 
         // Capture stdout
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         let stdout = if stdout.is_empty() { None } else { Some(stdout) };
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let stderr = if stderr.is_empty() { None } else { Some(stderr) };
 
         let agent_context: Option<Box<dyn std::any::Any + Send>> = working_dir.map(|dir| {
             Box::new(KiroSessionContext {
                 working_dir: dir,
                 start_time_ms,
+                end_time_ms,
             }) as Box<dyn std::any::Any + Send>
         });
 
         Ok(RawExecutionResult {
             session_log_path: None, // Kiro uses SQLite database, not log files
             stdout,
+            stderr,
+            exit_code: output.status.code(),
             agent_context,
         })
     }
@@ -302,7 +405,12 @@ impl Agent for KiroAdapter {
                 anyhow!("No Kiro session context in execution result - cannot query database")
             })?;
 
-        query_tool_calls(&db_path, &ctx.working_dir, Some(ctx.start_time_ms))
+        query_tool_calls(
+            &db_path,
+            &ctx.working_dir,
+            Some(ctx.start_time_ms),
+            Some(ctx.end_time_ms),
+        )
     }
 
     fn tool_mapping(&self) -> &ToolNameMapping {
@@ -364,6 +472,7 @@ impl Agent for KiroAdapter {
 mod tests {
     use super::*;
     use proptest::prelude::*;
+    use serde_json::json;
 
     /// Arbitrary generator for valid tool names (non-empty alphanumeric with underscores)
     fn arb_tool_name() -> impl Strategy<Value = String> {
@@ -420,6 +529,33 @@ mod tests {
         .to_string()
     }
 
+    /// Serialize tool uses followed by a user message carrying a `Text` result
+    /// for each tool use that has one.
+    fn serialize_with_results(tool_uses: &[(String, String, Value)], results: &[Option<String>]) -> String {
+        let mut conversation: Value = serde_json::from_str(&serialize_to_kiro_format(tool_uses)).unwrap();
+        let tool_use_results: Vec<Value> = tool_uses
+            .iter()
+            .zip(results)
+            .filter_map(|((id, _, _), result)| {
+                result.as_ref().map(|text| {
+                    json!({
+                        "tool_use_id": id,
+                        "content": [{"Text": text}],
+                        "status": "Success"
+                    })
+                })
+            })
+            .collect();
+        conversation["history"].as_array_mut().unwrap().push(json!({
+            "user": {
+                "content": {"ToolUseResults": {"tool_use_results": tool_use_results}},
+                "timestamp": "2025-01-01T00:00:01Z"
+            },
+            "assistant": {"Response": {"message_id": "reply", "content": "Done."}}
+        }));
+        conversation.to_string()
+    }
+
     // Feature: kiro-agent-support, Property 1: Tool call parsing produces valid structs
     proptest! {
         #![proptest_config(ProptestConfig::with_cases(100))]
@@ -471,6 +607,32 @@ mod tests {
             }
         }
 
+        /// Each tool result is attached to the call with the matching id,
+        /// and calls without a result have none.
+        #[test]
+        fn tool_results_attach_by_id(
+            entries in prop::collection::vec(
+                (arb_kiro_tool_use(), prop::option::of("[a-z0-9 \n]{0,20}")),
+                1..10
+            )
+        ) {
+            // Suffix ids with their index so generated ids never collide
+            let tool_uses: Vec<_> = entries
+                .iter()
+                .enumerate()
+                .map(|(i, ((id, name, args), _))| (format!("{}_{}", id, i), name.clone(), args.clone()))
+                .collect();
+            let results: Vec<_> = entries.iter().map(|(_, result)| result.clone()).collect();
+
+            let json = serialize_with_results(&tool_uses, &results);
+            let parsed = parse_kiro_tool_uses(&json).expect("Parsing should succeed");
+
+            prop_assert_eq!(parsed.len(), tool_uses.len());
+            for (call, result) in parsed.iter().zip(&results) {
+                prop_assert_eq!(&call.result, &result.clone().map(Value::String));
+            }
+        }
+
         /// Property 1 variant: Empty tool_uses array produces empty Vec
         #[test]
         fn empty_tool_uses_produces_empty_vec(_seed in any::<u64>()) {
@@ -778,4 +940,166 @@ mod tests {
         let calls = parse_kiro_tool_uses(json).unwrap();
         assert!(calls.is_empty());
     }
+
+    #[test]
+    fn test_parse_tool_results() {
+        let json = r#"{
+            "history": [
+                {
+                    "user": {
+                        "content": {"Prompt": {"prompt": "Run the tests"}},
+                        "timestamp": "2025-01-01T00:00:00Z"
+                    },
+                    "assistant": {
+                        "ToolUse": {
+                            "message_id": "m1",
+                            "content": "",
+                            "tool_uses": [
+                                {"id": "bash_1", "name": "execute_bash", "args": {"command": "cargo test"}},
+                                {"id": "read_1", "name": "fs_read", "args": {"path": "README.md"}}
+                            ]
+                        }
+                    }
+                },
+                {
+                    "user": {
+                        "content": {
+                            "ToolUseResults": {
+                                "tool_use_results": [
+                                    {
+                                        "tool_use_id": "read_1",
+                                        "content": [{"Text": "Line one"}, {"Text": "Line two"}],
+                                        "status": "Success"
+                                    },
+                                    {
+                                        "tool_use_id": "bash_1",
+                                        "content": [{"Json": {"exit_status": "0", "stdout": "ok", "stderr": ""}}],
+                                        "status": "Success"
+                                    }
+                                ]
+                            }
+                        },
+                        "timestamp": "2025-01-01T00:00:05Z"
+                    },
+                    "assistant": {
+                        "ToolUse": {
+                            "message_id": "m2",
+                            "content": "",
+                            "tool_uses": [
+                                {"id": "grep_1", "name": "grep", "args": {"pattern": "TODO"}}
+                            ]
+                        }
+                    }
+                },
+                {
+                    "user": {
+                        "content": {"CancelledToolUses": {"prompt": "stop", "tool_use_results": []}},
+                        "timestamp": "2025-01-01T00:00:09Z"
+                    },
+                    "assistant": null
+                }
+            ]
+        }"#;
+
+        let calls = parse_kiro_tool_uses(json).unwrap();
+        assert_eq!(calls.len(), 3);
+
+        // A single Json block is unwrapped to its value
+        assert_eq!(
+            calls[0].result,
+            Some(json!({"exit_status": "0", "stdout": "ok", "stderr": ""}))
+        );
+        // Several blocks become an array
+        assert_eq!(calls[1].result, Some(json!(["Line one", "Line two"])));
+        // Cancelled before a result was recorded
+        assert_eq!(calls[2].name, "grep");
+        assert!(calls[2].result.is_none());
+    }
+
+    fn conversation(tool_name: &str) -> String {
+        json!({
+            "history": [{
+                "user": {},
+                "assistant": {
+                    "ToolUse": {
+                        "tool_uses": [{"id": "t1", "name": tool_name, "args": {}}]
+                    }
+                }
+            }]
+        })
+        .to_string()
+    }
+
+    /// Create a Kiro-style database with `(key, tool name, updated_at)` rows.
+    fn make_db(dir: &Path, rows: &[(&str, &str, i64)]) -> PathBuf {
+        let db_path = dir.join("data.sqlite3");
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute(
+            "CREATE TABLE conversations_v2 (key TEXT, value TEXT, created_at INTEGER, updated_at INTEGER)",
+            [],
+        )
+        .unwrap();
+        for (key, tool_name, updated_at) in rows {
+            conn.execute(
+                "INSERT INTO conversations_v2 VALUES (?1, ?2, ?3, ?3)",
+                rusqlite::params![key, conversation(tool_name), updated_at],
+            )
+            .unwrap();
+        }
+        db_path
+    }
+
+    fn names(calls: &[ToolCall]) -> Vec<&str> {
+        calls.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_query_tool_calls_picks_latest_in_working_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = make_db(
+            dir.path(),
+            &[
+                ("/work/a", "fs_read", 1_000),
+                ("/work/a", "fs_write", 2_000),
+                ("/work/b", "execute_bash", 3_000),
+            ],
+        );
+
+        let calls = query_tool_calls(&db, Path::new("/work/a"), None, None).unwrap();
+        assert_eq!(names(&calls), ["fs_write"]);
+
+        let calls = query_tool_calls(&db, Path::new("/work/c"), None, None).unwrap();
+        assert!(calls.is_empty());
+    }
+
+    #[test]
+    fn test_query_tool_calls_time_bounds() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = make_db(
+            dir.path(),
+            &[
+                ("/work", "glob", 1_000),
+                ("/work", "fs_read", 2_000),
+                // Another session in the same directory, after this run exited
+                ("/work", "execute_bash", 3_000),
+            ],
+        );
+        let work = Path::new("/work");
+
+        let calls = query_tool_calls(&db, work, Some(1_500), Some(2_500)).unwrap();
+        assert_eq!(names(&calls), ["fs_read"]);
+
+        // Bounds are inclusive
+        let calls = query_tool_calls(&db, work, Some(1_000), Some(1_000)).unwrap();
+        assert_eq!(names(&calls), ["glob"]);
+
+        let calls = query_tool_calls(&db, work, None, Some(1_999)).unwrap();
+        assert_eq!(names(&calls), ["glob"]);
+
+        let calls = query_tool_calls(&db, work, Some(1_500), None).unwrap();
+        assert_eq!(names(&calls), ["execute_bash"]);
+
+        let calls = query_tool_calls(&db, work, Some(2_100), Some(2_900)).unwrap();
+        assert!(calls.is_empty());
+    }
 }
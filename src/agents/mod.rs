@@ -24,24 +24,34 @@
 //! ```
 
 mod claude;
+#[cfg(feature = "cursor")]
+mod cursor;
+#[cfg(feature = "gemini")]
+mod gemini;
 mod harness;
 #[cfg(feature = "kiro")]
 mod kiro;
+mod replay;
 
 use std::any::Any;
 use std::collections::HashMap;
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
+use std::process::{Child, Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 
-use crate::parser::ToolCall;
+use crate::parser::{ToolCall, UsageStats};
 
 pub use harness::{AgentHarness, AgentType, ExecutionOutput, NormalizedResult};
+pub use replay::{recording_path, turn_recording_path, Recording, RecordingAgent, ReplayAgent};
 
 // Re-export Claude session helpers for streaming module
 pub(crate) use claude::{
-    find_new_session, get_claude_projects_dir, get_project_dir_for_workdir, list_session_files,
+    claude_command, find_new_session, get_claude_projects_dir, get_project_dir_for_workdir, list_session_files,
 };
 
 // =========================================================================
@@ -55,6 +65,29 @@ pub struct ExecutionConfig {
     pub working_dir: Option<PathBuf>,
     /// Extra arguments to pass to the agent CLI.
     pub extra_args: Vec<String>,
+    /// Environment variables set on the agent process.
+    pub env: HashMap<String, String>,
+    /// Kill the agent and fail if it runs longer than this.
+    pub timeout: Option<Duration>,
+    /// Extra attempts allowed when a test's assertions fail.
+    ///
+    /// Test runners re-execute the agent; a single `execute()` never retries.
+    pub retries: u32,
+    /// Send the prompt on stdin instead of as a command-line argument, for
+    /// prompts too large for the OS argument limit. Used by Claude; Kiro
+    /// always reads its prompt from stdin.
+    pub stdin_prompt: bool,
+    /// Rewrite absolute `file_path`, `path` and `command` parameters relative
+    /// to the working directory before results are returned, so assertions
+    /// can match `src/main.rs` instead of `/home/user/proj/src/main.rs`.
+    pub normalize_paths: bool,
+    /// Text appended to the agent's default system prompt. Claude passes it
+    /// as `--append-system-prompt`; agents without an equivalent flag fail
+    /// with [`HarnessError::Unsupported`](crate::HarnessError::Unsupported).
+    pub system_prompt: Option<String>,
+    /// Extra agent tool name -> canonical name mappings, applied on top of
+    /// the agent's own [`ToolNameMapping`] (e.g. a config's `tool_mappings`).
+    pub tool_mappings: HashMap<String, String>,
 }
 
 impl ExecutionConfig {
@@ -66,6 +99,235 @@ impl ExecutionConfig {
         self.working_dir = Some(dir);
         self
     }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Allow `retries` extra attempts when a test's assertions fail.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Pin the model by appending `--model <model>` to the extra arguments.
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.extra_args.push("--model".to_string());
+        self.extra_args.push(model.to_string());
+        self
+    }
+
+    /// Set the environment variable `key` to `value` on the agent process.
+    pub fn with_env(mut self, key: &str, value: &str) -> Self {
+        self.env.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Send the prompt on stdin instead of as an argument.
+    pub fn with_stdin_prompt(mut self, stdin_prompt: bool) -> Self {
+        self.stdin_prompt = stdin_prompt;
+        self
+    }
+
+    /// Rewrite tool call paths relative to the working directory.
+    pub fn with_normalize_paths(mut self, normalize_paths: bool) -> Self {
+        self.normalize_paths = normalize_paths;
+        self
+    }
+
+    /// Map the agent tool name `agent_name` to `canonical_name`, on top of
+    /// the agent's own mapping.
+    pub fn with_tool_mapping(mut self, agent_name: &str, canonical_name: &str) -> Self {
+        self.tool_mappings
+            .insert(agent_name.to_string(), canonical_name.to_string());
+        self
+    }
+
+    /// Append `text` to the agent's system prompt.
+    pub fn with_system_prompt(mut self, text: &str) -> Self {
+        self.system_prompt = Some(text.to_string());
+        self
+    }
+
+    /// Fail with [`HarnessError::Unsupported`](crate::HarnessError::Unsupported)
+    /// if a system prompt is set, for agents that can't take one.
+    #[cfg(any(feature = "kiro", feature = "gemini", feature = "cursor"))]
+    pub(crate) fn reject_system_prompt(&self, agent: &str) -> Result<()> {
+        if self.system_prompt.is_some() {
+            return Err(crate::HarnessError::Unsupported {
+                agent: agent.to_string(),
+                feature: "appending to the system prompt".to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Apply the working directory, extra arguments and environment to `cmd`.
+    pub(crate) fn apply_to(&self, cmd: &mut Command) {
+        if let Some(dir) = &self.working_dir {
+            cmd.current_dir(dir);
+        }
+        cmd.args(&self.extra_args);
+        cmd.envs(&self.env);
+    }
+
+    /// Spawn `cmd` with `prompt` as its next argument, or written to stdin when
+    /// [`stdin_prompt`](Self::stdin_prompt) is set. The config is applied after
+    /// the prompt, and stdout and stderr are piped.
+    pub(crate) fn spawn_with_prompt(&self, cmd: &mut Command, prompt: &str) -> io::Result<Child> {
+        if self.stdin_prompt {
+            cmd.stdin(Stdio::piped());
+        } else {
+            cmd.arg(prompt).stdin(Stdio::null());
+        }
+        self.apply_to(cmd);
+
+        let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // Write from another thread: a large prompt fills the pipe before the
+            // agent reads it all, and the caller needs to be draining its output.
+            // A write error means the agent exited early, which its status reports.
+            let prompt = prompt.to_string();
+            thread::spawn(move || {
+                let _ = stdin.write_all(prompt.as_bytes());
+            });
+        }
+        Ok(child)
+    }
+}
+
+impl ExecutionConfig {
+    /// Async counterpart of [`spawn_with_prompt`](Self::spawn_with_prompt)
+    /// followed by [`wait_with_timeout`], awaiting the process with Tokio.
+    ///
+    /// Must be called from within a Tokio runtime. On timeout the process is
+    /// killed and an error naming `program` is returned.
+    pub(crate) async fn output_with_prompt_async(
+        &self,
+        mut cmd: Command,
+        prompt: &str,
+        program: &str,
+    ) -> Result<Output> {
+        if self.stdin_prompt {
+            cmd.stdin(Stdio::piped());
+        } else {
+            cmd.arg(prompt).stdin(Stdio::null());
+        }
+        self.apply_to(&mut cmd);
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+        let mut cmd = tokio::process::Command::from(cmd);
+        // Dropping the child when the timeout fires kills it
+        cmd.kill_on_drop(true);
+        let mut child = cmd
+            .spawn()
+            .with_context(|| format!("Failed to execute {} command", program))?;
+
+        // Write the prompt while the output is drained, as in `spawn_with_prompt`.
+        // A write error means the agent exited early, which its status reports.
+        let stdin = child.stdin.take();
+        let write = async move {
+            if let Some(mut stdin) = stdin {
+                let _ = tokio::io::AsyncWriteExt::write_all(&mut stdin, prompt.as_bytes()).await;
+            }
+        };
+        let run = async {
+            let ((), output) = tokio::join!(write, child.wait_with_output());
+            output.with_context(|| format!("Failed to wait for {} process", program))
+        };
+
+        match self.timeout {
+            None => run.await,
+            Some(timeout) => match tokio::time::timeout(timeout, run).await {
+                Ok(output) => output,
+                Err(_) => bail!("{} timed out after {:?} and was killed", program, timeout),
+            },
+        }
+    }
+}
+
+/// Run a grading command and return its stdout as the grader's response.
+///
+/// An empty response is an error, since there is nothing to parse a grade
+/// from. `program` names the command in error messages.
+pub(crate) fn run_grader(mut cmd: Command, program: &str) -> Result<String> {
+    let output = cmd
+        .output()
+        .with_context(|| format!("Failed to execute {} command for grading", program))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+
+    if stdout.trim().is_empty() {
+        bail!("Grading agent returned empty response");
+    }
+
+    Ok(stdout)
+}
+
+/// Like [`run_grader`], but on its own thread so the caller's executor isn't
+/// blocked while the grader runs. Works under any async runtime.
+pub(crate) async fn run_grader_async(cmd: Command, program: &'static str) -> Result<String> {
+    let (tx, rx) = futures::channel::oneshot::channel();
+    thread::spawn(move || {
+        let _ = tx.send(run_grader(cmd, program));
+    });
+    rx.await
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("{} grading thread panicked", program)))
+}
+
+/// Wait for a child process to exit, killing it if `timeout` elapses.
+///
+/// Like [`Child::wait_with_output`], stdout and stderr are collected if they
+/// were piped. Returns an error naming `program` if the process was killed.
+pub(crate) fn wait_with_timeout(
+    mut child: Child,
+    timeout: Option<Duration>,
+    program: &str,
+) -> Result<Output> {
+    let Some(timeout) = timeout else {
+        return child
+            .wait_with_output()
+            .with_context(|| format!("Failed to wait for {} process", program));
+    };
+
+    // Drain pipes on separate threads so a chatty process can't block on a full pipe
+    let stdout = child.stdout.take().map(|mut out| {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = out.read_to_end(&mut buf);
+            buf
+        })
+    });
+    let stderr = child.stderr.take().map(|mut err| {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = err.read_to_end(&mut buf);
+            buf
+        })
+    });
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .with_context(|| format!("Failed to wait for {} process", program))?
+        {
+            break status;
+        }
+        if start.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("{} timed out after {:?} and was killed", program, timeout);
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.and_then(|h| h.join().ok()).unwrap_or_default(),
+        stderr: stderr.and_then(|h| h.join().ok()).unwrap_or_default(),
+    })
 }
 
 /// Raw result from agent execution before normalization.
@@ -74,6 +336,10 @@ pub struct RawExecutionResult {
     pub session_log_path: Option<PathBuf>,
     /// Stdout from the agent command.
     pub stdout: Option<String>,
+    /// Stderr from the agent command, if it was captured and non-empty.
+    pub stderr: Option<String>,
+    /// Exit code of the agent command, if it exited normally.
+    pub exit_code: Option<i32>,
     /// Opaque agent-specific context passed from `execute()` to `parse_session()`.
     ///
     /// Each agent can store its own session-recovery data here (e.g., database
@@ -86,6 +352,8 @@ impl std::fmt::Debug for RawExecutionResult {
         f.debug_struct("RawExecutionResult")
             .field("session_log_path", &self.session_log_path)
             .field("stdout", &self.stdout)
+            .field("stderr", &self.stderr)
+            .field("exit_code", &self.exit_code)
             .field("agent_context", &self.agent_context.as_ref().map(|_| "..."))
             .finish()
     }
@@ -105,11 +373,52 @@ pub trait Agent: Send + Sync {
     /// Returns a raw execution result that can be parsed for tool calls.
     fn execute(&self, prompt: &str, config: &ExecutionConfig) -> Result<RawExecutionResult>;
 
+    /// Async version of [`execute`](Agent::execute) for running many agents
+    /// concurrently.
+    ///
+    /// The default runs the synchronous `execute` via
+    /// `tokio::task::block_in_place` when inside a multi-threaded Tokio
+    /// runtime, so other tasks move to another worker meanwhile, and calls it
+    /// directly otherwise. (`spawn_blocking` would need `'static` data, which
+    /// `&self` isn't.) Agents can override this with a native async
+    /// implementation.
+    async fn execute_async(
+        &self,
+        prompt: &str,
+        config: &ExecutionConfig,
+    ) -> Result<RawExecutionResult> {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| self.execute(prompt, config))
+            }
+            _ => self.execute(prompt, config),
+        }
+    }
+
     /// Parse the agent's output/log format and extract tool calls.
     ///
     /// Returns tool calls in the agent's native naming convention.
     fn parse_session(&self, result: &RawExecutionResult) -> Result<Vec<ToolCall>>;
 
+    /// Extract token and cost usage from the agent's output, if it reports any.
+    ///
+    /// Default is `None` — agents opt in when their logs record usage.
+    fn parse_usage(&self, _result: &RawExecutionResult) -> Option<UsageStats> {
+        None
+    }
+
+    /// Extract tool calls and usage together.
+    ///
+    /// Default calls [`parse_session()`](Self::parse_session) and
+    /// [`parse_usage()`](Self::parse_usage). Agents that read both from the
+    /// same log override this to read it once.
+    fn parse_session_and_usage(
+        &self,
+        result: &RawExecutionResult,
+    ) -> Result<(Vec<ToolCall>, Option<UsageStats>)> {
+        Ok((self.parse_session(result)?, self.parse_usage(result)))
+    }
+
     /// Return the tool name mapping for this agent.
     ///
     /// Maps agent-specific tool names to canonical names.
@@ -123,6 +432,25 @@ pub trait Agent: Send + Sync {
         false
     }
 
+    /// Whether this agent can continue a previous session with [`resume`](Agent::resume).
+    ///
+    /// Default is `false` — agents opt in to follow-up turns.
+    fn supports_resume(&self) -> bool {
+        false
+    }
+
+    /// Send a follow-up prompt in the session started by `previous`.
+    ///
+    /// Only called when [`supports_resume`](Agent::supports_resume) returns `true`.
+    fn resume(
+        &self,
+        _previous: &RawExecutionResult,
+        _prompt: &str,
+        _config: &ExecutionConfig,
+    ) -> Result<RawExecutionResult> {
+        anyhow::bail!("Agent '{}' does not support follow-up turns", self.name())
+    }
+
     /// Check if this agent is available on the system.
     fn is_available(&self) -> bool;
 
@@ -171,6 +499,14 @@ impl ToolNameMapping {
         self
     }
 
+    /// Add several mappings, replacing any existing ones for the same agent names.
+    pub fn extend(&mut self, mappings: &HashMap<String, String>) -> &mut Self {
+        for (agent_name, canonical_name) in mappings {
+            self.add(agent_name, canonical_name);
+        }
+        self
+    }
+
     /// Convert agent-specific tool name to canonical.
     ///
     /// If no mapping exists, returns the original name unchanged.
@@ -197,4 +533,140 @@ mod tests {
         // Unmapped names pass through unchanged
         assert_eq!(mapping.to_canonical("Unknown"), "Unknown");
     }
+
+    #[test]
+    fn test_apply_to_sets_env() {
+        let config = ExecutionConfig::new()
+            .with_env("APTITUDE_TEST_ENV", "test")
+            .with_working_dir(std::env::temp_dir());
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("echo \"$APTITUDE_TEST_ENV $(pwd)\"");
+        config.apply_to(&mut cmd);
+
+        let output = cmd.output().unwrap();
+        let expected = format!(
+            "test {}\n",
+            std::env::temp_dir().canonicalize().unwrap().display()
+        );
+        assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+    }
+
+    #[test]
+    fn test_spawn_with_prompt_on_stdin() {
+        // Far beyond the per-argument limit (128 KiB on Linux)
+        let prompt = "x".repeat(4 * 1024 * 1024);
+
+        let config = ExecutionConfig::new().with_stdin_prompt(true);
+        let mut cmd = Command::new("wc");
+        cmd.arg("-c");
+        let child = config.spawn_with_prompt(&mut cmd, &prompt).unwrap();
+        let output = wait_with_timeout(child, Some(Duration::from_secs(10)), "wc").unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), prompt.len().to_string());
+
+        // The same prompt as an argument is rejected by the OS
+        let err = ExecutionConfig::new().spawn_with_prompt(&mut Command::new("true"), &prompt);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_spawn_with_prompt_as_argument() {
+        let config = ExecutionConfig::new().with_env("APTITUDE_TEST_ENV", "set");
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("echo \"$0 $APTITUDE_TEST_ENV\"");
+        let child = config.spawn_with_prompt(&mut cmd, "hello").unwrap();
+        let output = wait_with_timeout(child, None, "sh").unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello set\n");
+    }
+
+    #[test]
+    fn test_wait_with_timeout_kills_process() {
+        let child = std::process::Command::new("sleep")
+            .arg("10")
+            .spawn()
+            .unwrap();
+
+        let start = Instant::now();
+        let err = wait_with_timeout(child, Some(Duration::from_millis(200)), "sleep").unwrap_err();
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(err.to_string().contains("sleep timed out after 200ms"));
+    }
+
+    #[test]
+    fn test_wait_with_timeout_collects_output() {
+        let child = std::process::Command::new("echo")
+            .arg("hello")
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let output = wait_with_timeout(child, Some(Duration::from_secs(5)), "echo").unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello\n");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_execute_async_runs_agents_concurrently() {
+        let agent = std::sync::Arc::new(
+            crate::test_util::MockAgent::new()
+                .with_stdout("done")
+                .with_delay(Duration::from_millis(300)),
+        );
+
+        let start = Instant::now();
+        let tasks: Vec<_> = (0..2)
+            .map(|_| {
+                let agent = agent.clone();
+                tokio::spawn(async move { agent.execute_async("Go", &ExecutionConfig::new()).await })
+            })
+            .collect();
+        for task in tasks {
+            let result = task.await.unwrap().unwrap();
+            assert_eq!(result.stdout.as_deref(), Some("done"));
+        }
+
+        // Two 300ms runs on separate workers overlap instead of taking 600ms
+        assert!(start.elapsed() < Duration::from_millis(550), "{:?}", start.elapsed());
+        assert_eq!(agent.runs(), 2);
+    }
+
+    #[test]
+    fn test_execute_async_without_runtime() {
+        let agent = crate::test_util::MockAgent::new().with_exit_code(3);
+        let result =
+            futures::executor::block_on(agent.execute_async("Go", &ExecutionConfig::new())).unwrap();
+        assert_eq!(result.exit_code, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_output_with_prompt_async() {
+        let config = ExecutionConfig::new().with_stdin_prompt(true);
+        let mut cmd = Command::new("wc");
+        cmd.arg("-c");
+        let prompt = "x".repeat(1024 * 1024);
+        let output = config.output_with_prompt_async(cmd, &prompt, "wc").await.unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), prompt.len().to_string());
+
+        let config = ExecutionConfig::new().with_env("APTITUDE_TEST_ENV", "set");
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("echo \"$0 $APTITUDE_TEST_ENV\"");
+        let output = config.output_with_prompt_async(cmd, "hello", "sh").await.unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello set\n");
+    }
+
+    #[tokio::test]
+    async fn test_output_with_prompt_async_timeout() {
+        let config = ExecutionConfig::new().with_timeout(Duration::from_millis(200));
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("sleep 10");
+
+        let start = Instant::now();
+        let err = config.output_with_prompt_async(cmd, "ignored", "sleep").await.unwrap_err();
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(err.to_string().contains("sleep timed out after 200ms"));
+    }
 }
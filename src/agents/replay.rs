@@ -0,0 +1,479 @@
+//! Record agent runs to fixture files and replay them without the agent.
+//!
+//! [`RecordingAgent`] wraps a real agent and saves each run's stdout, exit
+//! code, tool calls and usage as a JSON [`Recording`]. [`ReplayAgent`] wraps
+//! the same agent and answers each prompt from its recording instead of
+//! spawning anything, so a suite recorded once runs fast and deterministically
+//! in CI.
+//!
+//! Recordings are named by a SHA-256 of the agent, prompt, extra arguments
+//! (e.g. `--model`) and system prompt, so a run replays only the recording
+//! made with the same inputs. The working directory is left out, so
+//! recordings still match when a checkout moves. Each follow-up turn of a
+//! conversation is its own recording, keyed by the prompts before it too.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use std::sync::Arc;
+//! use aptitude::agents::{RecordingAgent, ReplayAgent};
+//! use aptitude::AgentHarness;
+//!
+//! // Once, against the real agent
+//! let mut harness = AgentHarness::new();
+//! harness.wrap_agents(|agent| Arc::new(RecordingAgent::new(agent, "tests/recordings")));
+//!
+//! // Later, in CI
+//! let mut harness = AgentHarness::new();
+//! harness.wrap_agents(|agent| Arc::new(ReplayAgent::new(agent, "tests/recordings")));
+//! ```
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::{Agent, ExecutionConfig, RawExecutionResult, ToolNameMapping};
+use crate::error::HarnessError;
+use crate::parser::{ToolCall, UsageStats};
+
+/// One recorded agent run.
+///
+/// Tool calls keep the agent's own names, before mapping to canonical ones
+/// or rewriting paths, so replaying goes through the same normalization as
+/// the original run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    /// Name of the agent that ran.
+    pub agent: String,
+    pub prompt: String,
+    /// Earlier prompts in the conversation, when this run was a follow-up turn.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<String>,
+    #[serde(default)]
+    pub stdout: Option<String>,
+    #[serde(default)]
+    pub stderr: Option<String>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(default)]
+    pub usage: Option<UsageStats>,
+}
+
+impl Recording {
+    /// Load a recording from a JSON file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let json =
+            fs::read_to_string(path).with_context(|| format!("Failed to read recording {:?}", path))?;
+        serde_json::from_str(&json).with_context(|| format!("Failed to parse recording {:?}", path))
+    }
+
+    /// Write the recording as pretty-printed JSON, creating parent directories.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create recording directory {:?}", dir))?;
+        }
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize recording")?;
+        fs::write(path, json + "\n").with_context(|| format!("Failed to write recording {:?}", path))
+    }
+
+    /// The execution result the recorded run returned.
+    fn into_result(self) -> RawExecutionResult {
+        RawExecutionResult {
+            session_log_path: None,
+            stdout: self.stdout.clone(),
+            stderr: self.stderr.clone(),
+            exit_code: self.exit_code,
+            agent_context: Some(Box::new(self)),
+        }
+    }
+}
+
+/// Path of the recording for running `prompt` on `agent` with `config`, in `dir`.
+pub fn recording_path(dir: &Path, agent: &str, prompt: &str, config: &ExecutionConfig) -> PathBuf {
+    turn_recording_path(dir, agent, &[], prompt, config)
+}
+
+/// Path of the recording for a follow-up `prompt` after the prompts in
+/// `history`. With no history, the same as [`recording_path`].
+pub fn turn_recording_path(
+    dir: &Path,
+    agent: &str,
+    history: &[String],
+    prompt: &str,
+    config: &ExecutionConfig,
+) -> PathBuf {
+    dir.join(format!("{}-{}.json", agent, recording_key(agent, history, prompt, config)))
+}
+
+/// Hex SHA-256 over the inputs that select a recording. Each field is
+/// length-prefixed so moving text between fields changes the key, and
+/// earlier prompts are tagged apart from the other fields.
+fn recording_key(agent: &str, history: &[String], prompt: &str, config: &ExecutionConfig) -> String {
+    let mut hasher = Sha256::new();
+    let mut field = |tag: u8, value: Option<&str>| match value {
+        Some(s) => {
+            hasher.update([tag]);
+            hasher.update((s.len() as u64).to_le_bytes());
+            hasher.update(s.as_bytes());
+        }
+        None => hasher.update([0]),
+    };
+    field(1, Some(agent));
+    for earlier in history {
+        field(2, Some(earlier));
+    }
+    field(1, Some(prompt));
+    for arg in &config.extra_args {
+        field(1, Some(arg));
+    }
+    field(1, config.system_prompt.as_deref());
+
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The recording carried by a result from [`RecordingAgent`] or [`ReplayAgent`].
+fn recorded(result: &RawExecutionResult) -> Result<&Recording> {
+    result
+        .agent_context
+        .as_ref()
+        .and_then(|c| c.downcast_ref::<Recording>())
+        .ok_or_else(|| anyhow!("No recording in execution result"))
+}
+
+/// The prompts of the conversation up to and including the run in `result`.
+fn conversation_so_far(result: &RawExecutionResult) -> Result<Vec<String>> {
+    let recording = recorded(result)?;
+    let mut history = recording.history.clone();
+    history.push(recording.prompt.clone());
+    Ok(history)
+}
+
+/// Wraps an agent and saves each run as a [`Recording`] in a directory.
+///
+/// Runs go through [`execute`](Agent::execute), so a streaming agent's tool
+/// calls arrive when it finishes rather than live. Follow-up turns go to the
+/// wrapped agent's [`resume`](Agent::resume) and are recorded one per turn.
+pub struct RecordingAgent {
+    inner: Arc<dyn Agent>,
+    dir: PathBuf,
+}
+
+impl RecordingAgent {
+    /// Record runs of `inner` into `dir`, created on first write.
+    pub fn new(inner: Arc<dyn Agent>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+        }
+    }
+}
+
+impl RecordingAgent {
+    /// Save the run in `result` as the recording for `prompt` after `history`.
+    fn record(
+        &self,
+        result: RawExecutionResult,
+        history: Vec<String>,
+        prompt: &str,
+        config: &ExecutionConfig,
+    ) -> Result<RawExecutionResult> {
+        let (tool_calls, usage) = self.inner.parse_session_and_usage(&result)?;
+        let path = turn_recording_path(&self.dir, self.name(), &history, prompt, config);
+        let recording = Recording {
+            agent: self.name().to_string(),
+            prompt: prompt.to_string(),
+            history,
+            tool_calls,
+            usage,
+            stdout: result.stdout.clone(),
+            stderr: result.stderr.clone(),
+            exit_code: result.exit_code,
+        };
+        recording.save(&path)?;
+
+        // Keep the real session log path for display, but answer parsing from
+        // the recording so the session isn't parsed twice
+        Ok(RawExecutionResult {
+            session_log_path: result.session_log_path,
+            ..recording.into_result()
+        })
+    }
+}
+
+#[async_trait]
+impl Agent for RecordingAgent {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn execute(&self, prompt: &str, config: &ExecutionConfig) -> Result<RawExecutionResult> {
+        let result = self.inner.execute(prompt, config)?;
+        self.record(result, Vec::new(), prompt, config)
+    }
+
+    fn parse_session(&self, result: &RawExecutionResult) -> Result<Vec<ToolCall>> {
+        Ok(recorded(result)?.tool_calls.clone())
+    }
+
+    fn parse_usage(&self, result: &RawExecutionResult) -> Option<UsageStats> {
+        recorded(result).ok()?.usage
+    }
+
+    fn tool_mapping(&self) -> &ToolNameMapping {
+        self.inner.tool_mapping()
+    }
+
+    fn supports_resume(&self) -> bool {
+        self.inner.supports_resume()
+    }
+
+    /// Resume with the wrapped agent, which sees the previous run's real
+    /// session log path, and record the turn.
+    fn resume(
+        &self,
+        previous: &RawExecutionResult,
+        prompt: &str,
+        config: &ExecutionConfig,
+    ) -> Result<RawExecutionResult> {
+        let history = conversation_so_far(previous)?;
+        let result = self.inner.resume(previous, prompt, config)?;
+        self.record(result, history, prompt, config)
+    }
+
+    fn is_available(&self) -> bool {
+        self.inner.is_available()
+    }
+
+    fn grade(&self, prompt: &str, model: Option<&str>) -> Result<String> {
+        self.inner.grade(prompt, model)
+    }
+
+    async fn grade_async(&self, prompt: &str, model: Option<&str>) -> Result<String> {
+        self.inner.grade_async(prompt, model).await
+    }
+}
+
+/// Wraps an agent and answers each prompt from a [`Recording`] in a
+/// directory, without running the agent.
+///
+/// A prompt with no recording fails with [`HarnessError::SessionNotFound`].
+/// Follow-up turns replay when the wrapped agent supports resuming.
+/// Grading still goes to the wrapped agent.
+pub struct ReplayAgent {
+    inner: Arc<dyn Agent>,
+    dir: PathBuf,
+}
+
+impl ReplayAgent {
+    /// Replay runs of `inner` from the recordings in `dir`.
+    pub fn new(inner: Arc<dyn Agent>, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+        }
+    }
+
+    /// Answer from the recording at `path`.
+    fn replay(&self, path: &Path) -> Result<RawExecutionResult> {
+        if !path.exists() {
+            return Err(HarnessError::SessionNotFound {
+                agent: self.name().to_string(),
+                detail: format!("no recording at {} for this prompt; record one with --record", path.display()),
+            }
+            .into());
+        }
+        Ok(Recording::load(path)?.into_result())
+    }
+}
+
+#[async_trait]
+impl Agent for ReplayAgent {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn execute(&self, prompt: &str, config: &ExecutionConfig) -> Result<RawExecutionResult> {
+        self.replay(&recording_path(&self.dir, self.name(), prompt, config))
+    }
+
+    fn parse_session(&self, result: &RawExecutionResult) -> Result<Vec<ToolCall>> {
+        Ok(recorded(result)?.tool_calls.clone())
+    }
+
+    fn parse_usage(&self, result: &RawExecutionResult) -> Option<UsageStats> {
+        recorded(result).ok()?.usage
+    }
+
+    fn tool_mapping(&self) -> &ToolNameMapping {
+        self.inner.tool_mapping()
+    }
+
+    fn supports_resume(&self) -> bool {
+        self.inner.supports_resume()
+    }
+
+    fn resume(
+        &self,
+        previous: &RawExecutionResult,
+        prompt: &str,
+        config: &ExecutionConfig,
+    ) -> Result<RawExecutionResult> {
+        let history = conversation_so_far(previous)?;
+        self.replay(&turn_recording_path(&self.dir, self.name(), &history, prompt, config))
+    }
+
+    /// Always available: replaying never runs the agent.
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn grade(&self, prompt: &str, model: Option<&str>) -> Result<String> {
+        self.inner.grade(prompt, model)
+    }
+
+    async fn grade_async(&self, prompt: &str, model: Option<&str>) -> Result<String> {
+        self.inner.grade_async(prompt, model).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::{AgentHarness, AgentType};
+    use crate::test_util::MockAgent;
+    use serde_json::json;
+
+    fn mock() -> MockAgent {
+        let mut mapping = ToolNameMapping::new();
+        mapping.add("read_file", "Read");
+        MockAgent::new()
+            .with_name("claude")
+            .with_mapping(mapping)
+            .with_tool_call("read_file", json!({ "file_path": "src/main.rs" }))
+            .with_stdout("Read it.")
+            .with_exit_code(2)
+            .with_usage(UsageStats {
+                input_tokens: 10,
+                output_tokens: 5,
+                total_cost_usd: 0.01,
+            })
+    }
+
+    #[test]
+    fn test_record_then_replay_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ExecutionConfig::new().with_model("haiku");
+        let agent = mock();
+
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(agent.clone()));
+        harness.wrap_agents(|inner| Arc::new(RecordingAgent::new(inner, dir.path())));
+        let recorded = harness.execute(None, "Read main", config.clone()).unwrap();
+        assert_eq!(agent.runs(), 1);
+
+        let path = recording_path(dir.path(), "claude", "Read main", &config);
+        let recording = Recording::load(&path).unwrap();
+        assert_eq!(recording.prompt, "Read main");
+        // Recordings keep the agent's own tool names
+        assert_eq!(recording.tool_calls[0].name, "read_file");
+
+        // The replayed agent is never run, even when uninstalled
+        let unavailable = mock().unavailable();
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(unavailable.clone()));
+        harness.wrap_agents(|inner| Arc::new(ReplayAgent::new(inner, dir.path())));
+        let replayed = harness.execute(None, "Read main", config).unwrap();
+        assert_eq!(unavailable.runs(), 0);
+
+        for output in [&recorded, &replayed] {
+            assert_eq!(output.result.tool_calls.len(), 1);
+            assert_eq!(output.result.tool_calls[0].name, "Read");
+            assert_eq!(output.result.tool_calls[0].params["file_path"], "src/main.rs");
+            assert_eq!(output.stdout.as_deref(), Some("Read it."));
+            assert_eq!(output.exit_code, Some(2));
+            assert_eq!(output.usage.unwrap().total_tokens(), 15);
+        }
+    }
+
+    #[test]
+    fn test_record_then_replay_conversation() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ExecutionConfig::new();
+        let turns = vec!["Read main".to_string(), "Now summarize it".to_string()];
+        let agent = mock();
+
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(agent.clone()));
+        harness.wrap_agents(|inner| Arc::new(RecordingAgent::new(inner, dir.path())));
+        let recorded = harness.execute_conversation(None, &turns, config.clone()).unwrap();
+        assert_eq!(agent.runs(), 2);
+
+        // Each turn is its own recording, the follow-up keyed by the first prompt
+        let follow_up = turn_recording_path(dir.path(), "claude", &turns[..1], &turns[1], &config);
+        assert_ne!(follow_up, recording_path(dir.path(), "claude", &turns[1], &config));
+        let recording = Recording::load(&follow_up).unwrap();
+        assert_eq!(recording.prompt, "Now summarize it");
+        assert_eq!(recording.history, vec!["Read main".to_string()]);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+
+        let unavailable = mock().unavailable();
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(unavailable.clone()));
+        harness.wrap_agents(|inner| Arc::new(ReplayAgent::new(inner, dir.path())));
+        let replayed = harness.execute_conversation(None, &turns, config.clone()).unwrap();
+        assert_eq!(unavailable.runs(), 0);
+
+        assert_eq!(replayed.result.tool_calls.len(), recorded.result.tool_calls.len());
+        assert_eq!(replayed.result.tool_calls[0].name, "Read");
+        assert_eq!(replayed.stdout, recorded.stdout);
+        assert_eq!(replayed.exit_code, recorded.exit_code);
+
+        // A follow-up that wasn't recorded doesn't replay
+        let other = vec!["Read main".to_string(), "Now delete it".to_string()];
+        let err = harness.execute_conversation(None, &other, config).unwrap_err();
+        assert!(err.to_string().contains("no recording"), "{}", err);
+    }
+
+    #[test]
+    fn test_replay_without_recording_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay = ReplayAgent::new(Arc::new(mock()), dir.path());
+
+        let err = replay.execute("Never recorded", &ExecutionConfig::new()).unwrap_err();
+        let err = err.downcast::<HarnessError>().unwrap();
+        assert!(matches!(err, HarnessError::SessionNotFound { ref agent, .. } if agent == "claude"));
+    }
+
+    #[test]
+    fn test_recording_key_depends_on_inputs() {
+        let dir = Path::new("recordings");
+        let config = ExecutionConfig::new();
+        let path = recording_path(dir, "claude", "Fix it", &config);
+        assert!(path.starts_with(dir));
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("claude-"));
+
+        // The working directory and environment don't select recordings
+        let moved = config.clone().with_working_dir("/elsewhere".into()).with_env("CI", "1");
+        assert_eq!(recording_path(dir, "claude", "Fix it", &moved), path);
+
+        for other in [
+            recording_path(dir, "gemini", "Fix it", &config),
+            recording_path(dir, "claude", "Fix it!", &config),
+            recording_path(dir, "claude", "Fix it", &config.clone().with_model("haiku")),
+            recording_path(dir, "claude", "Fix it", &config.clone().with_system_prompt("Be brief")),
+        ] {
+            assert_ne!(other, path);
+        }
+    }
+}
@@ -1,12 +1,17 @@
 //! Configuration file support for aptitude.
 //!
 //! This module handles loading and discovering `.aptitude.yaml` configuration files.
+//! `.aptitude.toml` and `.aptitude.json` are accepted too, with the same fields.
 
 use anyhow::{Context, Result};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
+use crate::fluent::glob_match;
+use crate::parser::ToolCall;
+
 /// Default configuration embedded at compile time.
 const DEFAULT_CONFIG_STR: &str = include_str!("../default.aptitude.yaml");
 
@@ -19,8 +24,11 @@ fn default_config() -> &'static Config {
     })
 }
 
+/// Config file names, in the order they are looked for in each directory.
+const CONFIG_FILE_NAMES: [&str; 3] = [".aptitude.yaml", ".aptitude.toml", ".aptitude.json"];
+
 /// Configuration for test discovery.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct Config {
     /// Glob pattern for matching test files.
     pub test_pattern: String,
@@ -34,6 +42,23 @@ pub struct Config {
 
     /// Directories to exclude from scanning.
     pub exclude: Vec<String>,
+
+    /// Treat `called_after`/`called_before` as `immediately_after`/
+    /// `immediately_before` in every test. Assertions can opt out with
+    /// `strict_order: false`.
+    #[serde(default)]
+    pub ordering_strict: bool,
+
+    /// Glob patterns for tool calls to leave out of assertions, e.g.
+    /// `**/node_modules/**`. A call is ignored if any of its string
+    /// parameters matches.
+    #[serde(default)]
+    pub ignore_params: Vec<String>,
+
+    /// Extra agent tool name -> canonical name mappings, e.g.
+    /// `my_custom_read: Read`, added to the agent's own mapping.
+    #[serde(default)]
+    pub tool_mappings: HashMap<String, String>,
 }
 
 impl Default for Config {
@@ -73,6 +98,31 @@ impl Config {
         self
     }
 
+    /// Drop tool calls matching any `ignore_params` pattern.
+    ///
+    /// Apply this before running assertions so ignored calls count toward
+    /// neither positive nor negative assertions.
+    pub fn filter_ignored(&self, tool_calls: Vec<ToolCall>) -> Vec<ToolCall> {
+        if self.ignore_params.is_empty() {
+            return tool_calls;
+        }
+        tool_calls
+            .into_iter()
+            .filter(|call| !self.is_ignored(call))
+            .collect()
+    }
+
+    fn is_ignored(&self, call: &ToolCall) -> bool {
+        let Some(params) = call.params.as_object() else {
+            return false;
+        };
+        params.values().filter_map(|v| v.as_str()).any(|value| {
+            self.ignore_params
+                .iter()
+                .any(|pattern| glob_match(pattern, value))
+        })
+    }
+
     /// Get the search directory, resolving root relative to config_dir if needed.
     pub fn search_dir(&self, base_dir: &Path, config_dir: Option<&Path>) -> PathBuf {
         match (&self.root, config_dir) {
@@ -84,13 +134,18 @@ impl Config {
 }
 
 /// Search for a config file starting from start_dir and walking up to root.
+///
+/// In each directory, `.aptitude.yaml` wins over `.aptitude.toml`, which
+/// wins over `.aptitude.json`.
 fn find_config_file(start: &Path) -> Option<PathBuf> {
     let mut current = start.canonicalize().ok()?;
 
     loop {
-        let candidate = current.join(".aptitude.yaml");
-        if candidate.exists() {
-            return Some(candidate);
+        for name in CONFIG_FILE_NAMES {
+            let candidate = current.join(name);
+            if candidate.exists() {
+                return Some(candidate);
+            }
         }
 
         if !current.pop() {
@@ -99,12 +154,18 @@ fn find_config_file(start: &Path) -> Option<PathBuf> {
     }
 }
 
-/// Load and parse a config file.
+/// Load and parse a config file, choosing the format by extension.
+///
+/// `.toml` and `.json` files use their own parsers; anything else is YAML.
 fn load_config(path: &Path) -> Result<Config> {
     let content = std::fs::read_to_string(path)
         .with_context(|| format!("Failed to read config file: {:?}", path))?;
-    let config: Config = serde_yaml::from_str(&content)
-        .with_context(|| format!("Failed to parse config file: {:?}", path))?;
+    let config: Config = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(anyhow::Error::from),
+        Some("json") => serde_json::from_str(&content).map_err(anyhow::Error::from),
+        _ => serde_yaml::from_str(&content).map_err(anyhow::Error::from),
+    }
+    .with_context(|| format!("Failed to parse config file: {:?}", path))?;
     Ok(config)
 }
 
@@ -118,6 +179,21 @@ mod tests {
         assert_eq!(config.test_pattern, "*.aptitude.{yaml,yml}");
         assert!(config.recursive);
         assert!(config.exclude.contains(&"target".to_string()));
+        assert!(!config.ordering_strict);
+    }
+
+    #[test]
+    fn test_ordering_strict_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".aptitude.yaml");
+        std::fs::write(
+            &path,
+            "test_pattern: \"*.yaml\"\nrecursive: true\nexclude: []\nordering_strict: true\n",
+        )
+        .unwrap();
+
+        let (config, _) = Config::load(&path).unwrap();
+        assert!(config.ordering_strict);
     }
 
     #[test]
@@ -130,8 +206,10 @@ mod tests {
 
     #[test]
     fn test_search_dir_with_root() {
-        let mut config = Config::default();
-        config.root = Some(PathBuf::from("tests"));
+        let config = Config {
+            root: Some(PathBuf::from("tests")),
+            ..Config::default()
+        };
 
         let base = Path::new("/project");
         let config_dir = Path::new("/project/subdir");
@@ -146,4 +224,127 @@ mod tests {
 
         assert_eq!(config.search_dir(base, None), PathBuf::from("/project/tests"));
     }
+
+    #[test]
+    fn test_load_config_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            (
+                ".aptitude.yaml",
+                "test_pattern: \"*.test.yaml\"\nroot: tests\nrecursive: false\nexclude: [target]\nordering_strict: true\nignore_params: [\"**/vendor/**\"]\ntool_mappings:\n  my_read: Read\n",
+            ),
+            (
+                ".aptitude.toml",
+                "test_pattern = \"*.test.yaml\"\nroot = \"tests\"\nrecursive = false\nexclude = [\"target\"]\nordering_strict = true\nignore_params = [\"**/vendor/**\"]\n\n[tool_mappings]\nmy_read = \"Read\"\n",
+            ),
+            (
+                ".aptitude.json",
+                r#"{"test_pattern": "*.test.yaml", "root": "tests", "recursive": false, "exclude": ["target"], "ordering_strict": true, "ignore_params": ["**/vendor/**"], "tool_mappings": {"my_read": "Read"}}"#,
+            ),
+        ];
+
+        let configs: Vec<Config> = files
+            .iter()
+            .map(|(name, content)| {
+                let path = dir.path().join(name);
+                std::fs::write(&path, content).unwrap();
+                Config::load(&path).unwrap().0
+            })
+            .collect();
+
+        assert_eq!(configs[0].test_pattern, "*.test.yaml");
+        assert_eq!(configs[0].root, Some(PathBuf::from("tests")));
+        assert_eq!(configs[0].tool_mappings["my_read"], "Read");
+        assert_eq!(configs[0], configs[1]);
+        assert_eq!(configs[0], configs[2]);
+    }
+
+    #[test]
+    fn test_discover_prefers_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(".aptitude.json"),
+            r#"{"test_pattern": "*.json-test", "recursive": true, "exclude": []}"#,
+        )
+        .unwrap();
+
+        let nested = dir.path().join("tests");
+        std::fs::create_dir(&nested).unwrap();
+        let (config, _) = Config::discover(&nested).unwrap();
+        assert_eq!(config.test_pattern, "*.json-test");
+
+        std::fs::write(
+            dir.path().join(".aptitude.yaml"),
+            "test_pattern: \"*.yaml-test\"\nrecursive: true\nexclude: []\n",
+        )
+        .unwrap();
+        let (config, _) = Config::discover(&nested).unwrap();
+        assert_eq!(config.test_pattern, "*.yaml-test");
+    }
+
+    #[test]
+    fn test_tool_mappings_normalize_calls() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".aptitude.yaml");
+        std::fs::write(
+            &path,
+            "test_pattern: \"*.yaml\"\nrecursive: true\nexclude: []\ntool_mappings:\n  my_custom_read: Read\n  fs_read: Glob\n",
+        )
+        .unwrap();
+        let (config, _) = Config::load(&path).unwrap();
+        assert!(Config::default().tool_mappings.is_empty());
+
+        let mut mapping = crate::agents::ToolNameMapping::new();
+        mapping.add("fs_read", "Read").add("execute_bash", "Bash");
+        mapping.extend(&config.tool_mappings);
+
+        assert_eq!(mapping.to_canonical("my_custom_read"), "Read");
+        // Configured mappings override the agent's own
+        assert_eq!(mapping.to_canonical("fs_read"), "Glob");
+        assert_eq!(mapping.to_canonical("execute_bash"), "Bash");
+        assert_eq!(mapping.to_canonical("unmapped_tool"), "unmapped_tool");
+    }
+
+    fn read(path: &str) -> ToolCall {
+        ToolCall {
+            name: "Read".to_string(),
+            params: serde_json::json!({"file_path": path}),
+            timestamp: String::new(),
+            result: None,
+        }
+    }
+
+    #[test]
+    fn test_filter_ignored_in_assertions() {
+        let config = Config {
+            ignore_params: vec!["**/node_modules/**".to_string()],
+            ..Config::default()
+        };
+        let calls = config.filter_ignored(vec![
+            read("/proj/src/index.js"),
+            read("/proj/node_modules/left-pad/index.js"),
+        ]);
+        assert_eq!(calls.len(), 1);
+
+        let yaml = r#"
+name: Ignore node_modules
+prompt: Read the entry point
+assertions:
+  - tool: Read
+    call_count: 1
+  - tool: Read
+    called: false
+    params:
+      file_path: "node_modules"
+"#;
+        let test: crate::yaml::Test = serde_yaml::from_str(yaml).unwrap();
+        let results = crate::yaml::run_yaml_test(&test, &calls, &None, None);
+        assert!(results.iter().all(|(_, r)| r.is_pass()), "{:?}", results);
+    }
+
+    #[test]
+    fn test_filter_ignored_without_patterns() {
+        let calls = vec![read("/proj/node_modules/a.js")];
+        assert_eq!(Config::default().filter_ignored(calls).len(), 1);
+    }
 }
@@ -0,0 +1,279 @@
+//! Compare the tool calls of two sessions.
+//!
+//! [`diff_tool_calls`] lines up two sessions by call signature (tool name
+//! and params; timestamps and results are ignored) using a longest common
+//! subsequence. Calls outside it are reported as added or removed, except
+//! that a call removed from one place and added at another is reported once,
+//! as reordered.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use aptitude::diff::{diff_tool_calls, Change};
+//! use aptitude::parse_session;
+//!
+//! let before = parse_session("before.jsonl".as_ref())?;
+//! let after = parse_session("after.jsonl".as_ref())?;
+//! let diff = diff_tool_calls(&before, &after);
+//! for change in &diff.changes {
+//!     if let Change::Added { after } = change {
+//!         println!("+ {}", diff.after[*after].name);
+//!     }
+//! }
+//! ```
+
+use crate::parser::ToolCall;
+
+/// How one tool call differs between two sessions.
+///
+/// Indices are 0-based positions in the `before` and `after` sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// In both sessions, in the same order relative to the other unchanged calls.
+    Unchanged { before: usize, after: usize },
+    /// Only in the `after` session.
+    Added { after: usize },
+    /// Only in the `before` session.
+    Removed { before: usize },
+    /// In both sessions, but in a different order.
+    Reordered { before: usize, after: usize },
+}
+
+/// The result of [`diff_tool_calls`].
+#[derive(Debug, Clone)]
+pub struct SessionDiff<'a> {
+    pub before: &'a [ToolCall],
+    pub after: &'a [ToolCall],
+    /// Every call of both sessions, in `after` order with removed calls at
+    /// the point they were dropped. Reordered calls appear at their new position.
+    pub changes: Vec<Change>,
+}
+
+impl<'a> SessionDiff<'a> {
+    /// Whether both sessions made the same calls in the same order.
+    pub fn is_empty(&self) -> bool {
+        self.changes
+            .iter()
+            .all(|c| matches!(c, Change::Unchanged { .. }))
+    }
+
+    /// The call a change refers to, from `after` when it has a position there.
+    pub fn call(&self, change: &Change) -> &'a ToolCall {
+        match *change {
+            Change::Unchanged { after, .. }
+            | Change::Added { after }
+            | Change::Reordered { after, .. } => &self.after[after],
+            Change::Removed { before } => &self.before[before],
+        }
+    }
+
+    /// Number of added calls.
+    pub fn added(&self) -> usize {
+        self.count(|c| matches!(c, Change::Added { .. }))
+    }
+
+    /// Number of removed calls.
+    pub fn removed(&self) -> usize {
+        self.count(|c| matches!(c, Change::Removed { .. }))
+    }
+
+    /// Number of reordered calls.
+    pub fn reordered(&self) -> usize {
+        self.count(|c| matches!(c, Change::Reordered { .. }))
+    }
+
+    fn count(&self, f: impl Fn(&Change) -> bool) -> usize {
+        self.changes.iter().filter(|c| f(c)).count()
+    }
+}
+
+/// Whether two calls have the same signature: tool name and params.
+fn same_call(a: &ToolCall, b: &ToolCall) -> bool {
+    a.name == b.name && a.params == b.params
+}
+
+/// Diff the tool calls of two sessions by name and params.
+///
+/// Tool names should already be canonical (see
+/// [`ToolNameMapping`](crate::agents::ToolNameMapping)) when the sessions
+/// come from different agents.
+pub fn diff_tool_calls<'a>(before: &'a [ToolCall], after: &'a [ToolCall]) -> SessionDiff<'a> {
+    let (n, m) = (before.len(), after.len());
+
+    // lcs[i][j]: longest common subsequence of before[i..] and after[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if same_call(&before[i], &after[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && same_call(&before[i], &after[j]) {
+            changes.push(Change::Unchanged { before: i, after: j });
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Removals first on ties, like `diff`
+            changes.push(Change::Removed { before: i });
+            i += 1;
+        } else {
+            changes.push(Change::Added { after: j });
+            j += 1;
+        }
+    }
+
+    // A call both removed and added moved rather than changed: keep it once,
+    // at its new position
+    let mut moved_from = vec![None; changes.len()];
+    let mut paired = vec![false; changes.len()];
+    for k in 0..changes.len() {
+        let Change::Added { after: a } = changes[k] else {
+            continue;
+        };
+        let removed = (0..changes.len()).find(|&r| {
+            !paired[r]
+                && matches!(changes[r], Change::Removed { before: b } if same_call(&before[b], &after[a]))
+        });
+        if let Some(r) = removed {
+            paired[r] = true;
+            moved_from[k] = Some(r);
+        }
+    }
+
+    let changes = changes
+        .iter()
+        .enumerate()
+        .filter(|(k, _)| !paired[*k])
+        .map(|(k, change)| match (change, moved_from[k].map(|r| changes[r])) {
+            (Change::Added { after }, Some(Change::Removed { before })) => Change::Reordered {
+                before,
+                after: *after,
+            },
+            _ => *change,
+        })
+        .collect();
+
+    SessionDiff {
+        before,
+        after,
+        changes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn calls(names: &[&str]) -> Vec<ToolCall> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, spec)| {
+                let (name, path) = spec.split_once(' ').unwrap_or((spec, ""));
+                ToolCall {
+                    name: name.to_string(),
+                    params: json!({ "file_path": path }),
+                    timestamp: format!("2024-01-19T12:00:{:02}Z", i),
+                    result: None,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_sessions() {
+        let before = calls(&["Read a", "Edit a"]);
+        // Timestamps differ, but only name and params are compared
+        let mut after = calls(&["Read a", "Edit a"]);
+        after[0].timestamp = "2025-06-01T00:00:00Z".to_string();
+
+        let diff = diff_tool_calls(&before, &after);
+        assert!(diff.is_empty());
+        assert_eq!(
+            diff.changes,
+            vec![
+                Change::Unchanged { before: 0, after: 0 },
+                Change::Unchanged { before: 1, after: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_insertions_and_removals() {
+        let before = calls(&["Read a", "Bash", "Edit a"]);
+        let after = calls(&["Read a", "Glob", "Edit a", "Read b"]);
+
+        let diff = diff_tool_calls(&before, &after);
+        assert_eq!(
+            diff.changes,
+            vec![
+                Change::Unchanged { before: 0, after: 0 },
+                Change::Removed { before: 1 },
+                Change::Added { after: 1 },
+                Change::Unchanged { before: 2, after: 2 },
+                Change::Added { after: 3 },
+            ]
+        );
+        assert_eq!((diff.added(), diff.removed(), diff.reordered()), (2, 1, 0));
+        assert_eq!(diff.call(&diff.changes[1]).name, "Bash");
+        assert_eq!(diff.call(&diff.changes[4]).params["file_path"], "b");
+    }
+
+    #[test]
+    fn test_params_distinguish_calls() {
+        let before = calls(&["Read a"]);
+        let after = calls(&["Read b"]);
+
+        let diff = diff_tool_calls(&before, &after);
+        assert_eq!(
+            diff.changes,
+            vec![Change::Removed { before: 0 }, Change::Added { after: 0 }]
+        );
+    }
+
+    #[test]
+    fn test_reordering() {
+        let before = calls(&["Read a", "Read b", "Edit a"]);
+        let after = calls(&["Read b", "Edit a", "Read a"]);
+
+        let diff = diff_tool_calls(&before, &after);
+        assert_eq!(
+            diff.changes,
+            vec![
+                Change::Unchanged { before: 1, after: 0 },
+                Change::Unchanged { before: 2, after: 1 },
+                Change::Reordered { before: 0, after: 2 },
+            ]
+        );
+        assert!(!diff.is_empty());
+        assert_eq!((diff.added(), diff.removed(), diff.reordered()), (0, 0, 1));
+    }
+
+    #[test]
+    fn test_repeated_calls_pair_once() {
+        // One extra Read of the same file is an addition, not a reorder
+        let before = calls(&["Edit a", "Read a"]);
+        let after = calls(&["Read a", "Edit a", "Read a"]);
+
+        let diff = diff_tool_calls(&before, &after);
+        assert_eq!((diff.added(), diff.removed(), diff.reordered()), (1, 0, 0));
+    }
+
+    #[test]
+    fn test_empty_sessions() {
+        let diff = diff_tool_calls(&[], &[]);
+        assert!(diff.is_empty() && diff.changes.is_empty());
+
+        let after = calls(&["Read a"]);
+        let diff = diff_tool_calls(&[], &after);
+        assert_eq!(diff.changes, vec![Change::Added { after: 0 }]);
+    }
+}
@@ -1,12 +1,19 @@
 //! Test file discovery using glob patterns and walkdir.
 
 use anyhow::Result;
-use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
 use walkdir::WalkDir;
 
 use crate::config::Config;
 
+/// Number of hex digits in a [`test_id`].
+const ID_LEN: usize = 8;
+
 /// Discover test files in a directory according to config.
+///
+/// The result is sorted by path, whatever order the filesystem lists
+/// entries in.
 pub fn discover_tests(dir: &Path, config: &Config) -> Result<Vec<PathBuf>> {
     let mut tests = Vec::new();
 
@@ -32,6 +39,39 @@ pub fn discover_tests(dir: &Path, config: &Config) -> Result<Vec<PathBuf>> {
     Ok(tests)
 }
 
+/// Directory that [`test_id`]s are relative to: the directory holding the
+/// config file when there is one, otherwise the current directory.
+///
+/// This stays the same whichever directory or file is passed to `run`, so a
+/// test keeps its id across invocations.
+pub fn id_root(config_dir: Option<&Path>) -> PathBuf {
+    config_dir
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default()
+}
+
+/// Short stable id for a test file: the leading hex digits of the SHA-256
+/// of its path relative to `root` (see [`id_root`]), joined with `/` on
+/// every platform.
+///
+/// Both paths are canonicalized when they exist, so `tests/a.yaml` and
+/// `./tests/../tests/a.yaml` get the same id.
+pub fn test_id(root: &Path, path: &Path) -> String {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let relative = path.strip_prefix(&root).unwrap_or(&path);
+    let key: Vec<_> = relative
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    let digest = Sha256::digest(key.join("/").as_bytes());
+    let mut id: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    id.truncate(ID_LEN);
+    id
+}
+
 /// Check if a file name matches the glob pattern (with brace expansion).
 fn matches_pattern(path: &Path, pattern: &str) -> bool {
     let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
@@ -50,7 +90,7 @@ fn matches_pattern(path: &Path, pattern: &str) -> bool {
 }
 
 /// Expand brace expressions: "*.{yaml,yml}" -> ["*.yaml", "*.yml"]
-fn expand_braces(pattern: &str) -> Vec<String> {
+pub(crate) fn expand_braces(pattern: &str) -> Vec<String> {
     let Some(start) = pattern.find('{') else {
         return vec![pattern.to_string()];
     };
@@ -72,7 +112,7 @@ fn expand_braces(pattern: &str) -> Vec<String> {
 fn is_excluded(path: &Path, excludes: &[String]) -> bool {
     path.components().any(|c| {
         matches!(c, std::path::Component::Normal(name)
-            if name.to_str().map_or(false, |s| excludes.iter().any(|e| e == s)))
+            if name.to_str().is_some_and(|s| excludes.iter().any(|e| e == s)))
     })
 }
 
@@ -96,6 +136,60 @@ mod tests {
         assert!(!matches_pattern(Path::new("/foo/test.yaml"), "*.test.yaml"));
     }
 
+    #[test]
+    fn test_discover_tests_order_is_stable() {
+        let dir = tempfile::tempdir().unwrap();
+        // Create files in an order unrelated to the sorted one
+        for name in ["z/read.test.yaml", "a/read.test.yaml", "m.test.yaml", "a/b/read.test.yaml"] {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, "").unwrap();
+        }
+        let config = Config {
+            test_pattern: "*.test.yaml".to_string(),
+            ..Config::default()
+        };
+
+        let found: Vec<_> = discover_tests(dir.path(), &config)
+            .unwrap()
+            .iter()
+            .map(|p| p.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                PathBuf::from("a/b/read.test.yaml"),
+                PathBuf::from("a/read.test.yaml"),
+                PathBuf::from("m.test.yaml"),
+                PathBuf::from("z/read.test.yaml"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_test_id() {
+        let id = test_id(Path::new("/repo/tests"), Path::new("/repo/tests/auth/login.yaml"));
+        assert_eq!(id.len(), ID_LEN);
+        assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+        // Depends only on the path relative to the root
+        assert_eq!(id, test_id(Path::new("/elsewhere"), Path::new("/elsewhere/auth/login.yaml")));
+        assert_eq!(id, test_id(Path::new("."), Path::new("./auth/login.yaml")));
+        assert_ne!(id, test_id(Path::new("/repo/tests"), Path::new("/repo/tests/auth/logout.yaml")));
+    }
+
+    #[test]
+    fn test_test_id_resolves_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("auth").join("login.yaml");
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, "").unwrap();
+
+        let id = test_id(dir.path(), &file);
+        assert_eq!(id, test_id(Path::new("/repo/tests"), Path::new("/repo/tests/auth/login.yaml")));
+        let roundabout = dir.path().join("auth").join("..").join("auth").join("login.yaml");
+        assert_eq!(id, test_id(&dir.path().join("."), &roundabout));
+    }
+
     #[test]
     fn test_is_excluded() {
         let excludes = vec!["target".to_string(), "node_modules".to_string()];
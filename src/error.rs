@@ -0,0 +1,106 @@
+//! Error type for running agents through the harness.
+//!
+//! [`AgentHarness`](crate::AgentHarness) execution and
+//! [`PromptBuilder`](crate::PromptBuilder) runs return [`HarnessError`], so
+//! tests can tell a missing agent apart from a failed run or an unreadable
+//! session. It converts into `anyhow::Error` with `?` where the distinction
+//! doesn't matter.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use aptitude::{prompt, HarnessError};
+//!
+//! match prompt("Read config.json").run() {
+//!     Ok(tool_calls) => println!("{} calls", tool_calls.len()),
+//!     Err(HarnessError::AgentUnavailable(agent)) => eprintln!("skipping: {} not installed", agent),
+//!     Err(e) => panic!("{}", e),
+//! }
+//! ```
+
+/// Why running an agent failed.
+#[derive(Debug, thiserror::Error)]
+pub enum HarnessError {
+    /// No agent is registered under this type or name.
+    #[error("Agent not registered: '{0}'")]
+    AgentNotRegistered(String),
+
+    /// The agent's CLI is not installed.
+    #[error("Agent '{0}' is not available on this system")]
+    AgentUnavailable(String),
+
+    /// The agent can't do what was asked, e.g. follow-up turns.
+    #[error("Agent '{agent}' does not support {feature}")]
+    Unsupported { agent: String, feature: String },
+
+    /// The request itself is invalid, e.g. a conversation with no prompts.
+    #[error("{0}")]
+    InvalidRequest(String),
+
+    /// The agent process could not be run, or timed out.
+    #[error("{agent} failed to run: {cause:#}")]
+    ExecutionFailed { agent: String, cause: anyhow::Error },
+
+    /// The agent ran but its session log could not be found.
+    #[error("Could not find {agent} session log: {detail}")]
+    SessionNotFound { agent: String, detail: String },
+
+    /// The session log was found but could not be parsed.
+    #[error("Failed to parse {agent} session: {cause:#}")]
+    ParseError { agent: String, cause: anyhow::Error },
+}
+
+impl HarnessError {
+    /// Keep a `HarnessError` an agent adapter returned inside `err`, or wrap
+    /// `err` with `wrap` otherwise.
+    ///
+    /// Adapters return `anyhow::Result`; the ones that know more specifically
+    /// what went wrong (like a missing session log) return a `HarnessError`.
+    pub(crate) fn from_agent(err: anyhow::Error, wrap: impl FnOnce(anyhow::Error) -> Self) -> Self {
+        match err.downcast::<HarnessError>() {
+            Ok(e) => e,
+            Err(err) => wrap(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_from_agent_keeps_typed_errors() {
+        let typed = anyhow::Error::new(HarnessError::SessionNotFound {
+            agent: "claude".to_string(),
+            detail: "no session logs in /tmp".to_string(),
+        });
+        let err = HarnessError::from_agent(typed, |cause| HarnessError::ExecutionFailed {
+            agent: "claude".to_string(),
+            cause,
+        });
+        assert!(matches!(err, HarnessError::SessionNotFound { ref agent, .. } if agent == "claude"));
+
+        let untyped: anyhow::Result<()> = Err(anyhow::anyhow!("exit 1")).context("Failed to execute claude command");
+        let err = HarnessError::from_agent(untyped.unwrap_err(), |cause| HarnessError::ExecutionFailed {
+            agent: "claude".to_string(),
+            cause,
+        });
+        assert!(matches!(err, HarnessError::ExecutionFailed { .. }));
+        assert_eq!(err.to_string(), "claude failed to run: Failed to execute claude command: exit 1");
+    }
+
+    #[test]
+    fn test_converts_to_anyhow() {
+        let run = || -> anyhow::Result<()> {
+            Err(HarnessError::AgentUnavailable("kiro".to_string()))?;
+            Ok(())
+        };
+        let err = run().unwrap_err();
+        assert_eq!(err.to_string(), "Agent 'kiro' is not available on this system");
+        assert!(matches!(
+            err.downcast_ref::<HarnessError>(),
+            Some(HarnessError::AgentUnavailable(_))
+        ));
+    }
+}
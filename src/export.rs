@@ -0,0 +1,101 @@
+//! CSV export of tool calls, for analysis in a spreadsheet.
+//!
+//! Each call becomes one row of `timestamp,tool,primary_param`, where the
+//! primary parameter is the one [`OutputFormatter::format_params`] shows
+//! (see [`primary_param`]), written in full.
+//!
+//! [`OutputFormatter::format_params`]: crate::output::OutputFormatter::format_params
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use aptitude::export::write_csv;
+//!
+//! let file = std::fs::File::create("calls.csv")?;
+//! write_csv(&tool_calls, file)?;
+//! ```
+
+use std::io::Write;
+
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::output::primary_param;
+use crate::parser::ToolCall;
+
+/// Column names of the header row.
+pub const CSV_HEADER: [&str; 3] = ["timestamp", "tool", "primary_param"];
+
+/// Write `calls` as CSV, with a header row, to `writer`.
+///
+/// Fields with commas, quotes or newlines are quoted and escaped. String
+/// params are written as-is; other values as JSON.
+pub fn write_csv<W: Write>(calls: &[ToolCall], writer: W) -> Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(CSV_HEADER)?;
+    for call in calls {
+        let param = match primary_param(&call.params) {
+            Some(Value::String(s)) => s.clone(),
+            Some(other) => other.to_string(),
+            None => String::new(),
+        };
+        csv.write_record([call.timestamp.as_str(), call.name.as_str(), param.as_str()])?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(name: &str, params: Value) -> ToolCall {
+        ToolCall {
+            name: name.to_string(),
+            params,
+            timestamp: "2024-01-19T12:00:00Z".to_string(),
+            result: None,
+        }
+    }
+
+    #[test]
+    fn test_write_csv_round_trips() {
+        let calls = vec![
+            call("Read", json!({"file_path": "/tmp/a,b.txt", "limit": 5})),
+            call("Bash", json!({"command": "echo \"hi, there\"\nls"})),
+            call("Task", json!({"count": 3})),
+            call("Glob", json!({})),
+        ];
+
+        let mut out = Vec::new();
+        write_csv(&calls, &mut out).unwrap();
+
+        let mut reader = csv::Reader::from_reader(out.as_slice());
+        assert_eq!(reader.headers().unwrap(), &csv::StringRecord::from(CSV_HEADER.to_vec()));
+        let rows: Vec<Vec<String>> = reader
+            .records()
+            .map(|r| r.unwrap().iter().map(str::to_string).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["2024-01-19T12:00:00Z", "Read", "/tmp/a,b.txt"],
+                vec!["2024-01-19T12:00:00Z", "Bash", "echo \"hi, there\"\nls"],
+                vec!["2024-01-19T12:00:00Z", "Task", "3"],
+                vec!["2024-01-19T12:00:00Z", "Glob", ""],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_csv_escapes_quotes() {
+        let mut out = Vec::new();
+        write_csv(&[call("Bash", json!({"command": "say \"a,b\""}))], &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(
+            text,
+            "timestamp,tool,primary_param\n2024-01-19T12:00:00Z,Bash,\"say \"\"a,b\"\"\"\n"
+        );
+    }
+}
@@ -5,15 +5,27 @@
 //! - `expect_tools()` - Entry point for creating assertions from tool calls only
 //! - `ExecutionExpectation` - Holds execution output and creates specific assertions
 //! - `ToolAssertion` - Builder for assertions on a specific tool
-
-use std::collections::HashMap;
+//! - `SequenceAssertion` - Builder for assertions on an ordered pattern of tools
+//! - `FilesAssertion` - Builder for assertions on the distinct files read or written
+//! - `ToolCallsAssertion` - Builder for assertions on the whole tool-call sequence
+//! - `ExitCodeAssertion` - Builder for assertions on the agent's exit code
+//! - `UsageAssertion` - Builder for assertions on token and cost usage
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
+
+use regex::Regex;
 
 use crate::agents::{Agent, ExecutionOutput};
-use crate::parser::ToolCall;
-use super::matchers::params_match;
+use crate::parser::{ToolCall, UsageStats};
+use crate::snapshot;
+use super::matchers::{glob_match, json_contains, params_match, path_params_match, MatchMode};
+use super::exit_code::ExitCodeAssertion;
 use super::stdout::StdoutAssertion;
-use super::Tool;
+use super::usage::UsageAssertion;
+use super::{Tool, ToolMatcher};
 
 /// Result of evaluating an assertion.
 #[derive(Debug, Clone)]
@@ -46,6 +58,46 @@ impl AssertionResult {
     }
 }
 
+/// Collect assertion results, returning `Err` with every failure if any failed.
+///
+/// This is the non-panicking counterpart to the fluent `to_*` methods, for
+/// embedding aptitude in a larger harness. Accepts anything convertible into
+/// an [`AssertionResult`], including YAML runner results.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use aptitude::{assert_no_failures, expect, Tool};
+///
+/// let verdict = assert_no_failures([
+///     expect(&output).tool(Tool::Read).evaluate(),
+///     expect(&output).tool(Tool::Bash).evaluate_not_called(),
+/// ]);
+///
+/// if let Err(failures) = verdict {
+///     for f in &failures {
+///         eprintln!("{}: {}", f.description, f.reason.as_deref().unwrap_or(""));
+///     }
+/// }
+/// ```
+pub fn assert_no_failures<I, T>(results: I) -> Result<(), Vec<AssertionResult>>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<AssertionResult>,
+{
+    let failures: Vec<AssertionResult> = results
+        .into_iter()
+        .map(Into::into)
+        .filter(|r| !r.passed)
+        .collect();
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
+
 /// Create an expectation on execution output.
 ///
 /// This is the entry point for the fluent assertion API.
@@ -88,6 +140,9 @@ pub fn expect_tools(tool_calls: &[ToolCall]) -> ExecutionExpectation {
 pub struct ExecutionExpectation {
     tool_calls: Vec<ToolCall>,
     stdout: Option<String>,
+    exit_code: Option<i32>,
+    usage: Option<UsageStats>,
+    working_dir: Option<PathBuf>,
     grader: Option<Arc<dyn Agent>>,
 }
 
@@ -96,6 +151,9 @@ impl std::fmt::Debug for ExecutionExpectation {
         f.debug_struct("ExecutionExpectation")
             .field("tool_calls", &self.tool_calls)
             .field("stdout", &self.stdout)
+            .field("exit_code", &self.exit_code)
+            .field("usage", &self.usage)
+            .field("working_dir", &self.working_dir)
             .field("grader", &self.grader.as_ref().map(|g| g.name()))
             .finish()
     }
@@ -107,6 +165,9 @@ impl ExecutionExpectation {
         Self {
             tool_calls: output.result.tool_calls.clone(),
             stdout: output.stdout.clone(),
+            exit_code: output.exit_code,
+            usage: output.usage,
+            working_dir: output.working_dir.clone(),
             grader: None,
         }
     }
@@ -116,6 +177,9 @@ impl ExecutionExpectation {
         Self {
             tool_calls: tool_calls.to_vec(),
             stdout: None,
+            exit_code: None,
+            usage: None,
+            working_dir: None,
             grader: None,
         }
     }
@@ -149,354 +213,1325 @@ impl ExecutionExpectation {
     ///     .to_be_called();
     /// ```
     pub fn tool(&self, tool: Tool) -> ToolAssertion {
-        ToolAssertion::new(self.tool_calls.clone(), tool)
+        self.tool_assertion(tool.into())
     }
 
-    /// Create an assertion for stdout content.
+    /// Create an assertion for a tool by name, for tools [`Tool`] doesn't
+    /// cover: custom subagent or plugin tools, and MCP tools
+    /// (`mcp__<server>__<tool>`). Names are compared exactly.
     ///
-    /// If a grading agent has been set via `.with_grader()`, it will be
-    /// automatically passed to the `StdoutAssertion`.
+    /// A trailing `*` matches every tool with that prefix, e.g.
+    /// `mcp__github__*` for any tool from the `github` MCP server. See
+    /// [`ToolMatcher`] to build matchers directly.
     ///
     /// # Example
     ///
     /// ```rust,ignore
     /// expect(&output)
-    ///     .with_grader(agent)
-    ///     .stdout()
-    ///     .review("should confirm success")
-    ///     .to_pass();
+    ///     .tool_named("mcp__github__create_issue")
+    ///     .to_be_called_once();
+    ///
+    /// expect(&output)
+    ///     .tool_named("mcp__slack__*")
+    ///     .not_to_be_called();
     /// ```
-    pub fn stdout(&self) -> StdoutAssertion {
-        let mut assertion = StdoutAssertion::new(self.stdout.clone());
-        if let Some(grader) = &self.grader {
-            assertion = assertion.with_grader(grader.clone());
-        }
-        assertion
+    pub fn tool_named(&self, name: &str) -> ToolAssertion {
+        self.tool_assertion(ToolMatcher::named(name))
     }
-}
-
-/// Builder for assertions on a specific tool.
-///
-/// Methods like `to_be_called()` evaluate immediately and panic on failure.
-/// Use `evaluate()` for non-panicking evaluation.
-#[derive(Debug, Clone)]
-pub struct ToolAssertion {
-    tool_calls: Vec<ToolCall>,
-    tool: Tool,
-    params: Option<HashMap<String, String>>,
-    expected_count: Option<usize>,
-    min_count: Option<usize>,
-    max_count: Option<usize>,
-    after_tool: Option<Tool>,
-    before_tool: Option<Tool>,
-}
 
-impl ToolAssertion {
-    /// Create a new tool assertion.
-    pub fn new(tool_calls: Vec<ToolCall>, tool: Tool) -> Self {
-        Self {
-            tool_calls,
-            tool,
-            params: None,
-            expected_count: None,
-            min_count: None,
-            max_count: None,
-            after_tool: None,
-            before_tool: None,
-        }
+    /// A tool assertion that resolves relative paths against the agent's
+    /// working directory, if known.
+    fn tool_assertion(&self, tool: ToolMatcher) -> ToolAssertion {
+        let mut assertion = ToolAssertion::new(self.tool_calls.clone(), tool);
+        assertion.path_base = self.working_dir.clone();
+        assertion
     }
 
-    // =========================================================================
-    // Builder methods (chainable)
-    // =========================================================================
-
-    /// Set parameter expectations for matching.
-    ///
-    /// Parameters use regex matching. Use `.*` for wildcards, escape special chars with `\`.
+    /// Create an assertion that tools were called in the given order.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// use aptitude::params;
+    /// expect(&output)
+    ///     .sequence(&[Tool::Glob, Tool::Read, Tool::Edit])
+    ///     .to_occur();
+    /// ```
+    pub fn sequence(&self, tools: &[Tool]) -> SequenceAssertion {
+        SequenceAssertion::new(self.tool_calls.clone(), tools.to_vec())
+    }
+
+    /// Create an assertion on the commands run with `Bash`.
     ///
-    /// expect(&tool_calls)
-    ///     .tool(Tool::Read)
-    ///     .with_params(params!{"file_path" => r".*\.txt"})
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&output)
+    ///     .bash()
+    ///     .ran_containing("cargo test")
     ///     .to_be_called();
     /// ```
-    pub fn with_params(mut self, params: HashMap<String, String>) -> Self {
-        self.params = Some(params);
-        self
+    pub fn bash(&self) -> BashAssertion {
+        BashAssertion::new(&self.tool_calls)
     }
 
-    /// Assert the tool was called exactly N times.
+    /// Create an assertion on the distinct files read with `Read`.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// expect(&tool_calls)
-    ///     .tool(Tool::Read)
-    ///     .times(3)
-    ///     .to_be_called();
+    /// expect(&output).files_read().count_at_most(3);
     /// ```
-    pub fn times(mut self, n: usize) -> Self {
-        self.expected_count = Some(n);
-        self
+    pub fn files_read(&self) -> FilesAssertion {
+        FilesAssertion::read(&self.tool_calls)
     }
 
-    /// Assert the tool was called at least N times.
+    /// Create an assertion on the distinct files changed with `Write`, `Edit`
+    /// or `NotebookEdit`.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// expect(&tool_calls)
-    ///     .tool(Tool::Read)
-    ///     .at_least(2)
-    ///     .to_be_called();
+    /// expect(&output).files_written().to_include("src/**/*.rs");
     /// ```
-    pub fn at_least(mut self, n: usize) -> Self {
-        self.min_count = Some(n);
-        self
+    pub fn files_written(&self) -> FilesAssertion {
+        FilesAssertion::written(&self.tool_calls)
     }
 
-    /// Assert the tool was called at most N times.
+    /// Create an assertion on the whole tool-call sequence, e.g. against a snapshot.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// expect(&tool_calls)
-    ///     .tool(Tool::Read)
-    ///     .at_most(5)
-    ///     .to_be_called();
+    /// expect(&output).tool_calls().matches_snapshot("fix_failing_test");
     /// ```
-    pub fn at_most(mut self, n: usize) -> Self {
-        self.max_count = Some(n);
-        self
+    pub fn tool_calls(&self) -> ToolCallsAssertion {
+        ToolCallsAssertion::new(&self.tool_calls)
     }
 
-    /// Assert this tool was called after another tool.
+    /// Create an assertion for stdout content.
+    ///
+    /// If a grading agent has been set via `.with_grader()`, it will be
+    /// automatically passed to the `StdoutAssertion`.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// expect(&tool_calls)
-    ///     .tool(Tool::Write)
-    ///     .after(Tool::Read)
-    ///     .to_be_called();
+    /// expect(&output)
+    ///     .with_grader(agent)
+    ///     .stdout()
+    ///     .review("should confirm success")
+    ///     .to_pass();
     /// ```
-    pub fn after(mut self, tool: Tool) -> Self {
-        self.after_tool = Some(tool);
-        self
+    pub fn stdout(&self) -> StdoutAssertion {
+        let mut assertion = StdoutAssertion::new(self.stdout.clone());
+        if let Some(grader) = &self.grader {
+            assertion = assertion.with_grader(grader.clone());
+        }
+        assertion
     }
 
-    /// Assert this tool was called before another tool.
+    /// Create an assertion for the agent's exit code.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// expect(&tool_calls)
-    ///     .tool(Tool::Read)
-    ///     .before(Tool::Write)
-    ///     .to_be_called();
+    /// expect(&output).exit_code().to_be(0);
     /// ```
-    pub fn before(mut self, tool: Tool) -> Self {
-        self.before_tool = Some(tool);
-        self
+    pub fn exit_code(&self) -> ExitCodeAssertion {
+        ExitCodeAssertion::new(self.exit_code)
+    }
+
+    /// Create an assertion for token and cost usage.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&output).usage().tokens_under(50_000);
+    /// ```
+    pub fn usage(&self) -> UsageAssertion {
+        UsageAssertion::new(self.usage)
     }
 
     // =========================================================================
-    // Assertion methods (panic on failure)
+    // Policy assertions (panic on failure)
     // =========================================================================
 
-    /// Assert the tool was called.
-    ///
-    /// Panics with a detailed error message if the assertion fails.
+    /// Assert the agent made no web requests (`WebFetch` or `WebSearch`).
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// expect(&tool_calls)
-    ///     .tool(Tool::Read)
-    ///     .to_be_called();
+    /// expect(&output).no_web_access();
     /// ```
     ///
     /// # Panics
     ///
-    /// Panics if the tool was not called (or doesn't match params/ordering).
-    pub fn to_be_called(&self) {
-        let result = self.evaluate_called(true);
+    /// Panics with the offending call if any web tool was used.
+    pub fn no_web_access(&self) {
+        let result = self.evaluate_no_web_access();
         if !result.passed {
             self.panic_with_context(&result);
         }
     }
 
-    /// Assert the tool was NOT called.
-    ///
-    /// Panics with a detailed error message if the tool was called.
+    /// Assert the agent answered without calling any tools.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// expect(&tool_calls)
-    ///     .tool(Tool::Bash)
-    ///     .not_to_be_called();
+    /// expect(&output).no_tools_called();
     /// ```
     ///
     /// # Panics
     ///
-    /// Panics if the tool was called (matching any specified params).
-    pub fn not_to_be_called(&self) {
-        let result = self.evaluate_called(false);
+    /// Panics listing the calls made if there were any.
+    pub fn no_tools_called(&self) {
+        let result = self.evaluate_no_tools_called();
         if !result.passed {
             self.panic_with_context(&result);
         }
     }
 
-    // =========================================================================
-    // nth_call pattern
-    // =========================================================================
-
-    /// Get the nth call (1-indexed) of this tool for further assertions.
-    ///
-    /// Returns a `NthCallAssertion` builder for making assertions about
-    /// that specific call.
+    /// Assert the agent only called tools from an allowlist.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// use aptitude::params;
-    ///
-    /// expect(&tool_calls)
-    ///     .tool(Tool::Read)
-    ///     .nth_call(1)
-    ///     .has_params(params!{"file_path" => "/first.txt"});
+    /// expect(&output).only_tools_called(&[Tool::Read, Tool::Glob]);
     /// ```
     ///
     /// # Panics
     ///
-    /// Panics if the nth call doesn't exist.
-    pub fn nth_call(&self, n: usize) -> NthCallAssertion {
-        let matching_calls: Vec<&ToolCall> = self
-            .tool_calls
-            .iter()
-            .filter(|c| c.name == self.tool.as_str())
-            .collect();
-
-        if n == 0 || n > matching_calls.len() {
-            panic!(
-                "assertion failed: expected {} call #{} to exist\n\n  actual: {} calls made\n{}",
-                self.tool,
-                n,
-                matching_calls.len(),
-                format_tool_calls(&self.tool_calls)
-            );
+    /// Panics with every call to a tool outside `allowed`.
+    pub fn only_tools_called(&self, allowed: &[Tool]) {
+        let result = self.evaluate_only_tools_called(allowed);
+        if !result.passed {
+            self.panic_with_context(&result);
         }
-
-        let call = matching_calls[n - 1];
-        NthCallAssertion::new(call.clone(), self.tool, n, self.tool_calls.clone())
     }
 
-    /// Get the last call of this tool for further assertions.
+    /// Assert the agent made no network access of any kind.
+    ///
+    /// In addition to the checks of [`no_web_access`](Self::no_web_access),
+    /// this scans `Bash` commands for `curl`, `wget`, `nc`, and `ssh`.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// expect(&tool_calls)
-    ///     .tool(Tool::Read)
-    ///     .last_call()
-    ///     .has_params(params!{"file_path" => "/last.txt"});
+    /// expect(&output).no_network();
     /// ```
     ///
     /// # Panics
     ///
-    /// Panics if the tool was never called.
-    pub fn last_call(&self) -> NthCallAssertion {
-        let matching_calls: Vec<&ToolCall> = self
+    /// Panics with the offending call if any network access was found.
+    pub fn no_network(&self) {
+        let result = self.evaluate_no_network();
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
+    // =========================================================================
+    // Non-panicking evaluation
+    // =========================================================================
+
+    /// Evaluate that no web tools were called, without panicking.
+    pub fn evaluate_no_web_access(&self) -> AssertionResult {
+        let description = "no web access";
+        match self.find_web_call() {
+            Some((i, call)) => AssertionResult::fail(
+                description,
+                format!(
+                    "call #{} used '{}' with {}",
+                    i + 1,
+                    call.name,
+                    call.params
+                ),
+            ),
+            None => AssertionResult::pass(description),
+        }
+    }
+
+    /// Evaluate that no tools were called, without panicking.
+    pub fn evaluate_no_tools_called(&self) -> AssertionResult {
+        let description = "no tools called";
+        if self.tool_calls.is_empty() {
+            return AssertionResult::pass(description);
+        }
+
+        let names: Vec<&str> = self.tool_calls.iter().map(|c| c.name.as_str()).collect();
+        let calls = if names.len() == 1 { "call" } else { "calls" };
+        AssertionResult::fail(
+            description,
+            format!("{} tool {} made: {}", names.len(), calls, names.join(", ")),
+        )
+    }
+
+    /// Evaluate that only tools from `allowed` were called, without panicking.
+    pub fn evaluate_only_tools_called(&self, allowed: &[Tool]) -> AssertionResult {
+        let names: Vec<&str> = allowed.iter().map(|t| t.as_str()).collect();
+        let description = format!("only [{}] called", names.join(", "));
+
+        let offending: Vec<String> = self
             .tool_calls
             .iter()
-            .filter(|c| c.name == self.tool.as_str())
+            .enumerate()
+            .filter(|(_, c)| !names.contains(&c.name.as_str()))
+            .map(|(i, c)| format!("call #{} used '{}' with {}", i + 1, c.name, c.params))
             .collect();
 
-        if matching_calls.is_empty() {
-            panic!(
-                "assertion failed: expected {} to have been called\n\n  actual: 0 calls made\n{}",
-                self.tool,
-                format_tool_calls(&self.tool_calls)
+        if offending.is_empty() {
+            AssertionResult::pass(description)
+        } else {
+            AssertionResult::fail(description, offending.join("; "))
+        }
+    }
+
+    /// Evaluate that no network access occurred, without panicking.
+    pub fn evaluate_no_network(&self) -> AssertionResult {
+        let description = "no network access";
+        if let Some((i, call)) = self.find_web_call() {
+            return AssertionResult::fail(
+                description,
+                format!(
+                    "call #{} used '{}' with {}",
+                    i + 1,
+                    call.name,
+                    call.params
+                ),
             );
         }
 
-        let n = matching_calls.len();
-        let call = matching_calls[n - 1];
-        NthCallAssertion::new(call.clone(), self.tool, n, self.tool_calls.clone())
+        let network_re = Regex::new(NETWORK_COMMAND_PATTERN).expect("valid network pattern");
+        let offending = self.tool_calls.iter().enumerate().find(|(_, c)| {
+            c.name == Tool::Bash.as_str()
+                && c.params
+                    .get("command")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|cmd| network_re.is_match(cmd))
+        });
+
+        match offending {
+            Some((i, call)) => AssertionResult::fail(
+                description,
+                format!(
+                    "call #{} ran network command: {}",
+                    i + 1,
+                    call.params.get("command").and_then(|v| v.as_str()).unwrap_or_default()
+                ),
+            ),
+            None => AssertionResult::pass(description),
+        }
     }
 
     // =========================================================================
-    // Non-panicking evaluation
+    // Introspection (not assertions)
     // =========================================================================
 
-    /// Evaluate the assertion without panicking (expects tool to be called).
-    ///
-    /// Returns an `AssertionResult` that can be inspected.
+    /// The distinct tool names called in the session, sorted.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// let result = expect(&tool_calls)
-    ///     .tool(Tool::Read)
-    ///     .evaluate();
-    ///
-    /// if !result.passed {
-    ///     println!("Failed: {}", result.reason.unwrap());
-    /// }
+    /// let tools = expect(&output).called_tools();
+    /// assert!(tools.contains(&"Read".to_string()), "tools used: {:?}", tools);
     /// ```
-    pub fn evaluate(&self) -> AssertionResult {
-        self.evaluate_called(true)
+    pub fn called_tools(&self) -> Vec<String> {
+        let names: BTreeSet<&str> = self.tool_calls.iter().map(|c| c.name.as_str()).collect();
+        names.into_iter().map(String::from).collect()
     }
 
-    /// Evaluate that the tool was NOT called, without panicking.
-    ///
-    /// Returns an `AssertionResult` that can be inspected.
+    /// How many times each tool was called, keyed by tool name.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// let result = expect(&tool_calls)
-    ///     .tool(Tool::Bash)
-    ///     .evaluate_not_called();
-    ///
-    /// assert!(result.passed);
+    /// let counts = expect(&output).tool_call_counts();
+    /// let reads = counts.get("Read").copied().unwrap_or(0);
     /// ```
-    pub fn evaluate_not_called(&self) -> AssertionResult {
-        self.evaluate_called(false)
+    pub fn tool_call_counts(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for call in &self.tool_calls {
+            *counts.entry(call.name.clone()).or_insert(0) += 1;
+        }
+        counts
     }
 
     // =========================================================================
     // Internal helpers
     // =========================================================================
 
-    fn get_matching_calls(&self) -> Vec<&ToolCall> {
-        self.tool_calls
-            .iter()
-            .filter(|c| c.name == self.tool.as_str())
-            .filter(|c| {
-                if let Some(params) = &self.params {
-                    params_match(params, &c.params)
-                } else {
-                    true
-                }
-            })
-            .collect()
+    fn find_web_call(&self) -> Option<(usize, &ToolCall)> {
+        self.tool_calls.iter().enumerate().find(|(_, c)| {
+            c.name == Tool::WebFetch.as_str() || c.name == Tool::WebSearch.as_str()
+        })
     }
 
-    fn evaluate_called(&self, should_be_called: bool) -> AssertionResult {
-        let matching_calls = self.get_matching_calls();
-        let count = matching_calls.len();
-        let was_called = count > 0;
-
-        // Collect all failures - check ALL constraints
-        let mut failures: Vec<String> = Vec::new();
-
-        // Check called/not called first (fundamental constraint)
+    fn panic_with_context(&self, result: &AssertionResult) -> ! {
+        let reason = result.reason.as_deref().unwrap_or("unknown reason");
+        panic!(
+            "assertion failed: expected {}\n\n  reason: {}\n{}",
+            result.description,
+            reason,
+            format_tool_calls(&self.tool_calls)
+        );
+    }
+}
+
+/// Custom parameter check set via [`ToolAssertion::matching`].
+type ParamPredicate = Arc<dyn Fn(&serde_json::Value) -> bool + Send + Sync>;
+
+/// Shell commands treated as network access by [`ExecutionExpectation::no_network`].
+///
+/// The command may be given by path (`/usr/bin/curl`) or after a wrapper
+/// such as `env` or `sudo`.
+const NETWORK_COMMAND_PATTERN: &str = r"(^|[\s;&|(`])(\S*/)?(curl|wget|nc|ssh)(\s|$)";
+
+/// Builder for assertions on a specific tool.
+///
+/// Methods like `to_be_called()` evaluate immediately and panic on failure.
+/// Use `evaluate()` for non-panicking evaluation.
+#[derive(Clone)]
+pub struct ToolAssertion {
+    tool_calls: Vec<ToolCall>,
+    tool: ToolMatcher,
+    params: Option<HashMap<String, String>>,
+    match_mode: MatchMode,
+    match_paths: bool,
+    path_base: Option<PathBuf>,
+    json_params: Option<serde_json::Value>,
+    absent_params: Vec<String>,
+    params_not_matching: HashMap<String, String>,
+    expected_count: Option<usize>,
+    min_count: Option<usize>,
+    max_count: Option<usize>,
+    after_tool: Option<ToolMatcher>,
+    before_tool: Option<ToolMatcher>,
+    immediately_after_tool: Option<ToolMatcher>,
+    immediately_before_tool: Option<ToolMatcher>,
+    within_of: Option<(ToolMatcher, Duration)>,
+    param_sequence: Option<(String, Vec<String>)>,
+    sorted_param: Option<String>,
+    distinct_param: Option<String>,
+    url_domain: Option<String>,
+    cell_index: Option<usize>,
+    commands: Vec<CommandFilter>,
+    predicates: Vec<ParamPredicate>,
+}
+
+impl std::fmt::Debug for ToolAssertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolAssertion")
+            .field("tool_calls", &self.tool_calls)
+            .field("tool", &self.tool)
+            .field("params", &self.params)
+            .field("match_mode", &self.match_mode)
+            .field("match_paths", &self.match_paths)
+            .field("path_base", &self.path_base)
+            .field("json_params", &self.json_params)
+            .field("absent_params", &self.absent_params)
+            .field("params_not_matching", &self.params_not_matching)
+            .field("expected_count", &self.expected_count)
+            .field("min_count", &self.min_count)
+            .field("max_count", &self.max_count)
+            .field("after_tool", &self.after_tool)
+            .field("before_tool", &self.before_tool)
+            .field("immediately_after_tool", &self.immediately_after_tool)
+            .field("immediately_before_tool", &self.immediately_before_tool)
+            .field("within_of", &self.within_of)
+            .field("param_sequence", &self.param_sequence)
+            .field("sorted_param", &self.sorted_param)
+            .field("distinct_param", &self.distinct_param)
+            .field("url_domain", &self.url_domain)
+            .field("cell_index", &self.cell_index)
+            .field("commands", &self.commands)
+            .field("predicates", &self.predicates.len())
+            .finish()
+    }
+}
+
+impl ToolAssertion {
+    /// Create a new tool assertion for a [`Tool`] or any [`ToolMatcher`].
+    pub fn new(tool_calls: Vec<ToolCall>, tool: impl Into<ToolMatcher>) -> Self {
+        Self {
+            tool_calls,
+            tool: tool.into(),
+            params: None,
+            match_mode: MatchMode::Regex,
+            match_paths: false,
+            path_base: None,
+            json_params: None,
+            absent_params: Vec::new(),
+            params_not_matching: HashMap::new(),
+            expected_count: None,
+            min_count: None,
+            max_count: None,
+            after_tool: None,
+            before_tool: None,
+            immediately_after_tool: None,
+            immediately_before_tool: None,
+            within_of: None,
+            param_sequence: None,
+            sorted_param: None,
+            distinct_param: None,
+            url_domain: None,
+            cell_index: None,
+            commands: Vec::new(),
+            predicates: Vec::new(),
+        }
+    }
+
+    // =========================================================================
+    // Builder methods (chainable)
+    // =========================================================================
+
+    /// Set parameter expectations for matching.
+    ///
+    /// Parameters use regex matching. Use `.*` for wildcards, escape special chars with `\`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use aptitude::params;
+    ///
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .with_params(params!{"file_path" => r".*\.txt"})
+    ///     .to_be_called();
+    /// ```
+    pub fn with_params(mut self, params: HashMap<String, String>) -> Self {
+        self.params = Some(params);
+        self.match_mode = MatchMode::Regex;
+        self.match_paths = false;
+        self
+    }
+
+    /// Set parameter expectations compared literally rather than as regex.
+    ///
+    /// Strings must be identical; numbers and booleans are compared by value.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .with_exact_params(params!{"file_path" => "/tmp/a.txt"})
+    ///     .to_be_called();
+    /// ```
+    pub fn with_exact_params(mut self, params: HashMap<String, String>) -> Self {
+        self.params = Some(params);
+        self.match_mode = MatchMode::Exact;
+        self.match_paths = false;
+        self
+    }
+
+    /// Set parameter expectations using glob patterns.
+    ///
+    /// Supports `*`, `**/` and `{a,b}` alternatives; invalid globs fall back
+    /// to exact match.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .with_glob_params(params!{"file_path" => "*.env"})
+    ///     .not_to_be_called();
+    /// ```
+    pub fn with_glob_params(mut self, params: HashMap<String, String>) -> Self {
+        self.params = Some(params);
+        self.match_mode = MatchMode::Glob;
+        self.match_paths = false;
+        self
+    }
+
+    /// Set parameter expectations compared as file paths.
+    ///
+    /// Both the expected and actual paths are normalized before comparing:
+    /// `~` is expanded, relative paths are resolved against the working
+    /// directory (see [`in_workdir`](Self::in_workdir)), and `.`/`..` are
+    /// resolved. So `~/proj/src/main.rs`,
+    /// `/home/user/proj/src/main.rs` and `./src/main.rs` all match.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .with_path_params(params!{"file_path" => "./src/main.rs"})
+    ///     .in_workdir("/home/user/proj")
+    ///     .to_be_called();
+    /// ```
+    pub fn with_path_params(mut self, params: HashMap<String, String>) -> Self {
+        self.params = Some(params);
+        self.match_paths = true;
+        self
+    }
+
+    /// Resolve relative paths against `dir` when matching with
+    /// [`with_path_params`](Self::with_path_params).
+    ///
+    /// Defaults to the agent's working directory when the assertion was
+    /// built from an [`ExecutionOutput`] that records one, and to the
+    /// current directory otherwise. The other params matchers compare values
+    /// as written, so they ignore this.
+    pub fn in_workdir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.path_base = Some(dir.into());
+        self
+    }
+
+    /// Set parameter expectations with an explicit [`MatchMode`].
+    pub fn with_params_matching(mut self, params: HashMap<String, String>, mode: MatchMode) -> Self {
+        self.params = Some(params);
+        self.match_mode = mode;
+        self.match_paths = false;
+        self
+    }
+
+    /// Set parameter expectations compared structurally as JSON.
+    ///
+    /// Use this for nested or non-string params, like `{"options":
+    /// {"recursive": true}}`, which the string matchers can only see as
+    /// stringified JSON. Values match if they contain the expected structure
+    /// (see [`json_contains`]). Combines with `with_params` and the other
+    /// string matchers; a call must satisfy both.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use aptitude::params_json;
+    ///
+    /// expect(&tool_calls)
+    ///     .tool_named("list_files")
+    ///     .with_json_params(params_json!{"options" => {"recursive": true}})
+    ///     .to_be_called();
+    /// ```
+    pub fn with_json_params(self, params: HashMap<String, serde_json::Value>) -> Self {
+        self.with_params_containing(serde_json::Value::Object(params.into_iter().collect()))
+    }
+
+    /// Only consider calls whose params contain `expected` as a subset.
+    ///
+    /// Handy for tools with large input objects: name only the part you care
+    /// about, at any depth. See [`json_contains`] for the rules. Merges with
+    /// any earlier `with_json_params` or `with_params_containing`, so a call
+    /// must contain both; where both give a scalar for the same key, the
+    /// later one wins.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool_named("mcp__github__create_issue")
+    ///     .with_params_containing(json!({"labels": ["bug"], "body": {"template": "crash"}}))
+    ///     .to_be_called();
+    /// ```
+    pub fn with_params_containing(mut self, expected: serde_json::Value) -> Self {
+        match &mut self.json_params {
+            Some(existing) => merge_json(existing, expected),
+            None => self.json_params = Some(expected),
+        }
+        self
+    }
+
+    /// Require every call to this tool to lack the `key` param.
+    ///
+    /// Unlike `with_params`, this doesn't narrow which calls count: when the
+    /// tool is expected to be called, any call that has `key` fails the
+    /// assertion.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Bash)
+    ///     .without_param("run_in_background")
+    ///     .to_be_called();
+    /// ```
+    pub fn without_param(mut self, key: &str) -> Self {
+        self.absent_params.push(key.to_string());
+        self
+    }
+
+    /// Require every call to this tool to have a `key` param that is absent
+    /// or doesn't match `pattern`.
+    ///
+    /// The pattern is compared with the same [`MatchMode`] as `with_params`
+    /// (regex unless `with_exact_params`, `with_glob_params` or
+    /// `with_path_params` was used), so
+    /// it reads as the inverse of `with_params` for one key. Like
+    /// [`without_param`](Self::without_param), it is checked on every call
+    /// that `with_params` and the other filters keep, and one matching call
+    /// fails the assertion.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // Bash was called, and no call ran `rm -rf`
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Bash)
+    ///     .param_not_matching("command", r"rm\s+-rf")
+    ///     .to_be_called();
+    /// ```
+    pub fn param_not_matching(mut self, key: &str, pattern: &str) -> Self {
+        self.params_not_matching
+            .insert(key.to_string(), pattern.to_string());
+        self
+    }
+
+    /// Only consider calls whose params satisfy a custom predicate.
+    ///
+    /// For checks that patterns can't express, such as numeric comparisons
+    /// or array contents. Combines with `with_params` and other `matching`
+    /// calls; a call must satisfy all of them. This is Rust-API only: YAML
+    /// tests have no equivalent.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Bash)
+    ///     .matching(|p| p["timeout"].as_u64().is_some_and(|t| t > 5000))
+    ///     .to_be_called();
+    /// ```
+    pub fn matching(
+        mut self,
+        pred: impl Fn(&serde_json::Value) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.predicates.push(Arc::new(pred));
+        self
+    }
+
+    /// Only consider calls whose `url` param is on `domain` or one of its subdomains.
+    ///
+    /// Intended for `WebFetch`: the URL is parsed and its host compared, so
+    /// `example.com` matches `https://docs.example.com/path` but not
+    /// `https://notexample.com` or `https://evil.com/?r=example.com`. Calls
+    /// without a parseable `url` never match.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::WebFetch)
+    ///     .with_url_domain("evil.com")
+    ///     .not_to_be_called();
+    /// ```
+    pub fn with_url_domain(mut self, domain: &str) -> Self {
+        self.url_domain = Some(domain.trim_end_matches('.').to_ascii_lowercase());
+        self
+    }
+
+    /// Only consider `NotebookEdit` calls that edit the cell at `index`.
+    ///
+    /// Compares the `cell_number` param, or `cell_id` for agents that
+    /// identify cells that way, so tests don't depend on which one the agent
+    /// sends. Numbers and numeric strings both count; calls with neither
+    /// param never match.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::NotebookEdit)
+    ///     .with_cell_index(3)
+    ///     .to_be_called();
+    /// ```
+    pub fn with_cell_index(mut self, index: usize) -> Self {
+        self.cell_index = Some(index);
+        self
+    }
+
+    /// Only consider calls whose `command` param passes `filter`. Used by
+    /// [`BashAssertion`] and YAML `bash` assertions.
+    pub(crate) fn with_command(mut self, filter: CommandFilter) -> Self {
+        self.commands.push(filter);
+        self
+    }
+
+    /// Assert the tool was called exactly N times.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .times(3)
+    ///     .to_be_called();
+    /// ```
+    pub fn times(mut self, n: usize) -> Self {
+        self.expected_count = Some(n);
+        self
+    }
+
+    /// Assert the tool was called at least N times.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .at_least(2)
+    ///     .to_be_called();
+    /// ```
+    pub fn at_least(mut self, n: usize) -> Self {
+        self.min_count = Some(n);
+        self
+    }
+
+    /// Assert the tool was called at most N times.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .at_most(5)
+    ///     .to_be_called();
+    /// ```
+    pub fn at_most(mut self, n: usize) -> Self {
+        self.max_count = Some(n);
+        self
+    }
+
+    /// Assert this tool was called after another tool.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Write)
+    ///     .after(Tool::Read)
+    ///     .to_be_called();
+    /// ```
+    pub fn after(mut self, tool: impl Into<ToolMatcher>) -> Self {
+        self.after_tool = Some(tool.into());
+        self
+    }
+
+    /// Assert this tool was called before another tool.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .before(Tool::Write)
+    ///     .to_be_called();
+    /// ```
+    pub fn before(mut self, tool: impl Into<ToolMatcher>) -> Self {
+        self.before_tool = Some(tool.into());
+        self
+    }
+
+    /// Assert this tool was called directly after another tool, with no
+    /// calls in between.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Edit)
+    ///     .immediately_after(Tool::Read)
+    ///     .to_be_called();
+    /// ```
+    pub fn immediately_after(mut self, tool: impl Into<ToolMatcher>) -> Self {
+        self.immediately_after_tool = Some(tool.into());
+        self
+    }
+
+    /// Assert this tool was called directly before another tool, with no
+    /// calls in between.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .with_params(params!{"file_path" => "AGENTS.md"})
+    ///     .immediately_before(Tool::Edit)
+    ///     .to_be_called();
+    /// ```
+    pub fn immediately_before(mut self, tool: impl Into<ToolMatcher>) -> Self {
+        self.immediately_before_tool = Some(tool.into());
+        self
+    }
+
+    /// Assert this tool was called within `max` of the most recent preceding
+    /// call to another tool, going by session log timestamps.
+    ///
+    /// Passes if any matching call qualifies. Fails with "timestamps
+    /// unavailable" when the calls involved have no timestamps (Kiro
+    /// doesn't record them).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Edit)
+    ///     .within_of(Tool::Read, Duration::from_secs(5))
+    ///     .to_be_called();
+    /// ```
+    pub fn within_of(mut self, tool: impl Into<ToolMatcher>, max: Duration) -> Self {
+        self.within_of = Some((tool.into(), max));
+        self
+    }
+
+    /// Assert the values of a parameter across this tool's calls, in call order.
+    ///
+    /// Only calls matching any `with_params` filter are considered.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .param_sequence("file_path", &["/a.txt", "/b.txt", "/c.txt"])
+    ///     .to_be_called();
+    /// ```
+    pub fn param_sequence(mut self, key: &str, expected: &[&str]) -> Self {
+        let expected = expected.iter().map(|s| s.to_string()).collect();
+        self.param_sequence = Some((key.to_string(), expected));
+        self
+    }
+
+    /// Assert the values of a parameter across this tool's calls are in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .param_sequence_sorted("file_path")
+    ///     .to_be_called();
+    /// ```
+    pub fn param_sequence_sorted(mut self, key: &str) -> Self {
+        self.sorted_param = Some(key.to_string());
+        self
+    }
+
+    /// Assert no two of this tool's calls share a value for a parameter.
+    ///
+    /// Only calls matching any `with_params` filter are considered; calls
+    /// without the parameter are ignored. Catches wasteful repetition like
+    /// reading the same file twice.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .with_distinct_params("file_path")
+    ///     .to_be_called();
+    /// ```
+    pub fn with_distinct_params(mut self, key: &str) -> Self {
+        self.distinct_param = Some(key.to_string());
+        self
+    }
+
+    // =========================================================================
+    // Assertion methods (panic on failure)
+    // =========================================================================
+
+    /// Assert the tool was called.
+    ///
+    /// Panics with a detailed error message if the assertion fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .to_be_called();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tool was not called (or doesn't match params/ordering).
+    pub fn to_be_called(&self) {
+        let result = self.evaluate_called(true);
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
+    /// Assert the tool was NOT called.
+    ///
+    /// Panics with a detailed error message if the tool was called.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Bash)
+    ///     .not_to_be_called();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tool was called (matching any specified params).
+    pub fn not_to_be_called(&self) {
+        let result = self.evaluate_called(false);
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
+    /// Assert the tool was called exactly once.
+    ///
+    /// Shorthand for `.to_be_called_exactly(1)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tool was not called exactly once (or doesn't match params/ordering).
+    pub fn to_be_called_once(&self) {
+        self.to_be_called_exactly(1);
+    }
+
+    /// Assert the tool was called exactly N times.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .to_be_called_exactly(2);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the call count differs (or calls don't match params/ordering).
+    pub fn to_be_called_exactly(&self, n: usize) {
+        let result = self.evaluate_exactly(n);
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
+    /// Assert the tool was called, and keep the matching calls for inspection.
+    ///
+    /// Checks the same as `to_be_called()`. The returned
+    /// [`CalledToolAssertion`] only holds calls that passed every filter
+    /// (`with_params`, `matching`, ...), so its `first()` and `last()` don't
+    /// need the filters repeated.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Bash)
+    ///     .with_params(params!{"command" => "^cargo test"})
+    ///     .assert_called()
+    ///     .last()
+    ///     .has_params(params!{"timeout" => "600000"});
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tool was not called (or doesn't match params/ordering).
+    pub fn assert_called(self) -> CalledToolAssertion {
+        self.to_be_called();
+        let (positions, matching_calls) = self
+            .get_indexed_matching_calls()
+            .into_iter()
+            .map(|(index, call)| (index + 1, call.clone()))
+            .unzip();
+        CalledToolAssertion {
+            tool: self.tool,
+            matching_calls,
+            positions,
+            all_calls: self.tool_calls,
+        }
+    }
+
+    /// Assert the tool was not called between two other tools.
+    ///
+    /// The window runs from the first call to `start` up to the first call
+    /// to `end` after it, or to the end of the session if `end` never
+    /// follows. Only the call filters (`with_params`, `matching`, ...)
+    /// apply; counts and ordering constraints are not checked. Passes if
+    /// `start` was never called.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // No shell commands between reading the guide and editing
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Bash)
+    ///     .not_called_between(Tool::Read, Tool::Edit);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if a matching call falls inside the window.
+    pub fn not_called_between(&self, start: impl Into<ToolMatcher>, end: impl Into<ToolMatcher>) {
+        let result = self.evaluate_not_called_between(start, end);
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
+    // =========================================================================
+    // nth_call pattern
+    // =========================================================================
+
+    /// Get the nth call (1-indexed) of this tool for further assertions.
+    ///
+    /// Returns a `NthCallAssertion` builder for making assertions about
+    /// that specific call. Like [`first_call`](Self::first_call) and
+    /// [`last_call`](Self::last_call), only calls matching `with_params` and
+    /// the other call filters are counted.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use aptitude::params;
+    ///
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .nth_call(1)
+    ///     .has_params(params!{"file_path" => "/first.txt"});
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the nth call doesn't exist.
+    pub fn nth_call(&self, n: usize) -> NthCallAssertion {
+        let matching_calls = self.get_indexed_matching_calls();
+        if n == 0 || n > matching_calls.len() {
+            panic!(
+                "assertion failed: expected {} call #{} to exist\n\n  actual: {} matching calls made\n{}",
+                self.tool,
+                n,
+                matching_calls.len(),
+                format_tool_calls(&self.tool_calls)
+            );
+        }
+        self.call_assertion(n, matching_calls[n - 1])
+    }
+
+    /// Get the first call of this tool for further assertions.
+    ///
+    /// Only calls matching `with_params` and the other call filters are
+    /// considered, as with [`nth_call`](Self::nth_call).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .first_call()
+    ///     .has_params(params!{"file_path" => "/first.txt"});
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if no matching call exists.
+    pub fn first_call(&self) -> NthCallAssertion {
+        let Some(&first) = self.get_indexed_matching_calls().first() else {
+            self.panic_never_called();
+        };
+        self.call_assertion(1, first)
+    }
+
+    /// Get the last call of this tool for further assertions.
+    ///
+    /// Only calls matching `with_params` and the other call filters are
+    /// considered, as with [`nth_call`](Self::nth_call).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .last_call()
+    ///     .has_params(params!{"file_path" => "/last.txt"});
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if no matching call exists.
+    pub fn last_call(&self) -> NthCallAssertion {
+        let matching_calls = self.get_indexed_matching_calls();
+        let Some(&last) = matching_calls.last() else {
+            self.panic_never_called();
+        };
+        self.call_assertion(matching_calls.len(), last)
+    }
+
+    /// Wrap the `n`th matching call, found at `(index, call)` in the session.
+    fn call_assertion(&self, n: usize, (index, call): (usize, &ToolCall)) -> NthCallAssertion {
+        NthCallAssertion::new(call.clone(), self.tool.clone(), n, index + 1, self.tool_calls.clone())
+    }
+
+    fn panic_never_called(&self) -> ! {
+        panic!(
+            "assertion failed: expected {} to have been called\n\n  actual: 0 matching calls made\n{}",
+            self.tool,
+            format_tool_calls(&self.tool_calls)
+        );
+    }
+
+    // =========================================================================
+    // Non-panicking evaluation
+    // =========================================================================
+
+    /// Evaluate the assertion without panicking (expects tool to be called).
+    ///
+    /// Returns an `AssertionResult` that can be inspected.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let result = expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .evaluate();
+    ///
+    /// if !result.passed {
+    ///     println!("Failed: {}", result.reason.unwrap());
+    /// }
+    /// ```
+    pub fn evaluate(&self) -> AssertionResult {
+        self.evaluate_called(true)
+    }
+
+    /// Evaluate that the tool was NOT called, without panicking.
+    ///
+    /// Returns an `AssertionResult` that can be inspected.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let result = expect(&tool_calls)
+    ///     .tool(Tool::Bash)
+    ///     .evaluate_not_called();
+    ///
+    /// assert!(result.passed);
+    /// ```
+    pub fn evaluate_not_called(&self) -> AssertionResult {
+        self.evaluate_called(false)
+    }
+
+    /// Evaluate that the tool was called exactly N times, without panicking.
+    ///
+    /// Any `.times()` set on the builder is replaced by `n`. Other constraints
+    /// (params, ordering, bounds) are still checked.
+    pub fn evaluate_exactly(&self, n: usize) -> AssertionResult {
+        let count = self.get_matching_calls().len();
+        let mut rest = self.clone();
+        rest.expected_count = None;
+        let base = rest.evaluate_called(n > 0);
+
+        let times = match n {
+            1 => "exactly once".to_string(),
+            n => format!("exactly {} times", n),
+        };
+        let description = format!("{} {}", base.description, times);
+
+        let mut failures = Vec::new();
+        if count != n {
+            failures.push(format!(
+                "expected {} to be called {}, got {}",
+                self.tool, times, count
+            ));
+        }
+        // Skip the called/not-called failure when it only restates the count mismatch
+        if (count > 0) == (n > 0) {
+            failures.extend(base.reason);
+        }
+
+        if failures.is_empty() {
+            AssertionResult::pass(description)
+        } else {
+            AssertionResult::fail(description, failures.join("; "))
+        }
+    }
+
+    /// Evaluate only the count bounds set with [`times`](Self::times),
+    /// [`at_least`](Self::at_least) and [`at_most`](Self::at_most), without panicking.
+    ///
+    /// Unlike [`evaluate`](Self::evaluate), the tool doesn't have to be called:
+    /// `.at_most(1)` passes with no matching calls. Params and other call
+    /// filters still decide which calls are counted.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let result = expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .with_params_matching(params, MatchMode::Glob)
+    ///     .at_most(1)
+    ///     .evaluate_count();
+    /// ```
+    pub fn evaluate_count(&self) -> AssertionResult {
+        let count = self.get_matching_calls().len();
+        let description = self.build_description(true);
+
+        let mut failures = Vec::new();
+        if let Some(expected) = self.expected_count.filter(|&n| n != count) {
+            failures.push(format!("expected {} calls, got {}", expected, count));
+        }
+        if let Some(min) = self.min_count.filter(|&n| count < n) {
+            failures.push(format!("expected at least {} calls, got {}", min, count));
+        }
+        if let Some(max) = self.max_count.filter(|&n| count > n) {
+            failures.push(format!("expected at most {} calls, got {}", max, count));
+        }
+
+        if failures.is_empty() {
+            AssertionResult::pass(description)
+        } else {
+            AssertionResult::fail(description, failures.join("; "))
+        }
+    }
+
+    /// Evaluate that the tool was not called between two other tools, without panicking.
+    ///
+    /// See [`not_called_between`](Self::not_called_between) for how the window is chosen.
+    pub fn evaluate_not_called_between(
+        &self,
+        start: impl Into<ToolMatcher>,
+        end: impl Into<ToolMatcher>,
+    ) -> AssertionResult {
+        let (start, end) = (start.into(), end.into());
+        let mut description = self.build_description(false);
+        description.push_str(&format!(" between {} and {}", start, end));
+
+        match self.check_not_called_between(&start, &end) {
+            None => AssertionResult::pass(description),
+            Some(err) => AssertionResult::fail(description, err),
+        }
+    }
+
+    // =========================================================================
+    // Internal helpers
+    // =========================================================================
+
+    fn get_matching_calls(&self) -> Vec<&ToolCall> {
+        self.tool_calls
+            .iter()
+            .filter(|c| self.is_matching_call(c))
+            .collect()
+    }
+
+    /// Matching calls with their 0-based index in the session.
+    fn get_indexed_matching_calls(&self) -> Vec<(usize, &ToolCall)> {
+        self.tool_calls
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| self.is_matching_call(c))
+            .collect()
+    }
+
+    fn evaluate_called(&self, should_be_called: bool) -> AssertionResult {
+        let matching_calls = self.get_matching_calls();
+        let count = matching_calls.len();
+        let was_called = count > 0;
+
+        // Collect all failures - check ALL constraints
+        let mut failures: Vec<String> = Vec::new();
+
+        // Check called/not called first (fundamental constraint)
         if should_be_called && !was_called {
             let param_desc = self
                 .params
@@ -512,142 +1547,1100 @@ impl ToolAssertion {
             ));
         }
 
-        // Check count constraints (always check if constraint is set)
-        if should_be_called {
-            if let Some(expected) = self.expected_count {
-                if count != expected {
-                    failures.push(format!("expected {} calls, got {}", expected, count));
-                }
-            }
-            if let Some(min) = self.min_count {
-                if count < min {
-                    failures.push(format!("expected at least {} calls, got {}", min, count));
-                }
-            }
-            if let Some(max) = self.max_count {
-                if count > max {
-                    failures.push(format!("expected at most {} calls, got {}", max, count));
+        // Check count constraints (always check if constraint is set)
+        if should_be_called {
+            if let Some(expected) = self.expected_count {
+                if count != expected {
+                    failures.push(format!("expected {} calls, got {}", expected, count));
+                }
+            }
+            if let Some(min) = self.min_count {
+                if count < min {
+                    failures.push(format!("expected at least {} calls, got {}", min, count));
+                }
+            }
+            if let Some(max) = self.max_count {
+                if count > max {
+                    failures.push(format!("expected at most {} calls, got {}", max, count));
+                }
+            }
+        }
+
+        // Check parameter sequence constraints
+        if should_be_called {
+            if let Some((key, expected)) = &self.param_sequence {
+                if let Some(err) = check_param_sequence(&matching_calls, key, expected) {
+                    failures.push(err);
+                }
+            }
+            if let Some(key) = &self.sorted_param {
+                if let Some(err) = check_param_sorted(&matching_calls, key) {
+                    failures.push(err);
+                }
+            }
+            if let Some(key) = &self.distinct_param {
+                if let Some(err) = check_param_distinct(&self.get_indexed_matching_calls(), key) {
+                    failures.push(err);
+                }
+            }
+            failures.extend(self.negative_param_violations());
+        }
+
+        // Check ordering constraints
+        if let Some(after) = &self.after_tool {
+            if let Some(err) = self.check_after(after) {
+                failures.push(err);
+            }
+        }
+        if let Some(before) = &self.before_tool {
+            if let Some(err) = self.check_before(before) {
+                failures.push(err);
+            }
+        }
+        if let Some(after) = &self.immediately_after_tool {
+            if let Some(err) = self.check_immediately_after(after) {
+                failures.push(err);
+            }
+        }
+        if let Some(before) = &self.immediately_before_tool {
+            if let Some(err) = self.check_immediately_before(before) {
+                failures.push(err);
+            }
+        }
+        if let Some((other, max)) = &self.within_of {
+            if let Some(err) = self.check_within_of(other, *max) {
+                failures.push(err);
+            }
+        }
+
+        // Build description
+        let description = self.build_description(should_be_called);
+
+        if failures.is_empty() {
+            AssertionResult::pass(description)
+        } else {
+            AssertionResult::fail(description, failures.join("; "))
+        }
+    }
+
+    /// Build a human-readable description of what this assertion checks.
+    fn build_description(&self, should_be_called: bool) -> String {
+        let mut parts = vec![self.tool.to_string()];
+
+        if let Some(params) = self.params.as_ref().filter(|p| !p.is_empty()) {
+            let param_str: Vec<String> = params
+                .iter()
+                .map(|(k, v)| format!("{}='{}'", k, v))
+                .collect();
+            parts.push(format!("with {}", param_str.join(", ")));
+        }
+        if !self.absent_params.is_empty() {
+            parts.push(format!("without {}", self.absent_params.join(", ")));
+        }
+        if !self.params_not_matching.is_empty() {
+            let param_str: Vec<String> = self
+                .params_not_matching
+                .iter()
+                .map(|(k, v)| format!("{} not matching '{}'", k, v))
+                .collect();
+            parts.push(format!("with {}", param_str.join(", ")));
+        }
+        match &self.json_params {
+            Some(serde_json::Value::Object(params)) => {
+                let param_str: Vec<String> = params
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect();
+                parts.push(format!("with {}", param_str.join(", ")));
+            }
+            Some(expected) => parts.push(format!("with params containing {}", expected)),
+            None => {}
+        }
+        if let Some(domain) = &self.url_domain {
+            parts.push(format!("on domain {}", domain));
+        }
+        if let Some(index) = self.cell_index {
+            parts.push(format!("on cell {}", index));
+        }
+        for filter in &self.commands {
+            parts.push(filter.to_string());
+        }
+        if !self.predicates.is_empty() {
+            parts.push("matching predicate".to_string());
+        }
+
+        if should_be_called {
+            parts.push("called".to_string());
+        } else {
+            parts.push("not called".to_string());
+        }
+
+        if let Some(after) = &self.after_tool {
+            parts.push(format!("after {}", after));
+        }
+        if let Some(before) = &self.before_tool {
+            parts.push(format!("before {}", before));
+        }
+        if let Some(after) = &self.immediately_after_tool {
+            parts.push(format!("immediately after {}", after));
+        }
+        if let Some(before) = &self.immediately_before_tool {
+            parts.push(format!("immediately before {}", before));
+        }
+        if let Some((other, max)) = &self.within_of {
+            parts.push(format!("within {:?} of {}", max, other));
+        }
+        if let Some(n) = self.expected_count {
+            parts.push(format!("{} times", n));
+        }
+        if let Some(n) = self.min_count {
+            parts.push(format!("at least {} times", n));
+        }
+        if let Some(n) = self.max_count {
+            parts.push(format!("at most {} times", n));
+        }
+        if let Some((key, expected)) = &self.param_sequence {
+            parts.push(format!("with {} sequence [{}]", key, expected.join(", ")));
+        }
+        if let Some(key) = &self.sorted_param {
+            parts.push(format!("with {} sorted", key));
+        }
+        if let Some(key) = &self.distinct_param {
+            parts.push(format!("with distinct {}", key));
+        }
+
+        parts.join(" ")
+    }
+
+    /// Check if tool was called after another tool. Returns error message if failed.
+    fn check_after(&self, after_tool: &ToolMatcher) -> Option<String> {
+        let mut seen_after = false;
+
+        for call in &self.tool_calls {
+            if after_tool.matches(&call.name) {
+                seen_after = true;
+            }
+            if seen_after && self.is_matching_call(call) {
+                return None; // Success
+            }
+        }
+
+        if !seen_after {
+            Some(format!("'{}' was never called", after_tool))
+        } else {
+            Some(format!("'{}' was not called after '{}'", self.tool, after_tool))
+        }
+    }
+
+    /// Check if tool was called before another tool. Returns error message if failed.
+    fn check_before(&self, before_tool: &ToolMatcher) -> Option<String> {
+        let mut seen_this = false;
+
+        for call in &self.tool_calls {
+            if self.is_matching_call(call) {
+                seen_this = true;
+            }
+            if before_tool.matches(&call.name) && seen_this {
+                return None; // Success
+            }
+        }
+
+        let this_called = self.tool_calls.iter().any(|c| self.tool.matches(&c.name));
+        let before_called = self
+            .tool_calls
+            .iter()
+            .any(|c| before_tool.matches(&c.name));
+
+        if !this_called {
+            Some(format!("'{}' was never called", self.tool))
+        } else if !before_called {
+            Some(format!("'{}' was never called", before_tool))
+        } else {
+            Some(format!("'{}' was not called before '{}'", self.tool, before_tool))
+        }
+    }
+
+    /// Check if tool was called directly after another tool. Returns error message if failed.
+    fn check_immediately_after(&self, after_tool: &ToolMatcher) -> Option<String> {
+        let adjacent = self.tool_calls.windows(2).any(|pair| {
+            after_tool.matches(&pair[0].name) && self.is_matching_call(&pair[1])
+        });
+        if adjacent {
+            return None;
+        }
+
+        if !self.tool_calls.iter().any(|c| after_tool.matches(&c.name)) {
+            Some(format!("'{}' was never called", after_tool))
+        } else {
+            Some(format!(
+                "'{}' was not called immediately after '{}'",
+                self.tool, after_tool
+            ))
+        }
+    }
+
+    /// Check if tool was called directly before another tool. Returns error message if failed.
+    fn check_immediately_before(&self, before_tool: &ToolMatcher) -> Option<String> {
+        let adjacent = self.tool_calls.windows(2).any(|pair| {
+            self.is_matching_call(&pair[0]) && before_tool.matches(&pair[1].name)
+        });
+        if adjacent {
+            return None;
+        }
+
+        if !self.tool_calls.iter().any(|c| before_tool.matches(&c.name)) {
+            Some(format!("'{}' was never called", before_tool))
+        } else {
+            Some(format!(
+                "'{}' was not called immediately before '{}'",
+                self.tool, before_tool
+            ))
+        }
+    }
+
+    /// Check if tool was called within `max` of the preceding call to
+    /// another tool. Returns error message if failed.
+    fn check_within_of(&self, other: &ToolMatcher, max: Duration) -> Option<String> {
+        // Pair each matching call with the most recent call to `other` before it
+        let mut previous: Option<&ToolCall> = None;
+        let mut pairs = Vec::new();
+        for call in &self.tool_calls {
+            if self.is_matching_call(call) {
+                if let Some(prev) = previous {
+                    pairs.push((prev, call));
+                }
+            }
+            if other.matches(&call.name) {
+                previous = Some(call);
+            }
+        }
+
+        if pairs.is_empty() {
+            return Some(format!("'{}' was not called after '{}'", self.tool, other));
+        }
+
+        let mut closest: Option<Duration> = None;
+        for (prev, call) in pairs {
+            let (Some(start), Some(end)) = (prev.time(), call.time()) else {
+                return Some(format!(
+                    "timestamps unavailable for '{}' and '{}' calls",
+                    other, self.tool
+                ));
+            };
+            // Calls are in session order, so a negative gap means the
+            // timestamps themselves are out of order and can't be trusted
+            let Ok(delta) = (end - start).to_std() else {
+                return Some(format!(
+                    "'{}' call is timestamped before the preceding '{}' call",
+                    self.tool, other
+                ));
+            };
+            closest = Some(closest.map_or(delta, |c| c.min(delta)));
+        }
+
+        let closest = closest?;
+        if closest <= max {
+            None
+        } else {
+            Some(format!(
+                "'{}' came {:?} after '{}' at the closest",
+                self.tool, closest, other
+            ))
+        }
+    }
+
+    /// Check the tool wasn't called between the first call to `start` and
+    /// the next call to `end`. Returns error message if failed.
+    fn check_not_called_between(&self, start: &ToolMatcher, end: &ToolMatcher) -> Option<String> {
+        let first = self.tool_calls.iter().position(|c| start.matches(&c.name))?;
+        let window = self.tool_calls[first + 1..]
+            .iter()
+            .take_while(|c| !end.matches(&c.name));
+
+        let found = window.clone().find(|c| self.is_matching_call(c))?;
+        let closed = window.count() < self.tool_calls.len() - first - 1;
+        let until = if closed {
+            format!("'{}'", end)
+        } else {
+            format!("the end of the session ('{}' never followed)", end)
+        };
+        Some(format!(
+            "'{}' was called between '{}' and {}. Found: {:?}",
+            self.tool, start, until, found.params
+        ))
+    }
+
+    /// Whether a call is this tool and satisfies any params filter and predicates.
+    fn is_matching_call(&self, call: &ToolCall) -> bool {
+        self.tool.matches(&call.name)
+            && self
+                .params
+                .as_ref()
+                .is_none_or(|params| self.params_match(params, &call.params))
+            && self
+                .json_params
+                .as_ref()
+                .is_none_or(|expected| json_contains(expected, &call.params))
+            && self
+                .url_domain
+                .as_ref()
+                .is_none_or(|domain| url_on_domain(&call.params, domain))
+            && self
+                .cell_index
+                .is_none_or(|index| edits_cell(&call.params, index))
+            && self.commands.iter().all(|filter| filter.matches(&call.params))
+            && self.predicates.iter().all(|pred| pred(&call.params))
+    }
+
+    /// Why a call fails the params filters and predicates, one entry per
+    /// failed filter, e.g. `expected file_path '.*\.rs', got '/a.txt'`.
+    /// Empty if the call passes them all. The tool name isn't checked.
+    ///
+    /// Only for failure messages: [`is_matching_call`](Self::is_matching_call)
+    /// applies the same filters without formatting anything.
+    fn param_mismatches(&self, call: &ToolCall) -> Vec<String> {
+        let params = &call.params;
+        let mut mismatches = Vec::new();
+
+        if let Some(expected) = self.params.as_ref().filter(|p| !self.params_match(p, params)) {
+            // Break the filter down by key to show which ones failed
+            let mut keys: Vec<&String> = expected.keys().collect();
+            keys.sort();
+            for key in keys {
+                let pattern = &expected[key];
+                let param = HashMap::from([(key.clone(), pattern.clone())]);
+                if !self.params_match(&param, params) {
+                    mismatches.push(format!(
+                        "expected {} '{}', got {}",
+                        key,
+                        pattern,
+                        describe_param(params.get(key))
+                    ));
+                }
+            }
+        }
+        if let Some(expected) = self.json_params.as_ref().filter(|e| !json_contains(e, params)) {
+            mismatches.push(format!("expected params containing {}", expected));
+        }
+        if let Some(domain) = self.url_domain.as_ref().filter(|d| !url_on_domain(params, d)) {
+            mismatches.push(format!(
+                "expected url on domain {}, got {}",
+                domain,
+                describe_param(params.get("url"))
+            ));
+        }
+        if let Some(index) = self.cell_index.filter(|&i| !edits_cell(params, i)) {
+            mismatches.push(format!("expected cell {}", index));
+        }
+        for filter in self.commands.iter().filter(|f| !f.matches(params)) {
+            mismatches.push(format!(
+                "expected {} {}, got {}",
+                call.name,
+                filter,
+                describe_param(params.get("command"))
+            ));
+        }
+        if !self.predicates.iter().all(|pred| pred(params)) {
+            mismatches.push("rejected by predicate".to_string());
+        }
+
+        mismatches
+    }
+
+    /// Matching calls that break a [`without_param`](Self::without_param) or
+    /// [`param_not_matching`](Self::param_not_matching) rule, one entry per
+    /// broken rule, numbered by position in the session.
+    fn negative_param_violations(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        if self.absent_params.is_empty() && self.params_not_matching.is_empty() {
+            return violations;
+        }
+
+        let mut not_matching: Vec<(&String, &String)> = self.params_not_matching.iter().collect();
+        not_matching.sort();
+        for (i, call) in self.tool_calls.iter().enumerate() {
+            if !self.is_matching_call(call) {
+                continue;
+            }
+            for key in &self.absent_params {
+                if let Some(value) = call.params.get(key) {
+                    violations.push(format!(
+                        "{} call #{} has {} {}",
+                        call.name,
+                        i + 1,
+                        key,
+                        describe_param(Some(value))
+                    ));
+                }
+            }
+            for (key, pattern) in &not_matching {
+                let param = HashMap::from([((*key).clone(), (*pattern).clone())]);
+                if self.params_match(&param, &call.params) {
+                    violations.push(format!(
+                        "{} call #{} has {} {} matching '{}'",
+                        call.name,
+                        i + 1,
+                        key,
+                        describe_param(call.params.get(*key)),
+                        pattern
+                    ));
+                }
+            }
+        }
+        violations
+    }
+
+    /// Match params with the assertion's mode, or as paths resolved against
+    /// [`in_workdir`](Self::in_workdir) after `with_path_params`.
+    fn params_match(&self, expected: &HashMap<String, String>, actual: &serde_json::Value) -> bool {
+        if !self.match_paths {
+            return self.match_mode.matches(expected, actual);
+        }
+        match &self.path_base {
+            Some(base) => path_params_match(expected, actual, base),
+            None => path_params_match(expected, actual, &std::env::current_dir().unwrap_or_default()),
+        }
+    }
+
+    fn panic_with_context(&self, result: &AssertionResult) -> ! {
+        let reason = result.reason.as_deref().unwrap_or("unknown reason");
+        // Point out calls to this tool that a params filter ruled out
+        let annotate = |call: &ToolCall| {
+            if !self.tool.matches(&call.name) {
+                return None;
+            }
+            let mismatches = self.param_mismatches(call);
+            (!mismatches.is_empty())
+                .then(|| format!("(name matched, params mismatch: {})", mismatches.join("; ")))
+        };
+        panic!(
+            "assertion failed: expected {}\n\n  reason: {}\n{}",
+            result.description,
+            reason,
+            format_tool_calls_with(&self.tool_calls, annotate)
+        );
+    }
+}
+
+/// Assertion builder for a specific call (used in nth_call/last_call).
+///
+/// Provides methods to assert on parameter values for a specific tool call.
+#[derive(Debug, Clone)]
+pub struct NthCallAssertion {
+    call: ToolCall,
+    tool: ToolMatcher,
+    n: usize,
+    /// Position of the call in the session (1-indexed).
+    position: usize,
+    all_calls: Vec<ToolCall>,
+}
+
+impl NthCallAssertion {
+    fn new(call: ToolCall, tool: ToolMatcher, n: usize, position: usize, all_calls: Vec<ToolCall>) -> Self {
+        Self { call, tool, n, position, all_calls }
+    }
+
+    /// How messages name the call, e.g. `Read call #2`, adding its place in
+    /// the session when other calls come before it.
+    fn label(&self) -> String {
+        if self.n == self.position {
+            format!("{} call #{}", self.tool, self.n)
+        } else {
+            format!("{} call #{} (session call #{})", self.tool, self.n, self.position)
+        }
+    }
+
+    /// Assert this specific call has the given parameters (panics on mismatch).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .nth_call(1)
+    ///     .has_params(params!{"file_path" => "*.txt"});
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parameters don't match.
+    pub fn has_params(self, params: HashMap<String, String>) -> Self {
+        if !params_match(&params, &self.call.params) {
+            panic!(
+                "assertion failed: {} params did not match\n\n  expected: {:?}\n  actual: {:?}\n{}",
+                self.label(), params, self.call.params, format_tool_calls(&self.all_calls)
+            );
+        }
+        self
+    }
+
+    /// Assert this specific call's params satisfy a custom predicate (panics on mismatch).
+    ///
+    /// Rust-API only, like [`ToolAssertion::matching`].
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&tool_calls)
+    ///     .tool(Tool::Bash)
+    ///     .last_call()
+    ///     .matching(|p| p["timeout"].as_u64() == Some(10000));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the predicate returns `false`.
+    pub fn matching(self, pred: impl Fn(&serde_json::Value) -> bool) -> Self {
+        if !pred(&self.call.params) {
+            panic!(
+                "assertion failed: {} params did not satisfy predicate\n\n  actual: {:?}\n{}",
+                self.label(), self.call.params, format_tool_calls(&self.all_calls)
+            );
+        }
+        self
+    }
+
+    /// Evaluate parameter match without panicking.
+    ///
+    /// Returns an `AssertionResult` that can be inspected.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let result = expect(&tool_calls)
+    ///     .tool(Tool::Read)
+    ///     .nth_call(1)
+    ///     .evaluate_params(params!{"file_path" => "*.txt"});
+    ///
+    /// assert!(result.passed);
+    /// ```
+    pub fn evaluate_params(&self, params: HashMap<String, String>) -> AssertionResult {
+        self.evaluate_params_with(params, MatchMode::Regex)
+    }
+
+    /// Evaluate parameter match with an explicit [`MatchMode`], without panicking.
+    pub fn evaluate_params_with(
+        &self,
+        params: HashMap<String, String>,
+        mode: MatchMode,
+    ) -> AssertionResult {
+        let description = format!("{} params match", self.label());
+        if mode.matches(&params, &self.call.params) {
+            AssertionResult::pass(description)
+        } else {
+            AssertionResult::fail(
+                description,
+                format!("expected {:?}, got {:?}", params, self.call.params),
+            )
+        }
+    }
+
+    /// Get the actual parameters of this call.
+    ///
+    /// Useful for debugging or custom assertions.
+    pub fn params(&self) -> &serde_json::Value {
+        &self.call.params
+    }
+
+    /// Get a single parameter of this call, or `None` if it is missing.
+    pub fn param(&self, key: &str) -> Option<&serde_json::Value> {
+        self.call.params.get(key)
+    }
+
+    /// Get a single string parameter of this call.
+    ///
+    /// Returns `None` if the parameter is missing or not a string.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let call = expect(&tool_calls).tool(Tool::Read).first_call();
+    /// assert_eq!(call.param_str("file_path"), Some("/a.txt"));
+    /// ```
+    pub fn param_str(&self, key: &str) -> Option<&str> {
+        self.param(key)?.as_str()
+    }
+
+    /// Get the content of this call's `tool_result`, if one was recorded.
+    ///
+    /// Only Claude session logs currently carry tool results.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let call = expect(&output).tool(Tool::Bash).last_call();
+    /// assert_eq!(call.result().and_then(|r| r.as_str()), Some("ok"));
+    /// ```
+    pub fn result(&self) -> Option<&serde_json::Value> {
+        self.call.result.as_ref()
+    }
+
+    /// Get the call index (1-indexed) among the matching calls.
+    pub fn index(&self) -> usize {
+        self.n
+    }
+
+    /// Get the call's position in the whole session (1-indexed), as numbered
+    /// in failure output.
+    pub fn session_index(&self) -> usize {
+        self.position
+    }
+}
+
+/// The calls that matched a [`ToolAssertion`], from
+/// [`assert_called()`](ToolAssertion::assert_called).
+///
+/// Always holds at least one call. Call numbers in [`NthCallAssertion`]
+/// messages count matching calls, with the session position alongside.
+#[derive(Debug, Clone)]
+pub struct CalledToolAssertion {
+    tool: ToolMatcher,
+    matching_calls: Vec<ToolCall>,
+    /// Session position (1-indexed) of each matching call.
+    positions: Vec<usize>,
+    all_calls: Vec<ToolCall>,
+}
+
+impl CalledToolAssertion {
+    /// The calls that matched, in order.
+    pub fn matching_calls(&self) -> &[ToolCall] {
+        &self.matching_calls
+    }
+
+    /// The first matching call, for further assertions.
+    pub fn first(&self) -> NthCallAssertion {
+        self.nth(1)
+    }
+
+    /// The last matching call, for further assertions.
+    pub fn last(&self) -> NthCallAssertion {
+        self.nth(self.matching_calls.len())
+    }
+
+    fn nth(&self, n: usize) -> NthCallAssertion {
+        NthCallAssertion::new(
+            self.matching_calls[n - 1].clone(),
+            self.tool.clone(),
+            n,
+            self.positions[n - 1],
+            self.all_calls.clone(),
+        )
+    }
+}
+
+/// Builder for assertions on an ordered pattern of tool calls.
+///
+/// `to_occur()` allows other calls between the tools in the pattern;
+/// `to_occur_consecutively()` requires them to be adjacent. An empty
+/// pattern always matches.
+#[derive(Debug, Clone)]
+pub struct SequenceAssertion {
+    tool_calls: Vec<ToolCall>,
+    tools: Vec<Tool>,
+}
+
+impl SequenceAssertion {
+    /// Create a new sequence assertion.
+    pub fn new(tool_calls: Vec<ToolCall>, tools: Vec<Tool>) -> Self {
+        Self { tool_calls, tools }
+    }
+
+    // =========================================================================
+    // Assertion methods (panic on failure)
+    // =========================================================================
+
+    /// Assert the tools were called in order, allowing other calls in between.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // Passes for Glob, Read, Bash, Edit
+    /// expect(&output)
+    ///     .sequence(&[Tool::Glob, Tool::Read, Tool::Edit])
+    ///     .to_occur();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tools were not all called in this order.
+    pub fn to_occur(&self) {
+        let result = self.evaluate();
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
+    /// Assert the tools were called in order with no other calls in between.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no run of adjacent calls matches the pattern.
+    pub fn to_occur_consecutively(&self) {
+        let result = self.evaluate_consecutive();
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
+    // =========================================================================
+    // Non-panicking evaluation
+    // =========================================================================
+
+    /// Evaluate that the tools were called in order, without panicking.
+    pub fn evaluate(&self) -> AssertionResult {
+        let description = format!("{} in order", self.pattern());
+        match self.subsequence_failure() {
+            Some(reason) => AssertionResult::fail(description, reason),
+            None => AssertionResult::pass(description),
+        }
+    }
+
+    /// Evaluate that the tools were called consecutively, without panicking.
+    pub fn evaluate_consecutive(&self) -> AssertionResult {
+        let description = format!("{} consecutively", self.pattern());
+        if self.tools.is_empty() {
+            return AssertionResult::pass(description);
+        }
+
+        let adjacent = self.tool_calls.windows(self.tools.len()).any(|window| {
+            window
+                .iter()
+                .zip(&self.tools)
+                .all(|(call, tool)| call.name == tool.as_str())
+        });
+        if adjacent {
+            return AssertionResult::pass(description);
+        }
+
+        let reason = self.subsequence_failure().unwrap_or_else(|| {
+            "tools were called in order, but with other calls in between".to_string()
+        });
+        AssertionResult::fail(description, reason)
+    }
+
+    // =========================================================================
+    // Internal helpers
+    // =========================================================================
+
+    fn pattern(&self) -> String {
+        let names: Vec<&str> = self.tools.iter().map(|t| t.as_str()).collect();
+        format!("[{}]", names.join(", "))
+    }
+
+    /// Greedily scan for the pattern as a subsequence.
+    /// Returns why it failed, naming the first tool that couldn't be matched.
+    fn subsequence_failure(&self) -> Option<String> {
+        let mut calls = self.tool_calls.iter();
+        let mut matched: Vec<&str> = Vec::new();
+
+        for tool in &self.tools {
+            if !calls.any(|call| call.name == tool.as_str()) {
+                if matched.is_empty() {
+                    return Some(format!("'{}' was never called", tool));
+                }
+                return Some(format!(
+                    "'{}' was not called after [{}]",
+                    tool,
+                    matched.join(", ")
+                ));
+            }
+            matched.push(tool.as_str());
+        }
+
+        None
+    }
+
+    fn panic_with_context(&self, result: &AssertionResult) -> ! {
+        let reason = result.reason.as_deref().unwrap_or("unknown reason");
+        panic!(
+            "assertion failed: expected {}\n\n  reason: {}\n{}",
+            result.description,
+            reason,
+            format_tool_calls(&self.tool_calls)
+        );
+    }
+}
+
+/// Builder for assertions on the commands an agent ran with `Bash`.
+///
+/// Each method returns a [`ToolAssertion`] on `Bash` calls whose `command`
+/// param matches, so counts, ordering and `not_to_be_called()` work as
+/// usual. Unlike `with_params`, `ran` and `ran_containing` take the command
+/// literally: `&&`, `|` and other regex metacharacters need no escaping.
+#[derive(Debug, Clone)]
+pub struct BashAssertion {
+    tool_calls: Vec<ToolCall>,
+}
+
+impl BashAssertion {
+    /// Create a Bash command assertion over these tool calls.
+    pub fn new(tool_calls: &[ToolCall]) -> Self {
+        Self {
+            tool_calls: tool_calls.to_vec(),
+        }
+    }
+
+    /// Match calls that ran exactly `command`, ignoring surrounding whitespace.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&output)
+    ///     .bash()
+    ///     .ran("cargo fmt && cargo test")
+    ///     .to_be_called_once();
+    /// ```
+    pub fn ran(&self, command: &str) -> ToolAssertion {
+        self.assertion(CommandFilter::Exact(command.to_string()))
+    }
+
+    /// Match calls whose command matches an unanchored regex, falling back
+    /// to exact match for invalid patterns like `with_params`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&output)
+    ///     .bash()
+    ///     .ran_matching(r"^git (add|commit)\b")
+    ///     .to_be_called();
+    /// ```
+    pub fn ran_matching(&self, pattern: &str) -> ToolAssertion {
+        self.assertion(CommandFilter::Matching(pattern.to_string()))
+    }
+
+    /// Match calls whose command contains `text`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&output)
+    ///     .bash()
+    ///     .ran_containing("| xargs rm")
+    ///     .not_to_be_called();
+    /// ```
+    pub fn ran_containing(&self, text: &str) -> ToolAssertion {
+        self.assertion(CommandFilter::Containing(text.to_string()))
+    }
+
+    fn assertion(&self, filter: CommandFilter) -> ToolAssertion {
+        ToolAssertion::new(self.tool_calls.clone(), Tool::Bash).with_command(filter)
+    }
+}
+
+/// Merge `other` into `into` so that [`json_contains`] requires both:
+/// objects merge key by key and arrays are concatenated. Anything else is
+/// replaced by `other`.
+fn merge_json(into: &mut serde_json::Value, other: serde_json::Value) {
+    use serde_json::Value;
+
+    match (into, other) {
+        (Value::Object(into), Value::Object(other)) => {
+            for (key, value) in other {
+                match into.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        into.insert(key, value);
+                    }
                 }
             }
         }
+        (Value::Array(into), Value::Array(other)) => into.extend(other),
+        (into, other) => *into = other,
+    }
+}
 
-        // Check ordering constraints
-        if let Some(after) = &self.after_tool {
-            if let Some(err) = self.check_after(after) {
-                failures.push(err);
-            }
+/// How a `Bash` call's `command` param is compared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CommandFilter {
+    /// The whole command, ignoring surrounding whitespace.
+    Exact(String),
+    /// An unanchored regex.
+    Matching(String),
+    /// A literal substring.
+    Containing(String),
+}
+
+impl CommandFilter {
+    fn matches(&self, params: &serde_json::Value) -> bool {
+        let Some(command) = params.get("command").and_then(|v| v.as_str()) else {
+            return false;
+        };
+        match self {
+            CommandFilter::Exact(expected) => command.trim() == expected.trim(),
+            CommandFilter::Matching(pattern) => match regex::Regex::new(pattern) {
+                Ok(re) => re.is_match(command),
+                Err(_) => command == pattern,
+            },
+            CommandFilter::Containing(text) => command.contains(text.as_str()),
         }
-        if let Some(before) = &self.before_tool {
-            if let Some(err) = self.check_before(before) {
-                failures.push(err);
-            }
+    }
+}
+
+impl std::fmt::Display for CommandFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandFilter::Exact(command) => write!(f, "running '{}'", command),
+            CommandFilter::Matching(pattern) => write!(f, "with command matching '{}'", pattern),
+            CommandFilter::Containing(text) => write!(f, "with command containing '{}'", text),
         }
+    }
+}
 
-        // Build description
-        let description = self.build_description(should_be_called);
+/// Builder for assertions on the distinct files an agent read or wrote.
+///
+/// Paths are taken from each tool's file parameter (see
+/// [`Tool::file_path_param`]) and deduplicated, so reading the same file
+/// twice counts once. Paths are compared as written in the log, without
+/// normalization.
+#[derive(Debug, Clone)]
+pub struct FilesAssertion {
+    action: &'static str,
+    files: Vec<String>,
+    tool_calls: Vec<ToolCall>,
+}
 
-        if failures.is_empty() {
-            AssertionResult::pass(description)
-        } else {
-            AssertionResult::fail(description, failures.join("; "))
-        }
+impl FilesAssertion {
+    /// Collect the distinct files read with `Read`.
+    pub fn read(tool_calls: &[ToolCall]) -> Self {
+        Self::collect(tool_calls, &[Tool::Read], "read")
     }
 
-    /// Build a human-readable description of what this assertion checks.
-    fn build_description(&self, should_be_called: bool) -> String {
-        let mut parts = vec![self.tool.to_string()];
+    /// Collect the distinct files changed with `Write`, `Edit` or `NotebookEdit`.
+    pub fn written(tool_calls: &[ToolCall]) -> Self {
+        Self::collect(tool_calls, &[Tool::Write, Tool::Edit, Tool::NotebookEdit], "written")
+    }
 
-        if let Some(params) = &self.params {
-            let param_str: Vec<String> = params
+    fn collect(tool_calls: &[ToolCall], tools: &[Tool], action: &'static str) -> Self {
+        let mut files: Vec<String> = Vec::new();
+        for call in tool_calls {
+            let path = tools
                 .iter()
-                .map(|(k, v)| format!("{}='{}'", k, v))
-                .collect();
-            parts.push(format!("with {}", param_str.join(", ")));
+                .find(|t| t.as_str() == call.name)
+                .and_then(|t| t.file_path_param())
+                .and_then(|param| call.params.get(param))
+                .and_then(|v| v.as_str());
+            if let Some(path) = path {
+                if !files.iter().any(|f| f == path) {
+                    files.push(path.to_string());
+                }
+            }
         }
-
-        if should_be_called {
-            parts.push("called".to_string());
-        } else {
-            parts.push("not called".to_string());
+        Self {
+            action,
+            files,
+            tool_calls: tool_calls.to_vec(),
         }
+    }
 
-        if let Some(after) = &self.after_tool {
-            parts.push(format!("after {}", after));
-        }
-        if let Some(before) = &self.before_tool {
-            parts.push(format!("before {}", before));
-        }
-        if let Some(n) = self.expected_count {
-            parts.push(format!("{} times", n));
-        }
-        if let Some(n) = self.min_count {
-            parts.push(format!("at least {} times", n));
-        }
-        if let Some(n) = self.max_count {
-            parts.push(format!("at most {} times", n));
-        }
+    /// The distinct file paths, in the order they were first touched.
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
 
-        parts.join(" ")
+    /// Number of distinct files.
+    pub fn count(&self) -> usize {
+        self.files.len()
     }
 
-    /// Check if tool was called after another tool. Returns error message if failed.
-    fn check_after(&self, after_tool: &Tool) -> Option<String> {
-        let mut seen_after = false;
+    // =========================================================================
+    // Assertion methods (panic on failure)
+    // =========================================================================
 
-        for call in &self.tool_calls {
-            if call.name == after_tool.as_str() {
-                seen_after = true;
-            }
-            if call.name == self.tool.as_str() && seen_after {
-                if let Some(params) = &self.params {
-                    if params_match(params, &call.params) {
-                        return None; // Success
-                    }
-                } else {
-                    return None; // Success
-                }
-            }
+    /// Assert fewer than `n` distinct files were touched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` or more distinct files were touched.
+    pub fn count_under(&self, n: usize) {
+        let result = self.evaluate_count_under(n);
+        if !result.passed {
+            self.panic_with_context(&result);
         }
+    }
 
-        if !seen_after {
-            Some(format!("'{}' was never called", after_tool))
-        } else {
-            Some(format!("'{}' was not called after '{}'", self.tool, after_tool))
+    /// Assert at most `n` distinct files were touched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `n` distinct files were touched.
+    pub fn count_at_most(&self, n: usize) {
+        let result = self.evaluate_count_at_most(n);
+        if !result.passed {
+            self.panic_with_context(&result);
         }
     }
 
-    /// Check if tool was called before another tool. Returns error message if failed.
-    fn check_before(&self, before_tool: &Tool) -> Option<String> {
-        let mut seen_this = false;
+    /// Assert at least one touched file matches a glob pattern.
+    ///
+    /// Uses the same glob rules as `with_glob_params`: `*` matches any
+    /// characters including `/`, and `{a,b}` expands to alternatives.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no touched file matches.
+    pub fn to_include(&self, pattern: &str) {
+        let result = self.evaluate_include(pattern);
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
 
-        for call in &self.tool_calls {
-            if call.name == self.tool.as_str() {
-                if let Some(params) = &self.params {
-                    if params_match(params, &call.params) {
-                        seen_this = true;
-                    }
-                } else {
-                    seen_this = true;
-                }
-            }
-            if call.name == before_tool.as_str() && seen_this {
-                return None; // Success
-            }
+    // =========================================================================
+    // Non-panicking evaluation
+    // =========================================================================
+
+    /// Evaluate that fewer than `n` distinct files were touched, without panicking.
+    pub fn evaluate_count_under(&self, n: usize) -> AssertionResult {
+        let description = format!("fewer than {} {} {}", n, files_noun(n), self.action);
+        self.check_count(description, self.count() < n)
+    }
+
+    /// Evaluate that at most `n` distinct files were touched, without panicking.
+    pub fn evaluate_count_at_most(&self, n: usize) -> AssertionResult {
+        let description = format!("at most {} {} {}", n, files_noun(n), self.action);
+        self.check_count(description, self.count() <= n)
+    }
+
+    /// Evaluate that a touched file matches `pattern`, without panicking.
+    pub fn evaluate_include(&self, pattern: &str) -> AssertionResult {
+        let description = format!("files {} include '{}'", self.action, pattern);
+        if self.files.iter().any(|f| glob_match(pattern, f)) {
+            AssertionResult::pass(description)
+        } else {
+            AssertionResult::fail(
+                description,
+                format!("no file {} matches ({})", self.action, self.format_files()),
+            )
         }
+    }
 
-        let this_called = self.tool_calls.iter().any(|c| c.name == self.tool.as_str());
-        let before_called = self
-            .tool_calls
-            .iter()
-            .any(|c| c.name == before_tool.as_str());
+    // =========================================================================
+    // Internal helpers
+    // =========================================================================
 
-        if !this_called {
-            Some(format!("'{}' was never called", self.tool))
-        } else if !before_called {
-            Some(format!("'{}' was never called", before_tool))
+    fn check_count(&self, description: String, passed: bool) -> AssertionResult {
+        if passed {
+            AssertionResult::pass(description)
         } else {
-            Some(format!("'{}' was not called before '{}'", self.tool, before_tool))
+            AssertionResult::fail(
+                description,
+                format!(
+                    "{} {} {}: {}",
+                    self.count(),
+                    files_noun(self.count()),
+                    self.action,
+                    self.format_files()
+                ),
+            )
+        }
+    }
+
+    fn format_files(&self) -> String {
+        if self.files.is_empty() {
+            return "none".to_string();
         }
+        self.files.join(", ")
     }
 
     fn panic_with_context(&self, result: &AssertionResult) -> ! {
@@ -661,86 +2654,210 @@ impl ToolAssertion {
     }
 }
 
-/// Assertion builder for a specific call (used in nth_call/last_call).
+fn files_noun(n: usize) -> &'static str {
+    if n == 1 {
+        "file"
+    } else {
+        "files"
+    }
+}
+
+/// Builder for assertions on the whole tool-call sequence.
 ///
-/// Provides methods to assert on parameter values for a specific tool call.
+/// See [`crate::snapshot`] for the snapshot format and where snapshots are
+/// stored.
 #[derive(Debug, Clone)]
-pub struct NthCallAssertion {
-    call: ToolCall,
-    tool: Tool,
-    n: usize,
-    all_calls: Vec<ToolCall>,
+pub struct ToolCallsAssertion {
+    tool_calls: Vec<ToolCall>,
 }
 
-impl NthCallAssertion {
-    fn new(call: ToolCall, tool: Tool, n: usize, all_calls: Vec<ToolCall>) -> Self {
-        Self { call, tool, n, all_calls }
+impl ToolCallsAssertion {
+    /// Create an assertion on a run's tool calls.
+    pub fn new(tool_calls: &[ToolCall]) -> Self {
+        Self {
+            tool_calls: tool_calls.to_vec(),
+        }
     }
 
-    /// Assert this specific call has the given parameters (panics on mismatch).
+    /// Assert the calls match the snapshot `name`, writing it on first use.
+    ///
+    /// Set `UPDATE_SNAPSHOTS=1` to rewrite a snapshot the calls drifted from.
+    /// When `CI` is set, a missing snapshot fails instead of being written.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// expect(&tool_calls)
-    ///     .tool(Tool::Read)
-    ///     .nth_call(1)
-    ///     .has_params(params!{"file_path" => "*.txt"});
+    /// expect(&output).tool_calls().matches_snapshot("fix_failing_test");
     /// ```
     ///
     /// # Panics
     ///
-    /// Panics if the parameters don't match.
-    pub fn has_params(self, params: HashMap<String, String>) -> Self {
-        if !params_match(&params, &self.call.params) {
-            panic!(
-                "assertion failed: {} call #{} params did not match\n\n  expected: {:?}\n  actual: {:?}\n{}",
-                self.tool, self.n, params, self.call.params, format_tool_calls(&self.all_calls)
-            );
+    /// Panics with a diff if the calls differ from the snapshot.
+    pub fn matches_snapshot(&self, name: &str) {
+        let result = self.evaluate_snapshot(name);
+        if !result.passed {
+            let reason = result.reason.as_deref().unwrap_or("unknown reason");
+            panic!("assertion failed: expected {}\n\n  reason: {}", result.description, reason);
         }
-        self
     }
 
-    /// Evaluate parameter match without panicking.
-    ///
-    /// Returns an `AssertionResult` that can be inspected.
-    ///
-    /// # Example
+    /// Evaluate the calls against the snapshot `name`, without panicking.
     ///
-    /// ```rust,ignore
-    /// let result = expect(&tool_calls)
-    ///     .tool(Tool::Read)
-    ///     .nth_call(1)
-    ///     .evaluate_params(params!{"file_path" => "*.txt"});
-    ///
-    /// assert!(result.passed);
-    /// ```
-    pub fn evaluate_params(&self, params: HashMap<String, String>) -> AssertionResult {
-        if params_match(&params, &self.call.params) {
-            AssertionResult::pass(format!("{} call #{} params match", self.tool, self.n))
-        } else {
-            AssertionResult::fail(
-                format!("{} call #{} params match", self.tool, self.n),
-                format!("expected {:?}, got {:?}", params, self.call.params),
-            )
+    /// The snapshot is `<name>.snap` in [`snapshot::snapshot_dir`]; names
+    /// with path separators fail. Which snapshots may be written comes from
+    /// the environment (see [`snapshot::update_mode`]).
+    pub fn evaluate_snapshot(&self, name: &str) -> AssertionResult {
+        match snapshot::snapshot_path(name) {
+            Ok(path) => self.evaluate_snapshot_at(&path, snapshot::update_mode()),
+            Err(reason) => AssertionResult::fail(format!("tool calls to match snapshot {}", name), reason),
         }
     }
 
-    /// Get the actual parameters of this call.
+    /// Evaluate the calls against the snapshot file at `path`, without panicking.
     ///
-    /// Useful for debugging or custom assertions.
-    pub fn params(&self) -> &serde_json::Value {
-        &self.call.params
+    /// `update` says whether a missing snapshot is written and whether a
+    /// drifted one is rewritten instead of failing.
+    pub fn evaluate_snapshot_at(&self, path: &std::path::Path, update: snapshot::SnapshotUpdate) -> AssertionResult {
+        let description = format!("tool calls to match snapshot {}", path.display());
+        match snapshot::check_snapshot(path, &self.tool_calls, update) {
+            Ok(_) => AssertionResult::pass(description),
+            Err(reason) => AssertionResult::fail(description, reason),
+        }
     }
+}
 
-    /// Get the call index (1-indexed).
-    pub fn index(&self) -> usize {
-        self.n
+/// Whether the `url` param's host is `domain` or a subdomain of it.
+fn url_on_domain(params: &serde_json::Value, domain: &str) -> bool {
+    let Some(url) = params.get("url").and_then(|v| v.as_str()) else {
+        return false;
+    };
+    let Ok(url) = url::Url::parse(url) else {
+        return false;
+    };
+    url.host_str().is_some_and(|host| {
+        let host = host.trim_end_matches('.');
+        host == domain
+            || host
+                .strip_suffix(domain)
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
+
+/// Whether a `NotebookEdit` call's `cell_number` or `cell_id` is `index`.
+fn edits_cell(params: &serde_json::Value, index: usize) -> bool {
+    ["cell_number", "cell_id"].iter().any(|key| match params.get(key) {
+        Some(serde_json::Value::Number(n)) => n.as_u64() == Some(index as u64),
+        Some(serde_json::Value::String(s)) => s.trim().parse::<usize>() == Ok(index),
+        _ => false,
+    })
+}
+
+/// Stringify a parameter value the same way `params_match` does.
+fn param_value(call: &ToolCall, key: &str) -> Option<String> {
+    match call.params.get(key)? {
+        serde_json::Value::String(s) => Some(s.clone()),
+        v => Some(v.to_string()),
+    }
+}
+
+/// Compare a parameter's values across calls to an expected sequence.
+/// Returns an error message naming the first diverging index.
+fn check_param_sequence(calls: &[&ToolCall], key: &str, expected: &[String]) -> Option<String> {
+    let actual: Vec<Option<String>> = calls.iter().map(|c| param_value(c, key)).collect();
+
+    for (i, expected_value) in expected.iter().enumerate() {
+        match actual.get(i) {
+            Some(Some(v)) if v == expected_value => {}
+            Some(Some(v)) => {
+                return Some(format!(
+                    "'{}' sequence diverged at index {}: expected '{}', got '{}'",
+                    key, i, expected_value, v
+                ))
+            }
+            Some(None) => {
+                return Some(format!(
+                    "'{}' sequence diverged at index {}: expected '{}', param missing",
+                    key, i, expected_value
+                ))
+            }
+            None => {
+                return Some(format!(
+                    "'{}' sequence too short: expected {} values, got {}",
+                    key,
+                    expected.len(),
+                    actual.len()
+                ))
+            }
+        }
+    }
+
+    if actual.len() > expected.len() {
+        return Some(format!(
+            "'{}' sequence too long: expected {} values, got {}",
+            key,
+            expected.len(),
+            actual.len()
+        ));
+    }
+
+    None
+}
+
+/// Check a parameter's values across calls are in ascending order.
+/// Returns an error message naming the first out-of-order index.
+fn check_param_sorted(calls: &[&ToolCall], key: &str) -> Option<String> {
+    let mut previous: Option<String> = None;
+
+    for (i, call) in calls.iter().enumerate() {
+        let Some(value) = param_value(call, key) else {
+            return Some(format!("'{}' missing from call at index {}", key, i));
+        };
+        if let Some(prev) = &previous {
+            if value < *prev {
+                return Some(format!(
+                    "'{}' not sorted at index {}: '{}' came after '{}'",
+                    key, i, value, prev
+                ));
+            }
+        }
+        previous = Some(value);
+    }
+
+    None
+}
+
+/// Check a parameter's value never repeats across calls, skipping calls
+/// without it. Returns an error message naming the first duplicate.
+fn check_param_distinct(calls: &[(usize, &ToolCall)], key: &str) -> Option<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for (index, call) in calls {
+        let Some(value) = param_value(call, key) else {
+            continue;
+        };
+        if let Some(first) = seen.get(&value) {
+            return Some(format!(
+                "'{}' repeated at {} call #{}: '{}' was already used at call #{}",
+                key,
+                call.name,
+                index + 1,
+                value,
+                first
+            ));
+        }
+        seen.insert(value, index + 1);
     }
+
+    None
 }
 
 /// Format tool calls for inclusion in assertion error messages.
 fn format_tool_calls(calls: &[ToolCall]) -> String {
+    format_tool_calls_with(calls, |_| None)
+}
+
+/// Like [`format_tool_calls`], with an optional note after each call.
+fn format_tool_calls_with(calls: &[ToolCall], annotate: impl Fn(&ToolCall) -> Option<String>) -> String {
     if calls.is_empty() {
         return "  tool calls made: (none)\n".to_string();
     }
@@ -762,11 +2879,26 @@ fn format_tool_calls(calls: &[ToolCall]) -> String {
             })
             .unwrap_or_else(|| "...".to_string());
         output.push_str(&format!(
-            "    {}. {} {{ {} }}\n",
+            "    {}. {} {{ {} }}",
             i + 1,
             call.name,
             params_preview
         ));
+        if let Some(note) = annotate(call) {
+            output.push_str("  ");
+            output.push_str(&note);
+        }
+        output.push('\n');
     }
     output
 }
+
+/// A parameter value for failure messages: strings quoted, other JSON as-is,
+/// `none` if missing.
+fn describe_param(value: Option<&serde_json::Value>) -> String {
+    match value {
+        Some(serde_json::Value::String(s)) => format!("'{}'", s),
+        Some(other) => other.to_string(),
+        None => "none".to_string(),
+    }
+}
@@ -0,0 +1,129 @@
+//! Fluent assertion builder for the agent's exit code.
+//!
+//! - `ExitCodeAssertion` - Builder for assertions on how the agent process exited
+
+use super::builder::AssertionResult;
+
+/// Builder for assertions on the agent's exit code.
+///
+/// Methods like `to_be()` evaluate immediately and panic on failure.
+/// Use the `evaluate_*` methods for non-panicking evaluation.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// expect(&output).exit_code().to_be(0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExitCodeAssertion {
+    exit_code: Option<i32>,
+}
+
+impl ExitCodeAssertion {
+    /// Create a new exit code assertion.
+    ///
+    /// `None` means the exit code was not captured (e.g. the process was
+    /// killed by a signal, or only tool calls were available).
+    pub fn new(exit_code: Option<i32>) -> Self {
+        Self { exit_code }
+    }
+
+    // =========================================================================
+    // Assertion methods (panic on failure)
+    // =========================================================================
+
+    /// Assert the agent exited with the given code.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the exit code differs or was not captured.
+    pub fn to_be(&self, expected: i32) {
+        let result = self.evaluate_to_be(expected);
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
+    /// Assert the agent exited with a non-zero code.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the exit code is zero or was not captured.
+    pub fn to_be_nonzero(&self) {
+        let result = self.evaluate_nonzero();
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
+    // =========================================================================
+    // Non-panicking evaluation
+    // =========================================================================
+
+    /// Evaluate that the agent exited with the given code, without panicking.
+    pub fn evaluate_to_be(&self, expected: i32) -> AssertionResult {
+        let description = format!("exit code {}", expected);
+        match self.exit_code {
+            Some(code) if code == expected => AssertionResult::pass(description),
+            Some(code) => AssertionResult::fail(description, format!("got exit code {}", code)),
+            None => AssertionResult::fail(description, "exit code was not captured"),
+        }
+    }
+
+    /// Evaluate that the agent exited with a non-zero code, without panicking.
+    pub fn evaluate_nonzero(&self) -> AssertionResult {
+        let description = "non-zero exit code";
+        match self.exit_code {
+            Some(0) => AssertionResult::fail(description, "got exit code 0"),
+            Some(_) => AssertionResult::pass(description),
+            None => AssertionResult::fail(description, "exit code was not captured"),
+        }
+    }
+
+    // =========================================================================
+    // Internal helpers
+    // =========================================================================
+
+    fn panic_with_context(&self, result: &AssertionResult) -> ! {
+        let reason = result.reason.as_deref().unwrap_or("unknown reason");
+        panic!(
+            "assertion failed: expected {}\n\n  reason: {}\n",
+            result.description, reason
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_be() {
+        ExitCodeAssertion::new(Some(0)).to_be(0);
+        ExitCodeAssertion::new(Some(2)).to_be(2);
+
+        let result = ExitCodeAssertion::new(Some(1)).evaluate_to_be(0);
+        assert!(!result.passed);
+        assert_eq!(result.description, "exit code 0");
+        assert_eq!(result.reason.as_deref(), Some("got exit code 1"));
+    }
+
+    #[test]
+    fn test_to_be_nonzero() {
+        ExitCodeAssertion::new(Some(1)).to_be_nonzero();
+        assert!(!ExitCodeAssertion::new(Some(0)).evaluate_nonzero().passed);
+    }
+
+    #[test]
+    fn test_not_captured() {
+        let result = ExitCodeAssertion::new(None).evaluate_to_be(0);
+        assert_eq!(result.reason.as_deref(), Some("exit code was not captured"));
+        assert!(!ExitCodeAssertion::new(None).evaluate_nonzero().passed);
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed: expected exit code 0")]
+    fn test_to_be_panics() {
+        ExitCodeAssertion::new(Some(127)).to_be(0);
+    }
+}
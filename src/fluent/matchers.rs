@@ -1,10 +1,38 @@
 //! Parameter matching utilities for tool call assertions.
 //!
 //! This module provides utilities for matching expected parameters against
-//! actual tool call parameters using regex patterns.
+//! actual tool call parameters using regex patterns, glob patterns, exact
+//! comparison, or normalized file paths.
 
+use crate::discovery::expand_braces;
 use regex::Regex;
+use serde::Deserialize;
 use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// How expected parameter values are compared against actual values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// Unanchored regex, falling back to exact match for invalid patterns (default).
+    #[default]
+    Regex,
+    /// Literal comparison. See [`params_match_exact`].
+    Exact,
+    /// Shell-style glob with brace expansion. See [`params_match_glob`].
+    Glob,
+}
+
+impl MatchMode {
+    /// Match expected parameters against actual parameters using this mode.
+    pub fn matches(&self, expected: &HashMap<String, String>, actual: &serde_json::Value) -> bool {
+        match self {
+            MatchMode::Regex => params_match(expected, actual),
+            MatchMode::Exact => params_match_exact(expected, actual),
+            MatchMode::Glob => params_match_glob(expected, actual),
+        }
+    }
+}
 
 /// Match expected parameters against actual tool call parameters.
 ///
@@ -60,6 +88,215 @@ pub fn params_match(expected: &HashMap<String, String>, actual: &serde_json::Val
     true
 }
 
+/// Match expected parameters against actual tool call parameters literally.
+///
+/// String values must be identical. Numbers and booleans are compared by value,
+/// so `"42"` matches `42` and `"42.0"`, and `"true"` matches `true`.
+///
+/// # Example
+///
+/// ```rust
+/// use aptitude::fluent::params_match_exact;
+/// use std::collections::HashMap;
+/// use serde_json::json;
+///
+/// let mut expected = HashMap::new();
+/// expected.insert("file_path".to_string(), "/tmp/a.txt".to_string());
+///
+/// assert!(params_match_exact(&expected, &json!({"file_path": "/tmp/a.txt"})));
+/// assert!(!params_match_exact(&expected, &json!({"file_path": "/tmp/a.txt.bak"})));
+/// ```
+pub fn params_match_exact(expected: &HashMap<String, String>, actual: &serde_json::Value) -> bool {
+    expected.iter().all(|(key, value)| match actual.get(key) {
+        Some(serde_json::Value::String(s)) => s == value,
+        Some(serde_json::Value::Number(n)) => value
+            .parse::<f64>()
+            .ok()
+            .zip(n.as_f64())
+            .is_some_and(|(e, a)| e == a),
+        Some(serde_json::Value::Bool(b)) => value.parse::<bool>() == Ok(*b),
+        Some(v) => &v.to_string() == value,
+        None => false,
+    })
+}
+
+/// Match expected parameters against actual tool call parameters using globs.
+///
+/// `*` matches any sequence of characters (including `/`), `**/` matches any
+/// number of directories, and `{a,b}` expands to alternatives. Patterns that
+/// are not valid globs fall back to exact match.
+///
+/// # Example
+///
+/// ```rust
+/// use aptitude::fluent::params_match_glob;
+/// use std::collections::HashMap;
+/// use serde_json::json;
+///
+/// let mut expected = HashMap::new();
+/// expected.insert("file_path".to_string(), "*.env".to_string());
+///
+/// assert!(params_match_glob(&expected, &json!({"file_path": "/app/.env"})));
+/// assert!(!params_match_glob(&expected, &json!({"file_path": "/app/.envrc"})));
+/// ```
+pub fn params_match_glob(expected: &HashMap<String, String>, actual: &serde_json::Value) -> bool {
+    expected.iter().all(|(key, pattern)| {
+        let actual_str = match actual.get(key) {
+            Some(serde_json::Value::String(s)) => s.clone(),
+            Some(v) => v.to_string(),
+            None => return false,
+        };
+
+        glob_match(pattern, &actual_str)
+    })
+}
+
+/// Match expected parameters against actual tool call parameters as file paths.
+///
+/// Both sides are normalized with [`normalize_path`], so `~/proj/src/main.rs`,
+/// `/home/user/proj/src/main.rs` and `./src/main.rs` (with `base` set to
+/// `/home/user/proj`) all match each other. Non-string values never match.
+///
+/// # Example
+///
+/// ```rust
+/// use aptitude::fluent::path_params_match;
+/// use std::collections::HashMap;
+/// use std::path::Path;
+/// use serde_json::json;
+///
+/// let mut expected = HashMap::new();
+/// expected.insert("file_path".to_string(), "./src/main.rs".to_string());
+///
+/// let base = Path::new("/work/proj");
+/// assert!(path_params_match(&expected, &json!({"file_path": "/work/proj/src/main.rs"}), base));
+/// assert!(path_params_match(&expected, &json!({"file_path": "/work/proj/tests/../src/main.rs"}), base));
+/// assert!(!path_params_match(&expected, &json!({"file_path": "/work/other/src/main.rs"}), base));
+/// ```
+pub fn path_params_match(expected: &HashMap<String, String>, actual: &serde_json::Value, base: &Path) -> bool {
+    expected.iter().all(|(key, pattern)| match actual.get(key) {
+        Some(serde_json::Value::String(path)) => normalize_path(pattern, base) == normalize_path(path, base),
+        _ => false,
+    })
+}
+
+/// Normalize a path for comparison.
+///
+/// A leading `~` is expanded to the home directory, relative paths are
+/// joined onto `base`, and `.` and `..` are resolved without touching the
+/// filesystem. The longest prefix that exists is then canonicalized, which
+/// resolves symlinks (e.g. `/tmp` to `/private/tmp` on macOS) while still
+/// allowing paths to files that don't exist.
+pub fn normalize_path(path: &str, base: &Path) -> PathBuf {
+    let expanded = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches('/')),
+            None => PathBuf::from(path),
+        },
+        _ => PathBuf::from(path),
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in base.join(expanded).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+
+    // Canonicalize the deepest existing ancestor and re-append the rest
+    let mut missing = Vec::new();
+    let mut existing = resolved.as_path();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return missing.iter().rev().fold(canonical, |path, name| path.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_owned());
+                existing = parent;
+            }
+            _ => return resolved,
+        }
+    }
+}
+
+/// Match expected parameters against actual tool call parameters structurally.
+///
+/// Unlike the string matchers, values are compared as JSON with
+/// [`json_contains`], so nested objects only need the expected keys.
+///
+/// # Example
+///
+/// ```rust
+/// use aptitude::fluent::params_match_json;
+/// use std::collections::HashMap;
+/// use serde_json::json;
+///
+/// let mut expected = HashMap::new();
+/// expected.insert("options".to_string(), json!({"recursive": true}));
+///
+/// assert!(params_match_json(&expected, &json!({"options": {"recursive": true, "depth": 2}})));
+/// assert!(!params_match_json(&expected, &json!({"options": {"recursive": "true"}})));
+/// ```
+pub fn params_match_json(
+    expected: &HashMap<String, serde_json::Value>,
+    actual: &serde_json::Value,
+) -> bool {
+    expected
+        .iter()
+        .all(|(key, value)| actual.get(key).is_some_and(|a| json_contains(value, a)))
+}
+
+/// Check that `expected` is a subset of `actual`.
+///
+/// - Objects: every expected key is present and its value is contained,
+///   recursively. Extra keys in `actual` are ignored.
+/// - Arrays: every expected element is contained in some element of
+///   `actual`, in any order. Extra elements are ignored.
+/// - Numbers compare by value (`1` matches `1.0`); other scalars must be equal.
+///
+/// # Example
+///
+/// ```rust
+/// use aptitude::fluent::json_contains;
+/// use serde_json::json;
+///
+/// let actual = json!({"options": {"recursive": true, "depth": 2}, "paths": ["src", "docs"]});
+///
+/// assert!(json_contains(&json!({"options": {"recursive": true}}), &actual));
+/// assert!(json_contains(&json!({"paths": ["docs"]}), &actual));
+/// assert!(!json_contains(&json!({"options": {"hidden": true}}), &actual));
+/// ```
+pub fn json_contains(expected: &serde_json::Value, actual: &serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|a| json_contains(value, a))),
+        (Value::Array(expected), Value::Array(actual)) => expected
+            .iter()
+            .all(|e| actual.iter().any(|a| json_contains(e, a))),
+        (Value::Number(e), Value::Number(a)) => e.as_f64() == a.as_f64(),
+        _ => expected == actual,
+    }
+}
+
+/// Match a single value against a glob pattern, with the same rules as
+/// [`params_match_glob`].
+pub(crate) fn glob_match(pattern: &str, value: &str) -> bool {
+    expand_braces(pattern)
+        .iter()
+        .any(|expanded| match glob::Pattern::new(expanded) {
+            Ok(pat) => pat.matches(value),
+            Err(_) => value == pattern,
+        })
+}
+
 /// Create a parameter map from key-value pairs.
 ///
 /// This is a convenience macro for creating parameter expectations.
@@ -85,6 +322,48 @@ macro_rules! params {
     }};
 }
 
+/// Create a structured parameter map for [`params_match_json`].
+///
+/// Values are written as JSON (anything `serde_json::json!` accepts:
+/// literals including negative numbers, variables, expressions, `{...}`
+/// objects and `[...]` arrays), so nested params can be matched without
+/// stringifying them. Uses the `serde_json` re-exported by this crate, so
+/// callers don't need their own dependency on it.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use aptitude::params_json;
+///
+/// let params = params_json! {
+///     "path" => "src",
+///     "options" => {"recursive": true},
+///     "offset" => -1
+/// };
+/// ```
+#[macro_export]
+macro_rules! params_json {
+    // Split entries on top-level commas, collecting each value's tokens
+    (@entries $map:ident ()) => {};
+    (@entries $map:ident ($key:expr => $($rest:tt)*)) => {
+        $crate::params_json!(@value $map $key; () ($($rest)*));
+    };
+    (@value $map:ident $key:expr; ($($value:tt)+) ($(, $($rest:tt)*)?)) => {
+        $map.insert($key.to_string(), $crate::serde_json::json!($($value)+));
+        $crate::params_json!(@entries $map ($($($rest)*)?));
+    };
+    (@value $map:ident $key:expr; ($($value:tt)*) ($next:tt $($rest:tt)*)) => {
+        $crate::params_json!(@value $map $key; ($($value)* $next) ($($rest)*));
+    };
+    ($($entries:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut map: std::collections::HashMap<String, $crate::serde_json::Value> =
+            std::collections::HashMap::new();
+        $crate::params_json!(@entries map ($($entries)*));
+        map
+    }};
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -202,6 +481,91 @@ mod tests {
         assert!(params_match(&params, &json!({"count": 42})));
     }
 
+    #[test]
+    fn test_exact_rejects_substrings() {
+        let mut params = HashMap::new();
+        params.insert("file_path".to_string(), "/tmp/a.txt".to_string());
+
+        assert!(params_match_exact(&params, &json!({"file_path": "/tmp/a.txt"})));
+        assert!(!params_match_exact(&params, &json!({"file_path": "/tmp/a.txt.bak"})));
+        assert!(!params_match_exact(&params, &json!({"other": "/tmp/a.txt"})));
+    }
+
+    #[test]
+    fn test_exact_treats_regex_chars_literally() {
+        let mut params = HashMap::new();
+        params.insert("pattern".to_string(), "*.rs".to_string());
+
+        assert!(params_match_exact(&params, &json!({"pattern": "*.rs"})));
+        assert!(!params_match_exact(&params, &json!({"pattern": "main.rs"})));
+    }
+
+    #[test]
+    fn test_exact_numbers_and_bools() {
+        let mut params = HashMap::new();
+        params.insert("limit".to_string(), "42".to_string());
+        params.insert("replace_all".to_string(), "true".to_string());
+
+        assert!(params_match_exact(&params, &json!({"limit": 42, "replace_all": true})));
+        assert!(params_match_exact(&params, &json!({"limit": 42.0, "replace_all": true})));
+        assert!(!params_match_exact(&params, &json!({"limit": 420, "replace_all": true})));
+        assert!(!params_match_exact(&params, &json!({"limit": 42, "replace_all": false})));
+    }
+
+    #[test]
+    fn test_glob_extension() {
+        let mut params = HashMap::new();
+        params.insert("file_path".to_string(), "*.env".to_string());
+
+        assert!(params_match_glob(&params, &json!({"file_path": ".env"})));
+        assert!(params_match_glob(&params, &json!({"file_path": "/app/prod.env"})));
+        assert!(!params_match_glob(&params, &json!({"file_path": "/app/.envrc"})));
+        // Under regex semantics the pattern is invalid and only matches itself
+        assert!(!params_match(&params, &json!({"file_path": "/app/prod.env"})));
+    }
+
+    #[test]
+    fn test_glob_recursive() {
+        let mut params = HashMap::new();
+        params.insert("file_path".to_string(), "**/*.rs".to_string());
+
+        assert!(params_match_glob(&params, &json!({"file_path": "main.rs"})));
+        assert!(params_match_glob(&params, &json!({"file_path": "src/fluent/builder.rs"})));
+        assert!(!params_match_glob(&params, &json!({"file_path": "src/lib.rs.orig"})));
+    }
+
+    #[test]
+    fn test_glob_brace_expansion() {
+        let mut params = HashMap::new();
+        params.insert("file_path".to_string(), "config.{json,yaml}".to_string());
+
+        assert!(params_match_glob(&params, &json!({"file_path": "config.json"})));
+        assert!(params_match_glob(&params, &json!({"file_path": "config.yaml"})));
+        assert!(!params_match_glob(&params, &json!({"file_path": "config.toml"})));
+    }
+
+    #[test]
+    fn test_glob_invalid_falls_back_to_exact() {
+        let mut params = HashMap::new();
+        params.insert("pattern".to_string(), "a[b".to_string());
+
+        assert!(params_match_glob(&params, &json!({"pattern": "a[b"})));
+        assert!(!params_match_glob(&params, &json!({"pattern": "ab"})));
+        assert!(!params_match_glob(&params, &json!({"other": "a[b"})));
+    }
+
+    #[test]
+    fn test_match_mode_dispatch() {
+        let mut params = HashMap::new();
+        params.insert("file_path".to_string(), "/tmp/a.txt".to_string());
+        let actual = json!({"file_path": "/tmp/a.txt.bak"});
+
+        assert!(MatchMode::Regex.matches(&params, &actual));
+        assert!(!MatchMode::Exact.matches(&params, &actual));
+        assert!(!MatchMode::Glob.matches(&params, &actual));
+        assert_eq!(MatchMode::default(), MatchMode::Regex);
+    }
+
     #[test]
     fn test_params_macro() {
         let params = params! {
@@ -212,4 +576,144 @@ mod tests {
         assert_eq!(params.get("file_path"), Some(&"test.txt".to_string()));
         assert_eq!(params.get("content"), Some(&"hello".to_string()));
     }
+
+    #[test]
+    fn test_params_json_macro() {
+        let depth = 2;
+        let params = params_json! {
+            "path" => "src",
+            "options" => {"recursive": true, "depth": depth},
+            "globs" => ["*.rs"]
+        };
+
+        assert_eq!(params["path"], json!("src"));
+        assert_eq!(params["options"], json!({"recursive": true, "depth": 2}));
+        assert_eq!(params["globs"], json!(["*.rs"]));
+
+        let params = params_json! {"offset" => -1, "scale" => depth * 2, "ids" => [-1, 2],};
+        assert_eq!(params["offset"], json!(-1));
+        assert_eq!(params["scale"], json!(4));
+        assert_eq!(params["ids"], json!([-1, 2]));
+        assert!(params_json! {}.is_empty());
+    }
+
+    #[test]
+    fn test_json_nested_subset_match() {
+        let params = params_json! {"options" => {"recursive": true}};
+
+        assert!(params_match_json(&params, &json!({"options": {"recursive": true}})));
+        // Extra keys at any level are ignored
+        assert!(params_match_json(
+            &params,
+            &json!({"path": "src", "options": {"recursive": true, "depth": 2}})
+        ));
+        // Values are not stringified, so the string "true" does not match
+        assert!(!params_match_json(&params, &json!({"options": {"recursive": "true"}})));
+        assert!(!params_match_json(&params, &json!({"options": {"recursive": false}})));
+        assert!(!params_match_json(&params, &json!({"options": {}})));
+        assert!(!params_match_json(&params, &json!({"path": "src"})));
+    }
+
+    #[test]
+    fn test_json_arrays_and_numbers() {
+        let params = params_json! {"paths" => [{"name": "a"}, "b"], "limit" => 10};
+
+        assert!(params_match_json(
+            &params,
+            &json!({"paths": [{"name": "a", "size": 1}, "b"], "limit": 10.0})
+        ));
+        // Arrays match as subsets: any order, extra elements ignored
+        assert!(params_match_json(&params, &json!({"paths": ["b", {"name": "a"}], "limit": 10})));
+        assert!(params_match_json(&params, &json!({"paths": [{"name": "a"}, "b", "c"], "limit": 10})));
+        assert!(!params_match_json(&params, &json!({"paths": [{"name": "a"}], "limit": 10})));
+        assert!(!params_match_json(&params, &json!({"paths": "b", "limit": 10})));
+        assert!(!params_match_json(&params, &json!({"paths": [{"name": "a"}, "b"], "limit": 11})));
+    }
+
+    #[test]
+    fn test_json_contains_nested_objects() {
+        let actual = json!({
+            "title": "Crash on start",
+            "body": {"template": "crash", "fields": {"os": "linux", "version": "1.2"}},
+        });
+
+        assert!(json_contains(&json!({}), &actual));
+        assert!(json_contains(&json!({"body": {"fields": {"os": "linux"}}}), &actual));
+        assert!(json_contains(&actual, &actual));
+        assert!(!json_contains(&json!({"body": {"fields": {"os": "macos"}}}), &actual));
+        // An object where the actual value is a scalar never matches
+        assert!(!json_contains(&json!({"title": {"text": "Crash on start"}}), &actual));
+    }
+
+    #[test]
+    fn test_json_contains_arrays() {
+        let actual = json!({"labels": ["bug", "p1"], "assignees": [{"login": "a", "id": 1}, {"login": "b"}]});
+
+        // Any order, extra elements ignored
+        assert!(json_contains(&json!({"labels": ["p1", "bug"]}), &actual));
+        assert!(json_contains(&json!({"labels": ["bug"]}), &actual));
+        assert!(json_contains(&json!({"labels": []}), &actual));
+        // Elements are themselves matched as subsets
+        assert!(json_contains(&json!({"assignees": [{"login": "b"}]}), &actual));
+        assert!(!json_contains(&json!({"labels": ["wontfix"]}), &actual));
+        assert!(!json_contains(&json!({"labels": "bug"}), &actual));
+    }
+
+    #[test]
+    fn test_json_contains_missing_keys() {
+        let actual = json!({"path": "src", "options": {"recursive": true}});
+
+        assert!(!json_contains(&json!({"pattern": "*.rs"}), &actual));
+        assert!(!json_contains(&json!({"options": {"depth": 2}}), &actual));
+        // null is a value, not a wildcard for a missing key
+        assert!(!json_contains(&json!({"options": {"depth": null}}), &actual));
+        assert!(json_contains(&json!({"depth": null}), &json!({"depth": null})));
+    }
+
+    #[test]
+    fn test_path_params_match_equivalent_forms() {
+        // A project under the home directory that doesn't exist on disk
+        let home = dirs::home_dir().unwrap();
+        let project = home.join("aptitude-missing-project");
+        let forms = [
+            "~/aptitude-missing-project/src/main.rs".to_string(),
+            project.join("src/main.rs").to_string_lossy().into_owned(),
+            "./src/main.rs".to_string(),
+        ];
+
+        for expected in &forms {
+            for actual in &forms {
+                let expected = HashMap::from([("file_path".to_string(), expected.clone())]);
+                assert!(
+                    path_params_match(&expected, &json!({ "file_path": actual }), &project),
+                    "{:?} should match {}",
+                    expected,
+                    actual
+                );
+            }
+        }
+
+        let expected = HashMap::from([("file_path".to_string(), "./src/main.rs".to_string())]);
+        assert!(!path_params_match(&expected, &json!({"file_path": "~/src/main.rs"}), &project));
+        assert!(!path_params_match(&expected, &json!({"file_path": 42}), &project));
+        assert!(!path_params_match(&expected, &json!({"path": "src/main.rs"}), &project));
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        let base = Path::new("/aptitude-missing/proj");
+
+        assert_eq!(normalize_path("src/./lib.rs", base), base.join("src/lib.rs"));
+        assert_eq!(normalize_path("../other/a.rs", base), Path::new("/aptitude-missing/other/a.rs"));
+        // `..` never climbs above the root
+        assert_eq!(normalize_path("/../../etc/hosts", base), Path::new("/etc/hosts"));
+        // Only a leading `~` or `~/` means the home directory
+        assert_eq!(normalize_path("~user/a.rs", base), base.join("~user/a.rs"));
+        assert_eq!(normalize_path("~", base), dirs::home_dir().unwrap().canonicalize().unwrap());
+
+        // Existing prefixes are canonicalized, so symlinked directories compare equal
+        let dir = tempfile::tempdir().unwrap();
+        let canonical = dir.path().canonicalize().unwrap();
+        assert_eq!(normalize_path("new/file.rs", dir.path()), canonical.join("new/file.rs"));
+    }
 }
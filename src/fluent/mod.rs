@@ -31,16 +31,26 @@
 //! ```
 
 mod builder;
+mod exit_code;
 mod matchers;
 mod stdout;
 mod tool;
+mod usage;
 
 pub use builder::{
-    expect, expect_tools, AssertionResult, ExecutionExpectation, NthCallAssertion, ToolAssertion,
+    assert_no_failures, expect, expect_tools, AssertionResult, BashAssertion, CalledToolAssertion, ExecutionExpectation, FilesAssertion, NthCallAssertion, SequenceAssertion, ToolAssertion, ToolCallsAssertion,
 };
-pub use matchers::params_match;
+pub use exit_code::ExitCodeAssertion;
+pub use matchers::{
+    json_contains, normalize_path, params_match, params_match_exact, params_match_glob,
+    params_match_json, path_params_match, MatchMode,
+};
+#[cfg(feature = "yaml")]
+pub(crate) use builder::CommandFilter;
+pub(crate) use matchers::glob_match;
 pub use stdout::StdoutAssertion;
-pub use tool::Tool;
+pub use tool::{Tool, ToolMatcher};
+pub use usage::UsageAssertion;
 
 #[cfg(test)]
 mod tests;
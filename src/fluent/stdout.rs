@@ -1,19 +1,23 @@
-//! Fluent assertion builder for LLM-powered stdout review.
+//! Fluent assertion builder for stdout content.
 //!
-//! This module provides the builder type for making assertions about stdout
-//! using an LLM grader:
-//! - `StdoutAssertion` - Builder for review-based assertions on stdout content
+//! This module provides the builder type for making assertions about stdout,
+//! either locally (substring/regex checks) or using an LLM grader:
+//! - `StdoutAssertion` - Builder for content and review-based assertions on stdout
 
 use std::sync::Arc;
 
+use regex::Regex;
+
 use super::builder::AssertionResult;
 use crate::agents::Agent;
 use crate::review::{self, ReviewConfig};
 
-/// Builder for LLM-powered assertions on stdout.
+/// Builder for assertions on stdout.
 ///
-/// Methods like `to_pass()` evaluate immediately and panic on failure.
-/// Use `evaluate()` for non-panicking evaluation.
+/// Content checks (`contains`, `not_contains`, `matches_regex`) run locally
+/// and are evaluated by `to_exist()`. Review criteria are graded by an LLM
+/// and evaluated by `to_pass()`. Use the `evaluate*` methods for
+/// non-panicking evaluation.
 ///
 /// # Example
 ///
@@ -23,6 +27,12 @@ use crate::review::{self, ReviewConfig};
 ///     .stdout()
 ///     .review("should confirm the file was created")
 ///     .to_pass();
+///
+/// expect(&output)
+///     .stdout()
+///     .contains("created")
+///     .matches_regex(r"\d+ files")
+///     .to_exist();
 /// ```
 #[derive(Clone)]
 pub struct StdoutAssertion {
@@ -30,7 +40,11 @@ pub struct StdoutAssertion {
     review: Option<String>,
     threshold: u32,
     model: Option<String>,
+    template: Option<String>,
     grader: Option<Arc<dyn Agent>>,
+    contains: Vec<String>,
+    not_contains: Vec<String>,
+    patterns: Vec<String>,
 }
 
 impl std::fmt::Debug for StdoutAssertion {
@@ -40,7 +54,11 @@ impl std::fmt::Debug for StdoutAssertion {
             .field("review", &self.review)
             .field("threshold", &self.threshold)
             .field("model", &self.model)
+            .field("template", &self.template)
             .field("grader", &self.grader.as_ref().map(|g| g.name()))
+            .field("contains", &self.contains)
+            .field("not_contains", &self.not_contains)
+            .field("patterns", &self.patterns)
             .finish()
     }
 }
@@ -53,7 +71,11 @@ impl StdoutAssertion {
             review: None,
             threshold: 7,
             model: None,
+            template: None,
             grader: None,
+            contains: Vec::new(),
+            not_contains: Vec::new(),
+            patterns: Vec::new(),
         }
     }
 
@@ -85,7 +107,11 @@ impl StdoutAssertion {
             review: Some(criteria.to_string()),
             threshold: 7, // sensible default for most cases
             model: None,
+            template: None,
             grader: None,
+            contains: Vec::new(),
+            not_contains: Vec::new(),
+            patterns: Vec::new(),
         }
     }
 
@@ -163,6 +189,27 @@ impl StdoutAssertion {
         self
     }
 
+    /// Replace the default grading prompt with a custom template.
+    ///
+    /// `{criteria}` and `{output}` are substituted with the review criteria
+    /// and the captured stdout. The template should still ask the grader to
+    /// respond with `{"score": <1-10>, "reasoning": "..."}`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&output)
+    ///     .with_grader(agent)
+    ///     .stdout()
+    ///     .review("should be polite")
+    ///     .with_template("Score this reply for tone.\n{criteria}\n{output}\nReply as JSON.")
+    ///     .to_pass();
+    /// ```
+    pub fn with_template(mut self, template: &str) -> Self {
+        self.template = Some(template.to_string());
+        self
+    }
+
     /// Set the agent to use for grading.
     ///
     /// This determines which CLI command is used to invoke the grading LLM.
@@ -172,10 +219,65 @@ impl StdoutAssertion {
         self
     }
 
+    /// Require stdout to contain a substring. Checked by `to_exist()`.
+    pub fn contains(mut self, needle: &str) -> Self {
+        self.contains.push(needle.to_string());
+        self
+    }
+
+    /// Require stdout to not contain a substring. Checked by `to_exist()`.
+    pub fn not_contains(mut self, needle: &str) -> Self {
+        self.not_contains.push(needle.to_string());
+        self
+    }
+
+    /// Require stdout to match a regex (unanchored). Checked by `to_exist()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// expect(&output)
+    ///     .stdout()
+    ///     .matches_regex(r"\d+ files changed")
+    ///     .to_exist();
+    /// ```
+    pub fn matches_regex(mut self, pattern: &str) -> Self {
+        self.patterns.push(pattern.to_string());
+        self
+    }
+
     // =========================================================================
     // Assertion methods (panic on failure)
     // =========================================================================
 
+    /// Assert stdout is non-empty and satisfies all content checks.
+    ///
+    /// Evaluated locally; no grading agent is needed. With only
+    /// `not_contains` checks, empty stdout passes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if stdout is empty or any `contains`/`not_contains`/`matches_regex`
+    /// check fails.
+    pub fn to_exist(&self) {
+        let result = self.evaluate_exists();
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
+    /// Assert stdout is empty or was not captured.
+    ///
+    /// # Panics
+    ///
+    /// Panics if stdout has any content.
+    pub fn to_not_exist(&self) {
+        let result = self.evaluate_not_exists();
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
     /// Assert stdout passes the review criteria.
     ///
     /// Panics with a detailed error message if the assertion fails.
@@ -236,6 +338,7 @@ impl StdoutAssertion {
             criteria: criteria.clone(),
             threshold: self.threshold,
             model: self.model.clone(),
+            prompt_template: self.template.clone(),
         };
 
         let result = review::grade_stdout(&self.stdout, &config, |prompt, model| {
@@ -261,6 +364,58 @@ impl StdoutAssertion {
         }
     }
 
+    /// Evaluate the content checks without panicking.
+    ///
+    /// Passes if every `contains`, `not_contains` and `matches_regex` check
+    /// holds. Empty stdout fails unless `not_contains` checks are the only
+    /// ones set, since empty output contains nothing. All failures are
+    /// reported.
+    pub fn evaluate_exists(&self) -> AssertionResult {
+        let description = self.content_description();
+        let stdout = self.stdout.as_deref().unwrap_or_default();
+        let needs_output =
+            !self.contains.is_empty() || !self.patterns.is_empty() || self.not_contains.is_empty();
+        if stdout.is_empty() && needs_output {
+            return AssertionResult::fail(description, "stdout is empty");
+        }
+
+        let mut failures: Vec<String> = Vec::new();
+        for needle in &self.contains {
+            if !stdout.contains(needle.as_str()) {
+                failures.push(format!("stdout does not contain \"{}\"", needle));
+            }
+        }
+        for needle in &self.not_contains {
+            if stdout.contains(needle.as_str()) {
+                failures.push(format!("stdout contains \"{}\"", needle));
+            }
+        }
+        for pattern in &self.patterns {
+            match Regex::new(pattern) {
+                Ok(re) if re.is_match(stdout) => {}
+                Ok(_) => failures.push(format!("stdout does not match /{}/", pattern)),
+                Err(e) => failures.push(format!("invalid regex /{}/: {}", pattern, e)),
+            }
+        }
+
+        if failures.is_empty() {
+            AssertionResult::pass(description)
+        } else {
+            AssertionResult::fail(description, failures.join("; "))
+        }
+    }
+
+    /// Evaluate that stdout is empty or was not captured, without panicking.
+    pub fn evaluate_not_exists(&self) -> AssertionResult {
+        match self.stdout.as_deref() {
+            Some(s) if !s.is_empty() => AssertionResult::fail(
+                "stdout to be empty",
+                format!("stdout has {} bytes", s.len()),
+            ),
+            _ => AssertionResult::pass("stdout to be empty"),
+        }
+    }
+
     /// Async version of evaluate for parallel processing.
     ///
     /// This method uses the async grading pipeline for better performance
@@ -296,6 +451,7 @@ impl StdoutAssertion {
             criteria: criteria.clone(),
             threshold: self.threshold,
             model: self.model.clone(),
+            prompt_template: self.template.clone(),
         };
 
         let result = review::grade_stdout_async(&self.stdout, &config, |prompt, model| {
@@ -343,7 +499,24 @@ impl StdoutAssertion {
     // Internal helpers
     // =========================================================================
 
-    fn format_stdout_preview(&self) -> String {
+    fn content_description(&self) -> String {
+        let mut checks: Vec<String> = Vec::new();
+        checks.extend(self.contains.iter().map(|c| format!("contains \"{}\"", c)));
+        checks.extend(
+            self.not_contains
+                .iter()
+                .map(|c| format!("does not contain \"{}\"", c)),
+        );
+        checks.extend(self.patterns.iter().map(|p| format!("matches /{}/", p)));
+
+        if checks.is_empty() {
+            "stdout to exist".to_string()
+        } else {
+            format!("stdout {}", checks.join(", "))
+        }
+    }
+
+    pub(crate) fn format_stdout_preview(&self) -> String {
         match &self.stdout {
             Some(s) if !s.is_empty() => {
                 if s.len() > 100 {
@@ -377,6 +550,7 @@ pub struct StdoutAssertionBuilder {
     review: Option<String>,
     threshold: u32,
     model: Option<String>,
+    template: Option<String>,
     grader: Option<Arc<dyn Agent>>,
 }
 
@@ -387,6 +561,7 @@ impl std::fmt::Debug for StdoutAssertionBuilder {
             .field("review", &self.review)
             .field("threshold", &self.threshold)
             .field("model", &self.model)
+            .field("template", &self.template)
             .field("grader", &self.grader.as_ref().map(|g| g.name()))
             .finish()
     }
@@ -400,6 +575,7 @@ impl StdoutAssertionBuilder {
             review: None,
             threshold: 7,
             model: None,
+            template: None,
             grader: None,
         }
     }
@@ -428,6 +604,12 @@ impl StdoutAssertionBuilder {
         self
     }
 
+    /// Set a custom grading prompt template.
+    pub fn with_template(mut self, template: &str) -> Self {
+        self.template = Some(template.to_string());
+        self
+    }
+
     /// Set the agent to use for grading.
     pub fn with_grader(mut self, agent: Arc<dyn Agent>) -> Self {
         self.grader = Some(agent);
@@ -441,7 +623,11 @@ impl StdoutAssertionBuilder {
             review: self.review,
             threshold: self.threshold,
             model: self.model,
+            template: self.template,
             grader: self.grader,
+            contains: Vec::new(),
+            not_contains: Vec::new(),
+            patterns: Vec::new(),
         }
     }
 }
@@ -455,67 +641,25 @@ impl Default for StdoutAssertionBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use anyhow::Result;
-    use crate::agents::{ExecutionConfig, RawExecutionResult, ToolNameMapping};
-    use crate::parser::ToolCall;
+    use crate::test_util::MockAgent;
 
-    /// A mock agent that returns a predetermined grading response.
-    struct MockAgent {
-        response: String,
+    fn passing() -> Arc<dyn Agent> {
+        Arc::new(MockAgent::new().with_grade(9, "Meets criteria well"))
     }
 
-    impl MockAgent {
-        fn passing() -> Arc<dyn Agent> {
-            Arc::new(Self {
-                response: r#"{"score": 9, "reasoning": "Meets criteria well"}"#.to_string(),
-            })
-        }
-
-        fn failing() -> Arc<dyn Agent> {
-            Arc::new(Self {
-                response: r#"{"score": 3, "reasoning": "Does not meet criteria"}"#.to_string(),
-            })
-        }
-
-        fn with_score(score: u32) -> Arc<dyn Agent> {
-            Arc::new(Self {
-                response: format!(r#"{{"score": {}, "reasoning": "score {}"}}"#, score, score),
-            })
-        }
+    fn failing() -> Arc<dyn Agent> {
+        Arc::new(MockAgent::new().with_grade(3, "Does not meet criteria"))
     }
 
-    #[async_trait::async_trait]
-    impl Agent for MockAgent {
-        fn name(&self) -> &'static str {
-            "mock"
-        }
-
-        fn execute(&self, _prompt: &str, _config: &ExecutionConfig) -> Result<RawExecutionResult> {
-            unimplemented!("not needed for grading tests")
-        }
-
-        fn parse_session(&self, _result: &RawExecutionResult) -> Result<Vec<ToolCall>> {
-            unimplemented!("not needed for grading tests")
-        }
-
-        fn tool_mapping(&self) -> &ToolNameMapping {
-            unimplemented!("not needed for grading tests")
-        }
-
-        fn is_available(&self) -> bool {
-            true
-        }
-
-        fn grade(&self, _prompt: &str, _model: Option<&str>) -> Result<String> {
-            Ok(self.response.clone())
-        }
+    fn with_score(score: u32) -> Arc<dyn Agent> {
+        Arc::new(MockAgent::new().with_grade(score, &format!("score {}", score)))
     }
 
     #[test]
     fn test_review_passing() {
         let assertion = StdoutAssertion::new(Some("Task completed".to_string()))
             .review("should confirm completion")
-            .with_grader(MockAgent::passing());
+            .with_grader(passing());
 
         let result = assertion.evaluate();
         assert!(result.passed);
@@ -526,7 +670,7 @@ mod tests {
     fn test_review_failing() {
         let assertion = StdoutAssertion::new(Some("Error occurred".to_string()))
             .review("should confirm success")
-            .with_grader(MockAgent::failing());
+            .with_grader(failing());
 
         let result = assertion.evaluate();
         assert!(!result.passed);
@@ -538,7 +682,7 @@ mod tests {
         let assertion = StdoutAssertion::new(Some("test".to_string()))
             .review("test criteria")
             .with_threshold(8)
-            .with_grader(MockAgent::with_score(7));
+            .with_grader(with_score(7));
 
         let result = assertion.evaluate();
         assert!(!result.passed); // 7 < threshold 8
@@ -547,7 +691,7 @@ mod tests {
     #[test]
     fn test_review_no_criteria() {
         let assertion = StdoutAssertion::new(Some("test".to_string()))
-            .with_grader(MockAgent::passing());
+            .with_grader(passing());
 
         let result = assertion.evaluate();
         assert!(!result.passed);
@@ -568,7 +712,7 @@ mod tests {
     fn test_review_empty_stdout() {
         let assertion = StdoutAssertion::new(None)
             .review("should have no output")
-            .with_grader(MockAgent::passing());
+            .with_grader(passing());
 
         let result = assertion.evaluate();
         assert!(result.passed);
@@ -580,7 +724,7 @@ mod tests {
         let assertion = StdoutAssertion::new(Some("test".to_string()))
             .review("criteria")
             .with_model("claude-sonnet-4-20250514")
-            .with_grader(MockAgent::passing());
+            .with_grader(passing());
 
         assert_eq!(assertion.model, Some("claude-sonnet-4-20250514".to_string()));
         let result = assertion.evaluate();
@@ -592,7 +736,79 @@ mod tests {
     fn test_to_pass_panics_on_failure() {
         StdoutAssertion::new(Some("bad output".to_string()))
             .review("should be good")
-            .with_grader(MockAgent::failing())
+            .with_grader(failing())
             .to_pass();
     }
+
+    #[test]
+    fn test_content_checks_pass() {
+        let assertion = StdoutAssertion::new(Some("Updated 3 files, no errors".to_string()))
+            .contains("Updated")
+            .not_contains("ERROR")
+            .matches_regex(r"\d+ files");
+
+        let result = assertion.evaluate_exists();
+        assert!(result.passed);
+        assert_eq!(
+            result.description,
+            r#"stdout contains "Updated", does not contain "ERROR", matches /\d+ files/"#
+        );
+        assertion.to_exist();
+    }
+
+    #[test]
+    fn test_content_checks_report_all_failures() {
+        let result = StdoutAssertion::new(Some("ERROR: build failed".to_string()))
+            .contains("success")
+            .not_contains("ERROR")
+            .matches_regex(r"\d+ files")
+            .evaluate_exists();
+
+        assert!(!result.passed);
+        assert_eq!(
+            result.reason.as_deref(),
+            Some(
+                r#"stdout does not contain "success"; stdout contains "ERROR"; stdout does not match /\d+ files/"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_content_invalid_regex() {
+        let result = StdoutAssertion::new(Some("output".to_string()))
+            .matches_regex("(unclosed")
+            .evaluate_exists();
+
+        assert!(!result.passed);
+        assert!(result.reason.unwrap().starts_with("invalid regex /(unclosed/"));
+    }
+
+    #[test]
+    fn test_exists_and_not_exists() {
+        assert!(StdoutAssertion::new(Some("x".to_string())).evaluate_exists().passed);
+        assert!(!StdoutAssertion::new(None).evaluate_exists().passed);
+        assert!(!StdoutAssertion::new(Some(String::new())).evaluate_exists().passed);
+
+        assert!(StdoutAssertion::new(None).evaluate_not_exists().passed);
+        assert!(StdoutAssertion::new(Some(String::new())).evaluate_not_exists().passed);
+        assert!(!StdoutAssertion::new(Some("x".to_string())).evaluate_not_exists().passed);
+    }
+
+    #[test]
+    fn test_not_contains_allows_empty_stdout() {
+        assert!(StdoutAssertion::new(None).not_contains("ERROR").evaluate_exists().passed);
+        let empty = StdoutAssertion::new(Some(String::new()));
+        assert!(empty.clone().not_contains("ERROR").evaluate_exists().passed);
+
+        let result = empty.not_contains("ERROR").contains("done").evaluate_exists();
+        assert_eq!(result.reason.as_deref(), Some("stdout is empty"));
+    }
+
+    #[test]
+    #[should_panic(expected = "stdout: \"ERROR: build failed\"")]
+    fn test_to_exist_panics_with_preview() {
+        StdoutAssertion::new(Some("ERROR: build failed".to_string()))
+            .not_contains("ERROR")
+            .to_exist();
+    }
 }
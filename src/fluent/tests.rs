@@ -1,8 +1,10 @@
 //! Tests for the fluent assertion API.
 
 use super::*;
-use crate::params;
+use crate::agents::{ExecutionOutput, NormalizedResult};
+use crate::{params, params_json};
 use crate::parser::ToolCall;
+use crate::snapshot::SnapshotUpdate;
 use serde_json::json;
 
 fn make_call(name: &str, params: serde_json::Value) -> ToolCall {
@@ -10,6 +12,7 @@ fn make_call(name: &str, params: serde_json::Value) -> ToolCall {
         name: name.to_string(),
         params,
         timestamp: String::new(),
+        result: None,
     }
 }
 
@@ -251,6 +254,81 @@ fn test_last_call() {
         .has_params(params! {"file_path" => "/last.txt"});
 }
 
+#[test]
+fn test_first_call() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/first.txt"})),
+        make_call("Read", json!({"file_path": "/config.json"})),
+        make_call("Read", json!({"file_path": "/last.txt"})),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .first_call()
+        .has_params(params! {"file_path" => "/first.txt"});
+
+    // Respects the params filter
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_params(params! {"file_path" => r"\.json$"})
+        .first_call()
+        .has_params(params! {"file_path" => "/config.json"});
+}
+
+#[test]
+fn test_call_accessors_share_filters() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.rs"})),
+        make_call("Bash", json!({"command": "ls"})),
+        make_call("Read", json!({"file_path": "/b.txt"})),
+        make_call("Read", json!({"file_path": "/c.rs"})),
+        make_call("Read", json!({"file_path": "/d.txt"})),
+    ];
+    let txt = expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_params(params! {"file_path" => r"\.txt$"});
+
+    let first = txt.first_call();
+    assert_eq!(first.param_str("file_path"), Some("/b.txt"));
+    assert_eq!((first.index(), first.session_index()), (1, 3));
+    assert_eq!(txt.nth_call(1).params(), first.params());
+    let last = txt.last_call();
+    assert_eq!(last.param_str("file_path"), Some("/d.txt"));
+    assert_eq!((last.index(), last.session_index()), (2, 5));
+    assert_eq!(txt.nth_call(2).params(), last.params());
+
+    let result = txt.nth_call(2).evaluate_params(params! {"file_path" => "/b.txt"});
+    assert_eq!(result.description, "Read call #2 (session call #5) params match");
+    let called = txt.clone().assert_called().last();
+    assert_eq!(called.session_index(), 5);
+}
+
+#[test]
+#[should_panic(expected = "expected Read call #3 to exist\n\n  actual: 2 matching calls made")]
+fn test_nth_call_counts_filtered_calls() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.rs"})),
+        make_call("Read", json!({"file_path": "/b.txt"})),
+        make_call("Read", json!({"file_path": "/c.txt"})),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_params(params! {"file_path" => r"\.txt$"})
+        .nth_call(3);
+}
+
+#[test]
+#[should_panic(expected = "expected Read to have been called")]
+fn test_first_call_no_match() {
+    let calls = vec![make_call("Read", json!({"file_path": "/a.txt"}))];
+
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_params(params! {"file_path" => r"\.rs$"})
+        .first_call();
+}
+
 #[test]
 fn test_nth_call_evaluate_params() {
     let calls = vec![make_call("Read", json!({"file_path": "/test.txt"}))];
@@ -383,3 +461,1309 @@ fn test_multiple_count_constraints() {
         .evaluate();
     assert!(!result.passed);
 }
+
+#[test]
+fn test_sequence_matches() {
+    let calls = vec![
+        make_call("Glob", json!({"pattern": "*.rs"})),
+        make_call("Read", json!({"file_path": "/a.rs"})),
+        make_call("Edit", json!({"file_path": "/a.rs"})),
+    ];
+
+    let sequence = expect_tools(&calls).sequence(&[Tool::Glob, Tool::Read, Tool::Edit]);
+    sequence.to_occur();
+    sequence.to_occur_consecutively();
+    assert_eq!(sequence.evaluate().description, "[Glob, Read, Edit] in order");
+}
+
+#[test]
+fn test_sequence_tolerates_gaps() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/notes.md"})),
+        make_call("Glob", json!({"pattern": "*.rs"})),
+        make_call("Bash", json!({"command": "ls"})),
+        make_call("Read", json!({"file_path": "/a.rs"})),
+        make_call("Edit", json!({"file_path": "/a.rs"})),
+    ];
+
+    let sequence = expect_tools(&calls).sequence(&[Tool::Glob, Tool::Read, Tool::Edit]);
+    sequence.to_occur();
+
+    // Bash between Glob and Read breaks adjacency, but Read then Edit is adjacent
+    let result = sequence.evaluate_consecutive();
+    assert!(!result.passed);
+    assert_eq!(
+        result.reason.as_deref(),
+        Some("tools were called in order, but with other calls in between")
+    );
+    expect_tools(&calls)
+        .sequence(&[Tool::Read, Tool::Edit])
+        .to_occur_consecutively();
+}
+
+#[test]
+fn test_sequence_wrong_order_fails() {
+    let calls = vec![
+        make_call("Edit", json!({"file_path": "/a.rs"})),
+        make_call("Read", json!({"file_path": "/a.rs"})),
+        make_call("Glob", json!({"pattern": "*.rs"})),
+    ];
+
+    let result = expect_tools(&calls)
+        .sequence(&[Tool::Glob, Tool::Read, Tool::Edit])
+        .evaluate();
+    assert!(!result.passed);
+    assert_eq!(result.reason.as_deref(), Some("'Read' was not called after [Glob]"));
+
+    let result = expect_tools(&calls)
+        .sequence(&[Tool::Write, Tool::Edit])
+        .evaluate_consecutive();
+    assert_eq!(result.reason.as_deref(), Some("'Write' was never called"));
+
+    // An empty pattern trivially occurs
+    expect_tools(&calls).sequence(&[]).to_occur_consecutively();
+}
+
+#[test]
+#[should_panic(expected = "assertion failed: expected [Read, Edit] in order")]
+fn test_sequence_to_occur_panics() {
+    let calls = vec![make_call("Edit", json!({"file_path": "/a.rs"}))];
+    expect_tools(&calls).sequence(&[Tool::Read, Tool::Edit]).to_occur();
+}
+
+#[test]
+fn test_files_read_deduplicates() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/src/main.rs"})),
+        make_call("Read", json!({"file_path": "/src/lib.rs"})),
+        make_call("Read", json!({"file_path": "/src/main.rs"})),
+        make_call("Edit", json!({"file_path": "/src/lib.rs"})),
+        make_call("Grep", json!({"pattern": "fn main"})),
+    ];
+
+    let files = expect_tools(&calls).files_read();
+    assert_eq!(files.files(), ["/src/main.rs", "/src/lib.rs"]);
+    files.count_under(3);
+    files.count_at_most(2);
+
+    let result = files.evaluate_count_under(2);
+    assert!(!result.passed);
+    assert_eq!(result.description, "fewer than 2 files read");
+    assert_eq!(
+        result.reason.as_deref(),
+        Some("2 files read: /src/main.rs, /src/lib.rs")
+    );
+}
+
+#[test]
+fn test_files_written_include_pattern() {
+    let calls = vec![
+        make_call("Write", json!({"file_path": "/app/config.toml", "content": ""})),
+        make_call("Edit", json!({"file_path": "/app/src/main.rs"})),
+        make_call("Edit", json!({"file_path": "/app/src/main.rs"})),
+        make_call("NotebookEdit", json!({"notebook_path": "/app/analysis.ipynb"})),
+        make_call("Read", json!({"file_path": "/app/.env"})),
+    ];
+
+    let files = expect_tools(&calls).files_written();
+    assert_eq!(files.count(), 3);
+    files.to_include("*.rs");
+    files.to_include("/app/*.{toml,ipynb}");
+
+    let result = files.evaluate_include("*.env");
+    assert!(!result.passed);
+    assert_eq!(result.description, "files written include '*.env'");
+    assert!(result.reason.unwrap().starts_with("no file written matches (/app/config.toml"));
+}
+
+#[test]
+#[should_panic(expected = "reason: no file read matches (none)")]
+fn test_files_to_include_panics_when_nothing_read() {
+    expect_tools(&[]).files_read().to_include("*.rs");
+}
+
+#[test]
+fn test_no_web_access_passes() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("Bash", json!({"command": "curl https://example.com"})),
+    ];
+
+    // Bash commands are not considered web access
+    expect_tools(&calls).no_web_access();
+}
+
+#[test]
+#[should_panic(expected = "call #2 used 'WebFetch'")]
+fn test_no_web_access_fails_on_web_fetch() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("WebFetch", json!({"url": "https://example.com"})),
+    ];
+
+    expect_tools(&calls).no_web_access();
+}
+
+#[test]
+fn test_no_tools_called() {
+    expect_tools(&[]).no_tools_called();
+
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("Bash", json!({"command": "ls"})),
+    ];
+    let result = expect_tools(&calls).evaluate_no_tools_called();
+    assert!(!result.passed);
+    assert_eq!(result.reason.as_deref(), Some("2 tool calls made: Read, Bash"));
+}
+
+#[test]
+fn test_only_tools_called() {
+    let calls = vec![
+        make_call("Glob", json!({"pattern": "**/*.rs"})),
+        make_call("Read", json!({"file_path": "/a.rs"})),
+    ];
+    expect_tools(&calls).only_tools_called(&[Tool::Read, Tool::Glob]);
+    expect_tools(&[]).only_tools_called(&[]);
+}
+
+#[test]
+#[should_panic(expected = "call #2 used 'Bash'")]
+fn test_only_tools_called_rejects_bash() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.rs"})),
+        make_call("Bash", json!({"command": "rm -rf /tmp/x"})),
+    ];
+    expect_tools(&calls).only_tools_called(&[Tool::Read, Tool::Glob]);
+}
+
+#[test]
+fn test_tool_calls_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("reads.snap");
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("Bash", json!({"command": "ls"})),
+    ];
+
+    assert!(expect_tools(&calls).tool_calls().evaluate_snapshot_at(&path, SnapshotUpdate::New).passed);
+    assert!(expect_tools(&calls).tool_calls().evaluate_snapshot_at(&path, SnapshotUpdate::New).passed);
+
+    let result = expect_tools(&calls[..1]).tool_calls().evaluate_snapshot_at(&path, SnapshotUpdate::New);
+    assert!(!result.passed);
+    assert!(result.reason.unwrap().contains("  - Bash ls\n"));
+
+    let result = expect_tools(&calls).tool_calls().evaluate_snapshot("../reads");
+    assert!(!result.passed);
+    assert!(result.reason.unwrap().contains("invalid snapshot name '../reads'"));
+}
+
+#[test]
+fn test_with_url_domain() {
+    let calls = vec![
+        make_call("WebFetch", json!({"url": "https://example.com/path"})),
+        make_call("WebFetch", json!({"url": "https://docs.Example.com/guide?q=1"})),
+        make_call("WebFetch", json!({"url": "https://notexample.com/"})),
+        make_call("WebFetch", json!({"url": "https://evil.io/?next=example.com"})),
+        make_call("WebFetch", json!({"url": "not a url"})),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::WebFetch)
+        .with_url_domain("example.com")
+        .times(2)
+        .to_be_called();
+
+    // Subdomains only match themselves and their own subdomains
+    expect_tools(&calls)
+        .tool(Tool::WebFetch)
+        .with_url_domain("docs.example.com")
+        .times(1)
+        .to_be_called();
+
+    let result = expect_tools(&calls)
+        .tool(Tool::WebFetch)
+        .with_url_domain("evil.io")
+        .evaluate_not_called();
+    assert!(!result.passed);
+    assert_eq!(result.description, "WebFetch on domain evil.io not called");
+}
+
+#[test]
+fn test_with_url_domain_ignores_calls_without_url() {
+    let calls = vec![make_call("WebSearch", json!({"query": "example.com docs"}))];
+
+    expect_tools(&calls)
+        .tool(Tool::WebSearch)
+        .with_url_domain("example.com")
+        .not_to_be_called();
+}
+
+#[test]
+fn test_with_cell_index() {
+    let calls = vec![
+        make_call(
+            "NotebookEdit",
+            json!({"notebook_path": "/app/analysis.ipynb", "cell_number": 3, "new_source": "df.describe()"}),
+        ),
+        make_call(
+            "NotebookEdit",
+            json!({"notebook_path": "/app/analysis.ipynb", "cell_id": "5", "new_source": "plt.show()", "edit_mode": "replace"}),
+        ),
+        make_call(
+            "NotebookEdit",
+            json!({"notebook_path": "/app/analysis.ipynb", "cell_id": "a1b2c3", "new_source": ""}),
+        ),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::NotebookEdit)
+        .with_cell_index(3)
+        .to_be_called_once();
+    expect_tools(&calls)
+        .tool(Tool::NotebookEdit)
+        .with_cell_index(5)
+        .with_params(params! {"new_source" => "plt"})
+        .to_be_called();
+
+    let result = expect_tools(&calls)
+        .tool(Tool::NotebookEdit)
+        .with_cell_index(0)
+        .evaluate();
+    assert!(!result.passed);
+    assert_eq!(result.description, "NotebookEdit on cell 0 called");
+}
+
+#[test]
+fn test_no_network_scans_bash_commands() {
+    let calls = vec![make_call("Bash", json!({"command": "cd /tmp && wget http://x.io/f"}))];
+
+    let result = expect_tools(&calls).evaluate_no_network();
+    assert!(!result.passed);
+    assert!(result.reason.unwrap().contains("wget http://x.io/f"));
+
+    let calls = vec![make_call("WebSearch", json!({"query": "rust"}))];
+    assert!(!expect_tools(&calls).evaluate_no_network().passed);
+}
+
+#[test]
+fn test_no_network_catches_paths_and_wrappers() {
+    for command in ["/usr/bin/curl https://x.io", "sudo wget http://x.io/f", "env curl x.io"] {
+        let calls = vec![make_call("Bash", json!({"command": command}))];
+        assert!(!expect_tools(&calls).evaluate_no_network().passed, "{}", command);
+    }
+}
+
+#[test]
+fn test_no_network_ignores_lookalike_commands() {
+    let calls = vec![
+        make_call("Bash", json!({"command": "cargo sync"})),
+        make_call("Bash", json!({"command": "cat ssh_config.md"})),
+        make_call("Bash", json!({"command": "echo hello"})),
+    ];
+
+    expect_tools(&calls).no_network();
+}
+
+#[test]
+fn test_param_sequence() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("Bash", json!({"command": "ls"})),
+        make_call("Read", json!({"file_path": "/b.txt"})),
+        make_call("Read", json!({"file_path": "/c.txt"})),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .param_sequence("file_path", &["/a.txt", "/b.txt", "/c.txt"])
+        .to_be_called();
+}
+
+#[test]
+fn test_param_sequence_reports_divergence() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("Read", json!({"file_path": "/c.txt"})),
+    ];
+
+    let result = expect_tools(&calls)
+        .tool(Tool::Read)
+        .param_sequence("file_path", &["/a.txt", "/b.txt"])
+        .evaluate();
+    assert!(!result.passed);
+    let reason = result.reason.unwrap();
+    assert!(reason.contains("diverged at index 1"), "{}", reason);
+    assert!(reason.contains("expected '/b.txt', got '/c.txt'"), "{}", reason);
+
+    let result = expect_tools(&calls)
+        .tool(Tool::Read)
+        .param_sequence("file_path", &["/a.txt"])
+        .evaluate();
+    assert!(result.reason.unwrap().contains("too long"));
+}
+
+#[test]
+fn test_param_sequence_sorted() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("Read", json!({"file_path": "/b.txt"})),
+    ];
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .param_sequence_sorted("file_path")
+        .to_be_called();
+
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/b.txt"})),
+        make_call("Read", json!({"file_path": "/a.txt"})),
+    ];
+    let result = expect_tools(&calls)
+        .tool(Tool::Read)
+        .param_sequence_sorted("file_path")
+        .evaluate();
+    assert!(!result.passed);
+    assert!(result.reason.unwrap().contains("not sorted at index 1"));
+}
+
+#[test]
+fn test_assert_no_failures() {
+    let calls = vec![make_call("Read", json!({"file_path": "/test.txt"}))];
+
+    let verdict = assert_no_failures([
+        expect_tools(&calls).tool(Tool::Read).evaluate(),
+        expect_tools(&calls).tool(Tool::Bash).evaluate_not_called(),
+    ]);
+    assert!(verdict.is_ok());
+
+    let failures = assert_no_failures([
+        expect_tools(&calls).tool(Tool::Read).evaluate(),
+        expect_tools(&calls).tool(Tool::Bash).evaluate(),
+        expect_tools(&calls).tool(Tool::Read).evaluate_not_called(),
+    ])
+    .unwrap_err();
+    assert_eq!(failures.len(), 2);
+    assert!(failures.iter().all(|f| !f.passed));
+}
+
+#[test]
+fn test_with_exact_params() {
+    let calls = vec![make_call("Read", json!({"file_path": "/tmp/a.txt.bak"}))];
+
+    // Regex matching is unanchored and accepts the backup file
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_params(params! {"file_path" => "/tmp/a.txt"})
+        .to_be_called();
+
+    // Exact matching does not
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_exact_params(params! {"file_path" => "/tmp/a.txt"})
+        .not_to_be_called();
+}
+
+#[test]
+fn test_with_json_params_nested() {
+    let calls = vec![
+        make_call("list_files", json!({"path": "src", "options": {"recursive": false}})),
+        make_call("list_files", json!({"path": "docs", "options": {"recursive": true, "depth": 3}})),
+    ];
+
+    let assertion = expect_tools(&calls)
+        .tool_named("list_files")
+        .with_json_params(params_json! {"options" => {"recursive": true}});
+    assertion.clone().times(1).to_be_called();
+    assertion.first_call().has_params(params! {"path" => "docs"});
+
+    // Combines with string params: both must match the same call
+    expect_tools(&calls)
+        .tool_named("list_files")
+        .with_params(params! {"path" => "src"})
+        .with_json_params(params_json! {"options" => {"recursive": true}})
+        .not_to_be_called();
+
+    let result = expect_tools(&calls)
+        .tool_named("list_files")
+        .with_json_params(params_json! {"options" => {"recursive": "yes"}})
+        .evaluate();
+    assert!(!result.passed);
+    assert_eq!(
+        result.description,
+        r#"list_files with options={"recursive":"yes"} called"#
+    );
+}
+
+#[test]
+fn test_with_params_containing_merges_with_json_params() {
+    let calls = vec![make_call(
+        "list_files",
+        json!({"path": "src", "options": {"recursive": true, "depth": 2}, "globs": ["*.rs", "*.toml"]}),
+    )];
+    let list = || {
+        expect_tools(&calls)
+            .tool_named("list_files")
+            .with_json_params(params_json! {"options" => {"recursive": true}, "globs" => ["*.rs"]})
+    };
+
+    list()
+        .with_params_containing(json!({"options": {"depth": 2}, "globs": ["*.toml"]}))
+        .to_be_called();
+    // The earlier expectation still applies
+    list()
+        .with_params_containing(json!({"path": "docs"}))
+        .not_to_be_called();
+    list()
+        .with_params_containing(json!({"globs": ["*.md"]}))
+        .not_to_be_called();
+}
+
+#[test]
+fn test_with_params_containing() {
+    let calls = vec![make_call(
+        "mcp__github__create_issue",
+        json!({
+            "owner": "acme",
+            "repo": "app",
+            "title": "Crash on start",
+            "labels": ["bug", "p1"],
+            "body": {"template": "crash", "fields": {"os": "linux"}},
+        }),
+    )];
+    let issue = || expect_tools(&calls).tool_named("mcp__github__create_issue");
+
+    issue()
+        .with_params_containing(json!({"labels": ["p1"], "body": {"fields": {"os": "linux"}}}))
+        .to_be_called();
+    issue()
+        .with_params_containing(json!({"labels": ["wontfix"]}))
+        .not_to_be_called();
+    issue()
+        .with_params_containing(json!({"milestone": "v2"}))
+        .not_to_be_called();
+
+    let result = issue()
+        .with_params_containing(json!({"body": {"template": "feature"}}))
+        .evaluate();
+    assert!(!result.passed);
+    assert_eq!(
+        result.description,
+        r#"mcp__github__create_issue with body={"template":"feature"} called"#
+    );
+}
+
+#[test]
+fn test_param_not_matching() {
+    let calls = [
+        make_call("Bash", json!({"command": "rm -rf /tmp/build"})),
+        make_call("Bash", json!({"description": "no command"})),
+    ];
+
+    // Present and matching: fails
+    let dangerous = expect_tools(&calls[..1]).tool(Tool::Bash).param_not_matching("command", r"rm\s+-rf");
+    let result = dangerous.evaluate();
+    assert!(!result.passed);
+    assert_eq!(result.description, r"Bash with command not matching 'rm\s+-rf' called");
+    assert_eq!(
+        result.reason.as_deref(),
+        Some(r"Bash call #1 has command 'rm -rf /tmp/build' matching 'rm\s+-rf'")
+    );
+
+    // Absent: passes
+    expect_tools(&calls[1..])
+        .tool(Tool::Bash)
+        .param_not_matching("command", r"rm\s+-rf")
+        .to_be_called();
+
+    // Present but not matching: passes
+    let safe = vec![make_call("Bash", json!({"command": "rm /tmp/build/a.o"}))];
+    expect_tools(&safe)
+        .tool(Tool::Bash)
+        .param_not_matching("command", r"rm\s+-rf")
+        .to_be_called();
+
+    // Follows the match mode of with_*_params
+    expect_tools(&safe)
+        .tool(Tool::Bash)
+        .with_params_matching(std::collections::HashMap::new(), MatchMode::Exact)
+        .param_not_matching("command", "rm")
+        .to_be_called();
+}
+
+#[test]
+fn test_param_not_matching_checks_every_call() {
+    let calls = vec![
+        make_call("Bash", json!({"command": "cargo test"})),
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("Bash", json!({"command": "rm -rf /"})),
+    ];
+
+    // One good call doesn't cover for the bad one
+    let result = expect_tools(&calls)
+        .tool(Tool::Bash)
+        .param_not_matching("command", r"rm\s+-rf")
+        .evaluate();
+    assert!(!result.passed);
+    assert_eq!(result.reason.as_deref(), Some(r"Bash call #3 has command 'rm -rf /' matching 'rm\s+-rf'"));
+
+    // with_params still narrows which calls are checked
+    expect_tools(&calls)
+        .tool(Tool::Bash)
+        .with_params(params! {"command" => "^cargo"})
+        .param_not_matching("command", r"rm\s+-rf")
+        .times(1)
+        .to_be_called();
+}
+
+#[test]
+fn test_without_param() {
+    let calls = vec![
+        make_call("Bash", json!({"command": "npm test", "run_in_background": true})),
+        make_call("Bash", json!({"command": "npm run build"})),
+    ];
+
+    let foreground = expect_tools(&calls).tool(Tool::Bash).without_param("run_in_background");
+    let result = foreground.evaluate();
+    assert!(!result.passed);
+    assert_eq!(result.description, "Bash without run_in_background called");
+    assert_eq!(result.reason.as_deref(), Some("Bash call #1 has run_in_background true"));
+
+    expect_tools(&calls[1..])
+        .tool(Tool::Bash)
+        .without_param("run_in_background")
+        .times(1)
+        .to_be_called();
+    // Vacuous when the tool isn't called
+    expect_tools(&calls[..0])
+        .tool(Tool::Bash)
+        .without_param("run_in_background")
+        .not_to_be_called();
+}
+
+#[test]
+fn test_with_glob_params() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/app/.envrc"})),
+        make_call("Read", json!({"file_path": "/app/src/main.rs"})),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_glob_params(params! {"file_path" => "*.env"})
+        .not_to_be_called();
+
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_glob_params(params! {"file_path" => "**/*.{rs,toml}"})
+        .times(1)
+        .to_be_called();
+}
+
+#[test]
+fn test_to_be_called_once() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("Read", json!({"file_path": "/b.txt"})),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_params(params! {"file_path" => "a.txt"})
+        .to_be_called_once();
+    expect_tools(&calls).tool(Tool::Read).to_be_called_exactly(2);
+}
+
+#[test]
+#[should_panic(expected = "expected Read to be called exactly once, got 3")]
+fn test_to_be_called_once_fails() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("Read", json!({"file_path": "/b.txt"})),
+        make_call("Read", json!({"file_path": "/c.txt"})),
+    ];
+
+    expect_tools(&calls).tool(Tool::Read).to_be_called_once();
+}
+
+#[test]
+fn test_evaluate_exactly() {
+    let calls = vec![
+        make_call("Bash", json!({"command": "ls"})),
+        make_call("Read", json!({"file_path": "/a.txt"})),
+    ];
+
+    let result = expect_tools(&calls).tool(Tool::Read).evaluate_exactly(1);
+    assert!(result.passed);
+    assert_eq!(result.description, "Read called exactly once");
+
+    // Exact count overrides any earlier .times()
+    let result = expect_tools(&calls).tool(Tool::Read).times(5).evaluate_exactly(1);
+    assert!(result.passed);
+
+    let result = expect_tools(&calls).tool(Tool::Write).evaluate_exactly(2);
+    assert!(!result.passed);
+    assert_eq!(
+        result.reason.as_deref(),
+        Some("expected Write to be called exactly 2 times, got 0")
+    );
+
+    // Other constraints are still reported
+    let result = expect_tools(&calls)
+        .tool(Tool::Bash)
+        .after(Tool::Read)
+        .evaluate_exactly(1);
+    assert!(!result.passed);
+    assert!(result.reason.unwrap().contains("was not called after"));
+
+    assert!(expect_tools(&calls).tool(Tool::Write).evaluate_exactly(0).passed);
+    assert!(!expect_tools(&calls).tool(Tool::Read).evaluate_exactly(0).passed);
+}
+
+#[test]
+fn test_with_path_params() {
+    let dir = tempfile::tempdir().unwrap();
+    let workdir = dir.path().canonicalize().unwrap();
+    let calls = vec![make_call(
+        "Read",
+        json!({"file_path": workdir.join("src/main.rs").to_string_lossy()}),
+    )];
+
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_path_params(params! {"file_path" => "./src/main.rs"})
+        .in_workdir(dir.path())
+        .to_be_called();
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_path_params(params! {"file_path" => "src/lib/../main.rs"})
+        .in_workdir(&workdir)
+        .to_be_called();
+
+    let result = expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_path_params(params! {"file_path" => "./main.rs"})
+        .in_workdir(dir.path())
+        .evaluate();
+    assert!(!result.passed);
+}
+
+#[test]
+fn test_with_path_params_defaults_to_agent_workdir() {
+    let dir = tempfile::tempdir().unwrap();
+    let workdir = dir.path().canonicalize().unwrap();
+    let output = ExecutionOutput {
+        result: NormalizedResult {
+            tool_calls: vec![make_call(
+                "Read",
+                json!({"file_path": workdir.join("src/main.rs").to_string_lossy()}),
+            )],
+            agent_name: "mock".to_string(),
+        },
+        session_log_path: None,
+        stdout: None,
+        stderr: None,
+        exit_code: Some(0),
+        usage: None,
+        working_dir: Some(workdir.clone()),
+    };
+
+    expect(&output)
+        .tool(Tool::Read)
+        .with_path_params(params! {"file_path" => "./src/main.rs"})
+        .to_be_called();
+    // in_workdir overrides the agent's working directory
+    let result = expect(&output)
+        .tool(Tool::Read)
+        .with_path_params(params! {"file_path" => "./src/main.rs"})
+        .in_workdir(workdir.join("src"))
+        .evaluate();
+    assert!(!result.passed);
+}
+
+#[test]
+fn test_called_tools_and_counts() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("Bash", json!({"command": "ls"})),
+        make_call("Read", json!({"file_path": "/b.txt"})),
+        make_call("Grep", json!({"pattern": "TODO"})),
+        make_call("mcp__github__create_issue", json!({"title": "Bug"})),
+        make_call("Read", json!({"file_path": "/a.txt"})),
+    ];
+
+    let expectation = expect_tools(&calls);
+    assert_eq!(
+        expectation.called_tools(),
+        vec!["Bash", "Grep", "Read", "mcp__github__create_issue"]
+    );
+
+    let counts = expectation.tool_call_counts();
+    assert_eq!(counts.len(), 4);
+    assert_eq!(counts["Read"], 3);
+    assert_eq!(counts["Bash"], 1);
+    assert_eq!(counts["mcp__github__create_issue"], 1);
+    assert!(!counts.contains_key("Write"));
+
+    assert!(expect_tools(&[]).called_tools().is_empty());
+    assert!(expect_tools(&[]).tool_call_counts().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "1. Read { /a.txt }  (name matched, params mismatch: expected file_path '.*\\.rs', got '/a.txt')")]
+fn test_to_be_called_panic_shows_params_mismatch() {
+    let calls = vec![make_call("Read", json!({"file_path": "/a.txt"}))];
+
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_params(params! {"file_path" => r".*\.rs"})
+        .to_be_called();
+}
+
+#[test]
+fn test_panic_annotates_only_mismatched_calls() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt", "limit": 10})),
+        make_call("Bash", json!({"command": "ls"})),
+        make_call("Read", json!({"file_path": "/src/lib.rs"})),
+        make_call("Read", json!({"path": "/b.txt"})),
+    ];
+
+    let message = *std::panic::catch_unwind(|| {
+        expect_tools(&calls)
+            .tool(Tool::Read)
+            .with_params(params! {"file_path" => r".*\.rs"})
+            .matching(|p| p.get("limit").is_none())
+            .times(2)
+            .to_be_called();
+    })
+    .unwrap_err()
+    .downcast::<String>()
+    .unwrap();
+
+    assert!(message.contains(
+        "1. Read { /a.txt }  (name matched, params mismatch: expected file_path '.*\\.rs', got '/a.txt'; rejected by predicate)\n"
+    ), "{}", message);
+    // Other tools and matching calls are listed without a note
+    assert!(message.contains("    2. Bash { ls }\n"), "{}", message);
+    assert!(message.contains("    3. Read { /src/lib.rs }\n"), "{}", message);
+    assert!(message.contains(
+        "4. Read { ... }  (name matched, params mismatch: expected file_path '.*\\.rs', got none)\n"
+    ), "{}", message);
+}
+
+#[test]
+fn test_evaluate_count() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "a.json"})),
+        make_call("Read", json!({"file_path": "config.yaml"})),
+        make_call("Read", json!({"file_path": "b.json"})),
+    ];
+    let json_reads = || {
+        expect_tools(&calls)
+            .tool(Tool::Read)
+            .with_params_matching(params! {"file_path" => "*.json"}, MatchMode::Glob)
+    };
+
+    let result = json_reads().times(2).evaluate_count();
+    assert!(result.passed);
+    assert_eq!(result.description, "Read with file_path='*.json' called 2 times");
+
+    let result = json_reads().at_least(3).evaluate_count();
+    assert!(!result.passed);
+    assert_eq!(result.reason.as_deref(), Some("expected at least 3 calls, got 2"));
+
+    // No matching calls is fine when the bounds allow it
+    let result = expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_params_matching(params! {"file_path" => "*.toml"}, MatchMode::Glob)
+        .at_most(1)
+        .evaluate_count();
+    assert!(result.passed);
+}
+
+#[test]
+fn test_immediately_after() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "AGENTS.md"})),
+        make_call("Edit", json!({"file_path": "src/lib.rs"})),
+        make_call("Bash", json!({"command": "cargo test"})),
+        make_call("Read", json!({"file_path": "src/main.rs"})),
+        make_call("Write", json!({"file_path": "src/main.rs"})),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::Edit)
+        .immediately_after(Tool::Read)
+        .to_be_called();
+    expect_tools(&calls)
+        .tool(Tool::Write)
+        .immediately_after(Tool::Read)
+        .to_be_called();
+
+    let result = expect_tools(&calls)
+        .tool(Tool::Write)
+        .immediately_after(Tool::Edit)
+        .evaluate();
+    assert!(!result.passed);
+    assert_eq!(
+        result.reason.as_deref(),
+        Some("'Write' was not called immediately after 'Edit'")
+    );
+}
+
+#[test]
+fn test_immediately_before_honors_params() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "AGENTS.md"})),
+        make_call("Edit", json!({"file_path": "src/lib.rs"})),
+        make_call("Read", json!({"file_path": "README.md"})),
+        make_call("Bash", json!({"command": "ls"})),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_params(params! {"file_path" => "AGENTS.md"})
+        .immediately_before(Tool::Edit)
+        .to_be_called();
+
+    let result = expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_params(params! {"file_path" => "README.md"})
+        .immediately_before(Tool::Edit)
+        .evaluate();
+    assert!(!result.passed);
+
+    let result = expect_tools(&calls)
+        .tool(Tool::Read)
+        .immediately_before(Tool::Write)
+        .evaluate();
+    assert_eq!(result.reason.as_deref(), Some("'Write' was never called"));
+}
+
+#[test]
+fn test_not_called_between() {
+    let calls = vec![
+        make_call("Bash", json!({"command": "ls"})),
+        make_call("Read", json!({"file_path": "AGENTS.md"})),
+        make_call("Grep", json!({"pattern": "fn main"})),
+        make_call("Edit", json!({"file_path": "src/lib.rs"})),
+        make_call("Bash", json!({"command": "cargo test"})),
+    ];
+
+    // Bash runs before the Read and after the Edit, never in between
+    expect_tools(&calls)
+        .tool(Tool::Bash)
+        .not_called_between(Tool::Read, Tool::Edit);
+
+    let result = expect_tools(&calls)
+        .tool(Tool::Grep)
+        .evaluate_not_called_between(Tool::Read, Tool::Edit);
+    assert!(!result.passed);
+    assert_eq!(result.description, "Grep not called between Read and Edit");
+    assert!(result
+        .reason
+        .unwrap()
+        .starts_with("'Grep' was called between 'Read' and 'Edit'"));
+
+    // Params filters narrow which calls count
+    expect_tools(&calls)
+        .tool(Tool::Grep)
+        .with_params(params! {"pattern" => "TODO"})
+        .not_called_between(Tool::Read, Tool::Edit);
+}
+
+#[test]
+fn test_not_called_between_open_window() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "AGENTS.md"})),
+        make_call("Bash", json!({"command": "rm -rf target"})),
+    ];
+
+    // With no Edit after the Read, the window runs to the end of the session
+    let result = expect_tools(&calls)
+        .tool(Tool::Bash)
+        .evaluate_not_called_between(Tool::Read, Tool::Edit);
+    assert!(!result.passed);
+    assert!(result.reason.unwrap().contains("the end of the session"));
+
+    // Without a Read there is no window
+    expect_tools(&calls)
+        .tool(Tool::Bash)
+        .not_called_between(Tool::Write, Tool::Edit);
+}
+
+#[test]
+fn test_with_distinct_params() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.rs"})),
+        make_call("Read", json!({"file_path": "/b.rs"})),
+        make_call("Glob", json!({"pattern": "*.rs"})),
+        make_call("Read", json!({"file_path": "/c.rs"})),
+    ];
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_distinct_params("file_path")
+        .to_be_called();
+}
+
+#[test]
+fn test_with_distinct_params_duplicate() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.rs"})),
+        make_call("Glob", json!({"pattern": "*.rs"})),
+        make_call("Read", json!({"file_path": "/b.rs"})),
+        make_call("Read", json!({"file_path": "/a.rs"})),
+    ];
+    let result = expect_tools(&calls)
+        .tool(Tool::Read)
+        .with_distinct_params("file_path")
+        .evaluate();
+    assert!(!result.passed);
+    assert_eq!(
+        result.reason.as_deref(),
+        Some("'file_path' repeated at Read call #4: '/a.rs' was already used at call #1")
+    );
+}
+
+#[test]
+fn test_matching_numeric_predicate() {
+    let calls = vec![
+        make_call("Bash", json!({"command": "cargo build", "timeout": 2000})),
+        make_call("Bash", json!({"command": "cargo test", "timeout": 60000})),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::Bash)
+        .matching(|p| p["timeout"].as_u64().is_some_and(|t| t > 5000))
+        .times(1)
+        .to_be_called();
+
+    // Predicates combine with params filters
+    expect_tools(&calls)
+        .tool(Tool::Bash)
+        .with_params(params! {"command" => "build"})
+        .matching(|p| p["timeout"].as_u64().is_some_and(|t| t > 5000))
+        .not_to_be_called();
+
+    let result = expect_tools(&calls)
+        .tool(Tool::Bash)
+        .matching(|p| p.get("run_in_background").is_some())
+        .evaluate();
+    assert!(!result.passed);
+    assert_eq!(result.description, "Bash matching predicate called");
+}
+
+#[test]
+fn test_matching_array_predicate() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call(
+            "TodoWrite",
+            json!({"todos": [{"content": "Write tests"}, {"content": "Fix bug"}]}),
+        ),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::TodoWrite)
+        .matching(|p| p["todos"].as_array().is_some_and(|t| t.len() == 2))
+        .after(Tool::Read)
+        .to_be_called();
+
+    expect_tools(&calls)
+        .tool(Tool::TodoWrite)
+        .last_call()
+        .matching(|p| p["todos"][0]["content"] == "Write tests");
+}
+
+#[test]
+#[should_panic(expected = "TodoWrite call #1 params did not satisfy predicate")]
+fn test_nth_call_matching_fails() {
+    let calls = vec![make_call("TodoWrite", json!({"todos": []}))];
+
+    expect_tools(&calls)
+        .tool(Tool::TodoWrite)
+        .nth_call(1)
+        .matching(|p| p["todos"].as_array().is_some_and(|t| !t.is_empty()));
+}
+
+#[test]
+fn test_nth_call_param_accessors() {
+    let calls = vec![make_call(
+        "Edit",
+        json!({"file_path": "/a.txt", "replace_all": true, "limit": 10}),
+    )];
+
+    let call = expect_tools(&calls).tool(Tool::Edit).nth_call(1);
+
+    assert_eq!(call.param_str("file_path"), Some("/a.txt"));
+    assert_eq!(call.param("file_path"), Some(&json!("/a.txt")));
+
+    // Missing keys
+    assert_eq!(call.param("old_string"), None);
+    assert_eq!(call.param_str("old_string"), None);
+
+    // Non-string values
+    assert_eq!(call.param("replace_all"), Some(&json!(true)));
+    assert_eq!(call.param("limit").and_then(|v| v.as_u64()), Some(10));
+    assert_eq!(call.param_str("limit"), None);
+}
+
+#[test]
+fn test_exit_code_from_execution_output() {
+    use crate::agents::{AgentHarness, AgentType, ExecutionConfig};
+    use crate::test_util::MockAgent;
+    use std::sync::Arc;
+
+    let mut harness = AgentHarness::new();
+    harness.register(AgentType::Claude, Arc::new(MockAgent::new().with_exit_code(1)));
+    let output = harness.execute(None, "prompt", ExecutionConfig::new()).unwrap();
+
+    expect(&output).exit_code().to_be(1);
+    expect(&output).exit_code().to_be_nonzero();
+    assert!(!expect(&output).exit_code().evaluate_to_be(0).passed);
+
+    // Tool-call-only expectations carry no exit code
+    assert!(!expect_tools(&[]).exit_code().evaluate_to_be(0).passed);
+}
+
+#[test]
+fn test_nth_call_result() {
+    let mut bash = make_call("Bash", json!({"command": "ls"}));
+    bash.result = Some(json!("a.txt"));
+    let calls = vec![bash, make_call("Bash", json!({"command": "pwd"}))];
+
+    let assertion = expect_tools(&calls).tool(Tool::Bash);
+    assert_eq!(assertion.first_call().result(), Some(&json!("a.txt")));
+    assert_eq!(assertion.last_call().result(), None);
+}
+
+#[test]
+fn test_tool_named_mcp_exact() {
+    let calls = vec![
+        make_call("mcp__github__create_issue", json!({"title": "Bug"})),
+        make_call("mcp__github__create_issue_comment", json!({"body": "+1"})),
+    ];
+
+    expect_tools(&calls)
+        .tool_named("mcp__github__create_issue")
+        .with_params(params! {"title" => "Bug"})
+        .to_be_called_once();
+    expect_tools(&calls)
+        .tool_named("mcp__github__create_issue")
+        .first_call()
+        .has_params(params! {"title" => "Bug"});
+
+    let result = expect_tools(&calls).tool_named("mcp__github__close_issue").evaluate();
+    assert!(!result.passed);
+    assert!(result.description.contains("mcp__github__close_issue"));
+}
+
+#[test]
+fn test_tool_named_mcp_server_prefix() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("mcp__github__list_prs", json!({})),
+        make_call("mcp__github__create_issue", json!({})),
+    ];
+
+    expect_tools(&calls).tool_named("mcp__github__*").times(2).to_be_called();
+    expect_tools(&calls)
+        .tool(Tool::Read)
+        .before(ToolMatcher::mcp_server("github"))
+        .to_be_called();
+    expect_tools(&calls).tool_named("mcp__slack__*").not_to_be_called();
+}
+
+#[test]
+#[should_panic(expected = "assertion failed: expected mcp__slack__* to have been called")]
+fn test_tool_named_prefix_last_call_fails() {
+    let calls = vec![make_call("mcp__github__list_prs", json!({}))];
+    expect_tools(&calls).tool_named("mcp__slack__*").last_call();
+}
+
+#[test]
+fn test_tool_named_custom_tool() {
+    let calls = vec![
+        make_call("Read", json!({"file_path": "/a.txt"})),
+        make_call("DeployPreview", json!({"branch": "main"})),
+    ];
+
+    expect_tools(&calls)
+        .tool_named("DeployPreview")
+        .with_params(params! {"branch" => "main"})
+        .after(Tool::Read)
+        .to_be_called();
+    expect_tools(&calls).tool_named("DeployProduction").not_to_be_called();
+    // Names are compared exactly, unlike YAML tool names
+    expect_tools(&calls).tool_named("deploypreview").not_to_be_called();
+}
+
+#[test]
+#[should_panic(expected = "assertion failed")]
+fn test_tool_named_custom_tool_not_called_fails() {
+    let calls = vec![make_call("DeployPreview", json!({}))];
+    expect_tools(&calls).tool_named("DeployPreview").not_to_be_called();
+}
+
+fn timed_call(name: &str, timestamp: &str) -> ToolCall {
+    ToolCall {
+        timestamp: timestamp.to_string(),
+        ..make_call(name, json!({}))
+    }
+}
+
+#[test]
+fn test_within_of() {
+    let calls = vec![
+        timed_call("Read", "2024-01-19T12:00:00Z"),
+        timed_call("Bash", "2024-01-19T12:00:02Z"),
+        timed_call("Edit", "2024-01-19T12:00:04.500Z"),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::Edit)
+        .within_of(Tool::Read, std::time::Duration::from_secs(5))
+        .to_be_called();
+
+    let result = expect_tools(&calls)
+        .tool(Tool::Edit)
+        .within_of(Tool::Read, std::time::Duration::from_secs(3))
+        .evaluate();
+    assert!(!result.passed);
+    assert_eq!(result.description, "Edit called within 3s of Read");
+    assert_eq!(result.reason.as_deref(), Some("'Edit' came 4.5s after 'Read' at the closest"));
+}
+
+#[test]
+fn test_within_of_uses_closest_pair() {
+    let calls = vec![
+        timed_call("Read", "2024-01-19T12:00:00Z"),
+        timed_call("Edit", "2024-01-19T12:01:00Z"),
+        timed_call("Read", "2024-01-19T12:02:00Z"),
+        timed_call("Edit", "2024-01-19T12:02:01Z"),
+    ];
+
+    expect_tools(&calls)
+        .tool(Tool::Edit)
+        .within_of(Tool::Read, std::time::Duration::from_secs(2))
+        .to_be_called();
+}
+
+#[test]
+fn test_within_of_without_timestamps() {
+    let calls = vec![make_call("Read", json!({})), make_call("Edit", json!({}))];
+
+    let result = expect_tools(&calls)
+        .tool(Tool::Edit)
+        .within_of(Tool::Read, std::time::Duration::from_secs(5))
+        .evaluate();
+    assert_eq!(
+        result.reason.as_deref(),
+        Some("timestamps unavailable for 'Read' and 'Edit' calls")
+    );
+}
+
+#[test]
+fn test_within_of_wrong_order() {
+    let calls = vec![
+        timed_call("Edit", "2024-01-19T12:00:00Z"),
+        timed_call("Read", "2024-01-19T12:00:01Z"),
+    ];
+
+    let result = expect_tools(&calls)
+        .tool(Tool::Edit)
+        .within_of(Tool::Read, std::time::Duration::from_secs(5))
+        .evaluate();
+    assert_eq!(result.reason.as_deref(), Some("'Edit' was not called after 'Read'"));
+}
+
+#[test]
+fn test_within_of_out_of_order_timestamps() {
+    let calls = vec![
+        timed_call("Read", "2024-01-19T12:00:05Z"),
+        timed_call("Edit", "2024-01-19T12:00:00Z"),
+    ];
+
+    let result = expect_tools(&calls)
+        .tool(Tool::Edit)
+        .within_of(Tool::Read, std::time::Duration::from_secs(5))
+        .evaluate();
+    assert!(!result.passed);
+    assert_eq!(
+        result.reason.as_deref(),
+        Some("'Edit' call is timestamped before the preceding 'Read' call")
+    );
+}
+
+#[test]
+fn test_bash_ran_commands() {
+    let calls = vec![
+        make_call("Bash", json!({"command": "cargo fmt && cargo test"})),
+        make_call("Bash", json!({"command": "cat Cargo.toml | grep version"})),
+        make_call("Bash", json!({"command": "ls -la (copy)", "description": "List files"})),
+        make_call("Read", json!({"file_path": "/app/run.sh", "command": "cargo fmt && cargo test"})),
+    ];
+    let bash = expect_tools(&calls).bash();
+
+    // Chained and piped commands need no escaping
+    bash.ran("cargo fmt && cargo test").to_be_called_once();
+    bash.ran("  cat Cargo.toml | grep version\n").to_be_called();
+    bash.ran("cargo test").not_to_be_called();
+    bash.ran_containing("&& cargo test").to_be_called_once();
+    bash.ran_containing("| grep").to_be_called();
+    bash.ran_containing("(copy)").to_be_called();
+    bash.ran_containing("rm -rf").not_to_be_called();
+
+    // Regexes are unanchored; invalid ones fall back to exact match
+    bash.ran_matching(r"^cargo \w+ && cargo test$").to_be_called();
+    bash.ran_matching(r"\|\s*grep").to_be_called();
+    bash.ran_matching("ls -la (copy").not_to_be_called();
+
+    // Only Bash calls count, and the result supports ordering
+    bash.ran_containing("cargo").times(1).to_be_called();
+    bash.ran_containing("grep")
+        .after(Tool::Bash)
+        .to_be_called();
+
+    let result = bash.ran_containing("| sort").evaluate();
+    assert!(!result.passed);
+    assert_eq!(result.description, "Bash with command containing '| sort' called");
+    let result = bash.ran("make").evaluate();
+    assert_eq!(result.description, "Bash running 'make' called");
+}
+
+#[test]
+fn test_assert_called_inspects_matching_calls() {
+    let calls = vec![
+        make_call("Bash", json!({"command": "cargo build", "timeout": 60000})),
+        make_call("Bash", json!({"command": "cargo test --lib", "timeout": 120000})),
+        make_call("Read", json!({"file_path": "/app/Cargo.toml"})),
+        make_call("Bash", json!({"command": "cargo test --doc", "timeout": 300000})),
+        make_call("Bash", json!({"command": "git status"})),
+    ];
+
+    let called = expect_tools(&calls)
+        .tool(Tool::Bash)
+        .with_params(params! {"command" => "^cargo test"})
+        .assert_called();
+    assert_eq!(called.matching_calls().len(), 2);
+
+    called
+        .first()
+        .has_params(params! {"command" => "--lib", "timeout" => "120000"});
+    let last = called
+        .last()
+        .has_params(params! {"command" => "--doc", "timeout" => "300000"});
+    assert_eq!(last.index(), 2);
+}
+
+#[test]
+#[should_panic(expected = "assertion failed: expected Bash with command='^npm' called")]
+fn test_assert_called_panics_without_match() {
+    let calls = vec![make_call("Bash", json!({"command": "cargo test"}))];
+
+    expect_tools(&calls)
+        .tool(Tool::Bash)
+        .with_params(params! {"command" => "^npm"})
+        .assert_called();
+}
@@ -29,6 +29,8 @@ pub enum Tool {
     Glob,
     /// Search file contents using grep/regex
     Grep,
+    /// List the contents of a directory
+    ListDirectory,
     /// Launch a subagent task
     Task,
     /// Fetch content from a URL
@@ -68,6 +70,7 @@ impl Tool {
             Tool::Bash => "Bash",
             Tool::Glob => "Glob",
             Tool::Grep => "Grep",
+            Tool::ListDirectory => "LS",
             Tool::Task => "Task",
             Tool::WebFetch => "WebFetch",
             Tool::WebSearch => "WebSearch",
@@ -80,6 +83,27 @@ impl Tool {
         }
     }
 
+    /// Name of the parameter holding the file this tool operates on.
+    ///
+    /// Returns `None` for tools that don't target a single file.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use aptitude::Tool;
+    ///
+    /// assert_eq!(Tool::Edit.file_path_param(), Some("file_path"));
+    /// assert_eq!(Tool::NotebookEdit.file_path_param(), Some("notebook_path"));
+    /// assert_eq!(Tool::Bash.file_path_param(), None);
+    /// ```
+    pub fn file_path_param(&self) -> Option<&'static str> {
+        match self {
+            Tool::Read | Tool::Write | Tool::Edit => Some("file_path"),
+            Tool::NotebookEdit => Some("notebook_path"),
+            _ => None,
+        }
+    }
+
     /// Get all known tool variants.
     ///
     /// Useful for generating documentation or autocomplete suggestions.
@@ -91,6 +115,7 @@ impl Tool {
             Tool::Bash,
             Tool::Glob,
             Tool::Grep,
+            Tool::ListDirectory,
             Tool::Task,
             Tool::WebFetch,
             Tool::WebSearch,
@@ -110,6 +135,77 @@ impl std::fmt::Display for Tool {
     }
 }
 
+/// Which tool calls an assertion targets.
+///
+/// Most assertions target a built-in [`Tool`]. Tools the enum can't
+/// represent, such as MCP tools (`mcp__<server>__<tool>`), are matched by
+/// name, or by prefix to cover every tool from one server.
+///
+/// # Example
+///
+/// ```rust
+/// use aptitude::{Tool, ToolMatcher};
+///
+/// assert!(ToolMatcher::from(Tool::Read).matches("Read"));
+/// assert!(ToolMatcher::named("mcp__github__create_issue").matches("mcp__github__create_issue"));
+/// assert!(ToolMatcher::named("mcp__github__*").matches("mcp__github__list_prs"));
+/// assert!(!ToolMatcher::mcp_server("github").matches("mcp__gitlab__list_prs"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ToolMatcher {
+    /// A built-in tool
+    Tool(Tool),
+    /// A tool with exactly this name
+    Named(String),
+    /// Any tool whose name starts with this prefix
+    Prefix(String),
+}
+
+impl ToolMatcher {
+    /// Match a tool by name. A trailing `*` matches any tool with that prefix.
+    pub fn named(name: &str) -> Self {
+        match name.strip_suffix('*') {
+            Some(prefix) => ToolMatcher::Prefix(prefix.to_string()),
+            None => ToolMatcher::Named(name.to_string()),
+        }
+    }
+
+    /// Match one MCP tool, named `mcp__<server>__<tool>` in session logs.
+    pub fn mcp(server: &str, tool: &str) -> Self {
+        ToolMatcher::Named(format!("mcp__{}__{}", server, tool))
+    }
+
+    /// Match every tool from an MCP server.
+    pub fn mcp_server(server: &str) -> Self {
+        ToolMatcher::Prefix(format!("mcp__{}__", server))
+    }
+
+    /// Whether a tool call with this name is targeted.
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            ToolMatcher::Tool(tool) => name == tool.as_str(),
+            ToolMatcher::Named(expected) => name == expected,
+            ToolMatcher::Prefix(prefix) => name.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+impl From<Tool> for ToolMatcher {
+    fn from(tool: Tool) -> Self {
+        ToolMatcher::Tool(tool)
+    }
+}
+
+impl std::fmt::Display for ToolMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToolMatcher::Tool(tool) => write!(f, "{}", tool),
+            ToolMatcher::Named(name) => write!(f, "{}", name),
+            ToolMatcher::Prefix(prefix) => write!(f, "{}*", prefix),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,6 +218,7 @@ mod tests {
         assert_eq!(Tool::Bash.as_str(), "Bash");
         assert_eq!(Tool::Glob.as_str(), "Glob");
         assert_eq!(Tool::Grep.as_str(), "Grep");
+        assert_eq!(Tool::ListDirectory.as_str(), "LS");
     }
 
     #[test]
@@ -136,6 +233,7 @@ mod tests {
         assert!(all.contains(&Tool::Read));
         assert!(all.contains(&Tool::Write));
         assert!(all.contains(&Tool::Bash));
+        assert!(all.contains(&Tool::ListDirectory));
     }
 
     #[test]
@@ -145,9 +243,27 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::clone_on_copy)]
     fn test_tool_clone() {
         let tool = Tool::Read;
         let cloned = tool.clone();
         assert_eq!(tool, cloned);
     }
+
+    #[test]
+    fn test_tool_matcher_names() {
+        let exact = ToolMatcher::named("mcp__github__create_issue");
+        assert_eq!(exact, ToolMatcher::mcp("github", "create_issue"));
+        assert!(exact.matches("mcp__github__create_issue"));
+        assert!(!exact.matches("mcp__github__create_issue_comment"));
+
+        let server = ToolMatcher::named("mcp__github__*");
+        assert_eq!(server, ToolMatcher::mcp_server("github"));
+        assert!(server.matches("mcp__github__list_prs"));
+        assert!(!server.matches("mcp__githubx__list_prs"));
+        assert_eq!(server.to_string(), "mcp__github__*");
+
+        assert!(ToolMatcher::from(Tool::Read).matches("Read"));
+        assert!(!ToolMatcher::from(Tool::Read).matches("mcp__fs__Read"));
+    }
 }
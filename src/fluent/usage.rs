@@ -0,0 +1,155 @@
+//! Fluent assertion builder for token and cost usage.
+//!
+//! - `UsageAssertion` - Builder for assertions on how many tokens a run consumed
+
+use crate::parser::UsageStats;
+
+use super::builder::AssertionResult;
+
+/// Builder for assertions on token and cost usage.
+///
+/// Methods like `tokens_under()` evaluate immediately and panic on failure.
+/// Use the `evaluate_*` methods for non-panicking evaluation.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// expect(&output).usage().tokens_under(50_000);
+/// expect(&output).usage().cost_under(0.25);
+/// ```
+#[derive(Debug, Clone)]
+pub struct UsageAssertion {
+    usage: Option<UsageStats>,
+}
+
+impl UsageAssertion {
+    /// Create a new usage assertion.
+    ///
+    /// `None` means usage was not captured (e.g. the agent's logs don't
+    /// record it, or only tool calls were available).
+    pub fn new(usage: Option<UsageStats>) -> Self {
+        Self { usage }
+    }
+
+    // =========================================================================
+    // Assertion methods (panic on failure)
+    // =========================================================================
+
+    /// Assert the run used fewer than `n` tokens (input and output combined).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the limit is reached or usage was not captured.
+    pub fn tokens_under(&self, n: u64) {
+        let result = self.evaluate_tokens_under(n);
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
+    /// Assert the run cost less than `usd` dollars.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the limit is reached or usage was not captured.
+    pub fn cost_under(&self, usd: f64) {
+        let result = self.evaluate_cost_under(usd);
+        if !result.passed {
+            self.panic_with_context(&result);
+        }
+    }
+
+    // =========================================================================
+    // Non-panicking evaluation
+    // =========================================================================
+
+    /// Evaluate that the run used fewer than `n` tokens, without panicking.
+    pub fn evaluate_tokens_under(&self, n: u64) -> AssertionResult {
+        let description = format!("fewer than {} tokens", n);
+        match self.usage {
+            Some(usage) if usage.total_tokens() < n => AssertionResult::pass(description),
+            Some(usage) => AssertionResult::fail(
+                description,
+                format!(
+                    "used {} tokens ({} input, {} output)",
+                    usage.total_tokens(),
+                    usage.input_tokens,
+                    usage.output_tokens
+                ),
+            ),
+            None => AssertionResult::fail(description, "usage was not captured"),
+        }
+    }
+
+    /// Evaluate that the run cost less than `usd` dollars, without panicking.
+    pub fn evaluate_cost_under(&self, usd: f64) -> AssertionResult {
+        let description = format!("cost under ${:.4}", usd);
+        match self.usage {
+            Some(usage) if usage.total_cost_usd < usd => AssertionResult::pass(description),
+            Some(usage) => {
+                AssertionResult::fail(description, format!("cost ${:.4}", usage.total_cost_usd))
+            }
+            None => AssertionResult::fail(description, "usage was not captured"),
+        }
+    }
+
+    // =========================================================================
+    // Internal helpers
+    // =========================================================================
+
+    fn panic_with_context(&self, result: &AssertionResult) -> ! {
+        let reason = result.reason.as_deref().unwrap_or("unknown reason");
+        panic!(
+            "assertion failed: expected {}\n\n  reason: {}\n",
+            result.description, reason
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage() -> UsageStats {
+        UsageStats {
+            input_tokens: 900,
+            output_tokens: 100,
+            total_cost_usd: 0.05,
+        }
+    }
+
+    #[test]
+    fn test_tokens_under() {
+        UsageAssertion::new(Some(usage())).tokens_under(1001);
+
+        let result = UsageAssertion::new(Some(usage())).evaluate_tokens_under(1000);
+        assert!(!result.passed);
+        assert_eq!(result.description, "fewer than 1000 tokens");
+        assert_eq!(
+            result.reason.as_deref(),
+            Some("used 1000 tokens (900 input, 100 output)")
+        );
+    }
+
+    #[test]
+    fn test_cost_under() {
+        UsageAssertion::new(Some(usage())).cost_under(0.10);
+
+        let result = UsageAssertion::new(Some(usage())).evaluate_cost_under(0.01);
+        assert!(!result.passed);
+        assert_eq!(result.reason.as_deref(), Some("cost $0.0500"));
+    }
+
+    #[test]
+    fn test_not_captured() {
+        let result = UsageAssertion::new(None).evaluate_tokens_under(10);
+        assert_eq!(result.reason.as_deref(), Some("usage was not captured"));
+        assert!(!UsageAssertion::new(None).evaluate_cost_under(1.0).passed);
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed: expected fewer than 10 tokens")]
+    fn test_tokens_under_panics() {
+        UsageAssertion::new(Some(usage())).tokens_under(10);
+    }
+}
@@ -57,43 +57,57 @@
 //! }
 //! ```
 
+mod agent_test;
 pub mod agents;
 pub mod config;
+pub mod diff;
 pub mod discovery;
+pub mod error;
+pub mod export;
 pub mod fluent;
 pub mod output;
+pub mod parallel;
 pub mod parser;
 pub mod prompt;
 pub mod review;
+pub mod snapshot;
+pub mod stats;
 pub mod streaming;
 
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+
 #[cfg(feature = "yaml")]
 pub mod yaml;
 
 // Core types
 pub use fluent::{
-    expect, expect_tools, params_match, ExecutionExpectation, StdoutAssertion, ToolAssertion,
+    assert_no_failures, expect, expect_tools, params_match, BashAssertion, CalledToolAssertion, ExecutionExpectation, ExitCodeAssertion, FilesAssertion, SequenceAssertion, StdoutAssertion, ToolAssertion, ToolCallsAssertion, UsageAssertion,
 };
-pub use parser::{parse_jsonl_file as parse_session, ToolCall};
+pub use parser::{parse_jsonl_file as parse_session, parse_session_with_usage, ToolCall, UsageStats};
 
 // Tool enum
-pub use fluent::Tool;
+pub use fluent::{Tool, ToolMatcher};
 
 // Agent execution
 pub use agents::{AgentHarness, AgentType, ExecutionConfig, ExecutionOutput, NormalizedResult};
+pub use error::HarnessError;
 
 // Prompt builder
 pub use prompt::{prompt, PromptBuilder};
 
 // Output formatting
-pub use output::{OutputConfig, OutputFormatter, OutputMode};
+pub use output::{ColorChoice, OutputConfig, OutputFormatter, OutputMode, ResultFormat, Verbosity};
 
 // Review/grading
-pub use review::{grade_stdout, ReviewConfig, ReviewResult};
+pub use review::{grade_stdout, grade_stdout_cached, GradeCache, ReviewConfig, ReviewResult};
 
 // Streaming
 pub use streaming::{StreamEvent, StreamHandle};
 
+// Used by params_json!, and for building with_params_containing values
+pub use serde_json;
+
 // YAML (feature-gated)
 #[cfg(feature = "yaml")]
 pub use yaml::{load_test, run_yaml_test, Assertion, Test as YamlTest};
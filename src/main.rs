@@ -1,17 +1,33 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use aptitude::agents::{AgentHarness, AgentType, ExecutionConfig};
+use aptitude::agents::{AgentHarness, AgentType, ExecutionConfig, RecordingAgent, ReplayAgent};
 use aptitude::config::Config;
-use aptitude::discovery::discover_tests;
-use aptitude::output::{OutputConfig, OutputFormatter};
-use aptitude::parser::{parse_jsonl_file, ToolCall};
+use aptitude::diff::{diff_tool_calls, Change};
+use aptitude::discovery::{discover_tests, id_root, test_id};
+use aptitude::export::write_csv;
+use aptitude::output::{
+    json_report, junit, tap, ColorChoice, OutputConfig, OutputFormatter, ResultFormat, TestReport,
+    Verbosity,
+};
+use aptitude::parallel::run_ordered_fail_fast;
+use aptitude::parser::{parse_jsonl_file, parse_jsonl_reader, ToolCall};
+use aptitude::review::GradeCache;
+use aptitude::stats::{aggregate, find_session_logs};
 use aptitude::agents::ToolNameMapping;
 use aptitude::streaming::{StreamEvent, StreamHandle};
 
 #[cfg(feature = "yaml")]
-use aptitude::yaml::{load_test, run_yaml_test, TestResult};
+use aptitude::yaml::{
+    check_test_args, lint_test, load_test, run_with_budget, run_with_retries, run_yaml_test_with_cache, validate_test, with_hooks,
+    LintIssue, Severity, Test, TestResult,
+};
 
 #[derive(Parser)]
 #[command(name = "aptitude")]
@@ -21,57 +37,91 @@ struct Cli {
     command: Commands,
 }
 
+/// `--format` of commands that print either human output or JSON.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable text
+    Human,
+    /// JSON, for scripts
+    Json,
+}
+
+/// Machine-readable report written alongside the human output.
+#[derive(Clone, Copy, ValueEnum)]
+enum Reporter {
+    /// JUnit XML, for CI systems like Jenkins and GitLab
+    Junit,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Run a test file (executes an agent with the prompt and asserts on results)
-    Run {
-        /// Path to test YAML file or directory
-        path: PathBuf,
+    Run(Box<RunArgs>),
 
-        /// Verbose output (show tool calls as they happen)
-        #[arg(short, long)]
-        verbose: bool,
+    /// Analyze an existing session log file
+    Analyze {
+        /// Path to test YAML file
+        test: PathBuf,
 
-        /// Working directory for agent execution
-        #[arg(short, long)]
-        workdir: Option<PathBuf>,
+        /// Path to session JSONL file, or `-` to read from stdin
+        session: PathBuf,
 
-        /// Agent to use (overrides test file setting)
+        /// Agent that produced this session (for tool name normalization)
         #[arg(short, long)]
         agent: Option<String>,
 
-        /// Test file pattern (overrides config)
+        /// Model used to grade stdout reviews (overrides the test file)
         #[arg(short, long)]
-        pattern: Option<String>,
+        model: Option<String>,
 
-        /// Root directory for test discovery (overrides config)
-        #[arg(short, long)]
-        root: Option<PathBuf>,
+        /// Always call the grading agent instead of reusing cached stdout review grades
+        #[arg(long)]
+        no_cache: bool,
 
-        /// Disable recursive directory scanning
+        /// Also write the session's tool calls to this CSV file (timestamp,tool,primary_param)
         #[arg(long)]
-        no_recursive: bool,
+        export: Option<PathBuf>,
+    },
 
-        /// Path to config file (default: auto-discover)
+    /// Compare the tool calls of two session logs (exits 1 if they differ)
+    Diff {
+        /// Session JSONL file to compare from
+        before: PathBuf,
+
+        /// Session JSONL file to compare to
+        after: PathBuf,
+
+        /// Agent that produced both sessions (for tool name normalization)
         #[arg(short, long)]
-        config: Option<PathBuf>,
+        agent: Option<String>,
 
-        /// List matched test files without running them
-        #[arg(long)]
-        list_tests: bool,
+        /// When to color output (auto honors NO_COLOR)
+        #[arg(long, value_enum, ignore_case = true, default_value_t = ColorChoice::Auto)]
+        color: ColorChoice,
     },
 
-    /// Analyze an existing session log file
-    Analyze {
-        /// Path to test YAML file
-        test: PathBuf,
+    /// Summarize tool usage across the session logs in a directory
+    Stats {
+        /// Directory searched recursively for *.jsonl session logs
+        dir: PathBuf,
 
-        /// Path to session JSONL file
-        session: PathBuf,
+        /// Output format
+        #[arg(long, value_enum, ignore_case = true, default_value_t = OutputFormat::Human)]
+        format: OutputFormat,
 
-        /// Agent that produced this session (for tool name normalization)
+        /// Number of most-read files to list
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+
+    /// Report contradictory or invalid assertions without running any agent
+    Lint {
+        /// Path to test YAML file or directory
+        path: PathBuf,
+
+        /// Path to config file (default: auto-discover)
         #[arg(short, long)]
-        agent: Option<String>,
+        config: Option<PathBuf>,
     },
 
     /// List available agents
@@ -93,71 +143,383 @@ enum Commands {
         /// Model to use (passed to Claude via --model)
         #[arg(short, long)]
         model: Option<String>,
+
+        /// Show full session log paths and agent stderr (-vv)
+        #[arg(short, long, action = ArgAction::Count)]
+        verbose: u8,
+
+        /// When to color output (auto honors NO_COLOR)
+        #[arg(long, value_enum, ignore_case = true, default_value_t = ColorChoice::Auto)]
+        color: ColorChoice,
+
+        /// Output format: json prints each tool call as a JSON line
+        #[arg(long, value_enum, ignore_case = true, default_value_t = OutputFormat::Human)]
+        format: OutputFormat,
     },
 }
 
+/// Arguments of `aptitude run`, boxed in [`Commands::Run`] as they far
+/// outweigh the other commands'.
+#[derive(Args)]
+struct RunArgs {
+    /// Path to test YAML file or directory
+    path: PathBuf,
+
+    /// More output: -v shows every tool call and assertion, -vv also responses,
+    /// full session log paths and agent stderr
+    #[arg(short, long, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Print only results: a line per test, failing assertions and the totals
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Working directory for agent execution
+    #[arg(short, long)]
+    workdir: Option<PathBuf>,
+
+    /// Agent to use (overrides test file setting)
+    #[arg(short, long)]
+    agent: Option<String>,
+
+    /// Test file pattern (overrides config)
+    #[arg(short, long)]
+    pattern: Option<String>,
+
+    /// Root directory for test discovery (overrides config)
+    #[arg(short, long)]
+    root: Option<PathBuf>,
+
+    /// Disable recursive directory scanning
+    #[arg(long)]
+    no_recursive: bool,
+
+    /// Path to config file (default: auto-discover)
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// List matched test files without running them
+    #[arg(long)]
+    list_tests: bool,
+
+    /// Only run tests whose `name` contains SUBSTR, or whose id (see --list-tests) is SUBSTR
+    #[arg(long, value_name = "SUBSTR")]
+    only: Option<String>,
+
+    /// Only run test files whose file name matches GLOB (e.g. "auth-*.yaml")
+    #[arg(long, value_name = "GLOB", value_parser = parse_glob)]
+    only_file: Option<glob::Pattern>,
+
+    /// Write a machine-readable report (requires --output)
+    #[arg(long, value_enum, requires = "output")]
+    reporter: Option<Reporter>,
+
+    /// Path to write the report to
+    #[arg(long, requires = "reporter")]
+    output: Option<PathBuf>,
+
+    /// Result format printed to stdout
+    #[arg(long, value_enum, ignore_case = true, default_value_t = ResultFormat::Human)]
+    format: ResultFormat,
+
+    /// Print one line per test plus totals (same as --format summary)
+    #[arg(long, conflicts_with = "format")]
+    summary: bool,
+
+    /// When to color output (auto honors NO_COLOR)
+    #[arg(long, value_enum, ignore_case = true, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Run up to N test files concurrently (tests sharing a working directory still run one at a time)
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Re-run a test up to N more times when its assertions fail
+    #[arg(long, default_value_t = 0)]
+    retries: u32,
+
+    /// Model to run the agent with and grade stdout reviews (overrides the test file)
+    #[arg(short, long)]
+    model: Option<String>,
+
+    /// Always call the grading agent instead of reusing cached stdout review grades
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Stop after the first test file that fails or errors, skipping the rest
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// Abort the run after SECS seconds: the running agent is killed and remaining test files are skipped
+    #[arg(long, value_name = "SECS")]
+    max_duration: Option<u64>,
+
+    /// Validate test files without running any agent
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Save each agent run (stdout, exit code, tool calls) as a JSON recording in DIR
+    #[arg(long, value_name = "DIR")]
+    record: Option<PathBuf>,
+
+    /// Answer each prompt from its recording in DIR instead of running the agent
+    #[arg(long, value_name = "DIR", conflicts_with = "record")]
+    replay: Option<PathBuf>,
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
     let harness = AgentHarness::new();
 
     match cli.command {
-        Commands::Run {
-            path,
-            verbose,
-            workdir,
-            agent,
-            pattern,
-            root,
-            no_recursive,
-            config: config_path,
-            list_tests,
-        } => {
-            let agent_type = parse_agent_type(agent.as_deref())?;
-
-            if path.is_file() {
+        Commands::Run(args) => {
+            let RunArgs {
+                path,
+                verbose,
+                quiet,
+                workdir,
+                agent,
+                pattern,
+                root,
+                no_recursive,
+                config: config_path,
+                list_tests,
+                only,
+                only_file,
+                reporter,
+                output,
+                format,
+                summary,
+                color,
+                jobs,
+                retries,
+                model,
+                no_cache,
+                fail_fast,
+                max_duration,
+                dry_run,
+                record,
+                replay,
+            } = *args;
+            let mut harness = harness;
+            if let Some(dir) = record {
+                harness.wrap_agents(|agent| Arc::new(RecordingAgent::new(agent, dir.clone())));
+            } else if let Some(dir) = replay {
+                harness.wrap_agents(|agent| Arc::new(ReplayAgent::new(agent, dir.clone())));
+            }
+            let cache = grade_cache(no_cache);
+            let (config, config_dir) = load_or_discover_config(&path, config_path.as_deref());
+            let id_root = id_root(config_dir.as_deref());
+            let agent = parse_agent_type(agent.as_deref())?;
+            let runs_agents = agent.is_none() && !dry_run && !list_tests;
+            let options = RunOptions {
+                verbosity: Verbosity::from_flags(verbose, quiet),
+                workdir: workdir.as_deref(),
+                agent,
+                default_agent: if runs_agents { fallback_agent(&harness) } else { None },
+                format: if summary { ResultFormat::Summary } else { format },
+                color,
+                jobs,
+                retries,
+                model: model.as_deref(),
+                cache: cache.as_ref(),
+                config: &config,
+                fail_fast,
+                deadline: max_duration.map(|secs| Instant::now() + Duration::from_secs(secs)),
+                filter: TestFilter {
+                    name: only.as_deref(),
+                    file: only_file.as_ref(),
+                },
+                id_root: &id_root,
+            };
+            let report = reporter.zip(output);
+
+            if dry_run {
+                let test_files = if path.is_file() {
+                    vec![path]
+                } else {
+                    let config = config.clone().with_overrides(pattern, root, no_recursive);
+                    discover_tests(&config.search_dir(&path, config_dir.as_deref()), &config)?
+                };
+                let test_files = options.filter.apply(options.id_root, test_files);
+                validate_test_files(&test_files, options.color.enabled());
+            } else if path.is_file() && options.filter.apply(options.id_root, vec![path.clone()]).is_empty() {
+                println!("No tests match {}", options.filter);
+            } else if path.is_file() {
                 // Single file mode - run directly
-                run_single_test(&harness, &path, verbose, workdir.as_deref(), agent_type)?;
+                let test_report = run_single_test(&harness, &path, &options)?;
+                if options.format != ResultFormat::Human {
+                    print_formatted_results(&options, std::slice::from_ref(&test_report));
+                }
+                let failed = !test_report.is_pass();
+                if let Some((reporter, output)) = &report {
+                    write_report(*reporter, output, &[test_report])?;
+                }
+                if failed {
+                    std::process::exit(1);
+                }
             } else {
                 // Directory mode - use discovery
-                let (config, config_dir) = load_or_discover_config(&path, config_path.as_deref());
-                let config = config.with_overrides(pattern, root, no_recursive);
+                let config = config.clone().with_overrides(pattern, root, no_recursive);
                 let search_root = config.search_dir(&path, config_dir.as_deref());
 
                 if list_tests {
-                    list_discovered_tests(&search_root, &config)?;
+                    list_discovered_tests(&search_root, &options, &config)?;
                 } else {
                     run_tests_in_directory(
                         &harness,
                         &search_root,
-                        verbose,
-                        workdir.as_deref(),
-                        agent_type,
+                        &options,
                         &config,
+                        report.as_ref(),
                     )?;
                 }
             }
         }
-        Commands::Analyze { test, session, agent } => {
+        Commands::Analyze {
+            test,
+            session,
+            agent,
+            model,
+            no_cache,
+            export,
+        } => {
             let agent_type = parse_agent_type(agent.as_deref())?;
-            analyze_session(&harness, &test, &session, agent_type)?;
+            let cache = grade_cache(no_cache);
+            analyze_session(
+                &harness,
+                &test,
+                &session,
+                agent_type,
+                model.as_deref(),
+                cache.as_ref(),
+                export.as_deref(),
+            )?;
+        }
+        Commands::Diff {
+            before,
+            after,
+            agent,
+            color,
+        } => {
+            let agent_type = parse_agent_type(agent.as_deref())?;
+            diff_sessions(&harness, &before, &after, agent_type, color)?;
+        }
+        Commands::Stats { dir, format, top } => {
+            session_stats(&dir, format == OutputFormat::Json, top)?;
+        }
+        Commands::Lint {
+            path,
+            config: config_path,
+        } => {
+            let test_files = if path.is_file() {
+                vec![path]
+            } else {
+                let (config, config_dir) = load_or_discover_config(&path, config_path.as_deref());
+                discover_tests(&config.search_dir(&path, config_dir.as_deref()), &config)?
+            };
+            lint_test_files(&test_files, ColorChoice::Auto.enabled());
         }
         Commands::Agents => {
-            list_agents(&harness);
+            list_agents(&harness, ColorChoice::Auto.enabled());
         }
         Commands::Log {
             prompt,
             workdir,
             agent,
             model,
+            verbose,
+            color,
+            format,
         } => {
-            let agent_type = parse_agent_type(agent.as_deref())?;
-            log_command(&harness, &prompt, workdir.as_deref(), agent_type, model.as_deref())?;
+            let options = LogOptions {
+                workdir: workdir.as_deref(),
+                agent: parse_agent_type(agent.as_deref())?.or_else(|| fallback_agent(&harness)),
+                model: model.as_deref(),
+                verbosity: Verbosity::from_flags(verbose, false),
+                color,
+                json: format == OutputFormat::Json,
+            };
+            log_command(&harness, &prompt, &options)?;
         }
     }
 
     Ok(())
 }
 
+/// Settings shared by every test in an `aptitude run` invocation.
+struct RunOptions<'a> {
+    verbosity: Verbosity,
+    workdir: Option<&'a Path>,
+    agent: Option<AgentType>,
+    /// Agent for tests that don't name one, when Claude isn't installed.
+    default_agent: Option<AgentType>,
+    format: ResultFormat,
+    color: ColorChoice,
+    jobs: usize,
+    retries: u32,
+    model: Option<&'a str>,
+    cache: Option<&'a GradeCache>,
+    /// Assertion settings from `.aptitude.yaml`.
+    config: &'a Config,
+    fail_fast: bool,
+    /// When the whole run must be done by (`--max-duration`).
+    deadline: Option<Instant>,
+    /// Which discovered tests to run (`--only`, `--only-file`).
+    filter: TestFilter<'a>,
+    /// Directory that test ids are relative to (see [`id_root`]).
+    id_root: &'a Path,
+}
+
+impl RunOptions<'_> {
+    /// Whether `--max-duration` has run out.
+    fn out_of_time(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// Settings for an `aptitude log` invocation.
+struct LogOptions<'a> {
+    workdir: Option<&'a Path>,
+    agent: Option<AgentType>,
+    model: Option<&'a str>,
+    verbosity: Verbosity,
+    color: ColorChoice,
+    /// Print each tool call as a JSON line and nothing else.
+    json: bool,
+}
+
+/// Cache for stdout review grades, unless disabled with `--no-cache`.
+fn grade_cache(no_cache: bool) -> Option<GradeCache> {
+    if no_cache {
+        None
+    } else {
+        GradeCache::default_location()
+    }
+}
+
+/// Parse `--only-file`, so a malformed glob is an error rather than a filter
+/// that matches nothing.
+fn parse_glob(glob: &str) -> Result<glob::Pattern, String> {
+    glob::Pattern::new(glob).map_err(|e| format!("invalid glob: {}", e))
+}
+
+/// The agent to use when none is named: `None` (the harness default, Claude)
+/// if Claude is installed, otherwise the first installed agent.
+///
+/// Probes the agents' CLIs, so call it once per command.
+fn fallback_agent(harness: &AgentHarness) -> Option<AgentType> {
+    if harness.is_agent_available(AgentType::Claude) {
+        return None;
+    }
+    let agent = harness
+        .list_available()
+        .into_iter()
+        .find_map(AgentType::from_str)?;
+    eprintln!("Note: claude is not installed, using {} instead", agent.as_str());
+    Some(agent)
+}
+
 fn parse_agent_type(agent: Option<&str>) -> Result<Option<AgentType>> {
     match agent {
         None => Ok(None),
@@ -182,73 +544,370 @@ fn load_or_discover_config(
     }
 }
 
-/// List discovered test files without running them.
-fn list_discovered_tests(dir: &Path, config: &Config) -> Result<()> {
-    let tests = discover_tests(dir, config)?;
+/// Narrows discovered test files to the ones picked by `--only` and `--only-file`.
+#[derive(Debug, Clone, Copy, Default)]
+struct TestFilter<'a> {
+    /// Substring the test's `name` must contain, or its exact [`test_id`].
+    name: Option<&'a str>,
+    /// Glob the file name must match.
+    file: Option<&'a glob::Pattern>,
+}
+
+impl TestFilter<'_> {
+    fn is_active(&self) -> bool {
+        self.name.is_some() || self.file.is_some()
+    }
+
+    /// Whether a test file is selected, given its path, its [`test_id`] and
+    /// the `name` it declares (`None` when it couldn't be loaded).
+    ///
+    /// A file that fails to load is only kept when no `--only` is given or
+    /// `--only` is its id, so its load error still gets reported.
+    fn selects(&self, path: &Path, id: &str, test_name: Option<&str>) -> bool {
+        if let Some(glob) = self.file {
+            let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            if !glob.matches(file_name) {
+                return false;
+            }
+        }
+        match self.name {
+            Some(substr) => substr == id || test_name.is_some_and(|name| name.contains(substr)),
+            None => true,
+        }
+    }
+
+    /// Keep the selected files, loading each one when filtering by name.
+    /// Ids are taken relative to `root`.
+    fn apply(&self, root: &Path, files: Vec<PathBuf>) -> Vec<PathBuf> {
+        if !self.is_active() {
+            return files;
+        }
+        files
+            .into_iter()
+            .filter(|path| {
+                let name = match self.name {
+                    Some(_) => load_test(path).ok().map(|test| test.name),
+                    None => None,
+                };
+                self.selects(path, &test_id(root, path), name.as_deref())
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for TestFilter<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let flags: Vec<String> = [("--only", self.name), ("--only-file", self.file.map(glob::Pattern::as_str))]
+            .into_iter()
+            .filter_map(|(flag, value)| value.map(|v| format!("{} '{}'", flag, v)))
+            .collect();
+        write!(f, "{}", flags.join(" and "))
+    }
+}
+
+/// List discovered test files, with their ids, without running them.
+fn list_discovered_tests(dir: &Path, options: &RunOptions, config: &Config) -> Result<()> {
+    let filter = options.filter;
+    let tests = filter.apply(options.id_root, discover_tests(dir, config)?);
+    if tests.is_empty() && filter.is_active() {
+        println!("No tests match {}", filter);
+        return Ok(());
+    }
 
     println!();
     println!("Discovered {} test file(s):", tests.len());
     println!();
 
     for path in &tests {
-        println!("  {}", path.display());
+        println!("  {}  {}", test_id(options.id_root, path), path.display());
     }
 
     println!();
     Ok(())
 }
 
-fn list_agents(harness: &AgentHarness) {
+/// Load and validate each test file, printing problems per file (`--dry-run`).
+///
+/// Exits with status 1 if any file fails to load or has invalid assertions.
+fn validate_test_files(paths: &[PathBuf], colors: bool) {
+    let totals = check_test_files(paths, colors, |test| {
+        validate_test(test)
+            .into_iter()
+            .map(|message| LintIssue { severity: Severity::Error, message })
+            .collect()
+    });
+
+    println!();
+    println!(
+        "Validated {} test file(s): {} valid, {} invalid",
+        paths.len(),
+        paths.len() - totals.failed_files,
+        totals.failed_files
+    );
+
+    if totals.failed_files > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Lint each test file, printing issues per file (`aptitude lint`).
+///
+/// Exits with status 1 if any file fails to load or has lint errors;
+/// warnings alone don't fail.
+fn lint_test_files(paths: &[PathBuf], colors: bool) {
+    let totals = check_test_files(paths, colors, lint_test);
+
+    println!();
+    println!(
+        "Linted {} test file(s): {} error(s), {} warning(s)",
+        paths.len(),
+        totals.errors,
+        totals.warnings
+    );
+
+    if totals.errors > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Issue counts from [`check_test_files`].
+struct CheckTotals {
+    /// Files that failed to load or have at least one error.
+    failed_files: usize,
+    errors: usize,
+    warnings: usize,
+}
+
+/// Load each test file and run `check` on it, printing the file marked ✓,
+/// ✗ (errors) or ! (only warnings) with its issues below.
+///
+/// A file that fails to load is reported with its load error.
+fn check_test_files(
+    paths: &[PathBuf],
+    colors: bool,
+    check: impl Fn(&Test) -> Vec<LintIssue>,
+) -> CheckTotals {
+    let mut totals = CheckTotals { failed_files: 0, errors: 0, warnings: 0 };
+
+    println!();
+    for path in paths {
+        let issues = match load_test(path) {
+            Ok(test) => check(&test),
+            Err(e) => vec![LintIssue {
+                severity: Severity::Error,
+                message: format!("{:#}", e),
+            }],
+        };
+
+        if issues.is_empty() {
+            println!("{} {}", paint("✓", GREEN, colors), path.display());
+            continue;
+        }
+
+        let file_errors = issues.iter().filter(|i| i.severity == Severity::Error).count();
+        totals.errors += file_errors;
+        totals.warnings += issues.len() - file_errors;
+        if file_errors > 0 {
+            totals.failed_files += 1;
+            println!("{} {}", paint("✗", RED, colors), path.display());
+        } else {
+            println!("{} {}", paint("!", YELLOW, colors), path.display());
+        }
+        for issue in &issues {
+            println!("    {}", issue);
+        }
+    }
+    totals
+}
+
+fn list_agents(harness: &AgentHarness, colors: bool) {
     println!();
     println!("Registered agents:");
+    let available = harness.list_available();
     for name in harness.registered_agents() {
-        let available = harness
-            .get_agent(AgentType::from_str(name).unwrap())
-            .map(|a| a.is_available())
-            .unwrap_or(false);
-        let status = if available { "\x1b[32mavailable\x1b[0m" } else { "\x1b[31mnot found\x1b[0m" };
+        let status = if available.contains(&name) {
+            paint("available", GREEN, colors)
+        } else {
+            paint("not found", RED, colors)
+        };
         println!("  - {} ({})", name, status);
     }
     println!();
 }
 
+/// Write the collected reports in the requested format.
+fn write_report(reporter: Reporter, path: &Path, reports: &[TestReport]) -> Result<()> {
+    match reporter {
+        Reporter::Junit => junit::write_junit(path, reports)
+            .with_context(|| format!("Failed to write JUnit report to {:?}", path)),
+    }
+}
+
+/// Print all collected reports at the end of a run in a non-human format.
+fn print_formatted_results(options: &RunOptions, reports: &[TestReport]) {
+    match options.format {
+        ResultFormat::Human => {}
+        ResultFormat::Summary => {
+            let formatter = OutputFormatter::new(OutputConfig::new().color(options.color));
+            print!("{}", formatter.format_summary(reports, options.verbosity >= Verbosity::Verbose));
+        }
+        ResultFormat::Json => println!("{}", json_report::to_json(reports)),
+        ResultFormat::Tap => {
+            // One test point per assertion across all files
+            let results: Vec<(String, TestResult)> = reports
+                .iter()
+                .flat_map(|report| {
+                    report.assertions.iter().map(move |(description, result)| {
+                        (format!("{}: {}", report.name, description), result.clone())
+                    })
+                })
+                .collect();
+            print!("{}", tap::to_tap(&results));
+        }
+    }
+}
+
 /// Print test results and summary. Returns true if all passed.
-fn print_results(results: &[(String, TestResult)]) -> bool {
+fn print_results(results: &[(String, TestResult)], colors: bool) -> bool {
+    let all_passed = results.iter().all(|(_, r)| r.is_pass());
     let mut passed = 0;
     let mut failed = 0;
 
     for (description, result) in results {
         match result {
             TestResult::Pass => {
-                println!("  \x1b[32m✓\x1b[0m {}", description);
+                println!("  {} {}", paint("✓", GREEN, colors), description);
                 passed += 1;
             }
             TestResult::Fail { reason } => {
-                println!("  \x1b[31m✗\x1b[0m {}", description);
+                println!("  {} {}", paint("✗", RED, colors), description);
                 println!("    └─ {}", reason);
                 failed += 1;
             }
         }
     }
 
-    let all_passed = failed == 0;
     println!();
-    if all_passed {
-        println!("\x1b[32mResults: {}/{} passed\x1b[0m", passed, passed + failed);
+    let line = format!("Results: {}/{} passed", passed, passed + failed);
+    println!("{}", paint(line, if all_passed { GREEN } else { RED }, colors));
+    all_passed
+}
+
+// ANSI SGR codes for `paint`
+const GREEN: &str = "32";
+const RED: &str = "31";
+const YELLOW: &str = "33";
+const DIM: &str = "2";
+
+/// Wrap `text` in the ANSI style `code` when `colors` is set.
+fn paint(text: impl std::fmt::Display, code: &str, colors: bool) -> String {
+    if colors {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
     } else {
-        println!("\x1b[31mResults: {}/{} passed\x1b[0m", passed, passed + failed);
+        text.to_string()
     }
-    all_passed
+}
+
+/// Print an agent's raw stderr, if it wrote any.
+fn print_stderr(stderr: Option<&str>, colors: bool) {
+    let Some(stderr) = stderr.filter(|s| !s.trim().is_empty()) else {
+        return;
+    };
+    println!("Stderr:");
+    for line in stderr.lines() {
+        println!("  {}", paint(line, DIM, colors));
+    }
+}
+
+/// A spinner and elapsed time on the current line while a non-streaming
+/// agent runs, which otherwise prints nothing until it finishes.
+///
+/// It redraws from a background thread. Dropping it stops and joins the
+/// thread and clears the line, including when the run returns an error.
+struct Spinner {
+    stop: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl Spinner {
+    const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Start a spinner for `agent`, unless disabled or stdout isn't a terminal.
+    fn start(agent: &str, enabled: bool) -> Option<Self> {
+        if !enabled || !std::io::stdout().is_terminal() {
+            return None;
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let agent = agent.to_string();
+        let handle = thread::spawn(move || {
+            let started = Instant::now();
+            let mut stdout = std::io::stdout();
+            for frame in Self::FRAMES.iter().cycle() {
+                if thread_stop.load(Ordering::Acquire) {
+                    break;
+                }
+                let _ = write!(stdout, "\r{} {} running ({})", frame, agent, format_elapsed(started.elapsed()));
+                let _ = stdout.flush();
+                thread::sleep(Self::INTERVAL);
+            }
+            // Erase the spinner line so output continues where it started
+            let _ = write!(stdout, "\r\x1b[2K");
+            let _ = stdout.flush();
+        });
+
+        Some(Self {
+            stop,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Format an elapsed time for the spinner, e.g. `42s`, `3m 07s` or `1h 02m 09s`.
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}h {:02}m {:02}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// What `drain_stream_events` prints as events arrive.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Live {
+    /// Nothing; the caller prints results later.
+    Off,
+    /// Tool calls, assistant text and the session log, for people.
+    Human,
+    /// Only tool calls, one JSON object per line.
+    Json,
 }
 
 /// Drain all events from a stream handle, normalizing tool names and printing live.
 ///
+/// With `live` off nothing is printed to stdout; stream errors still go to stderr.
 /// Returns the collected (normalized) tool calls.
 fn drain_stream_events(
     handle: &StreamHandle,
     mapping: &ToolNameMapping,
     formatter: &OutputFormatter,
-    verbose: bool,
+    verbosity: Verbosity,
+    live: Live,
 ) -> Vec<ToolCall> {
     let mut tool_calls = Vec::new();
     for event in &handle.receiver {
@@ -258,17 +917,32 @@ fn drain_stream_events(
                     name: mapping.to_canonical(&tc.name),
                     params: tc.params.clone(),
                     timestamp: tc.timestamp.clone(),
+                    result: tc.result.clone(),
                 };
-                println!("  {}", formatter.format_tool_call(&normalized));
+                match live {
+                    Live::Off => {}
+                    Live::Human => println!("  {}", formatter.format_tool_call(&normalized)),
+                    Live::Json => println!("{}", formatter.format_tool_call_json(&normalized)),
+                }
                 tool_calls.push(normalized);
             }
-            StreamEvent::SessionDetected(path) => {
-                let formatted = formatter.format_session_path(&path, verbose);
-                println!("  \x1b[2m[session: {}]\x1b[0m", formatted);
+            StreamEvent::AssistantText(text) if live == Live::Human => {
+                for line in text.lines() {
+                    println!("  {}", paint(line, DIM, formatter.colors_enabled()));
+                }
+            }
+            StreamEvent::AssistantText(_) => {}
+            StreamEvent::SessionDetected(path) if live == Live::Human => {
+                let formatted = formatter.format_session_path(&path, verbosity >= Verbosity::Debug);
+                let session = format!("[session: {}]", formatted);
+                println!("  {}", paint(session, DIM, formatter.colors_enabled()));
             }
+            StreamEvent::SessionDetected(_) => {}
             StreamEvent::Error(msg) => {
-                eprintln!("  \x1b[33m[stream error: {}]\x1b[0m", msg);
+                let error = format!("[stream error: {}]", msg);
+                eprintln!("  {}", paint(error, YELLOW, formatter.colors_enabled()));
             }
+            StreamEvent::Completed { .. } => {}
         }
     }
     tool_calls
@@ -285,160 +959,408 @@ fn get_mapping(harness: &AgentHarness, agent_type: Option<AgentType>) -> Result<
 fn run_single_test(
     harness: &AgentHarness,
     test_path: &Path,
-    verbose: bool,
-    workdir: Option<&Path>,
-    cli_agent: Option<AgentType>,
-) -> Result<bool> {
-    let test = load_test(test_path).context("Failed to load test file")?;
+    options: &RunOptions,
+) -> Result<TestReport> {
+    let started = Instant::now();
+    let mut test = load_test(test_path).context("Failed to load test file")?;
+    test.resolve_ordering(options.config.ordering_strict);
+    let verbosity = options.verbosity;
+    if let Some(problem) = check_test_args(&test).into_iter().next() {
+        anyhow::bail!("Invalid test file: {}", problem);
+    }
+    // Determine model: CLI flag > test file
+    if let Some(model) = options.model {
+        let flag = |arg: &String| arg.split_once('=').map_or(arg.as_str(), |(flag, _)| flag) == "--model";
+        if test.args.iter().any(flag) {
+            anyhow::bail!("--model conflicts with '--model' in the test file's args; set one or the other");
+        }
+        test.model = Some(model.to_string());
+    }
+    // Parallel runs print results once all tests finish, not live
+    let human = options.format == ResultFormat::Human && options.jobs <= 1;
+    // Banners, tool calls and progress, which quiet runs leave out
+    let banners = human && verbosity > Verbosity::Quiet;
+
+    // Determine working directory: test file > CLI flag
+    let test_workdir = test.resolve_workdir(test_path);
+    let workdir = test_workdir.as_deref().or(options.workdir);
 
-    // Determine agent: CLI flag > test file > default (claude)
-    let agent_type = match cli_agent {
+    // Determine agent: CLI flag > test file > default (claude, or an installed fallback)
+    let agent_type = match options.agent {
         Some(a) => Some(a),
-        None => test.agent.as_ref().and_then(|s| AgentType::from_str(s)),
+        None => test
+            .agent
+            .as_ref()
+            .and_then(|s| AgentType::from_str(s))
+            .or(options.default_agent),
     };
     let agent_name = agent_type
         .map(|a| a.as_str())
         .unwrap_or("claude");
 
-    println!();
-    println!("Running: \"{}\"", test.name);
-    println!("Prompt: \"{}\"", test.prompt);
-    println!("Agent: {}", agent_name);
-    println!();
-    println!("Executing {}...", agent_name);
-    println!();
+    let canonical_workdir = workdir.and_then(|d| d.canonicalize().ok());
+    let formatter = OutputFormatter::new(OutputConfig::verbose().color(options.color))
+        .with_workdir(canonical_workdir.clone());
+
+    if banners {
+        println!();
+        print!(
+            "{}",
+            formatter.format_preamble(&test.name, &test.prompt, agent_name, test.model.as_deref(), verbosity)
+        );
+    }
 
     // Build execution config
-    let mut config = ExecutionConfig::new();
+    let mut config = ExecutionConfig::new().with_retries(options.retries);
     if let Some(dir) = workdir {
         config = config.with_working_dir(dir.to_path_buf());
     }
+    config = test.apply_to_config(config);
+    config.tool_mappings = options.config.tool_mappings.clone();
 
-    let mapping = get_mapping(harness, agent_type)?;
-    let canonical_workdir = workdir.and_then(|d| d.canonicalize().ok());
-    let formatter = OutputFormatter::new(OutputConfig::verbose())
-        .with_workdir(canonical_workdir.clone());
+    let mut mapping = get_mapping(harness, agent_type)?;
+    mapping.extend(&config.tool_mappings);
 
     // Dispatch based on agent capability, not identity
     let resolved_type = agent_type.unwrap_or(AgentType::Claude);
     let agent = harness.get_agent(resolved_type)
         .ok_or_else(|| anyhow::anyhow!("Agent not found: {:?}", resolved_type))?;
+    let grading_agent = harness.get_agent(agent_type.unwrap_or(AgentType::Claude));
+    let attempts = config.retries + 1;
+    let mut stdout = None;
+
+    let budget = test.timeout_secs.map(Duration::from_secs);
+    let mut attempt = 1;
+    let results = run_with_budget(budget, |test_deadline| {
+        // The agent is killed when the test's budget or the whole run's runs out
+        let deadline = test_deadline.into_iter().chain(options.deadline).min();
+        let (results, last_attempt) = run_with_retries(config.retries, |attempt| {
+            let mut config = config.clone();
+            if let Some(deadline) = deadline {
+                let left = deadline.saturating_duration_since(Instant::now());
+                config.timeout = Some(config.timeout.map_or(left, |timeout| timeout.min(left)));
+            }
 
-    let (tool_calls, stdout, session_log_path) = if agent.supports_streaming() {
-        let handle = harness.execute_streaming(agent_type, &test.prompt, config)?;
-
-        println!("Tool calls (live):");
-        println!("{}", "─".repeat(40));
-
-        let tool_calls = drain_stream_events(&handle, &mapping, &formatter, verbose);
-
-        println!("{}", "─".repeat(40));
-
-        let raw_result = handle.wait()?;
-        (tool_calls, raw_result.stdout, raw_result.session_log_path)
-    } else {
-        println!("Tool calls:");
-        println!("{}", "─".repeat(40));
-
-        let output = harness.execute(agent_type, &test.prompt, config)?;
-        let tool_calls = output.result.tool_calls.clone();
+            // Setup and teardown run around every attempt so each starts from a clean fixture
+            with_hooks(&test, workdir, || {
+                if banners {
+                    println!();
+                    if attempt > 1 {
+                        println!("Retrying {} (attempt {}/{})...", agent_name, attempt, attempts);
+                    } else {
+                        println!("Executing {}...", agent_name);
+                    }
+                    println!();
+                }
 
-        formatter.print_tool_calls(&tool_calls, true);
+                let (tool_calls, attempt_stdout, stderr, exit_code, session_log_path) = if agent.supports_streaming() {
+                    let handle = harness.execute_streaming(agent_type, &test.prompt, config.clone())?;
+
+                    if banners {
+                        println!("Tool calls (live):");
+                        println!("{}", "─".repeat(40));
+                    }
+
+                    let mut tool_calls = drain_stream_events(
+                        &handle,
+                        &mapping,
+                        &formatter,
+                        verbosity,
+                        if banners { Live::Human } else { Live::Off },
+                    );
+                    harness.normalize_paths(&mut tool_calls, &config);
+
+                    if banners {
+                        println!("{}", "─".repeat(40));
+                    }
+
+                    let raw_result = handle.wait()?;
+                    (
+                        tool_calls,
+                        raw_result.stdout,
+                        raw_result.stderr,
+                        raw_result.exit_code,
+                        raw_result.session_log_path,
+                    )
+                } else {
+                    // Nothing streams in the meantime, so show that the run is still going
+                    let output = {
+                        let _spinner = Spinner::start(agent_name, banners);
+                        harness.execute(agent_type, &test.prompt, config.clone())?
+                    };
+                    let tool_calls = output.result.tool_calls.clone();
+
+                    if banners {
+                        println!("Tool calls:");
+                        println!("{}", "─".repeat(40));
+                        formatter.print_tool_calls(&tool_calls, true);
+                        println!("{}", "─".repeat(40));
+                    }
+
+                    (tool_calls, output.stdout, output.stderr, output.exit_code, output.session_log_path)
+                };
 
-        println!("{}", "─".repeat(40));
+                if banners {
+                    println!();
+                    println!("{} finished. Evaluating assertions...", agent_name);
+                    if let Some(log_path) = &session_log_path {
+                        let full = verbosity >= Verbosity::Debug;
+                        println!("Session log: {}", formatter.format_session_path(log_path, full));
+                    }
+                    if verbosity >= Verbosity::Debug {
+                        print_stderr(stderr.as_deref(), formatter.colors_enabled());
+                    }
+                    println!();
+                }
 
-        (tool_calls, output.stdout, output.session_log_path)
-    };
+                // Evaluate assertions, leaving out calls the config ignores
+                let tool_calls = options.config.filter_ignored(tool_calls);
+                let results = run_yaml_test_with_cache(
+                    &test,
+                    &tool_calls,
+                    &attempt_stdout,
+                    exit_code,
+                    grading_agent,
+                    options.cache,
+                );
+                stdout = attempt_stdout;
+
+                // Show failures of attempts that will be retried
+                if banners && attempt < attempts && results.iter().any(|(_, r)| r.is_fail()) {
+                    print_results(&results, formatter.colors_enabled());
+                }
+                Ok(results)
+            })
+        })?;
+        attempt = last_attempt;
+        Ok(results)
+    })?;
+    let test_passed = results.iter().all(|(_, r)| r.is_pass());
+
+    // Show results, and the response if verbose or failed
+    if banners {
+        print_results(&results, formatter.colors_enabled());
+        if test_passed && attempt > 1 {
+            println!("(passed on attempt {}/{})", attempt, attempts);
+        }
 
-    println!();
-    println!("{} finished. Evaluating assertions...", agent_name);
-    if let Some(log_path) = &session_log_path {
-        println!("Session log: {}", formatter.format_session_path(log_path, verbose));
+        let out_formatter = OutputFormatter::new(verbosity.output_config().color(options.color))
+            .with_workdir(canonical_workdir);
+        out_formatter.print_response(stdout.as_deref(), test_passed);
     }
-    println!();
 
-    // Evaluate assertions
-    let grading_agent = harness.get_agent(agent_type.unwrap_or(AgentType::Claude));
-    let results = run_yaml_test(&test, &tool_calls, &stdout, grading_agent);
-    let test_passed = print_results(&results);
+    let report = TestReport::new(
+        test.name,
+        test.prompt,
+        agent_name.to_string(),
+        results,
+        started.elapsed(),
+    );
 
-    // Show response if verbose or failed
-    let output_config = if verbose {
-        OutputConfig::verbose()
-    } else {
-        OutputConfig::new()
-    };
-    let out_formatter = OutputFormatter::new(output_config)
-        .with_workdir(canonical_workdir);
-    out_formatter.print_response(stdout.as_deref(), test_passed);
+    // Quiet runs show a line per test with what failed
+    if human && !banners {
+        let report_formatter = OutputFormatter::new(verbosity.output_config().color(options.color));
+        print!("{}", report_formatter.format_test_result(&report, verbosity));
+    }
 
-    Ok(test_passed)
+    Ok(report)
 }
 
 fn run_tests_in_directory(
     harness: &AgentHarness,
     dir: &Path,
-    verbose: bool,
-    workdir: Option<&Path>,
-    cli_agent: Option<AgentType>,
+    options: &RunOptions,
     config: &Config,
+    report: Option<&(Reporter, PathBuf)>,
 ) -> Result<()> {
-    let test_files = discover_tests(dir, config)?;
+    let discovered = discover_tests(dir, config)?;
+    let found_any = !discovered.is_empty();
+    let test_files = options.filter.apply(options.id_root, discovered);
+    let human = options.format == ResultFormat::Human;
+    // Quiet runs print a line per test and the totals, without banners or progress
+    let banners = human && options.verbosity > Verbosity::Quiet;
 
     if test_files.is_empty() {
+        if human && found_any {
+            println!();
+            println!("No tests match {} in {:?}", options.filter, dir);
+        } else if human {
+            println!();
+            println!(
+                "No test files found matching pattern '{}' in {:?}",
+                config.test_pattern, dir
+            );
+        } else {
+            print_formatted_results(options, &[]);
+        }
+        return Ok(());
+    }
+
+    if banners {
         println!();
         println!(
-            "No test files found matching pattern '{}' in {:?}",
-            config.test_pattern, dir
+            "Found {} test file(s) matching '{}'",
+            test_files.len(),
+            config.test_pattern
         );
-        return Ok(());
     }
 
-    println!();
-    println!(
-        "Found {} test file(s) matching '{}'",
-        test_files.len(),
-        config.test_pattern
+    let total = test_files.len();
+    let progress = OutputFormatter::new(OutputConfig::new().color(options.color));
+    let colors = progress.colors_enabled();
+    let parallel = options.jobs > 1;
+    let indexed: Vec<(usize, PathBuf)> = test_files.into_iter().enumerate().collect();
+
+    // Tests sharing a working directory never run concurrently (see `aptitude::parallel`)
+    let outcomes = run_ordered_fail_fast(
+        &indexed,
+        options.jobs,
+        |(_, path)| session_key(path, options.workdir),
+        |(i, path)| {
+            if banners && !parallel {
+                println!();
+                println!("{}", progress.format_progress(i + 1, total, path));
+            }
+            let outcome = run_single_test(harness, path, options);
+            if human && !parallel {
+                if let Err(e) = &outcome {
+                    println!("{}", paint(format!("Error running {:?}: {}", path, e), RED, colors));
+                }
+                if banners {
+                    println!();
+                    println!("{}", "─".repeat(60));
+                }
+            }
+            outcome
+        },
+        |outcome| {
+            options.out_of_time()
+                || (options.fail_fast && outcome.as_ref().map_or(true, |report| report.failed > 0))
+        },
     );
 
     let mut total_passed = 0;
     let mut total_failed = 0;
+    let mut total_skipped = 0;
+    let mut reports = Vec::new();
+
+    for ((i, path), outcome) in indexed.iter().zip(outcomes) {
+        // Never started because an earlier test failed with --fail-fast
+        let Some(outcome) = outcome else {
+            total_skipped += 1;
+            continue;
+        };
 
-    for path in test_files {
-        match run_single_test(harness, &path, verbose, workdir, cli_agent) {
-            Ok(passed) => {
-                if passed {
+        // Parallel runs print nothing live; show each test's results in order
+        if banners && parallel {
+            println!();
+            println!("{}", progress.format_progress(i + 1, total, path));
+        }
+
+        match outcome {
+            Ok(test_report) => {
+                if human && parallel && !banners {
+                    print!("{}", progress.format_test_result(&test_report, Verbosity::Quiet));
+                } else if human && parallel {
+                    println!();
+                    println!("Running: \"{}\"", test_report.name);
+                    println!("Agent: {}", test_report.agent);
+                    println!();
+                    print_results(&test_report.assertions, colors);
+                }
+                if test_report.is_pass() {
                     total_passed += 1;
                 } else {
                     total_failed += 1;
                 }
+                reports.push(test_report);
             }
             Err(e) => {
-                println!("\x1b[31mError running {:?}: {}\x1b[0m", path, e);
+                if human && parallel {
+                    println!("{}", paint(format!("Error running {:?}: {}", path, e), RED, colors));
+                }
                 total_failed += 1;
+                reports.push(TestReport::new(
+                    path.display().to_string(),
+                    String::new(),
+                    options.agent.map(|a| a.as_str()).unwrap_or("claude").to_string(),
+                    vec![(
+                        "run test".to_string(),
+                        TestResult::Fail { reason: e.to_string() },
+                    )],
+                    Duration::ZERO,
+                ));
             }
         }
+
+        if banners && parallel {
+            println!();
+            println!("{}", "─".repeat(60));
+        }
+    }
+
+    let stopped = if options.out_of_time() {
+        "Aborted after running out of time (--max-duration)"
+    } else {
+        "Stopped early after a failure (--fail-fast)"
+    };
+    if human {
         println!();
-        println!("{}", "─".repeat(60));
+        if total_skipped > 0 {
+            println!("{}: {} test file(s) skipped", stopped, total_skipped);
+            println!(
+                "Total: {} passed, {} failed, {} skipped",
+                total_passed, total_failed, total_skipped
+            );
+        } else {
+            println!("Total: {} passed, {} failed", total_passed, total_failed);
+        }
+    } else {
+        print_formatted_results(options, &reports);
+        // Keep machine-readable stdout clean
+        if total_skipped > 0 {
+            eprintln!("{}: {} test file(s) skipped", stopped, total_skipped);
+        }
     }
 
-    println!();
-    println!("Total: {} passed, {} failed", total_passed, total_failed);
+    if let Some((reporter, output)) = report {
+        write_report(*reporter, output, &reports)?;
+    }
 
-    if total_failed > 0 {
+    if total_failed > 0 || (total_skipped > 0 && options.out_of_time()) {
         std::process::exit(1);
     }
 
     Ok(())
 }
 
+/// Key identifying where a test's agent session lives: its resolved working directory.
+///
+/// Mirrors the precedence in [`run_single_test`] (test file > CLI flag). `None`
+/// means the current directory.
+fn session_key(test_path: &Path, cli_workdir: Option<&Path>) -> Option<PathBuf> {
+    let test_workdir = load_test(test_path)
+        .ok()
+        .and_then(|test| test.resolve_workdir(test_path));
+    let workdir = test_workdir.or_else(|| cli_workdir.map(Path::to_path_buf))?;
+    Some(workdir.canonicalize().unwrap_or(workdir))
+}
+
 fn analyze_session(
     harness: &AgentHarness,
     test_path: &Path,
     session_path: &Path,
     cli_agent: Option<AgentType>,
+    cli_model: Option<&str>,
+    cache: Option<&GradeCache>,
+    export: Option<&Path>,
 ) -> Result<()> {
-    let test = load_test(test_path).context("Failed to load test file")?;
+    let mut test = load_test(test_path).context("Failed to load test file")?;
+    let (config, _) = load_or_discover_config(test_path, None);
+    test.resolve_ordering(config.ordering_strict);
+    if let Some(model) = cli_model {
+        test.model = Some(model.to_string());
+    }
 
     // Determine agent for tool name normalization
     let agent_type = cli_agent
@@ -448,23 +1370,34 @@ fn analyze_session(
     let formatter = OutputFormatter::with_defaults();
     println!();
     println!("Analyzing: \"{}\"", test.name);
-    println!("Session: {}", formatter.format_session_path(session_path, false));
+    let from_stdin = session_path == Path::new("-");
+    if from_stdin {
+        println!("Session: <stdin>");
+    } else {
+        println!("Session: {}", formatter.format_session_path(session_path, false));
+    }
     println!("Agent: {}", agent_type.as_str());
     println!();
 
     // Parse the session log
-    let raw_tool_calls = parse_jsonl_file(session_path)?;
+    let raw_tool_calls = if from_stdin {
+        parse_jsonl_reader(std::io::stdin().lock())?
+    } else {
+        parse_jsonl_file(session_path)?
+    };
 
     // Normalize tool names using the agent's mapping
     let agent = harness.get_agent(agent_type)
         .ok_or_else(|| anyhow::anyhow!("Agent not found: {:?}", agent_type))?;
-    let mapping = agent.tool_mapping();
+    let mut mapping = agent.tool_mapping().clone();
+    mapping.extend(&config.tool_mappings);
     let tool_calls: Vec<_> = raw_tool_calls
         .iter()
         .map(|call| ToolCall {
             name: mapping.to_canonical(&call.name),
             params: call.params.clone(),
             timestamp: call.timestamp.clone(),
+            result: call.result.clone(),
         })
         .collect();
 
@@ -478,11 +1411,8 @@ fn analyze_session(
             .or_else(|| call.params.get("command"))
             .and_then(|v| v.as_str())
             .unwrap_or("");
-        let time = if call.timestamp.len() >= 19 {
-            &call.timestamp[11..19]
-        } else {
-            "??:??:??"
-        };
+        // Checked slice, so a malformed (e.g. non-ASCII) timestamp can't panic
+        let time = call.timestamp.get(11..19).unwrap_or("??:??:??");
         println!(
             "[{}] {}: {}",
             time,
@@ -491,14 +1421,23 @@ fn analyze_session(
         );
     }
 
+    if let Some(path) = export {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create {:?}", path))?;
+        write_csv(&tool_calls, file).with_context(|| format!("Failed to write CSV to {:?}", path))?;
+        println!();
+        println!("Exported {} tool calls to {}", tool_calls.len(), path.display());
+    }
+
     println!();
     println!("Evaluating assertions...");
     println!();
 
-    // Evaluate assertions (stdout not available in analyze mode)
+    // Evaluate assertions (stdout and exit code not available in analyze mode)
     let grading_agent = harness.get_agent(agent_type);
-    let results = run_yaml_test(&test, &tool_calls, &None, grading_agent);
-    let all_passed = print_results(&results);
+    let tool_calls = config.filter_ignored(tool_calls);
+    let results = run_yaml_test_with_cache(&test, &tool_calls, &None, None, grading_agent, cache);
+    let all_passed = print_results(&results, formatter.colors_enabled());
 
     if !all_passed {
         std::process::exit(1);
@@ -507,35 +1446,143 @@ fn analyze_session(
     Ok(())
 }
 
-fn log_command(
+/// Print how the tool calls of two sessions differ (`aptitude diff`).
+///
+/// Exits with status 1 if any call was added, removed or reordered.
+fn diff_sessions(
     harness: &AgentHarness,
-    prompt: &str,
-    workdir: Option<&Path>,
-    cli_agent: Option<AgentType>,
-    model: Option<&str>,
+    before_path: &Path,
+    after_path: &Path,
+    agent_type: Option<AgentType>,
+    color: ColorChoice,
 ) -> Result<()> {
+    let mapping = get_mapping(harness, agent_type)?;
+    let load = |path: &Path| -> Result<Vec<ToolCall>> {
+        let calls = parse_jsonl_file(path).with_context(|| format!("Failed to parse session {:?}", path))?;
+        Ok(calls
+            .into_iter()
+            .map(|call| ToolCall {
+                name: mapping.to_canonical(&call.name),
+                ..call
+            })
+            .collect())
+    };
+    let before = load(before_path)?;
+    let after = load(after_path)?;
+
+    let formatter = OutputFormatter::new(OutputConfig::new().color(color));
+    let colors = formatter.colors_enabled();
+    let diff = diff_tool_calls(&before, &after);
+
+    println!();
+    println!("--- {} ({} calls)", before_path.display(), before.len());
+    println!("+++ {} ({} calls)", after_path.display(), after.len());
+    println!();
+    for change in &diff.changes {
+        let call = diff.call(change);
+        let line = format!("{} {}", call.name, formatter.format_params(&call.params));
+        match *change {
+            Change::Unchanged { .. } => println!("  {}", line),
+            Change::Added { .. } => println!("{}", paint(format!("+ {}", line), GREEN, colors)),
+            Change::Removed { .. } => println!("{}", paint(format!("- {}", line), RED, colors)),
+            Change::Reordered { before, after } => println!(
+                "{}",
+                paint(format!("~ {} (moved from #{} to #{})", line, before + 1, after + 1), YELLOW, colors)
+            ),
+        }
+    }
+
+    println!();
+    if diff.is_empty() {
+        println!("Sessions made the same {} tool call(s)", after.len());
+        return Ok(());
+    }
+    println!(
+        "{} added, {} removed, {} reordered",
+        diff.added(),
+        diff.removed(),
+        diff.reordered()
+    );
+    std::process::exit(1);
+}
+
+/// Print tool usage aggregated over every session log under `dir` (`aptitude stats`).
+///
+/// Logs that fail to parse are skipped with a warning on stderr.
+fn session_stats(dir: &Path, json: bool, top: usize) -> Result<()> {
+    let logs = find_session_logs(dir).with_context(|| format!("Failed to search {:?}", dir))?;
+    let mut sessions = Vec::with_capacity(logs.len());
+    for log in &logs {
+        match parse_jsonl_file(log) {
+            Ok(calls) => sessions.push(calls),
+            Err(e) => eprintln!("Skipping {}: {:#}", log.display(), e),
+        }
+    }
+    let stats = aggregate(&sessions, top);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    println!();
+    println!(
+        "{} session(s), {} tool call(s), {:.1} per session",
+        stats.sessions, stats.calls, stats.calls_per_session
+    );
+    if stats.tools.is_empty() {
+        return Ok(());
+    }
+
+    println!();
+    println!("{:<16} {:>8} {:>12} {:>10}", "Tool", "Calls", "Per session", "Sessions");
+    for tool in &stats.tools {
+        println!(
+            "{:<16} {:>8} {:>12.1} {:>10}",
+            tool.name,
+            tool.calls,
+            tool.per_session,
+            format!("{}/{}", tool.sessions, stats.sessions)
+        );
+    }
+
+    if !stats.most_read.is_empty() {
+        println!();
+        println!("Most read files:");
+        for file in &stats.most_read {
+            println!("  {:>5}  {} ({} session(s))", file.reads, file.path, file.sessions);
+        }
+    }
+    Ok(())
+}
+
+fn log_command(harness: &AgentHarness, prompt: &str, options: &LogOptions) -> Result<()> {
+    let cli_agent = options.agent;
+    let verbosity = options.verbosity;
+    let human = !options.json;
     let agent_name = cli_agent
         .map(|a| a.as_str())
         .unwrap_or("claude");
 
-    println!();
-    println!("Executing Claude with prompt: \"{}\"", prompt);
-    println!("Agent: {}", agent_name);
-    println!();
+    if human {
+        println!();
+        println!("Executing Claude with prompt: \"{}\"", prompt);
+        println!("Agent: {}", agent_name);
+        println!();
+    }
 
     // Build execution config
     let mut config = ExecutionConfig::new();
-    if let Some(dir) = workdir {
+    if let Some(dir) = options.workdir {
         config = config.with_working_dir(dir.to_path_buf());
     }
-    if let Some(m) = model {
-        config.extra_args.push("--model".to_string());
-        config.extra_args.push(m.to_string());
+    if let Some(m) = options.model {
+        config = config.with_model(m);
     }
 
     let mapping = get_mapping(harness, cli_agent)?;
-    let canonical_workdir = workdir.and_then(|d| d.canonicalize().ok());
-    let formatter = OutputFormatter::new(OutputConfig::verbose())
+    let canonical_workdir = options.workdir.and_then(|d| d.canonicalize().ok());
+    let formatter = OutputFormatter::new(OutputConfig::verbose().color(options.color))
         .with_workdir(canonical_workdir);
 
     // Dispatch based on agent capability
@@ -543,31 +1590,51 @@ fn log_command(
     let agent = harness.get_agent(resolved_type)
         .ok_or_else(|| anyhow::anyhow!("Agent not found: {:?}", resolved_type))?;
 
-    let (tool_calls, stdout, session_log_path) = if agent.supports_streaming() {
-        println!("Tool calls (live):");
-        println!("{}", "─".repeat(60));
+    let (tool_calls, stdout, stderr, session_log_path) = if agent.supports_streaming() {
+        if human {
+            println!("Tool calls (live):");
+            println!("{}", "─".repeat(60));
+        }
 
         let handle = harness.execute_streaming(cli_agent, prompt, config)?;
-        let tool_calls = drain_stream_events(&handle, &mapping, &formatter, false);
+        let live = if human { Live::Human } else { Live::Json };
+        let tool_calls = drain_stream_events(&handle, &mapping, &formatter, verbosity, live);
 
-        println!("{}", "─".repeat(60));
+        if human {
+            println!("{}", "─".repeat(60));
+        }
 
         let raw_result = handle.wait()?;
-        (tool_calls, raw_result.stdout, raw_result.session_log_path)
+        (tool_calls, raw_result.stdout, raw_result.stderr, raw_result.session_log_path)
     } else {
-        println!("Tool calls:");
-        println!("{}", "─".repeat(60));
+        if human {
+            println!("Tool calls:");
+            println!("{}", "─".repeat(60));
+        }
 
-        let output = harness.execute(cli_agent, prompt, config)?;
+        let output = {
+            let _spinner = Spinner::start(agent_name, human);
+            harness.execute(cli_agent, prompt, config)?
+        };
         let tool_calls = output.result.tool_calls.clone();
 
-        formatter.print_tool_calls(&tool_calls, true);
-
-        println!("{}", "─".repeat(60));
+        if human {
+            formatter.print_tool_calls(&tool_calls, true);
+            println!("{}", "─".repeat(60));
+        } else {
+            for call in &tool_calls {
+                println!("{}", formatter.format_tool_call_json(call));
+            }
+        }
 
-        (tool_calls, output.stdout, output.session_log_path)
+        (tool_calls, output.stdout, output.stderr, output.session_log_path)
     };
 
+    // Keep JSON output to the tool call lines, so it can be piped into jq
+    if !human {
+        return Ok(());
+    }
+
     println!();
     println!("Total: {} tool call(s)", tool_calls.len());
 
@@ -581,8 +1648,77 @@ fn log_command(
 
     if let Some(log_path) = &session_log_path {
         println!();
-        println!("Session log: {}", formatter.format_session_path(log_path, false));
+        println!("Session log: {}", formatter.format_session_path(log_path, verbosity >= Verbosity::Debug));
+    }
+    if verbosity >= Verbosity::Debug {
+        println!();
+        print_stderr(stderr.as_deref(), formatter.colors_enabled());
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(Duration::ZERO), "0s");
+        assert_eq!(format_elapsed(Duration::from_millis(42_900)), "42s");
+        assert_eq!(format_elapsed(Duration::from_secs(60)), "1m 00s");
+        assert_eq!(format_elapsed(Duration::from_secs(187)), "3m 07s");
+        assert_eq!(format_elapsed(Duration::from_secs(3729)), "1h 02m 09s");
+    }
+
+    #[test]
+    fn test_filter_selects() {
+        let path = Path::new("tests/auth-login.yaml");
+        let none = TestFilter::default();
+        assert!(none.selects(path, "0a1b2c3d", Some("Login reads config")));
+        assert!(none.selects(path, "0a1b2c3d", None));
+
+        let by_name = TestFilter { name: Some("reads"), file: None };
+        assert!(by_name.selects(path, "0a1b2c3d", Some("Login reads config")));
+        assert!(!by_name.selects(path, "0a1b2c3d", Some("Login edits config")));
+        assert!(!by_name.selects(path, "0a1b2c3d", None));
+
+        let by_file = TestFilter { name: None, file: Some(&glob::Pattern::new("auth-*.yaml").unwrap()) };
+        assert!(by_file.selects(path, "0a1b2c3d", None));
+        assert!(!by_file.selects(Path::new("tests/billing.yaml"), "0a1b2c3d", Some("reads")));
+
+        let both = TestFilter { name: Some("edits"), file: Some(&glob::Pattern::new("auth-*").unwrap()) };
+        assert!(both.selects(path, "0a1b2c3d", Some("Login edits config")));
+        assert!(!both.selects(path, "0a1b2c3d", Some("Login reads config")));
+        assert!(!both.selects(Path::new("billing.yaml"), "0a1b2c3d", Some("Login edits config")));
+    }
+
+    #[test]
+    fn test_parse_glob() {
+        assert!(parse_glob("auth-*.yaml").unwrap().matches("auth-login.yaml"));
+        assert!(parse_glob("[a").unwrap_err().starts_with("invalid glob: "));
+    }
+
+    #[test]
+    fn test_filter_selects_by_id() {
+        let path = Path::new("tests/auth-login.yaml");
+        let by_id = TestFilter { name: Some("0a1b2c3d"), file: None };
+        assert!(by_id.selects(path, "0a1b2c3d", Some("Login reads config")));
+        // Still selected when the file fails to load, so the error is reported
+        assert!(by_id.selects(path, "0a1b2c3d", None));
+        // Ids match whole, not as a substring
+        assert!(!by_id.selects(path, "0a1b2c3d4", None));
+        assert!(!by_id.selects(path, "ffffffff", Some("Login reads config")));
+    }
+
+    #[test]
+    fn test_filter_display() {
+        let filter = TestFilter { name: Some("login"), file: Some(&glob::Pattern::new("*.yml").unwrap()) };
+        assert_eq!(filter.to_string(), "--only 'login' and --only-file '*.yml'");
+    }
+
+    #[test]
+    fn test_spinner_disabled_is_none() {
+        assert!(Spinner::start("claude", false).is_none());
+    }
+}
@@ -0,0 +1,172 @@
+//! JSON reporter for machine-readable run results.
+//!
+//! The document has a top-level `summary` with pass/fail counts and a `tests`
+//! array with one entry per test file.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use aptitude::output::json_report;
+//!
+//! println!("{}", json_report::to_json(&reports));
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::yaml::TestResult;
+
+use super::TestReport;
+
+/// A complete run: summary counts plus per-test results.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunReport {
+    /// Aggregate pass/fail counts.
+    pub summary: RunSummary,
+    /// One entry per test file, in run order.
+    pub tests: Vec<TestEntry>,
+}
+
+/// Aggregate counts across all test files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunSummary {
+    /// Number of test files run.
+    pub tests: usize,
+    /// Test files whose assertions all passed.
+    pub passed: usize,
+    /// Test files with at least one failing assertion.
+    pub failed: usize,
+    /// Number of assertions evaluated.
+    pub assertions: usize,
+    /// Number of failing assertions.
+    pub assertions_failed: usize,
+}
+
+/// Results for a single test file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestEntry {
+    /// Test name from the YAML file.
+    pub name: String,
+    /// Prompt sent to the agent.
+    pub prompt: String,
+    /// Agent that executed the prompt.
+    pub agent: String,
+    /// Whether every assertion passed.
+    pub passed: bool,
+    /// How long the test took, in milliseconds.
+    pub duration_ms: u64,
+    /// Individual assertion results.
+    pub assertions: Vec<AssertionEntry>,
+}
+
+/// Result of a single assertion.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssertionEntry {
+    /// What was asserted.
+    pub description: String,
+    /// Whether the assertion passed.
+    pub passed: bool,
+    /// Failure reason, `null` when the assertion passed.
+    pub reason: Option<String>,
+}
+
+impl From<&TestReport> for TestEntry {
+    fn from(report: &TestReport) -> Self {
+        Self {
+            name: report.name.clone(),
+            prompt: report.prompt.clone(),
+            agent: report.agent.clone(),
+            passed: report.is_pass(),
+            duration_ms: report.duration.as_millis() as u64,
+            assertions: report
+                .assertions
+                .iter()
+                .map(|(description, result)| AssertionEntry {
+                    description: description.clone(),
+                    passed: result.is_pass(),
+                    reason: match result {
+                        TestResult::Pass => None,
+                        TestResult::Fail { reason } => Some(reason.clone()),
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&[TestReport]> for RunReport {
+    fn from(reports: &[TestReport]) -> Self {
+        let tests: Vec<TestEntry> = reports.iter().map(TestEntry::from).collect();
+        let passed = tests.iter().filter(|t| t.passed).count();
+        Self {
+            summary: RunSummary {
+                tests: tests.len(),
+                passed,
+                failed: tests.len() - passed,
+                assertions: reports.iter().map(|r| r.assertions.len()).sum(),
+                assertions_failed: reports.iter().map(|r| r.failed).sum(),
+            },
+            tests,
+        }
+    }
+}
+
+/// Render test reports as a pretty-printed JSON document.
+pub fn to_json(reports: &[TestReport]) -> String {
+    serde_json::to_string_pretty(&RunReport::from(reports)).expect("report serializes to JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_json_round_trip() {
+        let reports = vec![
+            TestReport::new(
+                "Reads config".to_string(),
+                "Read config.toml".to_string(),
+                "claude".to_string(),
+                vec![
+                    ("Read called".to_string(), TestResult::Pass),
+                    (
+                        "Write not called".to_string(),
+                        TestResult::Fail {
+                            reason: "called 1 time".to_string(),
+                        },
+                    ),
+                ],
+                Duration::from_millis(2500),
+            ),
+            TestReport::new(
+                "Lists files".to_string(),
+                "List files".to_string(),
+                "kiro".to_string(),
+                vec![("Bash called".to_string(), TestResult::Pass)],
+                Duration::ZERO,
+            ),
+        ];
+
+        let json = to_json(&reports);
+        let parsed: RunReport = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, RunReport::from(reports.as_slice()));
+        assert_eq!(
+            parsed.summary,
+            RunSummary {
+                tests: 2,
+                passed: 1,
+                failed: 1,
+                assertions: 3,
+                assertions_failed: 1,
+            }
+        );
+        assert!(!parsed.tests[0].passed);
+        assert_eq!(parsed.tests[0].assertions[1].reason.as_deref(), Some("called 1 time"));
+        assert_eq!(parsed.tests[1].agent, "kiro");
+        assert_eq!(parsed.tests[0].duration_ms, 2500);
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value["tests"][0]["assertions"][0]["reason"].is_null());
+    }
+}
@@ -0,0 +1,190 @@
+//! JUnit XML reporter for CI systems.
+//!
+//! Each test file becomes a `<testsuite>` and each assertion a `<testcase>`.
+//! Failed assertions carry a `<failure>` element with the failure reason.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use aptitude::output::junit;
+//!
+//! junit::write_junit("results.xml", &reports)?;
+//! ```
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::yaml::TestResult;
+
+use super::TestReport;
+
+/// Render test reports as a JUnit XML document.
+pub fn to_junit_xml(reports: &[TestReport]) -> String {
+    let tests: usize = reports.iter().map(|r| r.assertions.len()).sum();
+    let failures: usize = reports.iter().map(|r| r.failed).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"aptitude\" tests=\"{}\" failures=\"{}\">",
+        tests, failures
+    );
+
+    for report in reports {
+        let suite = escape(&report.name);
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" time=\"{:.3}\">",
+            suite,
+            report.assertions.len(),
+            report.failed,
+            report.duration.as_secs_f64()
+        );
+        xml.push_str("    <properties>\n");
+        for (name, value) in [
+            ("name", &report.name),
+            ("prompt", &report.prompt),
+            ("agent", &report.agent),
+        ] {
+            let _ = writeln!(
+                xml,
+                "      <property name=\"{}\" value=\"{}\"/>",
+                name,
+                escape(value)
+            );
+        }
+        xml.push_str("    </properties>\n");
+
+        for (description, result) in &report.assertions {
+            let case = escape(description);
+            match result {
+                TestResult::Pass => {
+                    let _ = writeln!(
+                        xml,
+                        "    <testcase name=\"{}\" classname=\"{}\"/>",
+                        case, suite
+                    );
+                }
+                TestResult::Fail { reason } => {
+                    let _ = writeln!(
+                        xml,
+                        "    <testcase name=\"{}\" classname=\"{}\">",
+                        case, suite
+                    );
+                    let _ = writeln!(
+                        xml,
+                        "      <failure message=\"{}\"/>",
+                        escape(reason)
+                    );
+                    xml.push_str("    </testcase>\n");
+                }
+            }
+        }
+
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Write test reports as a JUnit XML document to `path`.
+pub fn write_junit(path: impl AsRef<Path>, reports: &[TestReport]) -> std::io::Result<()> {
+    std::fs::write(path, to_junit_xml(reports))
+}
+
+/// Escape a string for use in XML attribute values.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' => out.push_str("&#10;"),
+            '\r' => out.push_str("&#13;"),
+            '\t' => out.push_str("&#9;"),
+            // Other control characters are not allowed in XML 1.0
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn mixed_report() -> TestReport {
+        TestReport::new(
+            "Edits <config> & \"env\"".to_string(),
+            "Update the config\nthen exit".to_string(),
+            "claude".to_string(),
+            vec![
+                ("Read called".to_string(), TestResult::Pass),
+                (
+                    "Write with file_path='*.env' not called".to_string(),
+                    TestResult::Fail {
+                        reason: "called 2 times, expected <1".to_string(),
+                    },
+                ),
+            ],
+            Duration::from_millis(1500),
+        )
+    }
+
+    /// Check every tag is closed in order and nothing but whitespace sits between tags.
+    fn assert_well_formed(xml: &str) {
+        let body = xml
+            .strip_prefix("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")
+            .expect("missing XML declaration");
+        let mut stack: Vec<&str> = Vec::new();
+        let mut rest = body;
+        while let Some(start) = rest.find('<') {
+            assert!(rest[..start].trim().is_empty(), "stray text: {}", &rest[..start]);
+            let end = rest[start..].find('>').expect("unterminated tag") + start;
+            let tag = &rest[start + 1..end];
+            assert!(!tag.contains('<'), "unescaped '<' in tag: {}", tag);
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(stack.pop(), Some(name), "mismatched closing tag");
+            } else if !tag.ends_with('/') {
+                stack.push(tag.split_whitespace().next().unwrap());
+            }
+            rest = &rest[end + 1..];
+        }
+        assert!(stack.is_empty(), "unclosed tags: {:?}", stack);
+        assert!(rest.trim().is_empty());
+    }
+
+    #[test]
+    fn test_junit_mixed_results_well_formed() {
+        let passing = TestReport::new(
+            "Reads only".to_string(),
+            "Read a file".to_string(),
+            "kiro".to_string(),
+            vec![("Read called".to_string(), TestResult::Pass)],
+            Duration::ZERO,
+        );
+        let xml = to_junit_xml(&[mixed_report(), passing]);
+
+        assert_well_formed(&xml);
+        assert!(xml.contains("<testsuites name=\"aptitude\" tests=\"3\" failures=\"1\">"));
+        assert!(xml.contains(
+            "<testsuite name=\"Edits &lt;config&gt; &amp; &quot;env&quot;\" tests=\"2\" failures=\"1\" errors=\"0\" time=\"1.500\">"
+        ));
+        assert!(xml.contains("<property name=\"prompt\" value=\"Update the config&#10;then exit\"/>"));
+        assert!(xml.contains("<failure message=\"called 2 times, expected &lt;1\"/>"));
+        assert_eq!(xml.matches("<testcase ").count(), 3);
+    }
+
+    #[test]
+    fn test_junit_empty() {
+        let xml = to_junit_xml(&[]);
+        assert_well_formed(&xml);
+        assert!(xml.contains("tests=\"0\" failures=\"0\""));
+    }
+}
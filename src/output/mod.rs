@@ -0,0 +1,1060 @@
+//! Output formatting for test results, tool calls, and agent responses.
+//!
+//! Provides configurable output display for the test harness,
+//! with support for showing tool calls and responses either always,
+//! on failure, or never.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use aptitude::output::{OutputConfig, OutputFormatter, OutputMode};
+//!
+//! let config = OutputConfig::new()
+//!     .tool_calls(OutputMode::Always)
+//!     .response(OutputMode::OnFailure);
+//!
+//! let formatter = OutputFormatter::new(config);
+//! formatter.print_tool_calls(&tool_calls, test_passed);
+//! ```
+//!
+//! Machine-readable reports for CI live in submodules:
+//! - [`junit`] - JUnit XML documents
+//! - [`json_report`] - JSON run results, selected with [`ResultFormat::Json`]
+//! - [`tap`] - TAP version 13 streams, selected with [`ResultFormat::Tap`]
+//!
+//! [`ResultFormat::Summary`] prints one line per test via
+//! [`OutputFormatter::format_summary`].
+
+#[cfg(feature = "yaml")]
+pub mod json_report;
+#[cfg(feature = "yaml")]
+pub mod junit;
+#[cfg(feature = "yaml")]
+mod report;
+#[cfg(feature = "yaml")]
+pub mod tap;
+
+use clap::ValueEnum;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use crate::parser::ToolCall;
+use serde_json::Value;
+
+#[cfg(feature = "yaml")]
+pub use report::TestReport;
+
+// ANSI color codes
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+// =========================================================================
+// Configuration
+// =========================================================================
+
+/// When to display output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Always show output regardless of test result.
+    Always,
+    /// Only show output when tests fail (default).
+    #[default]
+    OnFailure,
+    /// Never show output.
+    Never,
+}
+
+/// How run results are printed, independent of [`OutputMode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ResultFormat {
+    /// ANSI-colored output for terminals (default).
+    #[default]
+    Human,
+    /// A single JSON document for programmatic consumption.
+    Json,
+    /// Test Anything Protocol (version 13), for `prove` and other TAP consumers.
+    Tap,
+    /// One line per test plus totals, printed once all tests finish.
+    Summary,
+}
+
+impl ResultFormat {
+    /// Get the string name for this format.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResultFormat::Human => "human",
+            ResultFormat::Json => "json",
+            ResultFormat::Tap => "tap",
+            ResultFormat::Summary => "summary",
+        }
+    }
+}
+
+/// Whether human output uses ANSI colors, chosen with `--color`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` is not set (default).
+    #[default]
+    Auto,
+    /// Always color, even when piped or with `NO_COLOR` set.
+    Always,
+    /// Never color.
+    Never,
+}
+
+impl ColorChoice {
+    /// Get the string name for this choice.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorChoice::Auto => "auto",
+            ColorChoice::Always => "always",
+            ColorChoice::Never => "never",
+        }
+    }
+
+    /// Whether to color output in this environment.
+    ///
+    /// An explicit `always` or `never` wins over `NO_COLOR`, which wins over
+    /// terminal detection.
+    pub fn enabled(self) -> bool {
+        self.resolve(no_color(), std::io::stdout().is_terminal())
+    }
+
+    /// [`enabled`](Self::enabled), given whether `NO_COLOR` is set and
+    /// whether stdout is a terminal.
+    fn resolve(self, no_color: bool, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => !no_color && is_terminal,
+        }
+    }
+}
+
+/// Whether the `NO_COLOR` convention (<https://no-color.org>) asks for no
+/// colors: the variable is set to a non-empty value.
+fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+/// How much of a run the human output shows.
+///
+/// Ordered from least to most output, so levels can be compared with `>=`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Only results: one line per test, its failing assertions, and the totals.
+    Quiet,
+    /// Per-test banners, plus tool calls and responses of failing tests (default).
+    #[default]
+    Normal,
+    /// Tool calls of every test, and each assertion; responses only on failure.
+    Verbose,
+    /// Everything, including responses of passing tests, full session log
+    /// paths and the agent's raw stderr.
+    Debug,
+}
+
+impl Verbosity {
+    /// The level for a command line with `verbose` repeated `-v` flags and
+    /// an optional `--quiet`: none is [`Normal`](Self::Normal), `-v` is
+    /// [`Verbose`](Self::Verbose), and `-vv` or more is [`Debug`](Self::Debug).
+    pub fn from_flags(verbose: u8, quiet: bool) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Debug,
+        }
+    }
+
+    /// The [`OutputConfig`] used at this level.
+    pub fn output_config(self) -> OutputConfig {
+        match self {
+            Verbosity::Quiet => OutputConfig::quiet(),
+            Verbosity::Normal => OutputConfig::new(),
+            Verbosity::Verbose => OutputConfig::new().tool_calls(OutputMode::Always),
+            Verbosity::Debug => OutputConfig::verbose(),
+        }
+    }
+}
+
+/// Configuration for output display.
+///
+/// Use the builder pattern to configure what gets displayed:
+///
+/// ```rust,ignore
+/// use aptitude::output::{OutputConfig, OutputMode};
+///
+/// let config = OutputConfig::new()
+///     .tool_calls(OutputMode::Always)
+///     .response(OutputMode::OnFailure)
+///     .truncate_at(80);
+/// ```
+#[derive(Debug, Clone)]
+pub struct OutputConfig {
+    /// When to show tool calls made during execution.
+    pub tool_calls: OutputMode,
+    /// When to show Claude's response output.
+    pub response: OutputMode,
+    /// Maximum characters before truncating parameter values.
+    pub truncate_at: usize,
+    /// Whether to use ANSI colors in output.
+    pub colors_enabled: bool,
+    /// Whether to emit OSC 8 terminal hyperlinks.
+    pub hyperlinks_enabled: bool,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            tool_calls: OutputMode::OnFailure,
+            response: OutputMode::OnFailure,
+            truncate_at: 1000,
+            colors_enabled: ColorChoice::Auto.enabled(),
+            hyperlinks_enabled: detect_hyperlinks(),
+        }
+    }
+}
+
+/// Detect whether the terminal supports OSC 8 hyperlinks.
+///
+/// Requires stdout to be a TTY and disables inside tmux/screen where
+/// passthrough is unreliable. `NO_COLOR` turns them off along with colors.
+fn detect_hyperlinks() -> bool {
+    if !std::io::stdout().is_terminal() || no_color() {
+        return false;
+    }
+    // tmux/screen may not pass through OSC 8 reliably
+    if std::env::var_os("TMUX").is_some() || std::env::var_os("STY").is_some() {
+        return false;
+    }
+    true
+}
+
+impl OutputConfig {
+    /// Create a new output configuration with defaults.
+    ///
+    /// Default: `OnFailure` for both tool calls and response,
+    /// 1000 character truncation, colors auto-detected from TTY.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configure when to show tool calls.
+    pub fn tool_calls(mut self, mode: OutputMode) -> Self {
+        self.tool_calls = mode;
+        self
+    }
+
+    /// Configure when to show Claude's response.
+    pub fn response(mut self, mode: OutputMode) -> Self {
+        self.response = mode;
+        self
+    }
+
+    /// Set the maximum characters before truncating parameter values.
+    pub fn truncate_at(mut self, chars: usize) -> Self {
+        self.truncate_at = chars;
+        self
+    }
+
+    /// Enable or disable ANSI colors.
+    pub fn colors(mut self, enabled: bool) -> Self {
+        self.colors_enabled = enabled;
+        self
+    }
+
+    /// Enable or disable ANSI colors from a [`ColorChoice`].
+    ///
+    /// Turning colors off also turns off hyperlinks, so `never` output has no
+    /// escape sequences at all.
+    pub fn color(mut self, choice: ColorChoice) -> Self {
+        self.colors_enabled = choice.enabled();
+        self.hyperlinks_enabled &= self.colors_enabled;
+        self
+    }
+
+    /// Enable or disable OSC 8 terminal hyperlinks.
+    pub fn hyperlinks(mut self, enabled: bool) -> Self {
+        self.hyperlinks_enabled = enabled;
+        self
+    }
+
+    /// Create a verbose configuration that always shows everything.
+    pub fn verbose() -> Self {
+        Self {
+            tool_calls: OutputMode::Always,
+            response: OutputMode::Always,
+            ..Self::default()
+        }
+    }
+
+    /// Create a quiet configuration that never shows output.
+    pub fn quiet() -> Self {
+        Self {
+            tool_calls: OutputMode::Never,
+            response: OutputMode::Never,
+            ..Self::default()
+        }
+    }
+}
+
+// =========================================================================
+// Formatter
+// =========================================================================
+
+/// Formatter for test output including tool calls and agent responses.
+pub struct OutputFormatter {
+    config: OutputConfig,
+    workdir: Option<PathBuf>,
+}
+
+impl OutputFormatter {
+    /// Create a new formatter with the given configuration.
+    pub fn new(config: OutputConfig) -> Self {
+        Self { config, workdir: None }
+    }
+
+    /// Create a formatter with default configuration.
+    pub fn with_defaults() -> Self {
+        Self::new(OutputConfig::new())
+    }
+
+    /// Whether this formatter uses ANSI colors.
+    pub fn colors_enabled(&self) -> bool {
+        self.config.colors_enabled
+    }
+
+    /// Set the working directory for making paths relative in output.
+    pub fn with_workdir(mut self, workdir: Option<PathBuf>) -> Self {
+        self.workdir = workdir;
+        self
+    }
+
+    /// Check if tool calls should be shown given the test result.
+    pub fn should_show_tool_calls(&self, test_passed: bool) -> bool {
+        match self.config.tool_calls {
+            OutputMode::Always => true,
+            OutputMode::OnFailure => !test_passed,
+            OutputMode::Never => false,
+        }
+    }
+
+    /// Check if response should be shown given the test result.
+    pub fn should_show_response(&self, test_passed: bool) -> bool {
+        match self.config.response {
+            OutputMode::Always => true,
+            OutputMode::OnFailure => !test_passed,
+            OutputMode::Never => false,
+        }
+    }
+
+    /// Format a parameter value, showing the primary parameter.
+    pub fn format_params(&self, params: &Value) -> String {
+        if !params.is_object() {
+            return params.to_string();
+        }
+        match primary_param(params) {
+            Some(Value::String(s)) => self.truncate(&self.make_relative(s)),
+            Some(other) => self.truncate(&other.to_string()),
+            None => String::new(),
+        }
+    }
+
+    /// Strip the working directory prefix from a path string, if applicable.
+    fn make_relative(&self, s: &str) -> String {
+        match &self.workdir {
+            Some(workdir) => make_relative(s, workdir),
+            None => s.to_string(),
+        }
+    }
+
+    /// Format a single tool call for display.
+    pub fn format_tool_call(&self, call: &ToolCall) -> String {
+        let params_str = self.format_params(&call.params);
+        let timestamp = extract_time(&call.timestamp);
+
+        if self.config.colors_enabled {
+            format!("  [{timestamp}] {CYAN}{}{RESET} {params_str}", call.name)
+        } else {
+            format!("  [{timestamp}] {} {params_str}", call.name)
+        }
+    }
+
+    /// Format a single tool call as a compact JSON object on one line:
+    /// `{"name":…,"params":…,"timestamp":…}`.
+    ///
+    /// Used for NDJSON output (`aptitude log --format json`), one call per
+    /// line. Params are written as recorded, without truncation or colors.
+    pub fn format_tool_call_json(&self, call: &ToolCall) -> String {
+        serde_json::json!({
+            "name": call.name,
+            "params": call.params,
+            "timestamp": call.timestamp,
+        })
+        .to_string()
+    }
+
+    /// Print tool calls if the output mode allows it.
+    pub fn print_tool_calls(&self, calls: &[ToolCall], test_passed: bool) {
+        if !self.should_show_tool_calls(test_passed) {
+            return;
+        }
+
+        println!();
+        if self.config.colors_enabled {
+            println!("{}Tool calls made during execution:{}", YELLOW, RESET);
+        } else {
+            println!("Tool calls made during execution:");
+        }
+
+        if calls.is_empty() {
+            println!("  (no tool calls)");
+        } else {
+            for call in calls {
+                println!("{}", self.format_tool_call(call));
+            }
+        }
+    }
+
+    /// Print Claude's response if the output mode allows it.
+    pub fn print_response(&self, response: Option<&str>, test_passed: bool) {
+        if !self.should_show_response(test_passed) {
+            return;
+        }
+
+        if let Some(stdout) = response {
+            if !stdout.is_empty() {
+                println!();
+                if self.config.colors_enabled {
+                    println!("{}Claude's response:{}", YELLOW, RESET);
+                } else {
+                    println!("Claude's response:");
+                }
+                for line in stdout.lines() {
+                    println!("  {}", line);
+                }
+            }
+        }
+    }
+
+    /// Format a session path for display.
+    ///
+    /// Shows only the file stem (typically a UUID) instead of the full path.
+    /// When the terminal supports OSC 8 hyperlinks, wraps the stem in a
+    /// clickable `file://` link. In verbose mode, appends the full path
+    /// on a second line.
+    pub fn format_session_path(&self, path: &Path, verbose: bool) -> String {
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown");
+
+        let display = if self.config.hyperlinks_enabled {
+            let uri = path_to_file_uri(path);
+            format!("\x1b]8;;{uri}\x1b\\{stem}\x1b]8;;\x1b\\")
+        } else {
+            stem.to_string()
+        };
+
+        if verbose {
+            format!("{display}\n    {}", path.display())
+        } else {
+            display
+        }
+    }
+
+    /// Format a progress line for a directory run, e.g. `[3/20] running foo.aptitude.yaml`.
+    ///
+    /// `current` is 1-based. Only the file name is shown.
+    pub fn format_progress(&self, current: usize, total: usize, path: &Path) -> String {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_else(|| path.to_string_lossy());
+
+        if self.config.colors_enabled {
+            format!("{DIM}[{current}/{total}]{RESET} running {name}")
+        } else {
+            format!("[{current}/{total}] running {name}")
+        }
+    }
+
+    /// Format the banner printed before a test runs: its name, prompt, agent
+    /// and model, one per line.
+    ///
+    /// Empty at [`Verbosity::Quiet`], where only results are printed.
+    pub fn format_preamble(
+        &self,
+        name: &str,
+        prompt: &str,
+        agent: &str,
+        model: Option<&str>,
+        verbosity: Verbosity,
+    ) -> String {
+        if verbosity == Verbosity::Quiet {
+            return String::new();
+        }
+        let mut out = format!("Running: \"{}\"\nPrompt: \"{}\"\nAgent: {}\n", name, prompt, agent);
+        if let Some(model) = model {
+            out.push_str(&format!("Model: {}\n", model));
+        }
+        out
+    }
+
+    /// Format one test's results as a single line, e.g. `✓ reads config (3 assertions)`
+    /// or `✗ edits env (1/3 failed)`, followed by the assertions shown at `verbosity`.
+    ///
+    /// [`Verbosity::Normal`] lists none, [`Verbosity::Quiet`] lists failing ones
+    /// with their reasons, and [`Verbosity::Verbose`] and above list all of them.
+    #[cfg(feature = "yaml")]
+    pub fn format_test_result(&self, report: &TestReport, verbosity: Verbosity) -> String {
+        use crate::yaml::TestResult;
+
+        let (pass_mark, fail_mark) = if self.config.colors_enabled {
+            (format!("{GREEN}✓{RESET}"), format!("{RED}✗{RESET}"))
+        } else {
+            ("✓".to_string(), "✗".to_string())
+        };
+
+        let total = report.assertions.len();
+        let mut out = if report.is_pass() {
+            let noun = if total == 1 { "assertion" } else { "assertions" };
+            format!("{} {} ({} {})\n", pass_mark, report.name, total, noun)
+        } else {
+            format!("{} {} ({}/{} failed)\n", fail_mark, report.name, report.failed, total)
+        };
+
+        for (description, result) in &report.assertions {
+            match result {
+                TestResult::Pass if verbosity >= Verbosity::Verbose => {
+                    out.push_str(&format!("    {} {}\n", pass_mark, description));
+                }
+                TestResult::Fail { reason } if verbosity != Verbosity::Normal => {
+                    out.push_str(&format!("    {} {}\n", fail_mark, description));
+                    out.push_str(&format!("      └─ {}\n", reason));
+                }
+                _ => {}
+            }
+        }
+        out
+    }
+
+    /// Format condensed run results: one line per test, then the totals.
+    ///
+    /// Passing tests show their assertion count, e.g. `✓ reads config (3 assertions)`;
+    /// failing ones show how many failed, e.g. `✗ edits env (1/3 failed)`. With
+    /// `verbose`, each test's assertions are listed beneath it.
+    #[cfg(feature = "yaml")]
+    pub fn format_summary(&self, reports: &[TestReport], verbose: bool) -> String {
+        let verbosity = if verbose { Verbosity::Verbose } else { Verbosity::Normal };
+        let mut out = String::new();
+        for report in reports {
+            out.push_str(&self.format_test_result(report, verbosity));
+        }
+
+        let passed = reports.iter().filter(|r| r.is_pass()).count();
+        out.push_str(&format!(
+            "\nTotal: {} passed, {} failed\n",
+            passed,
+            reports.len() - passed
+        ));
+        out
+    }
+
+    /// Truncate a string to the configured maximum length.
+    /// Handles multi-byte UTF-8 characters safely.
+    fn truncate(&self, s: &str) -> String {
+        let max = self.config.truncate_at;
+        let char_count = s.chars().count();
+
+        if char_count <= max {
+            s.to_string()
+        } else {
+            let truncated: String = s.chars().take(max.saturating_sub(3)).collect();
+            format!("{}...", truncated)
+        }
+    }
+}
+
+/// The parameter that best identifies a tool call: `command`, `file_path`,
+/// `pattern` or `url`, whichever comes first, else the first parameter.
+///
+/// Returns `None` when `params` isn't an object or is empty.
+pub fn primary_param(params: &Value) -> Option<&Value> {
+    let obj = params.as_object()?;
+    obj.get("command")
+        .or_else(|| obj.get("file_path"))
+        .or_else(|| obj.get("pattern"))
+        .or_else(|| obj.get("url"))
+        .or_else(|| obj.values().next())
+}
+
+/// Strip the `workdir` prefix from a path string.
+///
+/// Returns `"."` for the working directory itself, and `s` unchanged if it
+/// is not inside `workdir` (including sibling paths like `/proj-old` for
+/// `/proj`).
+pub(crate) fn make_relative(s: &str, workdir: &Path) -> String {
+    let prefix = workdir.to_string_lossy();
+    let prefix = prefix.trim_end_matches('/');
+    match s.strip_prefix(prefix) {
+        Some("") | Some("/") => ".".to_string(),
+        Some(rest) if rest.starts_with('/') => rest[1..].to_string(),
+        _ => s.to_string(),
+    }
+}
+
+/// Convert a filesystem path to a `file://` URI.
+///
+/// Resolves the path to an absolute path via `canonicalize` (falling back
+/// to the original if the file doesn't exist yet) and percent-encodes
+/// spaces per RFC 3986.
+fn path_to_file_uri(path: &Path) -> String {
+    let absolute = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let path_str = absolute.to_string_lossy().replace(' ', "%20");
+    format!("file://{path_str}")
+}
+
+/// Extract HH:MM:SS from an RFC3339 timestamp string.
+/// Falls back to "??:??:??" if the string is too short or doesn't contain a time.
+fn extract_time(ts: &str) -> &str {
+    // Handle RFC3339 formats:
+    // - "2024-01-19T12:00:00Z" — time starts at index 11
+    // - "2026-02-23T21:20:31.146289-08:00" — time starts at index 11
+    // Slices are checked so malformed (e.g. non-ASCII) timestamps can't panic
+    if let Some(t_pos) = ts.find('T') {
+        if let Some(time) = ts.get(t_pos + 1..t_pos + 9) {
+            return time;
+        }
+    }
+    // Fallback for old format without T
+    ts.get(11..19).unwrap_or("??:??:??")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_default_config() {
+        let config = OutputConfig::new();
+        assert_eq!(config.tool_calls, OutputMode::OnFailure);
+        assert_eq!(config.response, OutputMode::OnFailure);
+        assert_eq!(config.truncate_at, 1000);
+    }
+
+    #[test]
+    fn test_verbose_config() {
+        let config = OutputConfig::verbose();
+        assert_eq!(config.tool_calls, OutputMode::Always);
+        assert_eq!(config.response, OutputMode::Always);
+    }
+
+    #[test]
+    fn test_quiet_config() {
+        let config = OutputConfig::quiet();
+        assert_eq!(config.tool_calls, OutputMode::Never);
+        assert_eq!(config.response, OutputMode::Never);
+    }
+
+    #[test]
+    fn test_color_choice_precedence() {
+        // Terminal detection decides when nothing else does
+        assert!(ColorChoice::Auto.resolve(false, true));
+        assert!(!ColorChoice::Auto.resolve(false, false));
+        // NO_COLOR wins over a terminal
+        assert!(!ColorChoice::Auto.resolve(true, true));
+        // An explicit flag wins over NO_COLOR and terminal detection
+        assert!(ColorChoice::Always.resolve(true, false));
+        assert!(!ColorChoice::Never.resolve(false, true));
+    }
+
+    #[test]
+    fn test_color_choice_from_str() {
+        assert_eq!(ColorChoice::from_str("auto", true), Ok(ColorChoice::Auto));
+        assert_eq!(ColorChoice::from_str("ALWAYS", true), Ok(ColorChoice::Always));
+        assert_eq!(ColorChoice::from_str("never", true), Ok(ColorChoice::Never));
+        assert!(ColorChoice::from_str("sometimes", true).is_err());
+        assert_eq!(ColorChoice::default().as_str(), "auto");
+    }
+
+    #[test]
+    fn test_color_never_disables_hyperlinks() {
+        let config = OutputConfig::new().hyperlinks(true).color(ColorChoice::Never);
+        assert!(!config.colors_enabled);
+        assert!(!config.hyperlinks_enabled);
+
+        let config = OutputConfig::new().color(ColorChoice::Always);
+        assert!(config.colors_enabled);
+    }
+
+    #[test]
+    fn test_builder_chain() {
+        let config = OutputConfig::new()
+            .tool_calls(OutputMode::Always)
+            .response(OutputMode::Never)
+            .truncate_at(100)
+            .colors(false);
+
+        assert_eq!(config.tool_calls, OutputMode::Always);
+        assert_eq!(config.response, OutputMode::Never);
+        assert_eq!(config.truncate_at, 100);
+        assert!(!config.colors_enabled);
+    }
+
+    #[test]
+    fn test_truncate_short_string() {
+        let formatter = OutputFormatter::new(OutputConfig::new().truncate_at(60));
+        assert_eq!(formatter.truncate("hello"), "hello");
+    }
+
+    #[test]
+    fn test_truncate_long_string() {
+        let formatter = OutputFormatter::new(OutputConfig::new().truncate_at(10));
+        assert_eq!(formatter.truncate("hello world!"), "hello w...");
+    }
+
+    #[test]
+    fn test_truncate_unicode() {
+        let formatter = OutputFormatter::new(OutputConfig::new().truncate_at(6));
+        let result = formatter.truncate("日本語ですよね");
+        assert!(result.ends_with("..."));
+        assert_eq!(result.chars().count(), 6);
+        assert_eq!(result, "日本語...");
+    }
+
+    #[test]
+    fn test_format_params_object() {
+        let formatter = OutputFormatter::new(OutputConfig::new());
+        let params = json!({"file_path": "/tmp/test.txt", "content": "hello"});
+        let formatted = formatter.format_params(&params);
+        assert_eq!(formatted, "/tmp/test.txt");
+    }
+
+    #[test]
+    fn test_format_tool_call_json_round_trips() {
+        let formatter = OutputFormatter::new(OutputConfig::new().truncate_at(5).colors(true));
+        let call = ToolCall {
+            name: "Read".to_string(),
+            params: json!({"file_path": "/tmp/a long path.txt", "limit": 10}),
+            timestamp: "2024-01-19T12:00:00Z".to_string(),
+            result: Some(json!("file contents")),
+        };
+
+        let line = formatter.format_tool_call_json(&call);
+        assert!(!line.contains('\n'));
+
+        let parsed: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(
+            parsed,
+            json!({
+                "name": "Read",
+                "params": {"file_path": "/tmp/a long path.txt", "limit": 10},
+                "timestamp": "2024-01-19T12:00:00Z",
+            })
+        );
+    }
+
+    #[test]
+    fn test_should_show_always() {
+        let config = OutputConfig::new().tool_calls(OutputMode::Always);
+        let formatter = OutputFormatter::new(config);
+        assert!(formatter.should_show_tool_calls(true));
+        assert!(formatter.should_show_tool_calls(false));
+    }
+
+    #[test]
+    fn test_should_show_on_failure() {
+        let config = OutputConfig::new().tool_calls(OutputMode::OnFailure);
+        let formatter = OutputFormatter::new(config);
+        assert!(!formatter.should_show_tool_calls(true));
+        assert!(formatter.should_show_tool_calls(false));
+    }
+
+    #[test]
+    fn test_should_show_never() {
+        let config = OutputConfig::new().tool_calls(OutputMode::Never);
+        let formatter = OutputFormatter::new(config);
+        assert!(!formatter.should_show_tool_calls(true));
+        assert!(!formatter.should_show_tool_calls(false));
+    }
+
+    #[test]
+    fn test_extract_time() {
+        assert_eq!(extract_time("2024-01-19T12:00:00Z"), "12:00:00");
+        assert_eq!(extract_time("2026-02-23T21:20:31.146289-08:00"), "21:20:31");
+        assert_eq!(extract_time("2024-01-19 12:00:00"), "12:00:00");
+    }
+
+    #[test]
+    fn test_extract_time_malformed() {
+        assert_eq!(extract_time(""), "??:??:??");
+        assert_eq!(extract_time("T12"), "??:??:??");
+        assert_eq!(extract_time("not a timestamp"), "??:??:??");
+        // Multi-byte characters across the slice boundaries
+        assert_eq!(extract_time("2024-01-19T12:00:0é"), "??:??:??");
+        assert_eq!(extract_time("2024-01-19  ééééééééééé"), "??:??:??");
+    }
+
+    #[test]
+    fn test_make_relative_strips_workdir() {
+        let formatter = OutputFormatter::new(OutputConfig::new())
+            .with_workdir(Some(PathBuf::from("/home/user/project")));
+        assert_eq!(
+            formatter.make_relative("/home/user/project/src/main.rs"),
+            "src/main.rs"
+        );
+    }
+
+    #[test]
+    fn test_make_relative_no_workdir() {
+        let formatter = OutputFormatter::new(OutputConfig::new());
+        assert_eq!(
+            formatter.make_relative("/home/user/project/src/main.rs"),
+            "/home/user/project/src/main.rs"
+        );
+    }
+
+    #[test]
+    fn test_make_relative_different_prefix() {
+        let formatter = OutputFormatter::new(OutputConfig::new())
+            .with_workdir(Some(PathBuf::from("/home/user/other")));
+        assert_eq!(
+            formatter.make_relative("/home/user/project/src/main.rs"),
+            "/home/user/project/src/main.rs"
+        );
+    }
+
+    #[test]
+    fn test_make_relative_sibling_prefix() {
+        let workdir = Path::new("/home/user/project");
+        assert_eq!(
+            make_relative("/home/user/project-old/src/main.rs", workdir),
+            "/home/user/project-old/src/main.rs"
+        );
+        assert_eq!(make_relative("/home/user/project", workdir), ".");
+    }
+
+    #[test]
+    fn test_format_params_with_workdir() {
+        let formatter = OutputFormatter::new(OutputConfig::new())
+            .with_workdir(Some(PathBuf::from("/home/user/project")));
+        let params = json!({"file_path": "/home/user/project/src/main.rs"});
+        assert_eq!(formatter.format_params(&params), "src/main.rs");
+    }
+
+    #[test]
+    fn test_format_progress_plain() {
+        let formatter = OutputFormatter::new(OutputConfig::new().colors(false));
+        let line = formatter.format_progress(3, 20, Path::new("/suite/security.aptitude.yaml"));
+        assert_eq!(line, "[3/20] running security.aptitude.yaml");
+    }
+
+    #[test]
+    fn test_format_progress_colored() {
+        let formatter = OutputFormatter::new(OutputConfig::new().colors(true));
+        let line = formatter.format_progress(1, 2, Path::new("a.aptitude.yaml"));
+        assert!(line.starts_with(DIM));
+        assert!(line.ends_with("running a.aptitude.yaml"));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_format_summary() {
+        use crate::yaml::TestResult;
+
+        let report = |name: &str, results: Vec<(&str, TestResult)>| TestReport::new(
+            name.to_string(),
+            String::new(),
+            "claude".to_string(),
+            results
+                .into_iter()
+                .map(|(d, r)| (d.to_string(), r))
+                .collect(),
+            std::time::Duration::ZERO,
+        );
+        let fail = || TestResult::Fail {
+            reason: "was never called".to_string(),
+        };
+        let reports = vec![
+            report(
+                "reads config",
+                vec![
+                    ("Read called", TestResult::Pass),
+                    ("Write not called", TestResult::Pass),
+                    ("Bash not called", TestResult::Pass),
+                ],
+            ),
+            report(
+                "edits env",
+                vec![("Read called", TestResult::Pass), ("Edit called", fail())],
+            ),
+            report("single", vec![("Glob called", TestResult::Pass)]),
+        ];
+        let formatter = OutputFormatter::new(OutputConfig::new().colors(false));
+
+        assert_eq!(
+            formatter.format_summary(&reports, false),
+            "✓ reads config (3 assertions)\n\
+             ✗ edits env (1/2 failed)\n\
+             ✓ single (1 assertion)\n\
+             \n\
+             Total: 2 passed, 1 failed\n"
+        );
+
+        let verbose = formatter.format_summary(&reports[1..2], true);
+        assert_eq!(
+            verbose,
+            "✗ edits env (1/2 failed)\n    ✓ Read called\n    ✗ Edit called\n      └─ was never called\n\nTotal: 0 passed, 1 failed\n"
+        );
+    }
+
+    #[test]
+    fn test_format_preamble_by_verbosity() {
+        let formatter = OutputFormatter::new(OutputConfig::new().colors(false));
+
+        assert_eq!(
+            formatter.format_preamble("reads config", "Read config.json", "claude", None, Verbosity::Normal),
+            "Running: \"reads config\"\nPrompt: \"Read config.json\"\nAgent: claude\n"
+        );
+        let verbose = formatter.format_preamble("t", "p", "gemini", Some("flash"), Verbosity::Verbose);
+        assert!(verbose.ends_with("Agent: gemini\nModel: flash\n"), "{}", verbose);
+        assert_eq!(
+            formatter.format_preamble("t", "p", "claude", Some("opus"), Verbosity::Quiet),
+            ""
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_format_test_result_by_verbosity() {
+        use crate::yaml::TestResult;
+
+        let report = TestReport::new(
+            "edits env".to_string(),
+            String::new(),
+            "claude".to_string(),
+            vec![
+                ("Read called".to_string(), TestResult::Pass),
+                (
+                    "Edit called".to_string(),
+                    TestResult::Fail {
+                        reason: "was never called".to_string(),
+                    },
+                ),
+            ],
+            std::time::Duration::ZERO,
+        );
+        let formatter = OutputFormatter::new(OutputConfig::new().colors(false));
+
+        assert_eq!(
+            formatter.format_test_result(&report, Verbosity::Normal),
+            "✗ edits env (1/2 failed)\n"
+        );
+        // Quiet keeps only what failed and why
+        assert_eq!(
+            formatter.format_test_result(&report, Verbosity::Quiet),
+            "✗ edits env (1/2 failed)\n    ✗ Edit called\n      └─ was never called\n"
+        );
+        assert_eq!(
+            formatter.format_test_result(&report, Verbosity::Verbose),
+            "✗ edits env (1/2 failed)\n    ✓ Read called\n    ✗ Edit called\n      └─ was never called\n"
+        );
+    }
+
+    #[test]
+    fn test_verbosity_from_flags() {
+        assert_eq!(Verbosity::from_flags(0, false), Verbosity::Normal);
+        assert_eq!(Verbosity::from_flags(1, false), Verbosity::Verbose);
+        assert_eq!(Verbosity::from_flags(2, false), Verbosity::Debug);
+        assert_eq!(Verbosity::from_flags(5, false), Verbosity::Debug);
+        assert_eq!(Verbosity::from_flags(0, true), Verbosity::Quiet);
+        assert!(Verbosity::Quiet < Verbosity::Normal && Verbosity::Verbose < Verbosity::Debug);
+    }
+
+    #[test]
+    fn test_verbosity_output_config() {
+        assert_eq!(Verbosity::default(), Verbosity::Normal);
+
+        let quiet = Verbosity::Quiet.output_config();
+        assert_eq!((quiet.tool_calls, quiet.response), (OutputMode::Never, OutputMode::Never));
+        let normal = Verbosity::Normal.output_config();
+        assert_eq!((normal.tool_calls, normal.response), (OutputMode::OnFailure, OutputMode::OnFailure));
+        // The middle ground: every tool call, but responses only when a test fails
+        let verbose = Verbosity::Verbose.output_config();
+        assert_eq!((verbose.tool_calls, verbose.response), (OutputMode::Always, OutputMode::OnFailure));
+        let debug = Verbosity::Debug.output_config();
+        assert_eq!((debug.tool_calls, debug.response), (OutputMode::Always, OutputMode::Always));
+    }
+
+    // ── Session path / hyperlink tests ──────────────────────────────
+
+    #[test]
+    fn test_file_uri_construction() {
+        let uri = path_to_file_uri(Path::new("/tmp/sessions/abc-123.jsonl"));
+        assert_eq!(uri, "file:///tmp/sessions/abc-123.jsonl");
+    }
+
+    #[test]
+    fn test_file_uri_spaces_are_encoded() {
+        let uri = path_to_file_uri(Path::new("/tmp/my sessions/abc.jsonl"));
+        assert_eq!(uri, "file:///tmp/my%20sessions/abc.jsonl");
+    }
+
+    #[test]
+    fn test_format_session_path_plain() {
+        let config = OutputConfig::new().hyperlinks(false);
+        let formatter = OutputFormatter::new(config);
+        let path = Path::new("/home/user/.claude/projects/test/fdbb606e-98de-49f3-896c-0aa1b4e57af1.jsonl");
+        let result = formatter.format_session_path(path, false);
+        assert_eq!(result, "fdbb606e-98de-49f3-896c-0aa1b4e57af1");
+    }
+
+    #[test]
+    fn test_format_session_path_non_uuid_stem() {
+        let config = OutputConfig::new().hyperlinks(false);
+        let formatter = OutputFormatter::new(config);
+        let path = Path::new("/tmp/my-session.jsonl");
+        let result = formatter.format_session_path(path, false);
+        assert_eq!(result, "my-session");
+    }
+
+    #[test]
+    fn test_format_session_path_with_hyperlink() {
+        let config = OutputConfig::new().hyperlinks(true);
+        let formatter = OutputFormatter::new(config);
+        let path = Path::new("/tmp/abc-123.jsonl");
+        let result = formatter.format_session_path(path, false);
+        assert!(result.starts_with("\x1b]8;;file:///"));
+        assert!(result.contains("abc-123.jsonl"));
+        assert!(result.contains("abc-123"));
+        assert!(result.ends_with("\x1b]8;;\x1b\\"));
+    }
+
+    #[test]
+    fn test_format_session_path_hyperlink_disabled_no_escapes() {
+        let config = OutputConfig::new().hyperlinks(false);
+        let formatter = OutputFormatter::new(config);
+        let path = Path::new("/tmp/abc-123.jsonl");
+        let result = formatter.format_session_path(path, false);
+        assert!(!result.contains("\x1b"));
+        assert_eq!(result, "abc-123");
+    }
+
+    #[test]
+    fn test_format_session_path_verbose_shows_full_path() {
+        let config = OutputConfig::new().hyperlinks(false);
+        let formatter = OutputFormatter::new(config);
+        let path = Path::new("/home/user/.claude/projects/test/abc-123.jsonl");
+        let result = formatter.format_session_path(path, true);
+        assert!(result.contains("abc-123"));
+        assert!(result.contains("/home/user/.claude/projects/test/abc-123.jsonl"));
+        assert!(result.contains('\n'));
+    }
+}
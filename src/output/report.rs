@@ -0,0 +1,52 @@
+//! Collected results of a single YAML test file, shared by the report writers.
+
+use std::time::Duration;
+
+use crate::yaml::TestResult;
+
+/// Results of running one test file.
+#[derive(Debug, Clone)]
+pub struct TestReport {
+    /// Test name from the YAML file.
+    pub name: String,
+    /// Prompt sent to the agent.
+    pub prompt: String,
+    /// Agent that executed the prompt.
+    pub agent: String,
+    /// One `(description, result)` entry per assertion.
+    pub assertions: Vec<(String, TestResult)>,
+    /// Number of passing assertions.
+    pub passed: usize,
+    /// Number of failing assertions.
+    pub failed: usize,
+    /// Time taken to produce the results.
+    pub duration: Duration,
+}
+
+impl TestReport {
+    /// Roll up `assertions`, counting how many passed and failed.
+    pub fn new(
+        name: String,
+        prompt: String,
+        agent: String,
+        assertions: Vec<(String, TestResult)>,
+        duration: Duration,
+    ) -> Self {
+        let passed = assertions.iter().filter(|(_, r)| r.is_pass()).count();
+        let failed = assertions.iter().filter(|(_, r)| r.is_fail()).count();
+        Self {
+            name,
+            prompt,
+            agent,
+            assertions,
+            passed,
+            failed,
+            duration,
+        }
+    }
+
+    /// Whether every assertion passed.
+    pub fn is_pass(&self) -> bool {
+        self.failed == 0
+    }
+}
@@ -0,0 +1,84 @@
+//! TAP (Test Anything Protocol) version 13 reporter.
+//!
+//! Each assertion becomes one test point. Failure reasons are attached as
+//! YAML diagnostic blocks so consumers like `prove` can display them.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use aptitude::output::tap;
+//!
+//! print!("{}", tap::to_tap(&results));
+//! ```
+
+use std::fmt::Write as _;
+
+use crate::yaml::TestResult;
+
+/// Render assertion results as a TAP version 13 document.
+///
+/// The plan line `1..N` always matches the number of results.
+pub fn to_tap(results: &[(String, TestResult)]) -> String {
+    let mut tap = String::from("TAP version 13\n");
+    let _ = writeln!(tap, "1..{}", results.len());
+
+    for (i, (description, result)) in results.iter().enumerate() {
+        // '#' starts a directive in TAP, and test points must be single lines
+        let description = description.replace('#', "\\#").replace('\n', " ");
+        match result {
+            TestResult::Pass => {
+                let _ = writeln!(tap, "ok {} - {}", i + 1, description);
+            }
+            TestResult::Fail { reason } => {
+                let _ = writeln!(tap, "not ok {} - {}", i + 1, description);
+                tap.push_str("  ---\n");
+                // A JSON string is a valid YAML scalar and escapes newlines and quotes
+                let _ = writeln!(tap, "  message: {}", serde_json::Value::from(reason.as_str()));
+                tap.push_str("  ...\n");
+            }
+        }
+    }
+
+    tap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tap_plan_and_diagnostics() {
+        let results = vec![
+            ("Read called".to_string(), TestResult::Pass),
+            (
+                "Write #2 not called".to_string(),
+                TestResult::Fail {
+                    reason: "called 1 time\nwith \"a.txt\"".to_string(),
+                },
+            ),
+            ("Bash called".to_string(), TestResult::Pass),
+        ];
+
+        let tap = to_tap(&results);
+        let lines: Vec<&str> = tap.lines().collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "TAP version 13",
+                "1..3",
+                "ok 1 - Read called",
+                "not ok 2 - Write \\#2 not called",
+                "  ---",
+                r#"  message: "called 1 time\nwith \"a.txt\"""#,
+                "  ...",
+                "ok 3 - Bash called",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tap_empty_plan() {
+        assert_eq!(to_tap(&[]), "TAP version 13\n1..0\n");
+    }
+}
@@ -0,0 +1,238 @@
+//! Ordered parallel execution for running several test files at once.
+//!
+//! Agents find their session data by working directory: Claude snapshots the
+//! project's session directory before and after a run, and Kiro queries its
+//! shared SQLite database by working directory and start time. Two runs in
+//! the same directory at the same time could pick up each other's sessions,
+//! so [`run_ordered`] takes a key (the resolved working directory) and never
+//! runs items with the same key concurrently.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Run `f` over `items` on up to `jobs` threads, returning results in input order.
+///
+/// Items that share a key form a lane and run one after another on the same
+/// thread; distinct lanes run concurrently. With `jobs <= 1` everything runs
+/// sequentially on the calling thread.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let results = run_ordered(&paths, 4, |path| workdir_for(path), |path| run_test(path));
+/// ```
+pub fn run_ordered<T, K, R, KF, F>(items: &[T], jobs: usize, key: KF, f: F) -> Vec<R>
+where
+    T: Sync,
+    K: Eq + Hash,
+    R: Send,
+    KF: Fn(&T) -> K,
+    F: Fn(&T) -> R + Sync,
+{
+    if jobs <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    // Group item indices into lanes, keeping lanes in first-appearance order
+    let mut lanes: Vec<Vec<usize>> = Vec::new();
+    let mut lane_for_key: HashMap<K, usize> = HashMap::new();
+    for (i, item) in items.iter().enumerate() {
+        let lane = *lane_for_key.entry(key(item)).or_insert_with(|| {
+            lanes.push(Vec::new());
+            lanes.len() - 1
+        });
+        lanes[lane].push(i);
+    }
+
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    let next_lane = AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(lanes.len()) {
+            scope.spawn(|| loop {
+                let lane = next_lane.fetch_add(1, Ordering::SeqCst);
+                let Some(indices) = lanes.get(lane) else {
+                    break;
+                };
+                for &i in indices {
+                    let result = f(&items[i]);
+                    results.lock().unwrap()[i] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("every item ran"))
+        .collect()
+}
+
+/// Like [`run_ordered`], but stop starting new items once one fails.
+///
+/// Items that never started come back as `None`. With several jobs, items
+/// already running when the failure happens still finish.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let outcomes = run_ordered_fail_fast(&paths, 1, workdir_for, run_test, |r| r.is_err());
+/// let skipped = outcomes.iter().filter(|o| o.is_none()).count();
+/// ```
+pub fn run_ordered_fail_fast<T, K, R, KF, F, P>(
+    items: &[T],
+    jobs: usize,
+    key: KF,
+    f: F,
+    failed: P,
+) -> Vec<Option<R>>
+where
+    T: Sync,
+    K: Eq + Hash,
+    R: Send,
+    KF: Fn(&T) -> K,
+    F: Fn(&T) -> R + Sync,
+    P: Fn(&R) -> bool + Sync,
+{
+    let stop = AtomicBool::new(false);
+    run_ordered(items, jobs, key, |item| {
+        if stop.load(Ordering::SeqCst) {
+            return None;
+        }
+        let result = f(item);
+        if failed(&result) {
+            stop.store(true, Ordering::SeqCst);
+        }
+        Some(result)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::{AgentHarness, AgentType, ExecutionConfig};
+    use crate::test_util::MockAgent;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Tracks how many closures are running at once and the peak.
+    #[derive(Default)]
+    struct Concurrency {
+        current: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl Concurrency {
+        fn enter(&self) {
+            let now = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+        }
+
+        fn exit(&self) {
+            self.current.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn harness() -> AgentHarness {
+        let mock = MockAgent::new()
+            .with_tool_call("Read", serde_json::json!({"file_path": "/a.txt"}))
+            .with_delay(Duration::from_millis(200));
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(mock));
+        harness
+    }
+
+    fn run(
+        harness: &AgentHarness,
+        tests: &[(String, PathBuf)],
+        jobs: usize,
+    ) -> (Vec<String>, usize) {
+        let concurrency = Concurrency::default();
+        let results = run_ordered(
+            tests,
+            jobs,
+            |(_, workdir)| workdir.clone(),
+            |(name, workdir)| {
+                concurrency.enter();
+                let config = ExecutionConfig::new().with_working_dir(workdir.clone());
+                let output = harness.execute(None, name, config).unwrap();
+                concurrency.exit();
+                format!("{}: {}", name, output.result.tool_calls[0].name)
+            },
+        );
+        (results, concurrency.peak.load(Ordering::SeqCst))
+    }
+
+    #[test]
+    fn test_runs_concurrently_in_order() {
+        let tests: Vec<(String, PathBuf)> = (1..=4)
+            .map(|i| (format!("test {}", i), PathBuf::from(format!("/work/{}", i))))
+            .collect();
+
+        let (results, peak) = run(&harness(), &tests, 4);
+
+        assert_eq!(peak, 4);
+        assert_eq!(
+            results,
+            vec!["test 1: Read", "test 2: Read", "test 3: Read", "test 4: Read"]
+        );
+    }
+
+    #[test]
+    fn test_shared_workdir_runs_sequentially() {
+        let tests: Vec<(String, PathBuf)> = (1..=3)
+            .map(|i| (format!("test {}", i), PathBuf::from("/work/shared")))
+            .collect();
+
+        let (results, peak) = run(&harness(), &tests, 3);
+
+        assert_eq!(peak, 1);
+        assert_eq!(results, vec!["test 1: Read", "test 2: Read", "test 3: Read"]);
+    }
+
+    #[test]
+    fn test_fail_fast_skips_after_failure() {
+        let harness = harness();
+        let tests = ["test 1", "test 2 (fails)", "test 3", "test 4"];
+
+        let outcomes = run_ordered_fail_fast(
+            &tests,
+            1,
+            |name| name.to_string(),
+            |name| {
+                let output = harness.execute(None, name, ExecutionConfig::new())?;
+                if name.contains("fails") {
+                    anyhow::bail!("{} failed", name);
+                }
+                Ok(output.result.tool_calls.len())
+            },
+            |outcome: &anyhow::Result<usize>| outcome.is_err(),
+        );
+
+        assert_eq!(outcomes.len(), 4);
+        assert_eq!(*outcomes[0].as_ref().unwrap().as_ref().unwrap(), 1);
+        assert!(outcomes[1].as_ref().unwrap().is_err());
+        assert!(outcomes[2].is_none());
+        assert!(outcomes[3].is_none());
+    }
+
+    #[test]
+    fn test_fail_fast_runs_everything_without_failures() {
+        let items = [1, 2, 3];
+        let outcomes = run_ordered_fail_fast(&items, 2, |n| *n, |n| n * 10, |_| false);
+        assert_eq!(outcomes, vec![Some(10), Some(20), Some(30)]);
+    }
+
+    #[test]
+    fn test_single_job_is_sequential() {
+        let items = [3, 1, 2];
+        let results = run_ordered(&items, 1, |n| *n, |n| n * 10);
+        assert_eq!(results, vec![30, 10, 20]);
+    }
+}
@@ -1,17 +1,77 @@
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use chrono::{DateTime, FixedOffset};
+use flate2::bufread::MultiGzDecoder;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 
 /// A tool call extracted from Claude Code logs
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCall {
     pub name: String,
     pub params: Value,
     /// RFC3339 timestamp string from the session log (e.g. "2024-01-19T12:00:00Z").
     pub timestamp: String,
+    /// Content of the matching `tool_result`, if the session log recorded one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+}
+
+impl ToolCall {
+    /// The call's timestamp, parsed as RFC3339.
+    ///
+    /// Returns `None` when the log didn't record one (e.g. Kiro) or it
+    /// isn't valid RFC3339.
+    pub fn time(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.timestamp).ok()
+    }
+}
+
+/// Token and cost totals for a session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageStats {
+    /// Input tokens, including cache creation and cache read tokens.
+    pub input_tokens: u64,
+    /// Output tokens.
+    pub output_tokens: u64,
+    /// Sum of the per-entry `costUSD` values, when the log records them.
+    pub total_cost_usd: f64,
+}
+
+impl UsageStats {
+    /// Input and output tokens combined.
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// Options controlling how session logs are parsed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    /// Skip lines that fail to parse (with a warning on stderr) instead of
+    /// returning an error. Default is strict.
+    pub lenient: bool,
+}
+
+impl ParseOptions {
+    /// Options that skip malformed lines.
+    pub fn lenient() -> Self {
+        Self { lenient: true }
+    }
+}
+
+/// Everything extracted from a session log.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedSession {
+    /// Tool calls in log order.
+    pub tool_calls: Vec<ToolCall>,
+    /// Aggregated token and cost usage.
+    pub usage: UsageStats,
+    /// Number of malformed lines skipped in lenient mode.
+    pub skipped_lines: usize,
 }
 
 /// Lightweight struct to check entry type before full parse
@@ -21,16 +81,52 @@ struct EntryTypeCheck {
     entry_type: Option<String>,
 }
 
-/// Raw log entry from JSONL (only valid for assistant messages)
+/// Raw log entry from JSONL (assistant messages, or user messages carrying tool results)
 #[derive(Debug, Deserialize)]
 struct LogEntry {
     timestamp: Option<String>,
     message: Option<MessageContent>,
+    #[serde(rename = "costUSD")]
+    cost_usd: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
 struct MessageContent {
-    content: Option<Vec<ContentBlock>>,
+    /// API message id. A message with several content blocks is logged as
+    /// several lines sharing this id and the same `usage`.
+    id: Option<String>,
+    content: Option<Content>,
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Usage {
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_input_tokens: u64,
+    cache_read_input_tokens: u64,
+}
+
+/// Message content is either a list of blocks or, for plain user prompts, a string.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+#[allow(dead_code)]
+enum Content {
+    Blocks(Vec<ContentBlock>),
+    Text(String),
+}
+
+/// Tool uses and tool results found on a single line, keyed by tool use id.
+#[derive(Debug, Default)]
+struct ParsedLine {
+    tool_uses: Vec<(String, ToolCall)>,
+    tool_results: Vec<(String, Value)>,
+    /// Usage reported on this line, with the message id used for deduplication.
+    usage: Option<(Option<String>, UsageStats)>,
+    /// Assistant text, tool calls and tool results in block order (only
+    /// tool results for user messages).
+    items: Vec<LineItem>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -54,97 +150,424 @@ enum ContentBlock {
     Other,
 }
 
+/// First two bytes of every gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
 /// Parse a JSONL file and extract all tool calls
+///
+/// Gzip-compressed files (`.gz` extension or gzip magic bytes) are
+/// decompressed transparently.
 pub fn parse_jsonl_file(path: &Path) -> Result<Vec<ToolCall>> {
+    parse_jsonl_reader(open_jsonl_file(path)?)
+}
+
+/// Parse a JSONL file and extract all tool calls plus aggregated token usage
+///
+/// Usage is counted once per API message, even when the message is split
+/// across several log lines.
+pub fn parse_session_with_usage(path: &Path) -> Result<(Vec<ToolCall>, UsageStats)> {
+    let session = parse_jsonl_file_with_options(path, ParseOptions::default())?;
+    Ok((session.tool_calls, session.usage))
+}
+
+/// Parse a JSONL file with explicit [`ParseOptions`]
+pub fn parse_jsonl_file_with_options(path: &Path, options: ParseOptions) -> Result<ParsedSession> {
+    parse_jsonl_reader_with_options(open_jsonl_file(path)?, options)
+}
+
+/// Open a JSONL file, decompressing it if it is gzipped.
+fn open_jsonl_file(path: &Path) -> Result<Box<dyn BufRead>> {
     let file = File::open(path).context("Failed to open JSONL file")?;
-    let reader = BufReader::new(file);
-    let mut tool_calls = Vec::new();
+    let mut reader = BufReader::new(file);
+
+    let is_gzip = path.extension().is_some_and(|ext| ext == "gz")
+        || reader
+            .fill_buf()
+            .context("Failed to read JSONL file")?
+            .starts_with(&GZIP_MAGIC);
+    if is_gzip {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Parse JSONL from any buffered reader (e.g. stdin) and extract all tool calls
+///
+/// Each `tool_result` is matched back to its `tool_use` by id to populate
+/// [`ToolCall::result`]. Results usually arrive on later lines, so all lines
+/// are read before any call is returned.
+pub fn parse_jsonl_reader<R: BufRead>(reader: R) -> Result<Vec<ToolCall>> {
+    parse_jsonl_reader_with_options(reader, ParseOptions::default()).map(|s| s.tool_calls)
+}
+
+/// Parse JSONL from any buffered reader with explicit [`ParseOptions`]
+///
+/// In lenient mode, lines that fail to parse are skipped and counted in
+/// [`ParsedSession::skipped_lines`]. Read errors are always returned.
+pub fn parse_jsonl_reader_with_options<R: BufRead>(
+    reader: R,
+    options: ParseOptions,
+) -> Result<ParsedSession> {
+    // First pass: collect tool uses in order, tool results by id, and usage
+    let mut tool_uses = Vec::new();
+    let mut tool_results = HashMap::new();
+    let mut usage = UsageStats::default();
+    let mut seen_messages = HashSet::new();
+    let mut skipped_lines = 0;
 
-    for line in reader.lines() {
+    for (i, line) in reader.lines().enumerate() {
         let line = line.context("Failed to read line")?;
-        if let Some(calls) = parse_line_internal(&line)? {
-            tool_calls.extend(calls);
+        let parsed = match parse_line_blocks(&line) {
+            Ok(parsed) => parsed,
+            Err(e) if options.lenient => {
+                eprintln!("Warning: Skipping malformed JSONL line {}: {:#}", i + 1, e);
+                skipped_lines += 1;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        if let Some(parsed) = parsed {
+            tool_uses.extend(parsed.tool_uses);
+            tool_results.extend(parsed.tool_results);
+            if let Some((message_id, line_usage)) = parsed.usage {
+                let first_seen = message_id.is_none_or(|id| seen_messages.insert(id));
+                if first_seen {
+                    usage.input_tokens += line_usage.input_tokens;
+                    usage.output_tokens += line_usage.output_tokens;
+                    usage.total_cost_usd += line_usage.total_cost_usd;
+                }
+            }
         }
     }
 
-    Ok(tool_calls)
+    // Second pass: attach each result to its originating call
+    let tool_calls = tool_uses
+        .into_iter()
+        .map(|(id, mut call)| {
+            call.result = tool_results.remove(&id);
+            call
+        })
+        .collect();
+
+    Ok(ParsedSession {
+        tool_calls,
+        usage,
+        skipped_lines,
+    })
+}
+
+/// Assistant output and tool results found on a single line, used for streaming.
+#[derive(Debug, Clone)]
+pub(crate) enum LineItem {
+    /// Prose from a `text` content block.
+    Text(String),
+    /// A tool call, with the id its `tool_result` will refer to. The result
+    /// is not attached; it arrives later as a [`LineItem::ToolResult`].
+    ToolCall { id: String, call: ToolCall },
+    /// The content of a `tool_result`, for the call with id `tool_use_id`.
+    ToolResult { tool_use_id: String, content: Value },
 }
 
-/// Internal parsing: check type first, then parse full entry only for assistant messages
-pub(crate) fn parse_line_internal(line: &str) -> Result<Option<Vec<ToolCall>>> {
+/// Internal parsing of assistant text and tool calls on a single line, in
+/// the order the content blocks appear
+pub(crate) fn parse_line_internal(line: &str) -> Result<Vec<LineItem>> {
+    let Some(parsed) = parse_line_blocks(line)? else {
+        return Ok(Vec::new());
+    };
+    Ok(parsed.items)
+}
+
+/// Check type first, then parse the full entry only for assistant and user messages
+fn parse_line_blocks(line: &str) -> Result<Option<ParsedLine>> {
     if line.trim().is_empty() {
         return Ok(None);
     }
 
-    // First, check if this is an assistant message (lightweight parse)
+    // First, check the message type (lightweight parse)
     let type_check: EntryTypeCheck =
         serde_json::from_str(line).context("Failed to parse JSON line")?;
 
-    if type_check.entry_type.as_deref() != Some("assistant") {
-        // Not an assistant message, skip without full parse
-        return Ok(None);
+    match type_check.entry_type.as_deref() {
+        Some("assistant") => {
+            let entry: LogEntry =
+                serde_json::from_str(line).context("Failed to parse assistant message")?;
+            Ok(Some(extract_blocks(&entry)))
+        }
+        // Tool results come back in user messages. Entries that don't match the
+        // expected shape carry no results, so they're skipped rather than fatal.
+        Some("user") => Ok(serde_json::from_str::<LogEntry>(line).ok().map(|entry| {
+            let parsed = extract_blocks(&entry);
+            ParsedLine {
+                items: parsed
+                    .items
+                    .into_iter()
+                    .filter(|item| matches!(item, LineItem::ToolResult { .. }))
+                    .collect(),
+                ..parsed
+            }
+        })),
+        // Other message types are skipped without full parse
+        _ => Ok(None),
     }
-
-    // Now do the full parse for assistant messages
-    let entry: LogEntry =
-        serde_json::from_str(line).context("Failed to parse assistant message")?;
-
-    Ok(extract_tool_calls(&entry))
 }
 
-fn extract_tool_calls(entry: &LogEntry) -> Option<Vec<ToolCall>> {
+fn extract_blocks(entry: &LogEntry) -> ParsedLine {
     let timestamp = entry
         .timestamp
         .clone()
         .unwrap_or_default();
 
-    let message = entry.message.as_ref()?;
-    let content = message.content.as_ref()?;
-
-    let calls: Vec<ToolCall> = content
-        .iter()
-        .filter_map(|block| match block {
-            ContentBlock::ToolUse { name, input, .. } => Some(ToolCall {
-                name: name.clone(),
-                params: input.clone(),
-                timestamp: timestamp.clone(),
-            }),
-            _ => None,
-        })
-        .collect();
+    let mut parsed = ParsedLine::default();
+    if let Some(message) = &entry.message {
+        if message.usage.is_some() || entry.cost_usd.is_some() {
+            let usage = message.usage.as_ref();
+            parsed.usage = Some((
+                message.id.clone(),
+                UsageStats {
+                    input_tokens: usage.map_or(0, |u| {
+                        u.input_tokens + u.cache_creation_input_tokens + u.cache_read_input_tokens
+                    }),
+                    output_tokens: usage.map_or(0, |u| u.output_tokens),
+                    total_cost_usd: entry.cost_usd.unwrap_or(0.0),
+                },
+            ));
+        }
+    }
 
-    if calls.is_empty() {
-        None
-    } else {
-        Some(calls)
+    let blocks = match entry.message.as_ref().and_then(|m| m.content.as_ref()) {
+        Some(Content::Blocks(blocks)) => blocks,
+        _ => return parsed,
+    };
+
+    for block in blocks {
+        match block {
+            ContentBlock::Text { text } if !text.trim().is_empty() => {
+                parsed.items.push(LineItem::Text(text.clone()));
+            }
+            ContentBlock::ToolUse { id, name, input } => {
+                let call = ToolCall {
+                    name: name.clone(),
+                    params: input.clone(),
+                    timestamp: timestamp.clone(),
+                    result: None,
+                };
+                parsed.items.push(LineItem::ToolCall {
+                    id: id.clone(),
+                    call: call.clone(),
+                });
+                parsed.tool_uses.push((id.clone(), call));
+            }
+            ContentBlock::ToolResult { tool_use_id, content } => {
+                let content = content.clone().unwrap_or(Value::Null);
+                parsed.items.push(LineItem::ToolResult {
+                    tool_use_id: tool_use_id.clone(),
+                    content: content.clone(),
+                });
+                parsed.tool_results.push((tool_use_id.clone(), content));
+            }
+            _ => {}
+        }
     }
+
+    parsed
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn tool_calls_on(line: &str) -> Vec<ToolCall> {
+        parse_line_internal(line)
+            .unwrap()
+            .into_iter()
+            .filter_map(|item| match item {
+                LineItem::ToolCall { call, .. } => Some(call),
+                _ => None,
+            })
+            .collect()
+    }
+
     #[test]
     fn test_parse_tool_use() {
         let json = r#"{"type":"assistant","timestamp":"2024-01-19T12:00:00Z","message":{"content":[{"type":"tool_use","id":"123","name":"Read","input":{"file_path":"/tmp/test.txt"}}]}}"#;
-        let calls = parse_line_internal(json).unwrap().unwrap_or_default();
+        let calls = tool_calls_on(json);
         assert_eq!(calls.len(), 1);
         assert_eq!(calls[0].name, "Read");
     }
 
+    #[test]
+    fn test_tool_call_time() {
+        let json = r#"{"type":"assistant","timestamp":"2024-01-19T12:00:00.250-08:00","message":{"content":[{"type":"tool_use","id":"1","name":"Read","input":{}}]}}"#;
+        let call = &tool_calls_on(json)[0];
+        let time = call.time().unwrap();
+        assert_eq!(time.to_rfc3339(), "2024-01-19T12:00:00.250-08:00");
+
+        let untimed = ToolCall { timestamp: String::new(), ..call.clone() };
+        assert!(untimed.time().is_none());
+    }
+
+    #[test]
+    fn test_tool_call_time_malformed() {
+        for timestamp in ["2024-01-19 12:00:00", "2024-13-45T99:00:00Z", "yesterday", "T"] {
+            let json = format!(
+                r#"{{"type":"assistant","timestamp":"{}","message":{{"content":[{{"type":"tool_use","id":"1","name":"Read","input":{{}}}}]}}}}"#,
+                timestamp
+            );
+            let call = &tool_calls_on(&json)[0];
+            // The raw string is kept for display even when it doesn't parse
+            assert_eq!(call.timestamp, timestamp);
+            assert!(call.time().is_none());
+        }
+    }
+
     #[test]
     fn test_skip_user_messages() {
         // User messages are skipped early (before full parse) based on type field
         let json = r#"{"type":"user","timestamp":"2024-01-19T12:00:00Z","message":{"content":"hello"}}"#;
-        let calls = parse_line_internal(json).unwrap();
-        assert!(calls.is_none());
+        assert!(parse_line_internal(json).unwrap().is_empty());
     }
 
     #[test]
     fn test_skip_system_messages() {
         // System/meta messages are also skipped
         let json = r#"{"type":"system","subtype":"turn_duration","durationMs":318950}"#;
-        let calls = parse_line_internal(json).unwrap();
-        assert!(calls.is_none());
+        assert!(parse_line_internal(json).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_jsonl_reader() {
+        let session = concat!(
+            r#"{"type":"user","message":{"content":"list files"}}"#, "\n",
+            "\n",
+            r#"{"type":"assistant","timestamp":"2024-01-19T12:00:00Z","message":{"content":[{"type":"text","text":"Sure"},{"type":"tool_use","id":"1","name":"Bash","input":{"command":"ls"}},{"type":"tool_use","id":"2","name":"Read","input":{"file_path":"/a.txt"}}]}}"#, "\n",
+        );
+
+        let calls = parse_jsonl_reader(std::io::Cursor::new(session)).unwrap();
+        let names: Vec<&str> = calls.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Bash", "Read"]);
+        assert_eq!(calls[0].params["command"], "ls");
+        assert_eq!(calls[1].timestamp, "2024-01-19T12:00:00Z");
+
+        assert!(parse_jsonl_reader(std::io::Cursor::new("not json\n")).is_err());
+    }
+
+    #[test]
+    fn test_tool_result_attached_to_tool_use() {
+        let session = concat!(
+            r#"{"type":"assistant","timestamp":"2024-01-19T12:00:00Z","message":{"content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"ls"}},{"type":"tool_use","id":"toolu_2","name":"Read","input":{"file_path":"/a.txt"}}]}}"#, "\n",
+            r#"{"type":"user","timestamp":"2024-01-19T12:00:01Z","message":{"content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"a.txt\nb.txt"}]}}"#, "\n",
+            r#"{"type":"user","message":{"content":"thanks"}}"#, "\n",
+        );
+
+        let calls = parse_jsonl_reader(std::io::Cursor::new(session)).unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].result, Some(serde_json::json!("a.txt\nb.txt")));
+        // No tool_result recorded for the second call
+        assert!(calls[1].result.is_none());
+
+        // Single-line parsing never sees results
+        let line = session.lines().next().unwrap();
+        let calls = tool_calls_on(line);
+        assert_eq!(calls.len(), 2);
+        assert!(calls.iter().all(|c| c.result.is_none()));
+    }
+
+    #[test]
+    fn test_parse_session_with_usage() {
+        let session = concat!(
+            r#"{"type":"user","message":{"content":"read it"}}"#, "\n",
+            // One API message split across two lines, repeating the same usage
+            r#"{"type":"assistant","costUSD":0.01,"message":{"id":"msg_1","content":[{"type":"text","text":"Reading"}],"usage":{"input_tokens":100,"cache_read_input_tokens":400,"output_tokens":20}}}"#, "\n",
+            r#"{"type":"assistant","costUSD":0.01,"message":{"id":"msg_1","content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"/a.txt"}}],"usage":{"input_tokens":100,"cache_read_input_tokens":400,"output_tokens":20}}}"#, "\n",
+            r#"{"type":"assistant","costUSD":0.005,"message":{"id":"msg_2","content":[{"type":"text","text":"Done"}],"usage":{"input_tokens":50,"output_tokens":30}}}"#, "\n",
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        std::fs::write(&path, session).unwrap();
+
+        let (calls, usage) = parse_session_with_usage(&path).unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(usage.input_tokens, 550);
+        assert_eq!(usage.output_tokens, 50);
+        assert_eq!(usage.total_tokens(), 600);
+        assert!((usage.total_cost_usd - 0.015).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_usage_defaults_to_zero() {
+        let json = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"1","name":"Read","input":{}}]}}"#;
+        let session =
+            parse_jsonl_reader_with_options(std::io::Cursor::new(json), ParseOptions::default())
+                .unwrap();
+        assert_eq!(session.usage, UsageStats::default());
+    }
+
+    #[test]
+    fn test_lenient_skips_malformed_lines() {
+        let session = concat!(
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"1","name":"Read","input":{}}]}}"#, "\n",
+            "this is not json\n",
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Edit"}]}}"#, "\n",
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"2","name":"Write","input":{}}]}}"#, "\n",
+            "{\"truncated\n",
+        );
+
+        // Strict (default) fails on the first bad line
+        let err = parse_jsonl_reader(std::io::Cursor::new(session)).unwrap_err();
+        assert!(err.to_string().contains("Failed to parse JSON line"));
+
+        let parsed =
+            parse_jsonl_reader_with_options(std::io::Cursor::new(session), ParseOptions::lenient())
+                .unwrap();
+        let names: Vec<&str> = parsed.tool_calls.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Read", "Write"]);
+        assert_eq!(parsed.skipped_lines, 3);
+    }
+
+    #[test]
+    fn test_lenient_clean_file_skips_nothing() {
+        let json = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"1","name":"Read","input":{}}]}}"#;
+        let parsed =
+            parse_jsonl_reader_with_options(std::io::Cursor::new(json), ParseOptions::lenient())
+                .unwrap();
+        assert_eq!(parsed.tool_calls.len(), 1);
+        assert_eq!(parsed.skipped_lines, 0);
+    }
+
+    #[test]
+    fn test_parse_gzip_file() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let session = concat!(
+            r#"{"type":"user","message":{"content":"edit it"}}"#, "\n",
+            r#"{"type":"assistant","timestamp":"2024-01-19T12:00:00Z","message":{"content":[{"type":"tool_use","id":"1","name":"Read","input":{"file_path":"/a.txt"}}]}}"#, "\n",
+            r#"{"type":"assistant","timestamp":"2024-01-19T12:00:01Z","message":{"content":[{"type":"tool_use","id":"2","name":"Edit","input":{"file_path":"/a.txt"}}]}}"#, "\n",
+        );
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(session.as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let plain_path = dir.path().join("session.jsonl");
+        let gz_path = dir.path().join("session.jsonl.gz");
+        // Detected by magic bytes even without the extension
+        let sniffed_path = dir.path().join("archived.jsonl");
+        std::fs::write(&plain_path, session).unwrap();
+        std::fs::write(&gz_path, &gzipped).unwrap();
+        std::fs::write(&sniffed_path, &gzipped).unwrap();
+
+        let summarize = |calls: Vec<ToolCall>| {
+            calls
+                .into_iter()
+                .map(|c| (c.name, c.params, c.timestamp))
+                .collect::<Vec<_>>()
+        };
+        let plain = summarize(parse_jsonl_file(&plain_path).unwrap());
+        assert_eq!(plain.len(), 2);
+        assert_eq!(summarize(parse_jsonl_file(&gz_path).unwrap()), plain);
+        assert_eq!(summarize(parse_jsonl_file(&sniffed_path).unwrap()), plain);
     }
 }
@@ -35,9 +35,12 @@
 //! ```
 
 use crate::agents::{AgentHarness, AgentType, ExecutionConfig, ExecutionOutput};
+use crate::error::HarnessError;
 use crate::parser::ToolCall;
 use crate::streaming::StreamHandle;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Create a prompt builder for fluent configuration.
 ///
@@ -59,23 +62,72 @@ pub fn prompt(text: &str) -> PromptBuilder {
 ///
 /// The builder provides a fluent interface for setting up prompt execution
 /// with various options like working directory and agent type.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct PromptBuilder {
+    harness: Option<Arc<AgentHarness>>,
     text: String,
     working_dir: Option<PathBuf>,
     agent: Option<AgentType>,
+    env: HashMap<String, String>,
+    stdin: bool,
+    system_prompt: Option<String>,
+    follow_ups: Vec<String>,
+}
+
+impl std::fmt::Debug for PromptBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PromptBuilder")
+            .field("shared_harness", &self.harness.is_some())
+            .field("text", &self.text)
+            .field("working_dir", &self.working_dir)
+            .field("agent", &self.agent)
+            .field("env", &self.env)
+            .field("stdin", &self.stdin)
+            .field("system_prompt", &self.system_prompt)
+            .field("follow_ups", &self.follow_ups)
+            .finish()
+    }
 }
 
 impl PromptBuilder {
     /// Create a new prompt builder with the given prompt text.
     pub fn new(text: &str) -> Self {
         Self {
+            harness: None,
             text: text.to_string(),
             working_dir: None,
             agent: None,
+            env: HashMap::new(),
+            stdin: false,
+            system_prompt: None,
+            follow_ups: Vec::new(),
         }
     }
 
+    /// Run on `harness` instead of a fresh [`AgentHarness`] per run.
+    ///
+    /// Building a harness registers every built-in adapter, so suites that
+    /// issue many prompts can share one. Agents registered on it (including
+    /// replacements for built-ins) are used too. Pass an
+    /// `Arc<AgentHarness>` to keep using the harness elsewhere.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let harness = Arc::new(AgentHarness::new());
+    ///
+    /// for file in ["a.txt", "b.txt"] {
+    ///     let tool_calls = prompt(&format!("Read {}", file))
+    ///         .with_harness(Arc::clone(&harness))
+    ///         .run()?;
+    ///     expect_tools(&tool_calls).tool(Tool::Read).to_be_called();
+    /// }
+    /// ```
+    pub fn with_harness(mut self, harness: impl Into<Arc<AgentHarness>>) -> Self {
+        self.harness = Some(harness.into());
+        self
+    }
+
     /// Set the working directory for execution.
     ///
     /// The agent will execute the prompt as if it were run from this directory.
@@ -116,6 +168,95 @@ impl PromptBuilder {
         self
     }
 
+    /// Set an environment variable on the agent process.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let tool_calls = prompt("Run the tests")
+    ///     .with_env("NODE_ENV", "test")
+    ///     .run()
+    ///     .unwrap();
+    /// ```
+    pub fn with_env(mut self, key: &str, value: &str) -> Self {
+        self.env.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Send the prompt to the agent on stdin instead of as an argument.
+    ///
+    /// Use this for prompts too large for the OS argument limit (`ARG_MAX`).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let tool_calls = prompt(&std::fs::read_to_string("large_prompt.md")?)
+    ///     .stdin(true)
+    ///     .run()
+    ///     .unwrap();
+    /// ```
+    pub fn stdin(mut self, stdin: bool) -> Self {
+        self.stdin = stdin;
+        self
+    }
+
+    /// Append `text` to the agent's system prompt.
+    ///
+    /// Claude receives it as `--append-system-prompt`; other agents return
+    /// [`HarnessError::Unsupported`] before anything runs.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let tool_calls = prompt("Find the TODOs")
+    ///     .with_system_prompt("Always search with Grep, never Bash")
+    ///     .run()
+    ///     .unwrap();
+    /// ```
+    pub fn with_system_prompt(mut self, text: &str) -> Self {
+        self.system_prompt = Some(text.to_string());
+        self
+    }
+
+    /// Add a follow-up turn, sent in the same session once the previous
+    /// prompt finishes.
+    ///
+    /// The output covers the whole conversation: tool calls from every turn
+    /// in order, and stdout from the last turn. Follow-up turns resume the
+    /// agent's session, which only Claude supports (`claude --resume`);
+    /// other agents return an error before anything runs.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let tool_calls = prompt("Read config.json")
+    ///     .then("Now add a \"debug\": true field to it")
+    ///     .run()
+    ///     .unwrap();
+    ///
+    /// expect(&tool_calls).tool(Tool::Read).to_be_called();
+    /// expect(&tool_calls).tool(Tool::Edit).to_be_called();
+    /// ```
+    pub fn then(mut self, text: &str) -> Self {
+        self.follow_ups.push(text.to_string());
+        self
+    }
+
+    /// Build the conversation turns and execution config for this prompt.
+    fn config(self) -> (Vec<String>, Option<AgentType>, ExecutionConfig) {
+        let mut config = ExecutionConfig::new().with_stdin_prompt(self.stdin);
+        config.env = self.env;
+        config.system_prompt = self.system_prompt;
+
+        if let Some(dir) = self.working_dir {
+            config = config.with_working_dir(dir);
+        }
+
+        let mut turns = vec![self.text];
+        turns.extend(self.follow_ups);
+        (turns, self.agent, config)
+    }
+
     /// Execute the prompt and return the full execution output.
     ///
     /// Returns [`ExecutionOutput`] containing both the normalized result
@@ -138,15 +279,16 @@ impl PromptBuilder {
     ///     println!("Response: {}", stdout);
     /// }
     /// ```
-    pub fn run_full(self) -> anyhow::Result<ExecutionOutput> {
-        let harness = AgentHarness::new();
-        let mut config = ExecutionConfig::new();
-
-        if let Some(dir) = self.working_dir {
-            config = config.with_working_dir(dir);
+    pub fn run_full(self) -> Result<ExecutionOutput, HarnessError> {
+        match self.harness.clone() {
+            Some(harness) => self.run_full_in(&harness),
+            None => self.run_full_in(&AgentHarness::new()),
         }
+    }
 
-        harness.execute(self.agent, &self.text, config)
+    fn run_full_in(self, harness: &AgentHarness) -> Result<ExecutionOutput, HarnessError> {
+        let (turns, agent, config) = self.config();
+        harness.execute_conversation(agent, &turns, config)
     }
 
     /// Execute the prompt in streaming mode, returning a handle for live events.
@@ -169,15 +311,27 @@ impl PromptBuilder {
     ///
     /// let result = handle.wait().unwrap();
     /// ```
-    pub fn run_streaming(self) -> anyhow::Result<StreamHandle> {
-        let harness = AgentHarness::new();
-        let mut config = ExecutionConfig::new();
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if follow-up turns were added with [`then`](Self::then);
+    /// streaming covers a single prompt.
+    pub fn run_streaming(self) -> Result<StreamHandle, HarnessError> {
+        match self.harness.clone() {
+            Some(harness) => self.run_streaming_in(&harness),
+            None => self.run_streaming_in(&AgentHarness::new()),
+        }
+    }
 
-        if let Some(dir) = self.working_dir {
-            config = config.with_working_dir(dir);
+    fn run_streaming_in(self, harness: &AgentHarness) -> Result<StreamHandle, HarnessError> {
+        let (turns, agent, config) = self.config();
+        if turns.len() > 1 {
+            return Err(HarnessError::InvalidRequest(
+                "Streaming does not support follow-up turns; use run_full() instead".to_string(),
+            ));
         }
 
-        harness.execute_streaming(self.agent, &self.text, config)
+        harness.execute_streaming(agent, &turns[0], config)
     }
 
     /// Execute the prompt and return tool calls.
@@ -188,10 +342,10 @@ impl PromptBuilder {
     ///
     /// # Errors
     ///
-    /// Returns an error if:
-    /// - The agent is not available on the system
-    /// - The agent execution fails
-    /// - The session log cannot be parsed
+    /// Returns a [`HarnessError`] if:
+    /// - The agent is not available on the system (`AgentUnavailable`)
+    /// - The agent execution fails (`ExecutionFailed`)
+    /// - The session log cannot be found (`SessionNotFound`) or parsed (`ParseError`)
     ///
     /// # Example
     ///
@@ -199,14 +353,55 @@ impl PromptBuilder {
     /// let tool_calls = prompt("Read config.json").run().unwrap();
     /// assert!(!tool_calls.is_empty());
     /// ```
-    pub fn run(self) -> anyhow::Result<Vec<ToolCall>> {
+    pub fn run(self) -> Result<Vec<ToolCall>, HarnessError> {
         Ok(self.run_full()?.result.tool_calls)
     }
+
+    /// Execute the prompt like [`run`](Self::run), or return `Ok(None)` if the
+    /// agent's CLI is not installed.
+    ///
+    /// Lets a test skip itself on machines without the agent instead of
+    /// failing. Unlike `#[ignore]`, the test still runs wherever the agent is
+    /// available, but a skipped test is reported as passed, so a CI job that
+    /// expects the agent should check for it separately. Use `#[ignore]` for
+    /// tests that should only run when asked for (e.g. because they cost
+    /// money).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let Some(tool_calls) = prompt("Read config.json").run_if_available()? else {
+    ///     eprintln!("skipping: claude is not installed");
+    ///     return Ok(());
+    /// };
+    /// expect(&tool_calls).tool(Tool::Read).to_be_called();
+    /// ```
+    pub fn run_if_available(self) -> Result<Option<Vec<ToolCall>>, HarnessError> {
+        match self.harness.clone() {
+            Some(harness) => self.run_if_available_in(&harness),
+            None => self.run_if_available_in(&AgentHarness::new()),
+        }
+    }
+
+    fn run_if_available_in(
+        self,
+        harness: &AgentHarness,
+    ) -> Result<Option<Vec<ToolCall>>, HarnessError> {
+        let (turns, agent, config) = self.config();
+        if !harness.is_agent_available(agent.unwrap_or(AgentType::Claude)) {
+            return Ok(None);
+        }
+
+        let output = harness.execute_conversation(agent, &turns, config)?;
+        Ok(Some(output.result.tool_calls))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::MockAgent;
+    use serde_json::json;
 
     #[test]
     fn test_prompt_builder_creation() {
@@ -238,4 +433,105 @@ mod tests {
         assert_eq!(builder.working_dir, Some(PathBuf::from("/tmp")));
         assert_eq!(builder.agent, Some(AgentType::Claude));
     }
+
+    #[test]
+    fn test_prompt_builder_with_env() {
+        let (_, _, config) = prompt("Test")
+            .with_env("NODE_ENV", "test")
+            .with_env("DEBUG", "1")
+            .config();
+
+        assert_eq!(config.env.get("NODE_ENV").map(String::as_str), Some("test"));
+        assert_eq!(config.env.get("DEBUG").map(String::as_str), Some("1"));
+    }
+
+    #[test]
+    #[cfg(feature = "gemini")]
+    fn test_run_if_available_skips_unavailable_agent() {
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(MockAgent::new().unavailable()));
+        let mock = MockAgent::new().with_tool_call("Read", json!({"file_path": "/a.txt"}));
+        harness.register(AgentType::Gemini, Arc::new(mock.clone()));
+
+        let skipped = prompt("Read a.txt").run_if_available_in(&harness).unwrap();
+        assert!(skipped.is_none());
+
+        let tool_calls = prompt("Read a.txt")
+            .agent(AgentType::Gemini)
+            .run_if_available_in(&harness)
+            .unwrap()
+            .unwrap();
+        assert_eq!(tool_calls[0].name, "Read");
+        assert_eq!(mock.runs(), 1);
+    }
+
+    #[test]
+    fn test_prompt_builder_then_accumulates_turns() {
+        let (turns, _, _) = prompt("Read config.json")
+            .then("Add a debug field")
+            .then("Now revert it")
+            .config();
+        assert_eq!(turns, vec!["Read config.json", "Add a debug field", "Now revert it"]);
+
+        let mut harness = AgentHarness::new();
+        let mock = MockAgent::new().with_tool_call("Read", json!({"file_path": "/a.txt"}));
+        harness.register(AgentType::Claude, Arc::new(mock.clone()));
+
+        prompt("Read a.txt")
+            .then("Summarize it")
+            .run_if_available_in(&harness)
+            .unwrap();
+        assert_eq!(mock.runs(), 2);
+    }
+
+    #[test]
+    fn test_with_harness_reuses_shared_harness() {
+        let mut harness = AgentHarness::new();
+        let mock = MockAgent::new().with_tool_call("Read", json!({"file_path": "/a.txt"}));
+        harness.register(AgentType::Claude, Arc::new(mock.clone()));
+        let harness = Arc::new(harness);
+
+        for file in ["a.txt", "b.txt", "c.txt"] {
+            let tool_calls = prompt(&format!("Read {}", file))
+                .with_harness(Arc::clone(&harness))
+                .run()
+                .unwrap();
+            assert_eq!(tool_calls[0].name, "Read");
+        }
+        let output = prompt("Read a.txt")
+            .then("Summarize it")
+            .with_harness(harness)
+            .run_full()
+            .unwrap();
+        assert_eq!(output.result.agent_name, "mock");
+
+        // Every run went through the one registered mock
+        assert_eq!(mock.runs(), 5);
+    }
+
+    #[test]
+    fn test_run_streaming_rejects_follow_ups() {
+        let result = prompt("Read a.txt").then("Summarize it").run_streaming();
+        let err = result.err().expect("streaming should reject follow-up turns");
+        assert!(matches!(err, crate::HarnessError::InvalidRequest(_)));
+        assert!(err.to_string().contains("does not support follow-up turns"));
+    }
+
+    #[test]
+    fn test_prompt_builder_system_prompt() {
+        let (_, _, config) = prompt("Test").config();
+        assert_eq!(config.system_prompt, None);
+
+        let (_, _, config) = prompt("Test").with_system_prompt("Be terse").config();
+        assert_eq!(config.system_prompt.as_deref(), Some("Be terse"));
+    }
+
+    #[test]
+    fn test_prompt_builder_stdin() {
+        let (_, _, config) = prompt("Test").config();
+        assert!(!config.stdin_prompt);
+
+        let (_, _, config) = prompt("Test").stdin(true).config();
+        assert!(config.stdin_prompt);
+    }
 }
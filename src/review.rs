@@ -4,10 +4,16 @@
 //! prompt construction, JSON response parsing, and score evaluation.
 //! CLI invocation is delegated to the caller via a grader function,
 //! keeping this module decoupled from the agent system.
+//!
+//! [`GradeCache`] stores results on disk so re-running a suite with
+//! unchanged stdout doesn't pay for the same grading call twice.
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
 use std::future::Future;
+use std::path::{Path, PathBuf};
 
 /// Configuration for an LLM-powered stdout review.
 #[derive(Debug, Clone)]
@@ -18,10 +24,13 @@ pub struct ReviewConfig {
     pub threshold: u32,
     /// Model override passed to the grading agent (e.g., `--model`).
     pub model: Option<String>,
+    /// Custom grading prompt with `{criteria}` and `{output}` placeholders.
+    /// Uses the default rubric when `None`.
+    pub prompt_template: Option<String>,
 }
 
 /// Result of grading stdout against review criteria.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ReviewResult {
     /// Score from 1-10.
     pub score: u32,
@@ -33,17 +42,56 @@ pub struct ReviewResult {
 
 #[derive(Deserialize)]
 struct GradingResponse {
+    #[serde(deserialize_with = "deserialize_score")]
     score: u32,
     reasoning: String,
 }
 
+/// Accept a score as a number (`8`) or a numeric string (`"8"`).
+fn deserialize_score<'de, D>(deserializer: D) -> std::result::Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Score {
+        Number(u32),
+        Text(String),
+    }
+
+    match Score::deserialize(deserializer)? {
+        Score::Number(n) => Ok(n),
+        Score::Text(s) => s
+            .trim()
+            .parse()
+            .map_err(|_| serde::de::Error::custom(format!("invalid score: {:?}", s))),
+    }
+}
+
 /// Build the grading prompt from criteria and stdout content.
 pub fn build_grading_prompt(stdout: &Option<String>, criteria: &str) -> String {
+    build_grading_prompt_with_template(stdout, criteria, None)
+}
+
+/// Build the grading prompt, filling in `template` if one is given.
+///
+/// The template's `{criteria}` and `{output}` placeholders are filled in
+/// instead of using the default rubric. The template should still ask for
+/// the `{"score": ..., "reasoning": ...}` JSON response.
+pub fn build_grading_prompt_with_template(
+    stdout: &Option<String>,
+    criteria: &str,
+    template: Option<&str>,
+) -> String {
     let output_text = match stdout {
         Some(s) if !s.is_empty() => s.as_str(),
         _ => "(empty - no output was produced)",
     };
 
+    if let Some(template) = template {
+        return render_template(template, criteria, output_text);
+    }
+
     format!(
         r#"You are a test grader. Evaluate the following output against the given criteria.
 
@@ -65,6 +113,29 @@ Respond with ONLY a JSON object, no other text:
     )
 }
 
+/// Substitute `{criteria}` and `{output}` in a single pass, so placeholder
+/// text inside the criteria or the output is left as-is.
+fn render_template(template: &str, criteria: &str, output: &str) -> String {
+    let mut rendered = String::with_capacity(template.len() + criteria.len() + output.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        if let Some(after) = tail.strip_prefix("{criteria}") {
+            rendered.push_str(criteria);
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("{output}") {
+            rendered.push_str(output);
+            rest = after;
+        } else {
+            rendered.push('{');
+            rest = &tail[1..];
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
 /// Grade stdout against criteria using the provided grading function.
 ///
 /// The `grader` function takes a prompt and optional model, returning the raw
@@ -86,7 +157,11 @@ pub fn grade_stdout<F>(
 where
     F: FnOnce(&str, Option<&str>) -> Result<String>,
 {
-    let prompt = build_grading_prompt(stdout, &config.criteria);
+    let prompt = build_grading_prompt_with_template(
+        stdout,
+        &config.criteria,
+        config.prompt_template.as_deref(),
+    );
     let response = grader(&prompt, config.model.as_deref())?;
 
     let json_str = extract_json(&response);
@@ -101,6 +176,43 @@ where
     })
 }
 
+/// Grade stdout like [`grade_stdout`], reusing a cached result when available.
+///
+/// On a miss the grader is called and its result stored. Failing to write the
+/// cache only prints a warning, since the grade itself is still valid.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let cache = GradeCache::default_location();
+/// let result = grade_stdout_cached(agent.name(), &stdout, &config, cache.as_ref(), |prompt, model| {
+///     agent.grade(prompt, model)
+/// })?;
+/// ```
+pub fn grade_stdout_cached<F>(
+    agent: &str,
+    stdout: &Option<String>,
+    config: &ReviewConfig,
+    cache: Option<&GradeCache>,
+    grader: F,
+) -> Result<ReviewResult>
+where
+    F: FnOnce(&str, Option<&str>) -> Result<String>,
+{
+    let Some(cache) = cache else {
+        return grade_stdout(stdout, config, grader);
+    };
+    if let Some(result) = cache.get(agent, stdout, config) {
+        return Ok(result);
+    }
+
+    let result = grade_stdout(stdout, config, grader)?;
+    if let Err(e) = cache.put(agent, stdout, config, &result) {
+        eprintln!("Warning: {:#}", e);
+    }
+    Ok(result)
+}
+
 /// Async version of grade_stdout for parallel processing.
 ///
 /// The `grader` function returns a future for the grading operation.
@@ -121,7 +233,11 @@ where
     F: FnOnce(String, Option<String>) -> Fut,
     Fut: Future<Output = Result<String>>,
 {
-    let prompt = build_grading_prompt(stdout, &config.criteria);
+    let prompt = build_grading_prompt_with_template(
+        stdout,
+        &config.criteria,
+        config.prompt_template.as_deref(),
+    );
     let model = config.model.clone();
     let response = grader(prompt, model).await?;
 
@@ -141,7 +257,8 @@ where
 ///
 /// This function runs all grading requests concurrently and returns results
 /// in the same order as the inputs. This provides significant speedup when
-/// multiple stdout assertions need to be evaluated.
+/// multiple stdout assertions need to be evaluated. Fails with the first
+/// error; use [`grade_stdout_batch_each_async`] to keep the other results.
 ///
 /// # Example
 ///
@@ -158,6 +275,36 @@ pub async fn grade_stdout_batch_async<F, Fut>(
     requests: &[(Option<String>, ReviewConfig)],
     grader: F,
 ) -> Result<Vec<ReviewResult>>
+where
+    F: Fn(String, Option<String>) -> Fut + Clone,
+    Fut: Future<Output = Result<String>>,
+{
+    grade_stdout_batch_each_async(requests, grader)
+        .await
+        .into_iter()
+        .collect()
+}
+
+/// Like [`grade_stdout_batch_async`], but each request succeeds or fails on
+/// its own, so one malformed response doesn't discard the others.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let results = grade_stdout_batch_each_async(&requests, |prompt, model| {
+///     Box::pin(agent.grade_async(prompt, model))
+/// }).await;
+/// for result in results {
+///     match result {
+///         Ok(review) => println!("{}/10", review.score),
+///         Err(e) => eprintln!("grading failed: {:#}", e),
+///     }
+/// }
+/// ```
+pub async fn grade_stdout_batch_each_async<F, Fut>(
+    requests: &[(Option<String>, ReviewConfig)],
+    grader: F,
+) -> Vec<Result<ReviewResult>>
 where
     F: Fn(String, Option<String>) -> Fut + Clone,
     Fut: Future<Output = Result<String>>,
@@ -172,22 +319,155 @@ where
         })
         .collect();
 
-    // Use try_join_all to execute all grading requests in parallel
-    // and return the first error if any occurs
-    futures::future::try_join_all(futures).await
+    futures::future::join_all(futures).await
 }
 
-/// Extract JSON from a response that might be wrapped in markdown code fences.
+/// Extract the grading JSON object from a response.
+///
+/// Models may wrap the object in code fences, surround it with prose, or emit
+/// more than one object. Returns the first balanced `{...}` that parses as
+/// JSON, ignoring braces inside strings. Falls back to the trimmed response
+/// so the caller reports the parse error.
 fn extract_json(response: &str) -> &str {
     let trimmed = response.trim();
-    if let Some(start) = trimmed.find('{') {
-        if let Some(end) = trimmed.rfind('}') {
-            return &trimmed[start..=end];
+    let mut search_from = 0;
+    while let Some(offset) = trimmed[search_from..].find('{') {
+        let start = search_from + offset;
+        if let Some(end) = balanced_object_end(&trimmed[start..]) {
+            let candidate = &trimmed[start..start + end];
+            if serde_json::from_str::<serde_json::Value>(candidate).is_ok() {
+                return candidate;
+            }
         }
+        search_from = start + 1;
     }
     trimmed
 }
 
+/// Byte length of the balanced object at the start of `s` (which begins with
+/// `{`), or `None` if it is never closed.
+fn balanced_object_end(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in s.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// On-disk cache of grading results.
+///
+/// Entries are JSON files named by a SHA-256 of everything that affects the
+/// grade: the grading agent, the stdout, criteria, threshold, model and
+/// prompt template. Changing any of them is a miss, so stale results are
+/// never reused.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let cache = GradeCache::new("/tmp/grades");
+/// if cache.get("claude", &stdout, &config).is_none() {
+///     let result = grade_stdout(&stdout, &config, grader)?;
+///     cache.put("claude", &stdout, &config, &result)?;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GradeCache {
+    dir: PathBuf,
+}
+
+impl GradeCache {
+    /// Create a cache that stores entries in `dir`, created on first write.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Cache in the platform cache directory (`~/.cache/aptitude/grades` on
+    /// Linux), or `None` if it cannot be determined.
+    pub fn default_location() -> Option<Self> {
+        dirs::cache_dir().map(|dir| Self::new(dir.join("aptitude").join("grades")))
+    }
+
+    /// Directory the entries are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Look up a result cached for the grading agent named `agent`. Missing
+    /// or unreadable entries are a miss.
+    pub fn get(&self, agent: &str, stdout: &Option<String>, config: &ReviewConfig) -> Option<ReviewResult> {
+        let contents = fs::read_to_string(self.entry_path(agent, stdout, config)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// Store a result from the grading agent named `agent` for this stdout
+    /// and config.
+    pub fn put(
+        &self,
+        agent: &str,
+        stdout: &Option<String>,
+        config: &ReviewConfig,
+        result: &ReviewResult,
+    ) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create grade cache directory {:?}", self.dir))?;
+        let path = self.entry_path(agent, stdout, config);
+        let json = serde_json::to_string(result).context("Failed to serialize grading result")?;
+        fs::write(&path, json).with_context(|| format!("Failed to write grade cache entry {:?}", path))
+    }
+
+    fn entry_path(&self, agent: &str, stdout: &Option<String>, config: &ReviewConfig) -> PathBuf {
+        self.dir.join(format!("{}.json", cache_key(agent, stdout, config)))
+    }
+}
+
+/// Hex SHA-256 over the grading inputs. Each field is length-prefixed so
+/// moving text between fields changes the key.
+fn cache_key(agent: &str, stdout: &Option<String>, config: &ReviewConfig) -> String {
+    let mut hasher = Sha256::new();
+    let mut field = |value: Option<&str>| match value {
+        Some(s) => {
+            hasher.update([1]);
+            hasher.update((s.len() as u64).to_le_bytes());
+            hasher.update(s.as_bytes());
+        }
+        None => hasher.update([0]),
+    };
+    field(Some(agent));
+    field(stdout.as_deref());
+    field(Some(&config.criteria));
+    field(Some(&config.threshold.to_string()));
+    field(config.model.as_deref());
+    field(config.prompt_template.as_deref());
+
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -215,6 +495,49 @@ mod tests {
         assert!(prompt.contains("(empty - no output was produced)"));
     }
 
+    #[test]
+    fn test_build_grading_prompt_template() {
+        let stdout = Some("printed {criteria} literally".to_string());
+        let template = "Judge {output} against {criteria}. Keep {braces} and {criteria}.";
+        let prompt = build_grading_prompt_with_template(&stdout, "be terse", Some(template));
+        assert_eq!(
+            prompt,
+            "Judge printed {criteria} literally against be terse. Keep {braces} and be terse."
+        );
+
+        let prompt = build_grading_prompt_with_template(&None, "be terse", Some("Output: {output}"));
+        assert_eq!(prompt, "Output: (empty - no output was produced)");
+    }
+
+    #[test]
+    fn test_build_grading_prompt_default_without_template() {
+        let stdout = Some("done".to_string());
+        let prompt = build_grading_prompt(&stdout, "should finish");
+        assert!(prompt.starts_with("You are a test grader."));
+        assert!(prompt.contains("Criteria: should finish"));
+        assert!(prompt.contains("---\ndone\n---"));
+        assert!(prompt.ends_with(r#"{"score": <number>, "reasoning": "<brief explanation>"}"#));
+    }
+
+    #[test]
+    fn test_grade_stdout_uses_template() {
+        let stdout = Some("hello".to_string());
+        let config = ReviewConfig {
+            criteria: "greets".to_string(),
+            threshold: 7,
+            model: None,
+            prompt_template: Some("[{criteria}] {output}".to_string()),
+        };
+
+        let result = grade_stdout(&stdout, &config, |prompt, _model| {
+            assert_eq!(prompt, "[greets] hello");
+            Ok(r#"{"score": 9, "reasoning": "greeted"}"#.to_string())
+        })
+        .unwrap();
+
+        assert!(result.passed);
+    }
+
     #[test]
     fn test_extract_json_plain() {
         let input = r#"{"score": 8, "reasoning": "good"}"#;
@@ -236,6 +559,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extract_json_nested_braces_in_reasoning() {
+        let input = r#"{"score": 7, "reasoning": "prints {\"ok\": true} then a } brace"}"#;
+        assert_eq!(extract_json(input), input);
+    }
+
+    #[test]
+    fn test_extract_json_first_of_two_objects() {
+        let input = "Draft: {not json}\n{\"score\": 4, \"reasoning\": \"first\"}\n{\"score\": 9, \"reasoning\": \"second\"}";
+        assert_eq!(
+            extract_json(input),
+            r#"{"score": 4, "reasoning": "first"}"#
+        );
+    }
+
+    #[test]
+    fn test_grade_stdout_tolerant_response() {
+        let stdout = Some("test".to_string());
+        let config = ReviewConfig {
+            criteria: "test".to_string(),
+            threshold: 7,
+            model: None,
+            prompt_template: None,
+        };
+
+        // Quoted score, braces in reasoning, and trailing prose with a brace
+        let result = grade_stdout(&stdout, &config, |_prompt, _model| {
+            Ok(r#"{"score": "8", "reasoning": "uses {placeholders} correctly"}
+
+Let me know if you need more detail :}"#
+                .to_string())
+        })
+        .unwrap();
+        assert_eq!(result.score, 8);
+        assert_eq!(result.reasoning, "uses {placeholders} correctly");
+        assert!(result.passed);
+
+        let err = grade_stdout(&stdout, &config, |_prompt, _model| {
+            Ok(r#"{"score": "high", "reasoning": "?"}"#.to_string())
+        })
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("invalid score: \"high\""));
+    }
+
     #[test]
     fn test_grade_stdout_passing() {
         let stdout = Some("Task done".to_string());
@@ -243,6 +610,7 @@ mod tests {
             criteria: "should confirm completion".to_string(),
             threshold: 7,
             model: None,
+            prompt_template: None,
         };
 
         let result = grade_stdout(&stdout, &config, |_prompt, _model| {
@@ -262,6 +630,7 @@ mod tests {
             criteria: "should confirm success".to_string(),
             threshold: 7,
             model: None,
+            prompt_template: None,
         };
 
         let result = grade_stdout(&stdout, &config, |_prompt, _model| {
@@ -280,6 +649,7 @@ mod tests {
             criteria: "test".to_string(),
             threshold: 7,
             model: None,
+            prompt_template: None,
         };
 
         // Score above 10 gets clamped
@@ -306,6 +676,7 @@ mod tests {
             criteria: "test".to_string(),
             threshold: 7,
             model: None,
+            prompt_template: None,
         };
         let result = grade_stdout(&stdout, &config, |_, _| {
             Ok(r#"{"score": 7, "reasoning": "borderline"}"#.to_string())
@@ -328,6 +699,7 @@ mod tests {
             criteria: "test".to_string(),
             threshold: 7,
             model: Some("claude-sonnet-4-20250514".to_string()),
+            prompt_template: None,
         };
 
         let result = grade_stdout(&stdout, &config, |_prompt, model| {
@@ -345,6 +717,7 @@ mod tests {
             criteria: "test".to_string(),
             threshold: 7,
             model: None,
+            prompt_template: None,
         };
 
         let result = grade_stdout(&stdout, &config, |_, _| {
@@ -360,12 +733,43 @@ mod tests {
             criteria: "test".to_string(),
             threshold: 7,
             model: None,
+            prompt_template: None,
         };
 
         let result = grade_stdout(&stdout, &config, |_, _| Ok("not json".to_string()));
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_grade_stdout_batch_one_malformed_response() {
+        let config = |criteria: &str| ReviewConfig {
+            criteria: criteria.to_string(),
+            threshold: 7,
+            model: None,
+            prompt_template: None,
+        };
+        let requests = vec![
+            (Some("done".to_string()), config("good")),
+            (Some("done".to_string()), config("bad")),
+        ];
+        let grader = |prompt: String, _model: Option<String>| async move {
+            if prompt.contains("Criteria: bad") {
+                Ok("not json".to_string())
+            } else {
+                Ok(r#"{"score": 8, "reasoning": "ok"}"#.to_string())
+            }
+        };
+
+        let each = futures::executor::block_on(grade_stdout_batch_each_async(&requests, grader));
+        assert_eq!(each.len(), 2);
+        assert!(each[0].as_ref().unwrap().passed);
+        assert!(each[1].is_err());
+
+        assert!(futures::executor::block_on(grade_stdout_batch_async(&requests, grader)).is_err());
+        let results = futures::executor::block_on(grade_stdout_batch_async(&requests[..1], grader)).unwrap();
+        assert_eq!(results[0].score, 8);
+    }
+
     #[test]
     fn test_grade_stdout_json_in_code_fence() {
         let stdout = Some("test".to_string());
@@ -373,6 +777,7 @@ mod tests {
             criteria: "test".to_string(),
             threshold: 7,
             model: None,
+            prompt_template: None,
         };
 
         let result = grade_stdout(&stdout, &config, |_, _| {
@@ -382,4 +787,78 @@ mod tests {
         assert_eq!(result.score, 8);
         assert_eq!(result.reasoning, "wrapped");
     }
+
+    fn review_config(criteria: &str) -> ReviewConfig {
+        ReviewConfig {
+            criteria: criteria.to_string(),
+            threshold: 7,
+            model: None,
+            prompt_template: None,
+        }
+    }
+
+    #[test]
+    fn test_grade_cache_hit_and_miss() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = GradeCache::new(dir.path().join("grades"));
+        let stdout = Some("Done".to_string());
+        let config = review_config("should confirm completion");
+        assert!(cache.get("claude", &stdout, &config).is_none());
+
+        let mut calls = 0;
+        let mut grade = || {
+            grade_stdout_cached("claude", &stdout, &config, Some(&cache), |_, _| {
+                calls += 1;
+                Ok(r#"{"score": 8, "reasoning": "confirmed"}"#.to_string())
+            })
+            .unwrap()
+        };
+        let first = grade();
+        let second = grade();
+
+        assert_eq!(calls, 1);
+        assert_eq!(first, second);
+        assert_eq!(cache.get("claude", &stdout, &config), Some(first));
+    }
+
+    #[test]
+    fn test_grade_cache_key_sensitivity() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = GradeCache::new(dir.path());
+        let stdout = Some("Done".to_string());
+        let config = review_config("should confirm completion");
+        let result = ReviewResult {
+            score: 9,
+            reasoning: "good".to_string(),
+            passed: true,
+        };
+        cache.put("claude", &stdout, &config, &result).unwrap();
+
+        assert!(cache.get("claude", &stdout, &review_config("should report an error")).is_none());
+        assert!(cache.get("claude", &Some("Failed".to_string()), &config).is_none());
+        assert!(cache.get("claude", &None, &config).is_none());
+        let stricter = ReviewConfig {
+            threshold: 10,
+            ..config.clone()
+        };
+        assert!(cache.get("claude", &stdout, &stricter).is_none());
+        let other_model = ReviewConfig {
+            model: Some("claude-sonnet-4".to_string()),
+            ..config.clone()
+        };
+        assert!(cache.get("claude", &stdout, &other_model).is_none());
+        assert!(cache.get("gemini", &stdout, &config).is_none());
+        assert_eq!(cache.get("claude", &stdout, &config), Some(result));
+    }
+
+    #[test]
+    fn test_grade_cache_corrupt_entry_is_miss() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = GradeCache::new(dir.path());
+        let stdout = Some("Done".to_string());
+        let config = review_config("should confirm completion");
+        fs::write(cache.entry_path("claude", &stdout, &config), "{truncated").unwrap();
+
+        assert!(cache.get("claude", &stdout, &config).is_none());
+    }
 }
@@ -0,0 +1,326 @@
+//! Snapshot tests for tool-call sequences.
+//!
+//! A snapshot records each call as its tool name and primary parameter
+//! (the file path, command, pattern, URL, query or path), one call per line,
+//! in a `.snap` file. The first run writes the snapshot; later runs compare
+//! against it and fail with a diff when the sequence drifts. Set
+//! `UPDATE_SNAPSHOTS=1` to rewrite drifted snapshots instead. When `CI` is
+//! set, a missing snapshot fails rather than being written, so a CI run
+//! can't pass against snapshots that were never committed.
+//!
+//! Snapshots live under `tests/snapshots/` in the crate being tested
+//! (`CARGO_MANIFEST_DIR`), or the current directory outside of cargo.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use aptitude::{expect, prompt};
+//!
+//! let output = prompt("Fix the failing test").run_full()?;
+//! expect(&output).tool_calls().matches_snapshot("fix_failing_test");
+//! ```
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::diff::{diff_tool_calls, Change};
+use crate::fluent::Tool;
+use crate::parser::ToolCall;
+
+/// Environment variable that makes drifted snapshots get rewritten.
+pub const UPDATE_ENV: &str = "UPDATE_SNAPSHOTS";
+
+/// Environment variable CI services set, which stops missing snapshots
+/// from being written.
+pub const CI_ENV: &str = "CI";
+
+/// Parameters tried, in order, for calls without a file parameter.
+const PRIMARY_PARAMS: &[&str] = &["command", "pattern", "url", "query", "path"];
+
+/// First line of every snapshot file.
+const HEADER: &str = "# aptitude tool-call snapshot";
+
+/// Which snapshots a check may write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotUpdate {
+    /// Write nothing: missing and drifted snapshots both fail (CI).
+    No,
+    /// Write missing snapshots; drifted ones fail (the default).
+    New,
+    /// Write missing snapshots and rewrite drifted ones (`UPDATE_SNAPSHOTS=1`).
+    All,
+}
+
+/// What happened when a snapshot was checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotOutcome {
+    /// No snapshot existed, so one was written.
+    Created,
+    /// The calls matched the snapshot.
+    Matched,
+    /// The calls drifted and the snapshot was rewritten (`UPDATE_SNAPSHOTS=1`).
+    Updated,
+}
+
+/// Directory snapshots are stored in: `tests/snapshots` under
+/// `CARGO_MANIFEST_DIR`, or under the current directory.
+pub fn snapshot_dir() -> PathBuf {
+    let root = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    root.join("tests").join("snapshots")
+}
+
+/// Path of the snapshot `name`: `<name>.snap` in [`snapshot_dir`].
+///
+/// Fails if `name` is empty, `.` or `..`, or contains a path separator, so
+/// a snapshot can't be read or written outside the snapshot directory.
+pub fn snapshot_path(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(format!(
+            "invalid snapshot name '{}': use a plain file name without path separators",
+            name
+        ));
+    }
+    Ok(snapshot_dir().join(format!("{}.snap", name)))
+}
+
+/// Which snapshots may be written, from `UPDATE_SNAPSHOTS` and `CI`.
+pub fn update_mode() -> SnapshotUpdate {
+    resolve_update_mode(std::env::var_os(UPDATE_ENV), std::env::var_os(CI_ENV))
+}
+
+/// [`update_mode`] from the two variables' values: `UPDATE_SNAPSHOTS=1`
+/// (or `true`) rewrites everything, otherwise a `CI` value other than empty,
+/// `0` or `false` writes nothing.
+fn resolve_update_mode(update: Option<OsString>, ci: Option<OsString>) -> SnapshotUpdate {
+    let is_set = |value: Option<OsString>, truthy: fn(&str) -> bool| {
+        value.is_some_and(|v| truthy(&v.to_string_lossy()))
+    };
+    if is_set(update, |v| v == "1" || v.eq_ignore_ascii_case("true")) {
+        SnapshotUpdate::All
+    } else if is_set(ci, |v| !v.is_empty() && v != "0" && !v.eq_ignore_ascii_case("false")) {
+        SnapshotUpdate::No
+    } else {
+        SnapshotUpdate::New
+    }
+}
+
+/// The parameter a call is summarized by in a snapshot, if it has one.
+///
+/// File tools use their file parameter (see [`Tool::file_path_param`]);
+/// other calls use the first of `command`, `pattern`, `url`, `query` and
+/// `path` that is present.
+pub fn primary_param(call: &ToolCall) -> Option<String> {
+    let file_param = Tool::all()
+        .iter()
+        .find(|t| t.as_str() == call.name)
+        .and_then(|t| t.file_path_param());
+    file_param
+        .into_iter()
+        .chain(PRIMARY_PARAMS.iter().copied())
+        .find_map(|key| call.params.get(key))
+        .map(|value| match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+}
+
+/// Render calls as snapshot text: a header, then `Tool param` per line.
+///
+/// Newlines and backslashes in parameters are escaped so each call stays
+/// on one line.
+pub fn render(calls: &[ToolCall]) -> String {
+    let mut text = format!("{}\n", HEADER);
+    for call in calls {
+        text.push_str(&call.name);
+        if let Some(param) = primary_param(call) {
+            text.push(' ');
+            text.push_str(&param.replace('\\', "\\\\").replace('\n', "\\n"));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Compare calls against the snapshot at `path`.
+///
+/// A missing snapshot is written unless `update` is [`SnapshotUpdate::No`].
+/// With [`SnapshotUpdate::All`], a drifted snapshot is rewritten and
+/// reported as [`SnapshotOutcome::Updated`]. Otherwise drift is an error
+/// whose message is a line diff, `-` for snapshot lines and `+` for the new
+/// run.
+pub fn check_snapshot(path: &Path, calls: &[ToolCall], update: SnapshotUpdate) -> Result<SnapshotOutcome, String> {
+    let actual = render(calls);
+    let write = |outcome| {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(path, &actual)
+            .map_err(|e| format!("failed to write snapshot {}: {}", path.display(), e))?;
+        Ok(outcome)
+    };
+
+    let expected = match std::fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if update == SnapshotUpdate::No {
+                return Err(format!(
+                    "snapshot {} does not exist and {} is set; run the test locally and commit the snapshot",
+                    path.display(),
+                    CI_ENV
+                ));
+            }
+            return write(SnapshotOutcome::Created);
+        }
+        Err(e) => return Err(format!("failed to read snapshot {}: {}", path.display(), e)),
+    };
+
+    // Compare ignoring line endings, so checkouts with CRLF still match
+    if expected.lines().eq(actual.lines()) {
+        return Ok(SnapshotOutcome::Matched);
+    }
+    if update == SnapshotUpdate::All {
+        return write(SnapshotOutcome::Updated);
+    }
+
+    Err(format!(
+        "tool calls differ from snapshot {} (set {}=1 to update):\n{}",
+        path.display(),
+        UPDATE_ENV,
+        diff_lines(&expected, &actual)
+    ))
+}
+
+/// Line diff of two snapshots, using [`diff_tool_calls`] with each line
+/// standing in for a call.
+fn diff_lines(expected: &str, actual: &str) -> String {
+    let as_calls = |text: &str| -> Vec<ToolCall> {
+        text.lines()
+            .filter(|line| *line != HEADER)
+            .map(|line| ToolCall {
+                name: line.to_string(),
+                params: serde_json::Value::Null,
+                timestamp: String::new(),
+                result: None,
+            })
+            .collect()
+    };
+    let (before, after) = (as_calls(expected), as_calls(actual));
+    let diff = diff_tool_calls(&before, &after);
+
+    let mut out = String::new();
+    for change in &diff.changes {
+        let line = &diff.call(change).name;
+        let marker = match change {
+            Change::Unchanged { .. } => ' ',
+            Change::Added { .. } => '+',
+            Change::Removed { .. } => '-',
+            Change::Reordered { .. } => '~',
+        };
+        out.push_str(&format!("  {} {}\n", marker, line));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn make_call(name: &str, params: serde_json::Value) -> ToolCall {
+        ToolCall {
+            name: name.to_string(),
+            params,
+            timestamp: String::new(),
+            result: None,
+        }
+    }
+
+    fn session() -> Vec<ToolCall> {
+        vec![
+            make_call("Glob", json!({"pattern": "**/*.rs"})),
+            make_call("Read", json!({"file_path": "src/lib.rs", "limit": 100})),
+            make_call("Bash", json!({"command": "cat <<EOF\nhi\nEOF"})),
+            make_call("TodoWrite", json!({"todos": []})),
+        ]
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render(&session()),
+            "# aptitude tool-call snapshot\nGlob **/*.rs\nRead src/lib.rs\nBash cat <<EOF\\nhi\\nEOF\nTodoWrite\n"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_created_then_matched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("session.snap");
+
+        assert_eq!(check_snapshot(&path, &session(), SnapshotUpdate::New), Ok(SnapshotOutcome::Created));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), render(&session()));
+        assert_eq!(check_snapshot(&path, &session(), SnapshotUpdate::New), Ok(SnapshotOutcome::Matched));
+    }
+
+    #[test]
+    fn test_snapshot_drift() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.snap");
+        check_snapshot(&path, &session(), SnapshotUpdate::New).unwrap();
+
+        let mut drifted = session();
+        drifted.remove(0);
+        drifted.push(make_call("Edit", json!({"file_path": "src/lib.rs"})));
+
+        let err = check_snapshot(&path, &drifted, SnapshotUpdate::New).unwrap_err();
+        assert!(err.contains("set UPDATE_SNAPSHOTS=1 to update"), "{}", err);
+        assert!(err.contains("  - Glob **/*.rs\n"), "{}", err);
+        assert!(err.contains("    Read src/lib.rs\n"), "{}", err);
+        assert!(err.contains("  + Edit src/lib.rs\n"), "{}", err);
+        // A failed check leaves the snapshot alone
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), render(&session()));
+
+        assert_eq!(check_snapshot(&path, &drifted, SnapshotUpdate::All), Ok(SnapshotOutcome::Updated));
+        assert_eq!(check_snapshot(&path, &drifted, SnapshotUpdate::New), Ok(SnapshotOutcome::Matched));
+    }
+
+    #[test]
+    fn test_snapshot_missing_in_ci() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.snap");
+
+        let err = check_snapshot(&path, &session(), SnapshotUpdate::No).unwrap_err();
+        assert!(err.contains("does not exist and CI is set"), "{}", err);
+        assert!(!path.exists());
+
+        check_snapshot(&path, &session(), SnapshotUpdate::New).unwrap();
+        assert_eq!(check_snapshot(&path, &session(), SnapshotUpdate::No), Ok(SnapshotOutcome::Matched));
+        assert!(check_snapshot(&path, &session()[1..], SnapshotUpdate::No).is_err());
+    }
+
+    #[test]
+    fn test_resolve_update_mode() {
+        let value = |v: &str| Some(OsString::from(v));
+
+        assert_eq!(resolve_update_mode(None, None), SnapshotUpdate::New);
+        assert_eq!(resolve_update_mode(None, value("true")), SnapshotUpdate::No);
+        assert_eq!(resolve_update_mode(None, value("1")), SnapshotUpdate::No);
+        assert_eq!(resolve_update_mode(None, value("false")), SnapshotUpdate::New);
+        assert_eq!(resolve_update_mode(None, value("")), SnapshotUpdate::New);
+        // Updating wins over CI
+        assert_eq!(resolve_update_mode(value("1"), value("true")), SnapshotUpdate::All);
+        assert_eq!(resolve_update_mode(value("0"), value("true")), SnapshotUpdate::No);
+    }
+
+    #[test]
+    fn test_snapshot_path_rejects_separators() {
+        assert!(snapshot_path("fix_failing_test").unwrap().ends_with("tests/snapshots/fix_failing_test.snap"));
+        for name in ["../escape", "a/b", "a\\b", "..", ".", ""] {
+            let err = snapshot_path(name).unwrap_err();
+            assert!(err.contains("invalid snapshot name"), "{}: {}", name, err);
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Aggregate tool usage across many sessions.
+//!
+//! [`aggregate`] summarizes parsed sessions: calls per tool, in total and
+//! per session, and the files read most often. [`find_session_logs`]
+//! collects the session logs under a directory for `aptitude stats`.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use aptitude::parse_session;
+//! use aptitude::stats::{aggregate, find_session_logs};
+//!
+//! let sessions = find_session_logs("logs/".as_ref())?
+//!     .iter()
+//!     .map(|path| parse_session(path))
+//!     .collect::<anyhow::Result<Vec<_>>>()?;
+//! let stats = aggregate(&sessions, 10);
+//! println!("{:.1} calls per session", stats.calls_per_session);
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::fluent::Tool;
+use crate::parser::ToolCall;
+
+/// Usage of one tool across all sessions.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolStats {
+    pub name: String,
+    /// Calls across all sessions.
+    pub calls: usize,
+    /// Sessions that called the tool at least once.
+    pub sessions: usize,
+    /// Average calls per session, counting sessions that never called it.
+    pub per_session: f64,
+}
+
+/// How often one file was read with `Read`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileReads {
+    pub path: String,
+    /// `Read` calls on the file across all sessions.
+    pub reads: usize,
+    /// Sessions that read the file at least once.
+    pub sessions: usize,
+}
+
+/// Tool usage summarized across a set of sessions.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SessionStats {
+    /// Number of sessions summarized.
+    pub sessions: usize,
+    /// Tool calls across all sessions.
+    pub calls: usize,
+    /// Average tool calls per session.
+    pub calls_per_session: f64,
+    /// Every tool called, most called first (ties by name).
+    pub tools: Vec<ToolStats>,
+    /// The most read files, most reads first (ties by path).
+    pub most_read: Vec<FileReads>,
+}
+
+/// Summarize tool usage across `sessions`, keeping the `top_files` most read files.
+///
+/// Tool names are counted as given, so sessions from other agents should be
+/// normalized to canonical names first.
+pub fn aggregate(sessions: &[Vec<ToolCall>], top_files: usize) -> SessionStats {
+    let read_param = Tool::Read.file_path_param();
+    // Per name: (calls, sessions)
+    let mut tools: HashMap<&str, (usize, usize)> = HashMap::new();
+    let mut files: HashMap<&str, (usize, usize)> = HashMap::new();
+
+    for session in sessions {
+        let mut tools_seen: Vec<&str> = Vec::new();
+        let mut files_seen: Vec<&str> = Vec::new();
+        for call in session {
+            let entry = tools.entry(call.name.as_str()).or_default();
+            entry.0 += 1;
+            if !tools_seen.contains(&call.name.as_str()) {
+                tools_seen.push(&call.name);
+                entry.1 += 1;
+            }
+
+            let path = read_param
+                .filter(|_| call.name == Tool::Read.as_str())
+                .and_then(|param| call.params.get(param))
+                .and_then(|v| v.as_str());
+            if let Some(path) = path {
+                let entry = files.entry(path).or_default();
+                entry.0 += 1;
+                if !files_seen.contains(&path) {
+                    files_seen.push(path);
+                    entry.1 += 1;
+                }
+            }
+        }
+    }
+
+    let average = |count: usize| {
+        if sessions.is_empty() {
+            0.0
+        } else {
+            count as f64 / sessions.len() as f64
+        }
+    };
+
+    let mut tools: Vec<ToolStats> = tools
+        .into_iter()
+        .map(|(name, (calls, sessions))| ToolStats {
+            name: name.to_string(),
+            calls,
+            sessions,
+            per_session: average(calls),
+        })
+        .collect();
+    tools.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.name.cmp(&b.name)));
+
+    let mut most_read: Vec<FileReads> = files
+        .into_iter()
+        .map(|(path, (reads, sessions))| FileReads {
+            path: path.to_string(),
+            reads,
+            sessions,
+        })
+        .collect();
+    most_read.sort_by(|a, b| b.reads.cmp(&a.reads).then_with(|| a.path.cmp(&b.path)));
+    most_read.truncate(top_files);
+
+    let calls = sessions.iter().map(Vec::len).sum();
+    SessionStats {
+        sessions: sessions.len(),
+        calls,
+        calls_per_session: average(calls),
+        tools,
+        most_read,
+    }
+}
+
+/// Find session logs (`*.jsonl`, or gzip-compressed `*.jsonl.gz`) under
+/// `dir`, recursively, in path order.
+pub fn find_session_logs(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut logs = Vec::new();
+    for entry in WalkDir::new(dir) {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy();
+        if entry.file_type().is_file() && (name.ends_with(".jsonl") || name.ends_with(".jsonl.gz")) {
+            logs.push(entry.into_path());
+        }
+    }
+    logs.sort();
+    Ok(logs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn call(name: &str, params: serde_json::Value) -> ToolCall {
+        ToolCall {
+            name: name.to_string(),
+            params,
+            timestamp: "2024-01-19T12:00:00Z".to_string(),
+            result: None,
+        }
+    }
+
+    fn read(path: &str) -> ToolCall {
+        call("Read", json!({ "file_path": path }))
+    }
+
+    fn bash(command: &str) -> ToolCall {
+        call("Bash", json!({ "command": command }))
+    }
+
+    #[test]
+    fn test_aggregate_tool_totals_and_averages() {
+        let sessions = vec![
+            vec![read("src/main.rs"), bash("cargo test"), bash("cargo build")],
+            vec![read("src/main.rs"), read("Cargo.toml"), bash("ls")],
+            vec![read("src/main.rs"), read("src/main.rs")],
+            vec![],
+        ];
+
+        let stats = aggregate(&sessions, 10);
+        assert_eq!(stats.sessions, 4);
+        assert_eq!(stats.calls, 8);
+        assert_eq!(stats.calls_per_session, 2.0);
+        assert_eq!(
+            stats.tools,
+            vec![
+                ToolStats {
+                    name: "Read".to_string(),
+                    calls: 5,
+                    sessions: 3,
+                    per_session: 1.25,
+                },
+                ToolStats {
+                    name: "Bash".to_string(),
+                    calls: 3,
+                    sessions: 2,
+                    per_session: 0.75,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_most_read_files() {
+        let sessions = vec![
+            vec![read("b.rs"), read("a.rs"), call("Edit", json!({ "file_path": "c.rs" }))],
+            vec![read("a.rs"), read("a.rs"), read("b.rs"), read("d.rs")],
+        ];
+
+        let stats = aggregate(&sessions, 2);
+        // Edits don't count as reads, and ties sort by path
+        assert_eq!(
+            stats.most_read,
+            vec![
+                FileReads {
+                    path: "a.rs".to_string(),
+                    reads: 3,
+                    sessions: 2,
+                },
+                FileReads {
+                    path: "b.rs".to_string(),
+                    reads: 2,
+                    sessions: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_no_sessions() {
+        let stats = aggregate(&[], 10);
+        assert_eq!(stats.sessions, 0);
+        assert_eq!(stats.calls_per_session, 0.0);
+        assert!(stats.tools.is_empty() && stats.most_read.is_empty());
+    }
+
+    #[test]
+    fn test_stats_serialize_to_json() {
+        let stats = aggregate(&[vec![read("a.rs")]], 10);
+        let value = serde_json::to_value(&stats).unwrap();
+        assert_eq!(value["tools"][0], json!({"name": "Read", "calls": 1, "sessions": 1, "per_session": 1.0}));
+        assert_eq!(value["most_read"][0]["path"], "a.rs");
+    }
+
+    #[test]
+    fn test_find_session_logs() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("project");
+        std::fs::create_dir(&nested).unwrap();
+        for file in ["b.jsonl", "notes.txt", "project/a.jsonl.gz", "project/c.jsonl"] {
+            std::fs::write(dir.path().join(file), "").unwrap();
+        }
+
+        let logs = find_session_logs(dir.path()).unwrap();
+        let names: Vec<_> = logs
+            .iter()
+            .map(|p| p.strip_prefix(dir.path()).unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["b.jsonl", "project/a.jsonl.gz", "project/c.jsonl"]);
+    }
+}
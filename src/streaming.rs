@@ -1,8 +1,11 @@
 //! Streaming tool call support for real-time observation during agent execution.
 //!
-//! This module provides a polling-based file tail that emits tool calls through
-//! an `mpsc::channel` as Claude writes them to the session log, enabling
-//! real-time observation during execution.
+//! This module tails the session log as Claude writes it and emits tool calls
+//! and assistant text through an `mpsc::channel`, enabling real-time
+//! observation during execution. Each tool call is emitted once its result
+//! has been logged, with the result attached. Filesystem notifications wake the tail as
+//! soon as lines are appended, with polling as the fallback where they are
+//! unavailable.
 //!
 //! # Example
 //!
@@ -16,8 +19,10 @@
 //! for event in &handle.receiver {
 //!     match event {
 //!         StreamEvent::ToolCall(tc) => println!("Tool: {}", tc.name),
+//!         StreamEvent::AssistantText(text) => println!("{}", text),
 //!         StreamEvent::SessionDetected(path) => println!("Session: {:?}", path),
 //!         StreamEvent::Error(msg) => eprintln!("Error: {}", msg),
+//!         StreamEvent::Completed { exit_code } => println!("Done ({:?})", exit_code),
 //!     }
 //! }
 //!
@@ -25,29 +30,36 @@
 //! ```
 
 use anyhow::{Context, Result};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::VecDeque;
 use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::Child;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use crate::agents::{
-    find_new_session, get_claude_projects_dir, get_project_dir_for_workdir, list_session_files,
-    ExecutionConfig, RawExecutionResult,
+    claude_command, find_new_session, get_claude_projects_dir, get_project_dir_for_workdir, list_session_files,
+    wait_with_timeout, ExecutionConfig, RawExecutionResult,
 };
-use crate::parser::{parse_line_internal, ToolCall};
+use crate::parser::{parse_line_internal, LineItem, ToolCall};
 
 /// Events emitted during streaming execution.
 #[derive(Debug)]
 pub enum StreamEvent {
     /// A parsed tool call from the session log.
     ToolCall(ToolCall),
+    /// Text the model wrote, from an assistant `text` content block.
+    AssistantText(String),
     /// The session log file was detected at this path.
     SessionDetected(PathBuf),
     /// A non-fatal streaming error.
     Error(String),
+    /// The agent process exited. Always the last event before the channel
+    /// closes; `exit_code` is `None` if it was killed (e.g. on timeout).
+    Completed { exit_code: Option<i32> },
 }
 
 /// Handle to a streaming execution.
@@ -99,16 +111,21 @@ pub fn execute_streaming(
     let claude_dir = get_claude_projects_dir()?;
     let project_dir = get_project_dir_for_workdir(&claude_dir, &config.working_dir)?;
     let existing_sessions = list_session_files(&project_dir)?;
+    let start_time = SystemTime::now();
 
     let (sender, receiver) = mpsc::channel::<StreamEvent>();
 
     // Build the command but don't run it yet — the orchestrator thread will spawn it
     let prompt = prompt.to_string();
     let config = config.clone();
-    let project_dir = project_dir.clone();
-
     let join_handle = thread::spawn(move || -> Result<RawExecutionResult> {
-        orchestrate(prompt, config, project_dir, existing_sessions, sender)
+        let sessions = SessionSearch {
+            project_dir,
+            existing: existing_sessions,
+            start_time,
+            prompt: Some(prompt.clone()),
+        };
+        orchestrate(prompt, config, sessions, sender)
     });
 
     Ok(StreamHandle {
@@ -117,101 +134,137 @@ pub fn execute_streaming(
     })
 }
 
+/// Where to look for a run's session log, and how to tell it apart from
+/// other sessions in the same project.
+#[derive(Debug, Clone)]
+struct SessionSearch {
+    project_dir: PathBuf,
+    /// Session logs that existed before the run.
+    existing: Vec<PathBuf>,
+    start_time: SystemTime,
+    /// The run's prompt, to skip logs of concurrent sessions.
+    prompt: Option<String>,
+}
+
+impl SessionSearch {
+    fn find(&self) -> Result<PathBuf> {
+        find_new_session(
+            &self.project_dir,
+            &self.existing,
+            self.start_time,
+            self.prompt.as_deref(),
+        )
+    }
+}
+
 /// Orchestrator: spawns claude, spawns watcher, waits for completion.
 fn orchestrate(
     prompt: String,
     config: ExecutionConfig,
-    project_dir: PathBuf,
-    existing_sessions: Vec<PathBuf>,
+    sessions: SessionSearch,
     sender: mpsc::Sender<StreamEvent>,
 ) -> Result<RawExecutionResult> {
     // Spawn claude process (non-blocking)
-    let mut cmd = Command::new("claude");
-    cmd.arg("--print").arg(&prompt).stdin(Stdio::null());
-
-    if let Some(dir) = &config.working_dir {
-        cmd.current_dir(dir);
-    }
-
-    for arg in &config.extra_args {
-        cmd.arg(arg);
-    }
-
-    let child = cmd
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
+    let mut cmd = claude_command(&config, None);
+    let child = config
+        .spawn_with_prompt(&mut cmd, &prompt)
         .context("Failed to spawn claude command")?;
 
+    watch_child(child, config.timeout, sessions, sender)
+}
+
+/// Tail the session log while `child` runs, then send [`StreamEvent::Completed`]
+/// as the final event.
+fn watch_child(
+    child: Child,
+    timeout: Option<Duration>,
+    sessions: SessionSearch,
+    sender: mpsc::Sender<StreamEvent>,
+) -> Result<RawExecutionResult> {
     // Shared flag: orchestrator sets this when the process exits
     let process_exited = Arc::new(AtomicBool::new(false));
 
     // Spawn the watcher thread
     let watcher_sender = sender.clone();
     let watcher_exited = Arc::clone(&process_exited);
-    let watcher_project_dir = project_dir.clone();
-    let watcher_existing = existing_sessions.clone();
+    let watcher_sessions = sessions.clone();
 
     let watcher_handle = thread::spawn(move || {
-        watch_for_session(
-            watcher_project_dir,
-            watcher_existing,
-            watcher_sender,
-            watcher_exited,
-        )
+        watch_for_session(watcher_sessions, watcher_sender, watcher_exited)
     });
 
-    // Wait for the claude process to complete
-    let output = child.wait_with_output().context("Failed to wait for claude process")?;
+    // Wait for the claude process to complete (or be killed on timeout)
+    let output = wait_with_timeout(child, timeout, "claude");
 
     // Signal the watcher that the process has exited
     process_exited.store(true, Ordering::Release);
 
     // Wait for the watcher to finish its final drain
-    let session_path = watcher_handle
-        .join()
-        .map_err(|_| anyhow::anyhow!("Watcher thread panicked"))?;
+    let session_path = watcher_handle.join();
+
+    // The watcher has sent everything it will, so this is the last event
+    let _ = sender.send(StreamEvent::Completed {
+        exit_code: output.as_ref().ok().and_then(|o| o.status.code()),
+    });
+
+    let session_path = session_path.map_err(|_| anyhow::anyhow!("Watcher thread panicked"))?;
+    let output = output?;
 
     // Capture stdout
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
     let stdout = if stdout.is_empty() { None } else { Some(stdout) };
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    let stderr = if stderr.is_empty() { None } else { Some(stderr) };
 
     // If the watcher didn't find a session, try once more from the orchestrator
     let session_log_path = match session_path {
         Some(p) => Some(p),
-        None => find_new_session(&project_dir, &existing_sessions).ok(),
+        None => sessions.find().ok(),
     };
 
     Ok(RawExecutionResult {
         session_log_path,
         stdout,
+        stderr,
+        exit_code: output.status.code(),
         agent_context: None,
     })
 }
 
-/// Watcher: polls for a new session file, then tails it.
+// Filesystem events wake the watcher immediately. These intervals bound how
+// late it notices the process exiting, and are the poll rate when
+// notifications are unavailable.
+
+/// Longest wait between checks for the new session file.
+const SESSION_POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Longest wait between reads of the session file.
+const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Watcher: waits for a new session file, then tails it.
 fn watch_for_session(
-    project_dir: PathBuf,
-    existing_sessions: Vec<PathBuf>,
+    sessions: SessionSearch,
     sender: mpsc::Sender<StreamEvent>,
     process_exited: Arc<AtomicBool>,
 ) -> Option<PathBuf> {
-    // Poll for the new session file to appear
+    // Start watching before the first check so a file created in between still wakes us
+    let changes = ChangeNotifier::new(&sessions.project_dir);
+
     let session_path = loop {
-        if let Ok(path) = find_new_session(&project_dir, &existing_sessions) {
+        if let Ok(path) = sessions.find() {
             break path;
         }
 
         if process_exited.load(Ordering::Acquire) {
             // Process exited before we found a session — try one last time
-            if let Ok(path) = find_new_session(&project_dir, &existing_sessions) {
+            if let Ok(path) = sessions.find() {
                 break path;
             }
             return None;
         }
 
-        thread::sleep(Duration::from_millis(200));
+        changes.wait(SESSION_POLL_INTERVAL);
     };
+    drop(changes);
 
     // Notify that we found the session
     let _ = sender.send(StreamEvent::SessionDetected(session_path.clone()));
@@ -224,9 +277,18 @@ fn watch_for_session(
 
 /// Tail a session log file, parsing each new line and sending tool call events.
 fn tail_session_file(
-    path: &PathBuf,
+    path: &Path,
     sender: &mpsc::Sender<StreamEvent>,
     process_exited: &Arc<AtomicBool>,
+) {
+    tail_file(path, sender, process_exited, TAIL_POLL_INTERVAL);
+}
+
+fn tail_file(
+    path: &Path,
+    sender: &mpsc::Sender<StreamEvent>,
+    process_exited: &Arc<AtomicBool>,
+    poll_interval: Duration,
 ) {
     let file = match std::fs::File::open(path) {
         Ok(f) => f,
@@ -239,56 +301,147 @@ fn tail_session_file(
         }
     };
 
+    let changes = ChangeNotifier::new(path);
     let mut reader = BufReader::new(file);
+    let mut pending = String::new();
+    let mut held = HeldEvents::default();
 
     loop {
         let exited = process_exited.load(Ordering::Acquire);
 
         // Read all available lines
-        read_and_send_lines(&mut reader, sender);
+        read_and_send_lines(&mut reader, &mut pending, &mut held, sender);
 
         if exited {
-            // Final drain: read one more time to catch any remaining lines
-            read_and_send_lines(&mut reader, sender);
+            // Final drain: read one more time to catch any remaining lines,
+            // including a last line the agent never terminated, then send
+            // calls whose results were never logged
+            read_and_send_lines(&mut reader, &mut pending, &mut held, sender);
+            send_line(&pending, &mut held, sender);
+            held.flush(sender);
             break;
         }
 
-        thread::sleep(Duration::from_millis(100));
+        changes.wait(poll_interval);
+    }
+}
+
+/// Wakes the watcher when a file or directory changes.
+///
+/// Backed by the platform's filesystem notifications (inotify, FSEvents,
+/// ...). Where those can't be set up, [`wait`](Self::wait) just sleeps, so
+/// the watcher falls back to polling.
+struct ChangeNotifier {
+    watcher: Option<RecommendedWatcher>,
+    events: mpsc::Receiver<()>,
+}
+
+impl ChangeNotifier {
+    fn new(path: &Path) -> Self {
+        let (tx, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |_: notify::Result<notify::Event>| {
+            let _ = tx.send(());
+        })
+        .and_then(|mut watcher| {
+            watcher.watch(path, RecursiveMode::NonRecursive)?;
+            Ok(watcher)
+        })
+        .ok();
+        Self { watcher, events }
+    }
+
+    /// Block until the watched path changes or `timeout` elapses.
+    fn wait(&self, timeout: Duration) {
+        if self.watcher.is_none() {
+            thread::sleep(timeout);
+            return;
+        }
+        if self.events.recv_timeout(timeout).is_ok() {
+            // A burst of writes only needs one read pass
+            while self.events.try_recv().is_ok() {}
+        }
+    }
+}
+
+/// Events parsed from the log but not sent yet.
+///
+/// A tool call's result is logged on a later line than the call itself, so
+/// each call is held until its `tool_result` arrives and is sent with
+/// [`ToolCall::result`] filled in. Events behind a held call wait too, which
+/// keeps them in log order.
+#[derive(Default)]
+struct HeldEvents {
+    /// Each event, with the tool_use id it is still waiting on.
+    queue: VecDeque<(Option<String>, StreamEvent)>,
+}
+
+impl HeldEvents {
+    fn push(&mut self, item: LineItem) {
+        match item {
+            LineItem::Text(text) => self.queue.push_back((None, StreamEvent::AssistantText(text))),
+            LineItem::ToolCall { id, call } => {
+                self.queue.push_back((Some(id), StreamEvent::ToolCall(call)))
+            }
+            LineItem::ToolResult { tool_use_id, content } => {
+                let waiting = self
+                    .queue
+                    .iter_mut()
+                    .find(|(id, _)| id.as_deref() == Some(tool_use_id.as_str()));
+                if let Some((id, StreamEvent::ToolCall(call))) = waiting {
+                    call.result = Some(content);
+                    *id = None;
+                }
+            }
+        }
+    }
+
+    /// Send the events that are no longer waiting on a result. Returns false
+    /// if the receiver was dropped.
+    fn send_ready(&mut self, sender: &mpsc::Sender<StreamEvent>) -> bool {
+        while matches!(self.queue.front(), Some((None, _))) {
+            let (_, event) = self.queue.pop_front().unwrap();
+            if sender.send(event).is_err() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Send everything, including calls that never got a result.
+    fn flush(&mut self, sender: &mpsc::Sender<StreamEvent>) {
+        for (_, event) in self.queue.drain(..) {
+            if sender.send(event).is_err() {
+                return;
+            }
+        }
     }
 }
 
-/// Read all available complete lines from the reader and send tool call events.
+/// Read all available complete lines from the reader and send tool call and
+/// assistant text events.
+///
+/// The agent may be mid-write when we read, so a trailing line without its
+/// newline is kept in `pending` and completed by the next call.
 fn read_and_send_lines(
     reader: &mut BufReader<std::fs::File>,
+    pending: &mut String,
+    held: &mut HeldEvents,
     sender: &mpsc::Sender<StreamEvent>,
 ) {
     loop {
-        let mut line = String::new();
-        match reader.read_line(&mut line) {
+        match reader.read_line(pending) {
             Ok(0) => break, // No more data available
             Ok(_) => {
-                let line = line.trim_end();
-                if line.is_empty() {
-                    continue;
+                if !pending.ends_with('\n') {
+                    break; // Partial line: wait for the rest
                 }
-                match parse_line_internal(line) {
-                    Ok(Some(calls)) => {
-                        for call in calls {
-                            if sender.send(StreamEvent::ToolCall(call)).is_err() {
-                                return; // Receiver dropped
-                            }
-                        }
-                    }
-                    Ok(None) => {} // Not an assistant message with tool calls
-                    Err(e) => {
-                        let _ = sender.send(StreamEvent::Error(format!(
-                            "Parse error: {}",
-                            e
-                        )));
-                    }
+                let line = std::mem::take(pending);
+                if !send_line(&line, held, sender) {
+                    return; // Receiver dropped
                 }
             }
             Err(e) => {
+                pending.clear();
                 let _ = sender.send(StreamEvent::Error(format!("Read error: {}", e)));
                 break;
             }
@@ -296,10 +449,35 @@ fn read_and_send_lines(
     }
 }
 
+/// Parse one session log line and send the events it completes. Returns
+/// false if the receiver was dropped.
+fn send_line(line: &str, held: &mut HeldEvents, sender: &mpsc::Sender<StreamEvent>) -> bool {
+    let line = line.trim_end();
+    if line.is_empty() {
+        return true;
+    }
+    match parse_line_internal(line) {
+        Ok(items) => {
+            for item in items {
+                held.push(item);
+            }
+            return held.send_ready(sender);
+        }
+        Err(e) => {
+            let _ = sender.send(StreamEvent::Error(format!(
+                "Parse error: {}",
+                e
+            )));
+        }
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::io::Write;
+    use std::process::{Command, Stdio};
     use tempfile::TempDir;
 
     /// Helper: create a JSONL line with a tool_use content block.
@@ -310,6 +488,24 @@ mod tests {
         )
     }
 
+    /// Helper: create a JSONL user line with a tool_result for the call above.
+    fn make_tool_result_line(content: &str) -> String {
+        format!(
+            r#"{{"type":"user","timestamp":"2024-01-19T12:00:01Z","message":{{"content":[{{"type":"tool_result","tool_use_id":"123","content":"{}"}}]}}}}"#,
+            content
+        )
+    }
+
+    /// Helper: search `dir` for a session started now, with none before it.
+    fn search(dir: &Path) -> SessionSearch {
+        SessionSearch {
+            project_dir: dir.to_path_buf(),
+            existing: vec![],
+            start_time: SystemTime::now(),
+            prompt: None,
+        }
+    }
+
     #[test]
     fn test_tail_parse_single_line() {
         let dir = TempDir::new().unwrap();
@@ -324,7 +520,9 @@ mod tests {
         let mut reader = BufReader::new(file);
 
         let (sender, receiver) = mpsc::channel();
-        read_and_send_lines(&mut reader, &sender);
+        let mut held = HeldEvents::default();
+        read_and_send_lines(&mut reader, &mut String::new(), &mut held, &sender);
+        held.flush(&sender);
         drop(sender);
 
         let events: Vec<_> = receiver.iter().collect();
@@ -338,6 +536,99 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tail_emits_assistant_text() {
+        let dir = TempDir::new().unwrap();
+        let file_path = dir.path().join("session.jsonl");
+
+        let line = r#"{"type":"assistant","timestamp":"2024-01-19T12:00:00Z","message":{"content":[{"type":"text","text":"Let me read that file."},{"type":"tool_use","id":"123","name":"Read","input":{"file_path":"/tmp/test.txt"}}]}}"#;
+        std::fs::write(&file_path, format!("{}\n", line)).unwrap();
+
+        let file = std::fs::File::open(&file_path).unwrap();
+        let mut reader = BufReader::new(file);
+
+        let (sender, receiver) = mpsc::channel();
+        let mut held = HeldEvents::default();
+        read_and_send_lines(&mut reader, &mut String::new(), &mut held, &sender);
+        held.flush(&sender);
+        drop(sender);
+
+        let events: Vec<_> = receiver.iter().collect();
+        assert_eq!(events.len(), 2);
+        match &events[0] {
+            StreamEvent::AssistantText(text) => assert_eq!(text, "Let me read that file."),
+            other => panic!("Expected AssistantText, got {:?}", other),
+        }
+        assert!(matches!(&events[1], StreamEvent::ToolCall(tc) if tc.name == "Read"));
+    }
+
+    #[test]
+    fn test_tail_waits_for_partial_line() {
+        let dir = TempDir::new().unwrap();
+        let file_path = dir.path().join("session.jsonl");
+        let line = make_tool_use_line("Read", r#"{"file_path":"/tmp/test.txt"}"#);
+        let (head, tail) = line.split_at(line.len() / 2);
+        std::fs::write(&file_path, head).unwrap();
+
+        let file = std::fs::File::open(&file_path).unwrap();
+        let mut reader = BufReader::new(file);
+        let mut pending = String::new();
+        let mut held = HeldEvents::default();
+        let (sender, receiver) = mpsc::channel();
+
+        read_and_send_lines(&mut reader, &mut pending, &mut held, &sender);
+        assert!(receiver.try_recv().is_err(), "partial line should not be parsed");
+
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&file_path)
+            .unwrap();
+        writeln!(f, "{}", tail).unwrap();
+        read_and_send_lines(&mut reader, &mut pending, &mut held, &sender);
+        held.flush(&sender);
+        drop(sender);
+
+        let events: Vec<_> = receiver.iter().collect();
+        assert_eq!(events.len(), 1, "{:?}", events);
+        assert!(matches!(&events[0], StreamEvent::ToolCall(tc) if tc.params["file_path"] == "/tmp/test.txt"));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_tail_attaches_tool_results() {
+        let dir = TempDir::new().unwrap();
+        let file_path = dir.path().join("session.jsonl");
+        let line = make_tool_use_line("Read", r#"{"file_path":"/tmp/test.txt"}"#);
+        std::fs::write(&file_path, format!("{}\n", line)).unwrap();
+
+        let file = std::fs::File::open(&file_path).unwrap();
+        let mut reader = BufReader::new(file);
+        let mut pending = String::new();
+        let mut held = HeldEvents::default();
+        let (sender, receiver) = mpsc::channel();
+
+        read_and_send_lines(&mut reader, &mut pending, &mut held, &sender);
+        assert!(receiver.try_recv().is_err(), "call should wait for its result");
+
+        let mut f = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&file_path)
+            .unwrap();
+        writeln!(f, "{}", make_tool_result_line("hello world")).unwrap();
+        read_and_send_lines(&mut reader, &mut pending, &mut held, &sender);
+        drop(sender);
+
+        let events: Vec<_> = receiver.iter().collect();
+        assert_eq!(events.len(), 1, "{:?}", events);
+        match &events[0] {
+            StreamEvent::ToolCall(tc) => {
+                assert_eq!(tc.name, "Read");
+                assert_eq!(tc.result, Some(serde_json::json!("hello world")));
+            }
+            other => panic!("Expected ToolCall, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_final_drain_captures_all() {
         let dir = TempDir::new().unwrap();
@@ -382,6 +673,85 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_completed_is_last_event() {
+        let dir = TempDir::new().unwrap();
+        let project_dir = dir.path().to_path_buf();
+        let session_file = project_dir.join("test-session.jsonl");
+        let line = make_tool_use_line("Read", r#"{"file_path":"test.txt"}"#);
+
+        // Stand-in agent: writes a session log, then fails
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(format!("echo '{}' > '{}'; exit 3", line, session_file.display()))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let result = watch_child(child, None, search(&project_dir), sender).unwrap();
+
+        let events: Vec<_> = receiver.iter().collect();
+        assert_eq!(result.exit_code, Some(3));
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, StreamEvent::ToolCall(tc) if tc.name == "Read")),
+            "Expected ToolCall event, got {:?}",
+            events
+        );
+        assert!(
+            matches!(events.last(), Some(StreamEvent::Completed { exit_code: Some(3) })),
+            "Expected Completed last, got {:?}",
+            events
+        );
+    }
+
+    #[test]
+    fn test_tail_wakes_on_append() {
+        let dir = TempDir::new().unwrap();
+        let file_path = dir.path().join("session.jsonl");
+        std::fs::write(&file_path, "").unwrap();
+
+        // Far longer than the receive timeout below, so only a filesystem
+        // event can deliver the lines in time
+        let poll_interval = Duration::from_secs(60);
+        let process_exited = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+
+        let tail_path = file_path.clone();
+        let tail_exited = Arc::clone(&process_exited);
+        let tail_handle = thread::spawn(move || {
+            tail_file(&tail_path, &sender, &tail_exited, poll_interval);
+        });
+
+        // Let the tail do its initial read and start waiting
+        thread::sleep(Duration::from_millis(150));
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&file_path)
+            .unwrap();
+        for path in ["/tmp/a.txt", "/tmp/b.txt"] {
+            let params = format!(r#"{{"file_path":"{}"}}"#, path);
+            writeln!(file, "{}", make_tool_use_line("Read", &params)).unwrap();
+            writeln!(file, "{}", make_tool_result_line("contents")).unwrap();
+
+            let event = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+            assert!(
+                matches!(&event, StreamEvent::ToolCall(tc) if tc.params["file_path"] == path),
+                "{:?}",
+                event
+            );
+        }
+
+        // A blank line wakes the tail to see the exit without waiting out the interval
+        process_exited.store(true, Ordering::Release);
+        writeln!(file).unwrap();
+        tail_handle.join().unwrap();
+    }
+
     #[test]
     fn test_watch_detects_new_file() {
         let dir = TempDir::new().unwrap();
@@ -394,7 +764,7 @@ mod tests {
         let watch_exited = Arc::clone(&process_exited);
 
         let watch_handle = thread::spawn(move || {
-            watch_for_session(watch_dir, vec![], sender, watch_exited)
+            watch_for_session(search(&watch_dir), sender, watch_exited)
         });
 
         // Give watcher time to start polling
@@ -0,0 +1,302 @@
+//! Test-support utilities for exercising assertion logic without a real agent CLI.
+//!
+//! Enabled with the `test-util` feature. The [`MockAgent`] can be scripted
+//! with canned tool calls, stdout, and grading responses, then registered
+//! into an [`AgentHarness`](crate::AgentHarness) or passed directly as a grader.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use std::sync::Arc;
+//! use aptitude::test_util::MockAgent;
+//! use aptitude::{expect, AgentHarness, AgentType, ExecutionConfig, Tool};
+//! use serde_json::json;
+//!
+//! let mock = MockAgent::new()
+//!     .with_tool_call("Read", json!({"file_path": "/tmp/a.txt"}))
+//!     .with_stdout("done");
+//!
+//! let mut harness = AgentHarness::new();
+//! harness.register(AgentType::Claude, Arc::new(mock));
+//!
+//! let output = harness.execute(None, "Read a.txt", ExecutionConfig::new())?;
+//! expect(&output).tool(Tool::Read).to_be_called();
+//! ```
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::agents::{Agent, ExecutionConfig, RawExecutionResult, ToolNameMapping};
+use crate::parser::{ToolCall, UsageStats};
+
+/// A scriptable agent that returns canned results instead of invoking a CLI.
+#[derive(Debug, Clone)]
+pub struct MockAgent {
+    name: &'static str,
+    tool_calls: Vec<ToolCall>,
+    stdout: Option<String>,
+    exit_code: Option<i32>,
+    usage: Option<UsageStats>,
+    delay: Option<Duration>,
+    grade_delay: Option<Duration>,
+    failed_runs: usize,
+    runs: Arc<AtomicUsize>,
+    last_extra_args: Arc<Mutex<Vec<String>>>,
+    grade_models: Arc<Mutex<Vec<Option<String>>>>,
+    grade_response: String,
+    available: bool,
+    mapping: ToolNameMapping,
+}
+
+impl MockAgent {
+    /// Create a mock agent with no tool calls, no stdout, exit code 0, no usage, and a passing grade.
+    pub fn new() -> Self {
+        Self {
+            name: "mock",
+            tool_calls: Vec::new(),
+            stdout: None,
+            exit_code: Some(0),
+            usage: None,
+            delay: None,
+            grade_delay: None,
+            failed_runs: 0,
+            runs: Arc::new(AtomicUsize::new(0)),
+            last_extra_args: Arc::new(Mutex::new(Vec::new())),
+            grade_models: Arc::new(Mutex::new(Vec::new())),
+            grade_response: r#"{"score": 9, "reasoning": "Meets criteria"}"#.to_string(),
+            available: true,
+            mapping: ToolNameMapping::new(),
+        }
+    }
+
+    /// Append a tool call to the scripted session.
+    pub fn with_tool_call(mut self, name: &str, params: Value) -> Self {
+        self.tool_calls.push(ToolCall {
+            name: name.to_string(),
+            params,
+            timestamp: String::new(),
+            result: None,
+        });
+        self
+    }
+
+    /// Replace the scripted session with the given tool calls.
+    pub fn with_tool_calls(mut self, calls: Vec<ToolCall>) -> Self {
+        self.tool_calls = calls;
+        self
+    }
+
+    /// Set the stdout returned from execution.
+    pub fn with_stdout(mut self, stdout: &str) -> Self {
+        self.stdout = Some(stdout.to_string());
+        self
+    }
+
+    /// Set the exit code returned from execution.
+    pub fn with_exit_code(mut self, code: i32) -> Self {
+        self.exit_code = Some(code);
+        self
+    }
+
+    /// Set the token and cost usage reported after execution.
+    pub fn with_usage(mut self, usage: UsageStats) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Sleep for `delay` during execution, like a real agent would.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Sleep for `delay` in each [`Agent::grade`] call, like a real grading LLM would.
+    pub fn with_grade_delay(mut self, delay: Duration) -> Self {
+        self.grade_delay = Some(delay);
+        self
+    }
+
+    /// Make the first `n` executions report no tool calls, like a flaky agent.
+    pub fn with_failed_runs(mut self, n: usize) -> Self {
+        self.failed_runs = n;
+        self
+    }
+
+    /// Number of times [`Agent::execute`] has been called.
+    pub fn runs(&self) -> usize {
+        self.runs.load(Ordering::SeqCst)
+    }
+
+    /// The `extra_args` of the most recent [`Agent::execute`] call.
+    pub fn last_extra_args(&self) -> Vec<String> {
+        self.last_extra_args.lock().unwrap().clone()
+    }
+
+    /// The model passed to each [`Agent::grade`] call, in order.
+    pub fn grade_models(&self) -> Vec<Option<String>> {
+        self.grade_models.lock().unwrap().clone()
+    }
+
+    /// Set the raw text returned from [`Agent::grade`].
+    pub fn with_grade_response(mut self, response: &str) -> Self {
+        self.grade_response = response.to_string();
+        self
+    }
+
+    /// Set a well-formed grading response with the given score and reasoning.
+    pub fn with_grade(self, score: u32, reasoning: &str) -> Self {
+        let response = serde_json::json!({"score": score, "reasoning": reasoning}).to_string();
+        self.with_grade_response(&response)
+    }
+
+    /// Set the tool name mapping applied by the harness during normalization.
+    pub fn with_mapping(mut self, mapping: ToolNameMapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    /// Report `name` from [`Agent::name`] instead of "mock".
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Mark the agent as unavailable, as if its CLI were not installed.
+    pub fn unavailable(mut self) -> Self {
+        self.available = false;
+        self
+    }
+}
+
+impl Default for MockAgent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Agent for MockAgent {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn execute(&self, _prompt: &str, config: &ExecutionConfig) -> Result<RawExecutionResult> {
+        if let Some(delay) = self.delay {
+            std::thread::sleep(delay);
+        }
+        self.runs.fetch_add(1, Ordering::SeqCst);
+        *self.last_extra_args.lock().unwrap() = config.extra_args.clone();
+        Ok(RawExecutionResult {
+            session_log_path: None,
+            stdout: self.stdout.clone(),
+            stderr: None,
+            exit_code: self.exit_code,
+            agent_context: None,
+        })
+    }
+
+    fn parse_session(&self, _result: &RawExecutionResult) -> Result<Vec<ToolCall>> {
+        if self.runs() <= self.failed_runs {
+            return Ok(Vec::new());
+        }
+        Ok(self.tool_calls.clone())
+    }
+
+    fn parse_usage(&self, _result: &RawExecutionResult) -> Option<UsageStats> {
+        self.usage
+    }
+
+    fn tool_mapping(&self) -> &ToolNameMapping {
+        &self.mapping
+    }
+
+    fn supports_resume(&self) -> bool {
+        true
+    }
+
+    fn resume(
+        &self,
+        _previous: &RawExecutionResult,
+        prompt: &str,
+        config: &ExecutionConfig,
+    ) -> Result<RawExecutionResult> {
+        self.execute(prompt, config)
+    }
+
+    fn is_available(&self) -> bool {
+        self.available
+    }
+
+    fn grade(&self, _prompt: &str, model: Option<&str>) -> Result<String> {
+        if let Some(delay) = self.grade_delay {
+            std::thread::sleep(delay);
+        }
+        self.grade_models.lock().unwrap().push(model.map(str::to_string));
+        Ok(self.grade_response.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::{AgentHarness, AgentType};
+    use crate::fluent::{expect, Tool};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_mock_agent_through_harness() {
+        let mut mapping = ToolNameMapping::new();
+        mapping.add("read_file", "Read");
+        let mock = MockAgent::new()
+            .with_tool_call("read_file", json!({"file_path": "/tmp/a.txt"}))
+            .with_stdout("done")
+            .with_mapping(mapping);
+
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(mock));
+
+        let output = harness
+            .execute(None, "Read a.txt", ExecutionConfig::new())
+            .unwrap();
+        assert_eq!(output.result.agent_name, "mock");
+        assert_eq!(output.stdout.as_deref(), Some("done"));
+        expect(&output).tool(Tool::Read).times(1).to_be_called();
+    }
+
+    #[test]
+    fn test_unavailable_mock_agent() {
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(MockAgent::new().unavailable()));
+
+        let err = harness
+            .execute(None, "prompt", ExecutionConfig::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("not available"));
+    }
+
+    #[test]
+    fn test_with_grade() {
+        let mock = MockAgent::new().with_grade(4, "too vague");
+        let response = mock.grade("prompt", None).unwrap();
+        let parsed: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(parsed["score"], 4);
+        assert_eq!(parsed["reasoning"], "too vague");
+    }
+
+    #[test]
+    fn test_model_forwarded_to_agent() {
+        let mock = MockAgent::new();
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(mock.clone()));
+
+        let config = ExecutionConfig::new().with_model("claude-haiku-4-5");
+        harness.execute(None, "prompt", config).unwrap();
+
+        assert_eq!(mock.last_extra_args(), vec!["--model", "claude-haiku-4-5"]);
+    }
+}
@@ -0,0 +1,142 @@
+//! Setup and teardown shell hooks for YAML tests.
+//!
+//! Each hook is a shell command run with `sh -c` in the test's working
+//! directory, with the test's `env` applied. Hook output is captured so it
+//! doesn't interleave with machine-readable results.
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+use super::Test;
+
+/// Run `body` between the test's `setup` and `teardown` commands.
+///
+/// Setup commands run in order and the first failure aborts the test without
+/// running `body`. Teardown always runs, even when setup or `body` failed. A
+/// teardown failure is returned as an error only if everything before it
+/// succeeded; otherwise it is printed as a warning and the earlier error wins.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let results = with_hooks(&test, workdir, || {
+///     let output = harness.execute(None, &test.prompt, config.clone())?;
+///     Ok(run_yaml_test_with_cache(&test, &output.result.tool_calls, &output.stdout, output.exit_code, None, None))
+/// })?;
+/// ```
+pub fn with_hooks<T, F>(test: &Test, workdir: Option<&Path>, body: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T>,
+{
+    let result = run_hooks("setup", &test.setup, test, workdir).and_then(|_| body());
+    let teardown = run_hooks("teardown", &test.teardown, test, workdir);
+
+    match (result, teardown) {
+        (Ok(value), Ok(())) => Ok(value),
+        (Ok(_), Err(e)) => Err(e),
+        (Err(e), Ok(())) => Err(e),
+        (Err(e), Err(teardown_err)) => {
+            eprintln!("Warning: {:#}", teardown_err);
+            Err(e)
+        }
+    }
+}
+
+/// Run each command in order, stopping at the first failure.
+fn run_hooks(phase: &str, commands: &[String], test: &Test, workdir: Option<&Path>) -> Result<()> {
+    for command in commands {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command).envs(&test.env);
+        if let Some(dir) = workdir {
+            cmd.current_dir(dir);
+        }
+
+        let output = cmd
+            .output()
+            .with_context(|| format!("Failed to run {} command `{}`", phase, command))?;
+        if !output.status.success() {
+            let status = match output.status.code() {
+                Some(code) => format!("exit code {}", code),
+                None => "a signal".to_string(),
+            };
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr = stderr.trim();
+            if stderr.is_empty() {
+                bail!("{} command `{}` failed with {}", phase, command, status);
+            }
+            bail!("{} command `{}` failed with {}: {}", phase, command, status, stderr);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn hook_test(setup: &[&str], teardown: &[&str]) -> Test {
+        let yaml = "name: Hooks\nprompt: Do it\nassertions: []";
+        let mut test: Test = serde_yaml::from_str(yaml).unwrap();
+        test.setup = setup.iter().map(|s| s.to_string()).collect();
+        test.teardown = teardown.iter().map(|s| s.to_string()).collect();
+        test.env.insert("STEP".to_string(), "teardown".to_string());
+        test
+    }
+
+    #[test]
+    fn test_setup_before_and_teardown_after() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("log.txt");
+        let test = hook_test(&["echo setup >> log.txt"], &["echo $STEP >> log.txt"]);
+
+        let value = with_hooks(&test, Some(dir.path()), || {
+            // The agent would run here, after setup has prepared the fixture
+            fs::write(&log, fs::read_to_string(&log)? + "agent\n")?;
+            Ok(42)
+        })
+        .unwrap();
+
+        assert_eq!(value, 42);
+        assert_eq!(fs::read_to_string(&log).unwrap(), "setup\nagent\nteardown\n");
+    }
+
+    #[test]
+    fn test_failed_setup_aborts_but_tears_down() {
+        let dir = tempfile::tempdir().unwrap();
+        let test = hook_test(
+            &["echo broken >&2; exit 3", "touch never"],
+            &["touch cleaned"],
+        );
+
+        let mut ran = false;
+        let err = with_hooks(&test, Some(dir.path()), || {
+            ran = true;
+            Ok(())
+        })
+        .unwrap_err();
+
+        assert!(!ran);
+        assert_eq!(
+            err.to_string(),
+            "setup command `echo broken >&2; exit 3` failed with exit code 3: broken"
+        );
+        assert!(!dir.path().join("never").exists());
+        assert!(dir.path().join("cleaned").exists());
+    }
+
+    #[test]
+    fn test_teardown_runs_when_body_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let test = hook_test(&[], &["touch cleaned"]);
+
+        let err = with_hooks(&test, Some(dir.path()), || -> Result<()> {
+            bail!("agent crashed")
+        })
+        .unwrap_err();
+
+        assert_eq!(err.to_string(), "agent crashed");
+        assert!(dir.path().join("cleaned").exists());
+    }
+}
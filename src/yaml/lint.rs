@@ -0,0 +1,247 @@
+//! Static checks for contradictory YAML test assertions.
+//!
+//! [`lint_test`] looks at a test's assertions together, without running an
+//! agent, and reports combinations that can never pass: a tool asserted both
+//! called and not called, impossible count ranges, and ordering against a
+//! tool that is asserted never to be called.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use aptitude::yaml::{lint_test, load_test};
+//!
+//! let test = load_test("tests/read_file.aptitude.yaml")?;
+//! for issue in lint_test(&test) {
+//!     println!("{}", issue);
+//! }
+//! ```
+
+use std::fmt;
+
+use crate::fluent::Tool;
+
+use super::parser::{parse_tool_name, Assertion, Test};
+use super::runner::validate_test;
+
+/// How serious a lint issue is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The test might still pass, depending on parameters.
+    Warning,
+    /// The test can never pass as written.
+    Error,
+}
+
+/// A problem found by [`lint_test`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl LintIssue {
+    fn error(message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            message,
+        }
+    }
+
+    fn warning(message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}", label, self.message)
+    }
+}
+
+/// Report contradictory or invalid assertions in a test.
+///
+/// Everything [`validate_test`] reports is an error. On top of that:
+/// - A tool asserted called and also asserted not called, either with no
+///   param filters or with the same ones, is an error.
+/// - Ordering (`called_after`, `called_before`, `immediately_*`, `sequence`)
+///   against a tool asserted not called is an error, or a warning when the
+///   not-called assertion filters params, since other calls of that tool may
+///   still satisfy the ordering.
+///
+/// Assertion numbers in messages are 1-based.
+pub fn lint_test(test: &Test) -> Vec<LintIssue> {
+    let mut issues: Vec<LintIssue> = validate_test(test)
+        .into_iter()
+        .map(LintIssue::error)
+        .collect();
+
+    // Tool assertions whose tool name resolves, with their 1-based numbers
+    let tool_assertions: Vec<(usize, Tool, &Assertion)> = test
+        .assertions
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| a.stdout.is_none() && a.exit_code.is_none() && a.sequence.is_none())
+        .filter(|(_, a)| !a.no_tools && a.only_tools.is_none())
+        .filter(|(_, a)| a.files_read.is_none() && a.files_written.is_none())
+        .filter_map(|(i, a)| Some((i + 1, parse_tool_name(a.tool_name()?).ok()?, a)))
+        .collect();
+    let not_called: Vec<&(usize, Tool, &Assertion)> = tool_assertions
+        .iter()
+        .filter(|(_, _, a)| !a.called)
+        .collect();
+
+    for (n, tool, assertion) in tool_assertions
+        .iter()
+        .filter(|(_, _, a)| a.called && a.not_called_between.is_none())
+    {
+        for (m, _, negated) in not_called.iter().filter(|(_, t, _)| t == tool) {
+            let same_params = !has_param_filters(negated)
+                || (negated.params == assertion.params
+                    && negated.cell_index == assertion.cell_index
+                    && negated.bash == assertion.bash
+                    && negated.match_mode == assertion.match_mode);
+            if same_params {
+                issues.push(LintIssue::error(format!(
+                    "assertion {}: '{}' is asserted called, but assertion {} asserts it is not called",
+                    n, tool, m
+                )));
+            }
+        }
+    }
+
+    for (i, assertion) in test.assertions.iter().enumerate() {
+        let referenced: Vec<&String> = match &assertion.sequence {
+            Some(names) => names.iter().collect(),
+            None if assertion.tool_name().is_some() && assertion.called => [
+                &assertion.called_after,
+                &assertion.called_before,
+                &assertion.immediately_after,
+                &assertion.immediately_before,
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            None => Vec::new(),
+        };
+
+        for name in referenced {
+            let Ok(other) = parse_tool_name(name) else {
+                continue;
+            };
+            for (m, _, negated) in not_called.iter().filter(|(_, t, _)| *t == other) {
+                if !has_param_filters(negated) {
+                    issues.push(LintIssue::error(format!(
+                        "assertion {}: orders against '{}', but assertion {} asserts it is not called",
+                        i + 1,
+                        other,
+                        m
+                    )));
+                } else {
+                    issues.push(LintIssue::warning(format!(
+                        "assertion {}: orders against '{}', which assertion {} asserts is not called with some params",
+                        i + 1,
+                        other,
+                        m
+                    )));
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+/// Whether an assertion only covers some calls of its tool.
+fn has_param_filters(assertion: &Assertion) -> bool {
+    assertion.params.is_some()
+        || assertion.cell_index.is_some()
+        || assertion.bash.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(assertions: &str) -> Vec<LintIssue> {
+        let yaml = format!("name: Lint\nprompt: Do it\nassertions:\n{}", assertions);
+        let test: Test = serde_yaml::from_str(&yaml).unwrap();
+        lint_test(&test)
+    }
+
+    fn messages(issues: &[LintIssue]) -> Vec<String> {
+        issues.iter().map(|issue| issue.to_string()).collect()
+    }
+
+    #[test]
+    fn test_consistent_test_is_clean() {
+        let issues = lint(
+            "  - tool: Read\n  - tool: Edit\n    called_after: Read\n  - tool: Bash\n    called: false\n  - sequence: [Read, Edit]\n",
+        );
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_called_and_not_called() {
+        let issues = lint("  - tool: Read\n  - tool: read\n    called: false\n");
+        assert_eq!(
+            messages(&issues),
+            vec!["error: assertion 1: 'Read' is asserted called, but assertion 2 asserts it is not called"]
+        );
+
+        // Same params on both sides is just as impossible
+        let issues = lint(
+            "  - tool: Read\n    params:\n      file_path: a.txt\n  - tool: Read\n    called: false\n    params:\n      file_path: a.txt\n",
+        );
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Error);
+
+        // Different params can both hold
+        let issues = lint(
+            "  - tool: Read\n    params:\n      file_path: a.txt\n  - tool: Read\n    called: false\n    params:\n      file_path: secrets\n",
+        );
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_impossible_count_range() {
+        let issues = lint("  - tool: Read\n    min_calls: 3\n    max_calls: 1\n");
+        assert_eq!(
+            messages(&issues),
+            vec!["error: assertion 1: 'min_calls' (3) cannot be greater than 'max_calls' (1)"]
+        );
+
+        let issues = lint("  - tool: Bash\n    called: false\n    min_calls: 1\n");
+        assert_eq!(
+            messages(&issues),
+            vec!["error: assertion 1: 'called: false' cannot be combined with 'min_calls'"]
+        );
+    }
+
+    #[test]
+    fn test_ordering_against_not_called_tool() {
+        let issues = lint(
+            "  - tool: Edit\n    called_after: Read\n  - sequence: [Read, Edit]\n  - tool: Read\n    called: false\n",
+        );
+        assert_eq!(
+            messages(&issues),
+            vec![
+                "error: assertion 1: orders against 'Read', but assertion 3 asserts it is not called",
+                "error: assertion 2: orders against 'Read', but assertion 3 asserts it is not called",
+            ]
+        );
+
+        let issues = lint(
+            "  - tool: Edit\n    immediately_before: Bash\n  - tool: Bash\n    called: false\n    params:\n      command: rm\n",
+        );
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert!(issues[0].message.contains("with some params"));
+    }
+}
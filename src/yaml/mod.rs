@@ -29,8 +29,16 @@
 //! let results = run_yaml_test(&test, &tool_calls);
 //! ```
 
+mod hooks;
+mod lint;
 mod parser;
 mod runner;
 
-pub use parser::{load_test, parse_tool_name, Assertion, StdoutConstraints, Test, YamlError};
-pub use runner::{run_yaml_test, TestResult};
+pub use hooks::with_hooks;
+pub use lint::{lint_test, LintIssue, Severity};
+pub use parser::{
+    load_test, parse_tool_name, Assertion, FilesConstraints, ParamCount, StdoutConstraints, Test, YamlError,
+};
+pub use runner::{
+    check_test_args, run_with_budget, run_with_retries, run_yaml_test, run_yaml_test_report, run_yaml_test_with_cache, validate_test, TestResult,
+};
@@ -3,17 +3,18 @@
 //! This module handles YAML deserialization and string-to-Tool enum conversion.
 //! All string parsing logic (case handling, aliases) lives here.
 
-use crate::fluent::Tool;
+use crate::agents::ExecutionConfig;
+use crate::fluent::{MatchMode, Tool};
 use anyhow::{Context, Result};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Error type for YAML parsing issues.
 #[derive(Debug, thiserror::Error)]
 pub enum YamlError {
-    #[error("Unknown tool: '{0}'. Available tools: Read, Write, Edit, Bash, Glob, Grep, Task, WebFetch, WebSearch, NotebookEdit, AskUserQuestion, TodoWrite, Skill")]
+    #[error("Unknown tool: '{0}'. Available tools: Read, Write, Edit, Bash, Glob, Grep, LS, Task, WebFetch, WebSearch, NotebookEdit, AskUserQuestion, TodoWrite, KillShell, TaskOutput, Skill")]
     UnknownTool(String),
 
     #[error("IO error: {0}")]
@@ -33,30 +34,134 @@ pub struct Test {
     /// Agent to use for this test (defaults to "claude").
     #[serde(default)]
     pub agent: Option<String>,
+    /// Working directory for this test, relative to the test file.
+    /// Takes precedence over the CLI `--workdir` flag.
+    #[serde(default)]
+    pub workdir: Option<PathBuf>,
+    /// Model to run the agent with, also used to grade stdout reviews that
+    /// don't set their own. The CLI `--model` flag takes precedence.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Environment variables set on the agent process.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Extra command-line arguments appended to the agent command, such as
+    /// `--dangerously-skip-permissions`.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Shell commands run in the working directory before the agent.
+    /// If one fails, the test is aborted.
+    #[serde(default)]
+    pub setup: Vec<String>,
+    /// Shell commands run in the working directory after assertions,
+    /// even if the test failed.
+    #[serde(default)]
+    pub teardown: Vec<String>,
+    /// Rewrite absolute tool call paths relative to the working directory
+    /// before assertions run, so `file_path: "src/main.rs"` can match.
+    #[serde(default)]
+    pub normalize_paths: bool,
+    /// Wall-clock budget in seconds for running the agent and evaluating
+    /// assertions, across all retries. Overrunning fails the test.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
     /// List of assertions to evaluate.
     pub assertions: Vec<Assertion>,
 }
 
-/// A single assertion about tool usage or stdout.
+impl Test {
+    /// Resolve the test's `workdir` against the directory containing the test file.
+    ///
+    /// Absolute paths are returned unchanged. Returns `None` if the test
+    /// does not set a `workdir`.
+    pub fn resolve_workdir(&self, test_path: &Path) -> Option<PathBuf> {
+        let workdir = self.workdir.as_ref()?;
+        let base = test_path.parent().unwrap_or(Path::new("."));
+        Some(base.join(workdir))
+    }
+
+    /// Apply the test's `model`, `args`, `env` and `normalize_paths` to an
+    /// execution config. `--model` goes before the test's own `args`.
+    pub fn apply_to_config(&self, mut config: ExecutionConfig) -> ExecutionConfig {
+        if let Some(model) = &self.model {
+            config = config.with_model(model);
+        }
+        config.extra_args.extend(self.args.iter().cloned());
+        config.env.extend(self.env.clone());
+        config.with_normalize_paths(self.normalize_paths)
+    }
+
+    /// Turn `called_after`/`called_before` into their adjacency variants
+    /// when ordering is strict.
+    ///
+    /// An assertion's own `strict_order` wins over `strict_by_default` (the
+    /// config's `ordering_strict`). `immediately_after`/`immediately_before`
+    /// are always strict and are left alone.
+    pub fn resolve_ordering(&mut self, strict_by_default: bool) {
+        for assertion in &mut self.assertions {
+            if !assertion.strict_order.unwrap_or(strict_by_default) {
+                continue;
+            }
+            if assertion.immediately_after.is_none() {
+                assertion.immediately_after = assertion.called_after.take();
+            }
+            if assertion.immediately_before.is_none() {
+                assertion.immediately_before = assertion.called_before.take();
+            }
+        }
+    }
+}
+
+/// A single assertion about tool usage, stdout, or the exit code.
 #[derive(Debug, Deserialize)]
 pub struct Assertion {
-    /// Tool name (case-insensitive, supports aliases). Optional if using stdout assertion.
+    /// Tool name (case-insensitive, supports aliases). Optional if using a stdout, exit code, sequence or files assertion.
     pub tool: Option<String>,
     /// Whether this tool should be called (default: true).
     #[serde(default = "default_true")]
     pub called: bool,
     /// Parameter patterns to match (glob, regex, or exact).
     pub params: Option<HashMap<String, String>>,
+    /// How parameter values are compared: `regex` (default), `exact`, or `glob`.
+    /// Applies to `params` and the call-specific parameter fields.
+    #[serde(default, rename = "match")]
+    pub match_mode: MatchMode,
+    /// Fail if any matching call has one of these parameters.
+    pub params_absent: Option<Vec<String>>,
+    /// Fail if any matching call has a parameter matching its pattern.
+    /// Patterns follow `match` like `params`.
+    pub params_not_matching: Option<HashMap<String, String>>,
+    /// Only match `NotebookEdit` calls on this cell (`cell_number` or `cell_id`).
+    pub cell_index: Option<usize>,
+    /// Only match `Bash` calls whose command is or contains a literal string.
+    /// Implies `tool: Bash`.
+    pub bash: Option<BashConstraints>,
     /// Assert this tool is called after another tool.
     pub called_after: Option<String>,
     /// Assert this tool is called before another tool.
     pub called_before: Option<String>,
+    /// Assert this tool is called directly after another tool.
+    pub immediately_after: Option<String>,
+    /// Assert this tool is called directly before another tool.
+    pub immediately_before: Option<String>,
+    /// Assert this tool is not called between two other tools: after the
+    /// first call to the first one and before the next call to the second.
+    /// Replaces the usual called check.
+    pub not_called_between: Option<[String; 2]>,
+    /// Whether `called_after`/`called_before` require adjacent calls,
+    /// overriding the config's `ordering_strict`.
+    pub strict_order: Option<bool>,
     /// Assert exact number of times the tool was called.
     pub call_count: Option<u32>,
     /// Assert maximum number of times the tool can be called.
     pub max_calls: Option<u32>,
     /// Assert minimum number of times the tool must be called.
     pub min_calls: Option<u32>,
+    /// Assert no two matching calls share a value for this parameter.
+    pub distinct_param: Option<String>,
+    /// Call counts scoped to calls matching each entry's `params`, each
+    /// checked on its own.
+    pub param_counts: Option<Vec<ParamCount>>,
     /// Assert parameters for specific call indices (1-based).
     pub nth_call_params: Option<HashMap<u32, HashMap<String, String>>>,
     /// Assert parameters for the first call.
@@ -65,13 +170,79 @@ pub struct Assertion {
     pub last_call_params: Option<HashMap<String, String>>,
     /// Stdout assertion constraints. If present, this is a stdout assertion.
     pub stdout: Option<StdoutConstraints>,
+    /// Expected exit code of the agent command. If present, this is an exit code assertion.
+    pub exit_code: Option<i32>,
+    /// Tools that must be called in this order. If present, this is a sequence assertion.
+    pub sequence: Option<Vec<String>>,
+    /// Require the `sequence` tools to be called with no other calls in between.
+    #[serde(default)]
+    pub consecutive: bool,
+    /// Assert the agent called no tools at all. If `true`, this is a no-tools assertion.
+    #[serde(default)]
+    pub no_tools: bool,
+    /// The only tools the agent may call. If present, this is an allowlist assertion.
+    pub only_tools: Option<Vec<String>>,
+    /// Limits on the distinct files read. If present, this is a files assertion.
+    pub files_read: Option<FilesConstraints>,
+    /// Limits on the distinct files written or edited. If present, this is a files assertion.
+    pub files_written: Option<FilesConstraints>,
+}
+
+/// Constraints on a `Bash` call's command. At least one must be set.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct BashConstraints {
+    /// The exact command, ignoring surrounding whitespace.
+    pub ran: Option<String>,
+    /// Substring the command must contain, taken literally.
+    pub contains: Option<String>,
+}
+
+impl Assertion {
+    /// The tool this assertion is about: `tool`, or `Bash` for a `bash` assertion.
+    pub fn tool_name(&self) -> Option<&str> {
+        self.tool.as_deref().or(self.bash.as_ref().map(|_| "Bash"))
+    }
 }
 
-/// Constraints for stdout assertions (LLM-graded review).
+/// A call count for the calls whose parameters match `params`, from an
+/// assertion's `param_counts`.
+///
+/// Patterns follow the assertion's `match` mode. Unlike the assertion's own
+/// counts, no matching calls passes unless `count` or `min_calls` requires some.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ParamCount {
+    /// Parameter patterns selecting the calls to count.
+    pub params: HashMap<String, String>,
+    /// Exact number of matching calls.
+    pub count: Option<u32>,
+    /// Minimum number of matching calls.
+    pub min_calls: Option<u32>,
+    /// Maximum number of matching calls.
+    pub max_calls: Option<u32>,
+}
+
+/// Constraints for `files_read` and `files_written` assertions.
+#[derive(Debug, Deserialize, Clone)]
+pub struct FilesConstraints {
+    /// Maximum number of distinct files.
+    pub max: usize,
+}
+
+/// Constraints for stdout assertions.
+///
+/// `contains`, `not_contains` and `matches` are checked locally; `review` is
+/// graded by an LLM. At least one must be set.
 #[derive(Debug, Deserialize, Clone)]
 pub struct StdoutConstraints {
-    /// Natural language criteria for grading stdout.
+    /// Natural language criteria for grading stdout (empty: no review).
+    #[serde(default)]
     pub review: String,
+    /// Substring stdout must contain.
+    pub contains: Option<String>,
+    /// Substring stdout must not contain.
+    pub not_contains: Option<String>,
+    /// Regex stdout must match (unanchored).
+    pub matches: Option<String>,
     /// Minimum score to pass (1-10, default: 7).
     #[serde(default = "default_threshold")]
     pub threshold: u32,
@@ -79,6 +250,8 @@ pub struct StdoutConstraints {
     pub model: Option<String>,
     /// Agent to use for grading (default: uses the test's agent).
     pub agent: Option<String>,
+    /// Custom grading prompt with `{criteria}` and `{output}` placeholders.
+    pub template: Option<String>,
 }
 
 fn default_threshold() -> u32 {
@@ -139,6 +312,7 @@ pub fn parse_tool_name(s: &str) -> Result<Tool, YamlError> {
         "bash" => Ok(Tool::Bash),
         "glob" => Ok(Tool::Glob),
         "grep" => Ok(Tool::Grep),
+        "ls" => Ok(Tool::ListDirectory),
         "task" => Ok(Tool::Task),
         "webfetch" => Ok(Tool::WebFetch),
         "websearch" => Ok(Tool::WebSearch),
@@ -156,6 +330,7 @@ pub fn parse_tool_name(s: &str) -> Result<Tool, YamlError> {
         "execute_command" => Ok(Tool::Bash),
         "glob_files" => Ok(Tool::Glob),
         "search_files" => Ok(Tool::Grep),
+        "list_directory" | "listdirectory" => Ok(Tool::ListDirectory),
         "web_fetch" => Ok(Tool::WebFetch),
         "web_search" => Ok(Tool::WebSearch),
         "notebook_edit" => Ok(Tool::NotebookEdit),
@@ -193,6 +368,28 @@ mod tests {
         assert_eq!(parse_tool_name("write_file").unwrap(), Tool::Write);
         assert_eq!(parse_tool_name("execute_command").unwrap(), Tool::Bash);
         assert_eq!(parse_tool_name("search_files").unwrap(), Tool::Grep);
+        assert_eq!(parse_tool_name("list_directory").unwrap(), Tool::ListDirectory);
+    }
+
+    #[test]
+    fn test_parse_tool_name_list_directory() {
+        assert_eq!(parse_tool_name("LS").unwrap(), Tool::ListDirectory);
+        assert_eq!(parse_tool_name("ls").unwrap(), Tool::ListDirectory);
+        assert_eq!(parse_tool_name("ListDirectory").unwrap(), Tool::ListDirectory);
+
+        let yaml = "tool: ls
+called: false";
+        let assertion: Assertion = serde_yaml::from_str(yaml).unwrap();
+        let tool = parse_tool_name(assertion.tool.as_deref().unwrap()).unwrap();
+        assert_eq!(tool.as_str(), "LS");
+    }
+
+    #[test]
+    fn test_unknown_tool_lists_available_tools() {
+        let message = parse_tool_name("Teleport").unwrap_err().to_string();
+        for tool in Tool::all() {
+            assert!(message.contains(tool.as_str()), "missing {} in: {}", tool, message);
+        }
     }
 
     #[test]
@@ -215,6 +412,23 @@ params:
         assert!(assertion.params.is_some());
     }
 
+    #[test]
+    fn test_deserialize_match_mode() {
+        let yaml = r#"
+tool: Read
+match: exact
+params:
+  file_path: "/tmp/a.txt"
+"#;
+        let assertion: Assertion = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(assertion.match_mode, MatchMode::Exact);
+
+        let assertion: Assertion = serde_yaml::from_str("tool: Read").unwrap();
+        assert_eq!(assertion.match_mode, MatchMode::Regex);
+
+        assert!(serde_yaml::from_str::<Assertion>("tool: Read\nmatch: fuzzy").is_err());
+    }
+
     #[test]
     fn test_deserialize_stdout_assertion() {
         let yaml = r#"
@@ -223,6 +437,7 @@ stdout:
   threshold: 8
   model: "claude-sonnet-4-20250514"
   agent: "claude"
+  template: "Grade {output} for: {criteria}"
 "#;
         let assertion: Assertion = serde_yaml::from_str(yaml).unwrap();
         assert!(assertion.tool.is_none());
@@ -232,6 +447,10 @@ stdout:
         assert_eq!(stdout.threshold, 8);
         assert_eq!(stdout.model, Some("claude-sonnet-4-20250514".to_string()));
         assert_eq!(stdout.agent, Some("claude".to_string()));
+        assert_eq!(
+            stdout.template.as_deref(),
+            Some("Grade {output} for: {criteria}")
+        );
     }
 
     #[test]
@@ -244,8 +463,34 @@ stdout:
         let stdout = assertion.stdout.unwrap();
         assert_eq!(stdout.review, "should say hello");
         assert_eq!(stdout.threshold, 7);
+        assert!(stdout.contains.is_none());
         assert!(stdout.model.is_none());
         assert!(stdout.agent.is_none());
+        assert!(stdout.template.is_none());
+    }
+
+    #[test]
+    fn test_deserialize_stdout_content_checks() {
+        let yaml = r#"
+stdout:
+  contains: "Updated"
+  not_contains: "ERROR"
+  matches: '\d+ files'
+"#;
+        let assertion: Assertion = serde_yaml::from_str(yaml).unwrap();
+        let stdout = assertion.stdout.unwrap();
+        assert!(stdout.review.is_empty());
+        assert_eq!(stdout.contains.as_deref(), Some("Updated"));
+        assert_eq!(stdout.not_contains.as_deref(), Some("ERROR"));
+        assert_eq!(stdout.matches.as_deref(), Some(r"\d+ files"));
+    }
+
+    #[test]
+    fn test_deserialize_exit_code_assertion() {
+        let assertion: Assertion = serde_yaml::from_str("exit_code: 0").unwrap();
+        assert_eq!(assertion.exit_code, Some(0));
+        assert!(assertion.tool.is_none());
+        assert!(assertion.stdout.is_none());
     }
 
     #[test]
@@ -263,6 +508,75 @@ assertions:
         assert_eq!(test.assertions.len(), 1);
     }
 
+    #[test]
+    fn test_args_reach_spawned_command() {
+        let yaml = r#"
+name: "Args test"
+prompt: "Run the tests"
+model: claude-haiku-4-5
+args: ["--dangerously-skip-permissions", "--max-turns", "3"]
+assertions: []
+"#;
+        let test: Test = serde_yaml::from_str(yaml).unwrap();
+        let config = test.apply_to_config(ExecutionConfig::new());
+
+        let mut cmd = std::process::Command::new("sh");
+        cmd.arg("-c").arg("echo \"$@\"").arg("sh");
+        config.apply_to(&mut cmd);
+
+        let output = cmd.output().unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "--model claude-haiku-4-5 --dangerously-skip-permissions --max-turns 3\n"
+        );
+
+        let test: Test = serde_yaml::from_str("name: a\nprompt: b\nassertions: []").unwrap();
+        assert!(test.args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_env() {
+        let yaml = r#"
+name: "Env test"
+prompt: "Run the tests"
+env:
+  NODE_ENV: test
+  DEBUG: "1"
+assertions: []
+"#;
+        let test: Test = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(test.env.len(), 2);
+        assert_eq!(test.env["NODE_ENV"], "test");
+        assert_eq!(test.env["DEBUG"], "1");
+
+        let test: Test = serde_yaml::from_str("name: a\nprompt: b\nassertions: []").unwrap();
+        assert!(test.env.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_workdir() {
+        let yaml = r#"
+name: "Fixture test"
+prompt: "Read the config"
+workdir: "../fixtures/project"
+assertions: []
+"#;
+        let test: Test = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            test.resolve_workdir(Path::new("/suite/tests/a.aptitude.yaml")),
+            Some(PathBuf::from("/suite/tests/../fixtures/project"))
+        );
+
+        let test = Test { workdir: Some(PathBuf::from("/abs/dir")), ..test };
+        assert_eq!(
+            test.resolve_workdir(Path::new("/suite/tests/a.aptitude.yaml")),
+            Some(PathBuf::from("/abs/dir"))
+        );
+
+        let test = Test { workdir: None, ..test };
+        assert!(test.resolve_workdir(Path::new("a.aptitude.yaml")).is_none());
+    }
+
     #[test]
     fn test_default_called_true() {
         let yaml = r#"
@@ -5,13 +5,19 @@
 //! all assertion logic to the fluent API.
 
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use futures::channel::oneshot;
 
 use crate::agents::Agent;
-use crate::fluent::{expect_tools, AssertionResult, Tool};
+use crate::fluent::{expect_tools, AssertionResult, CommandFilter, ExitCodeAssertion, FilesAssertion, MatchMode, SequenceAssertion, StdoutAssertion, Tool};
+use crate::output::TestReport;
 use crate::parser::ToolCall;
-use crate::review::{self, ReviewConfig};
+use crate::review::{self, GradeCache, ReviewConfig, ReviewResult};
 
-use super::parser::{parse_tool_name, Assertion, StdoutConstraints, Test};
+use super::parser::{parse_tool_name, Assertion, ParamCount, StdoutConstraints, Test};
 
 /// Result of evaluating a single assertion.
 #[derive(Debug, Clone)]
@@ -46,17 +52,103 @@ impl From<AssertionResult> for TestResult {
     }
 }
 
+impl From<(String, TestResult)> for AssertionResult {
+    fn from((description, result): (String, TestResult)) -> Self {
+        match result {
+            TestResult::Pass => AssertionResult::pass(description),
+            TestResult::Fail { reason } => AssertionResult::fail(description, reason),
+        }
+    }
+}
+
+/// Run a test attempt, re-running it while assertions fail and retries remain.
+///
+/// `attempt` receives the 1-based attempt number and returns that attempt's
+/// results. Returns the final attempt's results and its number. Errors from
+/// `attempt` (e.g. the agent is not installed) are returned immediately:
+/// only assertion failures are retried.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let (results, attempt) = run_with_retries(config.retries, |_| {
+///     let output = harness.execute(None, &test.prompt, config.clone())?;
+///     Ok(run_yaml_test_with_cache(&test, &output.result.tool_calls, &output.stdout, output.exit_code, None, None))
+/// })?;
+/// ```
+pub fn run_with_retries<F>(
+    retries: u32,
+    mut attempt: F,
+) -> anyhow::Result<(Vec<(String, TestResult)>, u32)>
+where
+    F: FnMut(u32) -> anyhow::Result<Vec<(String, TestResult)>>,
+{
+    let mut n = 1;
+    loop {
+        let results = attempt(n)?;
+        if n > retries || results.iter().all(|(_, r)| r.is_pass()) {
+            return Ok((results, n));
+        }
+        n += 1;
+    }
+}
+
+/// Run a test within a wall-clock budget.
+///
+/// `run` receives the deadline, if any, so it can pass the time left on as
+/// the agent's process timeout and have an overrunning agent killed. If
+/// `run` returns after the budget is spent, its results get a failing
+/// "completes within" entry, and an error (such as the agent being killed)
+/// becomes that failure: an overrun fails the test rather than erroring it.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let budget = test.timeout_secs.map(Duration::from_secs);
+/// let results = run_with_budget(budget, |deadline| {
+///     let timeout = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+///     let output = harness.execute(None, &test.prompt, ExecutionConfig { timeout, ..config })?;
+///     Ok(run_yaml_test_with_cache(&test, &output.result.tool_calls, &output.stdout, output.exit_code, None, None))
+/// })?;
+/// ```
+pub fn run_with_budget<F>(
+    budget: Option<Duration>,
+    run: F,
+) -> anyhow::Result<Vec<(String, TestResult)>>
+where
+    F: FnOnce(Option<Instant>) -> anyhow::Result<Vec<(String, TestResult)>>,
+{
+    let started = Instant::now();
+    let outcome = run(budget.map(|budget| started + budget));
+
+    let elapsed = started.elapsed();
+    match budget {
+        Some(budget) if elapsed > budget => {
+            let mut results = outcome.unwrap_or_default();
+            results.push((
+                format!("completes within {:?}", budget),
+                TestResult::Fail {
+                    reason: format!("took {:.1?}", elapsed),
+                },
+            ));
+            Ok(results)
+        }
+        _ => outcome,
+    }
+}
+
 /// Run a YAML test against tool calls and optional stdout.
 ///
 /// This function evaluates all assertions in the test and returns the results.
 /// Unlike the fluent API's immediate evaluation, this collects all results
-/// without panicking.
+/// without panicking. No exit code is passed, so `exit_code` assertions fail;
+/// use [`run_yaml_test_with_cache`] to check them.
 ///
 /// # Example
 ///
 /// ```rust,ignore
 /// let test = load_test("test.yaml")?;
-/// let results = run_yaml_test(&test, &tool_calls, &stdout);
+/// let results = run_yaml_test(&test, &tool_calls, &stdout, None);
 ///
 /// for (description, result) in &results {
 ///     match result {
@@ -71,25 +163,122 @@ pub fn run_yaml_test(
     stdout: &Option<String>,
     grader: Option<&Arc<dyn Agent>>,
 ) -> Vec<(String, TestResult)> {
+    run_yaml_test_with_cache(test, tool_calls, stdout, None, grader, None)
+}
+
+/// Run a YAML test like [`run_yaml_test`], checking `exit_code` assertions
+/// against the agent's exit code and reusing cached stdout grades.
+///
+/// Stdout reviews whose stdout and review settings match an earlier run are
+/// answered from `cache` without calling the grading agent.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let cache = GradeCache::default_location();
+/// let results = run_yaml_test_with_cache(&test, &tool_calls, &stdout, exit_code, grader, cache.as_ref());
+/// ```
+pub fn run_yaml_test_with_cache(
+    test: &Test,
+    tool_calls: &[ToolCall],
+    stdout: &Option<String>,
+    exit_code: Option<i32>,
+    grader: Option<&Arc<dyn Agent>>,
+    cache: Option<&GradeCache>,
+) -> Vec<(String, TestResult)> {
+    run_yaml_test_report(test, tool_calls, stdout, exit_code, grader, cache).assertions
+}
+
+/// Run a YAML test like [`run_yaml_test_with_cache`], rolled up into a [`TestReport`].
+///
+/// The report's agent is the test's `agent` (default `claude`) and its
+/// duration covers evaluating the assertions, including stdout grading.
+/// Callers that ran the agent themselves can overwrite both.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let report = run_yaml_test_report(&test, &tool_calls, &stdout, exit_code, grader, None);
+/// println!("{}: {} passed, {} failed", report.name, report.passed, report.failed);
+/// ```
+pub fn run_yaml_test_report(
+    test: &Test,
+    tool_calls: &[ToolCall],
+    stdout: &Option<String>,
+    exit_code: Option<i32>,
+    grader: Option<&Arc<dyn Agent>>,
+    cache: Option<&GradeCache>,
+) -> TestReport {
+    let started = Instant::now();
     let mut results = Vec::new();
+    // Stdout reviews are graded together once all assertions are collected
+    let mut reviews: Vec<(usize, ReviewConfig)> = Vec::new();
 
     for assertion in &test.assertions {
         // Check if this is a stdout assertion
         if let Some(stdout_constraints) = &assertion.stdout {
             let description = format_stdout_description(stdout_constraints);
-            let result = evaluate_stdout_assertion(stdout_constraints, stdout, grader);
-            results.push((description, result));
+            match check_stdout_assertion(stdout_constraints, stdout, test.model.as_deref(), grader) {
+                StdoutCheck::Done(result) => results.push((description, result)),
+                StdoutCheck::Review(config) => {
+                    reviews.push((results.len(), config));
+                    results.push((description, TestResult::Pass));
+                }
+            }
+            continue;
+        }
+
+        // Check if this is an exit code assertion
+        if let Some(expected) = assertion.exit_code {
+            let result = ExitCodeAssertion::new(exit_code).evaluate_to_be(expected);
+            results.push((result.description.clone(), result.into()));
+            continue;
+        }
+
+        // Check if this is a sequence assertion
+        if let Some(names) = &assertion.sequence {
+            results.push(evaluate_sequence_assertion(names, assertion.consecutive, tool_calls));
+            continue;
+        }
+
+        // Check if this is a no-tools assertion
+        if assertion.no_tools {
+            if let Err(err) = validate_assertion(assertion) {
+                results.push(("no_tools (invalid)".to_string(), TestResult::Fail { reason: err }));
+                continue;
+            }
+            let result = expect_tools(tool_calls).evaluate_no_tools_called();
+            results.push((result.description.clone(), result.into()));
+            continue;
+        }
+
+        // Check if this is an allowlist assertion
+        if let Some(names) = &assertion.only_tools {
+            results.push(evaluate_only_tools_assertion(names, tool_calls));
+            continue;
+        }
+
+        // Check if this is a files assertion
+        if assertion.files_read.is_some() || assertion.files_written.is_some() {
+            if let Some(files) = &assertion.files_read {
+                let result = FilesAssertion::read(tool_calls).evaluate_count_at_most(files.max);
+                results.push((result.description.clone(), result.into()));
+            }
+            if let Some(files) = &assertion.files_written {
+                let result = FilesAssertion::written(tool_calls).evaluate_count_at_most(files.max);
+                results.push((result.description.clone(), result.into()));
+            }
             continue;
         }
 
         // Tool assertion - tool name is required
-        let tool_name = match &assertion.tool {
+        let tool_name = match assertion.tool_name() {
             Some(name) => name,
             None => {
                 results.push((
                     "invalid assertion".to_string(),
                     TestResult::Fail {
-                        reason: "Assertion must have either 'tool' or 'stdout'".to_string(),
+                        reason: "Assertion must have 'tool', 'stdout', 'exit_code', 'sequence', 'no_tools', 'only_tools', 'files_read' or 'files_written'".to_string(),
                     },
                 ));
                 continue;
@@ -128,31 +317,161 @@ pub fn run_yaml_test(
         if let Some(nth_params) = &assertion.nth_call_params {
             for (n, params) in nth_params {
                 let description = format!("{} call #{} params", tool, n);
-                let result = evaluate_nth_params(&tool, tool_calls, *n, params);
+                let result =
+                    evaluate_nth_params(&tool, tool_calls, *n, params, assertion.match_mode);
                 results.push((description, result));
             }
         }
 
         if let Some(first_params) = &assertion.first_call_params {
             let description = format!("{} first call params", tool);
-            let result = evaluate_nth_params(&tool, tool_calls, 1, first_params);
+            let result =
+                evaluate_nth_params(&tool, tool_calls, 1, first_params, assertion.match_mode);
             results.push((description, result));
         }
 
         if let Some(last_params) = &assertion.last_call_params {
             let description = format!("{} last call params", tool);
-            let result = evaluate_last_params(&tool, tool_calls, last_params);
+            let result = evaluate_last_params(&tool, tool_calls, last_params, assertion.match_mode);
             results.push((description, result));
         }
+
+        // Param-scoped counts, each its own result
+        for param_count in assertion.param_counts.iter().flatten() {
+            let result = evaluate_param_count(&tool, tool_calls, param_count, assertion.match_mode);
+            results.push((result.description.clone(), result.into()));
+        }
+    }
+
+    if let Some(grader) = grader.filter(|_| !reviews.is_empty()) {
+        let configs: Vec<ReviewConfig> = reviews.iter().map(|(_, config)| config.clone()).collect();
+        let graded = grade_reviews(stdout, &configs, grader, cache);
+        for ((index, config), review_result) in reviews.iter().zip(graded) {
+            results[*index].1 = review_test_result(config.threshold, review_result);
+        }
     }
 
-    results
+    TestReport::new(
+        test.name.clone(),
+        test.prompt.clone(),
+        test.agent.clone().unwrap_or_else(|| "claude".to_string()),
+        results,
+        started.elapsed(),
+    )
 }
 
 // =========================================================================
 // Internal: Delegation to fluent API
 // =========================================================================
 
+/// Flags aptitude passes to agents itself, which `args` must not repeat.
+const RESERVED_ARGS: &[&str] = &[
+    "--print",
+    "-p",
+    "--prompt",
+    "--output-format",
+    "--no-interactive",
+    "--resume",
+    "--append-system-prompt",
+];
+
+/// Check a test's `args` for flags aptitude already passes to the agent.
+///
+/// Reports reserved flags (`--print`, `--output-format`, ...), in either the
+/// `--flag value` or `--flag=value` form, and `--model` when the test also sets
+/// `model`. Returns an empty list if the arguments are safe to append.
+pub fn check_test_args(test: &Test) -> Vec<String> {
+    let mut problems = Vec::new();
+    for arg in &test.args {
+        let flag = arg.split_once('=').map_or(arg.as_str(), |(flag, _)| flag);
+        if RESERVED_ARGS.contains(&flag) {
+            problems.push(format!("args: '{}' is already passed by aptitude", flag));
+        } else if flag == "--model" && test.model.is_some() {
+            problems.push("args: '--model' conflicts with 'model'; set one or the other".to_string());
+        }
+    }
+    problems
+}
+
+/// Check a test's assertions without running anything.
+///
+/// Reports assertions that name no kind of check, tool names that don't
+/// resolve (including ordering targets and `sequence` entries), and
+/// inconsistent count constraints, each prefixed with the 1-based assertion
+/// number, after any problems with `args` (see [`check_test_args`]).
+/// Returns an empty list for a valid test.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let test = load_test("tests/read_file.aptitude.yaml")?;
+/// for problem in validate_test(&test) {
+///     eprintln!("{}", problem);
+/// }
+/// ```
+pub fn validate_test(test: &Test) -> Vec<String> {
+    let mut problems = check_test_args(test);
+
+    for (i, assertion) in test.assertions.iter().enumerate() {
+        let mut report = |problem: String| problems.push(format!("assertion {}: {}", i + 1, problem));
+
+        // Same precedence as `run_yaml_test_with_cache`
+        if assertion.stdout.is_some() || assertion.exit_code.is_some() {
+            continue;
+        }
+        if let Some(names) = &assertion.sequence {
+            for name in names {
+                if let Err(e) = parse_tool_name(name) {
+                    report(e.to_string());
+                }
+            }
+            continue;
+        }
+        if assertion.no_tools {
+            if let Err(e) = validate_assertion(assertion) {
+                report(e);
+            }
+            continue;
+        }
+        if let Some(names) = &assertion.only_tools {
+            for name in names {
+                if let Err(e) = parse_tool_name(name) {
+                    report(e.to_string());
+                }
+            }
+            continue;
+        }
+        if assertion.files_read.is_some() || assertion.files_written.is_some() {
+            continue;
+        }
+
+        let Some(tool) = assertion.tool_name() else {
+            report("Assertion must have 'tool', 'stdout', 'exit_code', 'sequence', 'no_tools', 'only_tools', 'files_read' or 'files_written'".to_string());
+            continue;
+        };
+
+        let ordering = [
+            &assertion.called_after,
+            &assertion.called_before,
+            &assertion.immediately_after,
+            &assertion.immediately_before,
+        ];
+        let between = assertion.not_called_between.iter().flatten();
+        for name in std::iter::once(tool)
+            .chain(ordering.into_iter().flatten().chain(between).map(String::as_str))
+        {
+            if let Err(e) = parse_tool_name(name) {
+                report(e.to_string());
+            }
+        }
+        if let Err(e) = validate_assertion(assertion) {
+            report(e);
+        }
+    }
+
+    problems
+}
+
 /// Evaluate the main assertion using the fluent API.
 fn evaluate_assertion(assertion: &Assertion, tool: &Tool, tool_calls: &[ToolCall]) -> TestResult {
     // Build fluent assertion with all constraints
@@ -160,13 +479,53 @@ fn evaluate_assertion(assertion: &Assertion, tool: &Tool, tool_calls: &[ToolCall
 
     // Add parameter constraints
     if let Some(params) = &assertion.params {
-        builder = builder.with_params(params.clone());
+        builder = builder.with_params_matching(params.clone(), assertion.match_mode);
+    }
+    for key in assertion.params_absent.iter().flatten() {
+        builder = builder.without_param(key);
+    }
+    if let Some(params) = &assertion.params_not_matching {
+        // Patterns follow `match` even without `params`
+        if assertion.params.is_none() {
+            builder = builder.with_params_matching(std::collections::HashMap::new(), assertion.match_mode);
+        }
+        for (key, pattern) in params {
+            builder = builder.param_not_matching(key, pattern);
+        }
+    }
+    if let Some(index) = assertion.cell_index {
+        builder = builder.with_cell_index(index);
+    }
+    if let Some(key) = &assertion.distinct_param {
+        builder = builder.with_distinct_params(key);
+    }
+    if let Some(bash) = &assertion.bash {
+        if let Some(command) = &bash.ran {
+            builder = builder.with_command(CommandFilter::Exact(command.clone()));
+        }
+        if let Some(text) = &bash.contains {
+            builder = builder.with_command(CommandFilter::Containing(text.clone()));
+        }
     }
 
-    // Add count constraints
-    if let Some(count) = assertion.call_count {
-        builder = builder.times(count as usize);
+    if let Some([start, end]) = &assertion.not_called_between {
+        let (start_tool, end_tool) = match (parse_tool_name(start), parse_tool_name(end)) {
+            (Ok(start_tool), Ok(end_tool)) => (start_tool, end_tool),
+            (Err(_), _) => {
+                return TestResult::Fail {
+                    reason: format!("Unknown tool in not_called_between: '{}'", start),
+                }
+            }
+            (_, Err(_)) => {
+                return TestResult::Fail {
+                    reason: format!("Unknown tool in not_called_between: '{}'", end),
+                }
+            }
+        };
+        return builder.evaluate_not_called_between(start_tool, end_tool).into();
     }
+
+    // Add count constraints (exact count is evaluated below)
     if let Some(min) = assertion.min_calls {
         builder = builder.at_least(min as usize);
     }
@@ -193,23 +552,64 @@ fn evaluate_assertion(assertion: &Assertion, tool: &Tool, tool_calls: &[ToolCall
             };
         }
     }
+    if let Some(after_str) = &assertion.immediately_after {
+        if let Ok(after_tool) = parse_tool_name(after_str) {
+            builder = builder.immediately_after(after_tool);
+        } else {
+            return TestResult::Fail {
+                reason: format!("Unknown tool in immediately_after: '{}'", after_str),
+            };
+        }
+    }
+    if let Some(before_str) = &assertion.immediately_before {
+        if let Ok(before_tool) = parse_tool_name(before_str) {
+            builder = builder.immediately_before(before_tool);
+        } else {
+            return TestResult::Fail {
+                reason: format!("Unknown tool in immediately_before: '{}'", before_str),
+            };
+        }
+    }
 
     // Evaluate based on called expectation
-    let result = if assertion.called {
-        builder.evaluate()
-    } else {
-        builder.evaluate_not_called()
+    let result = match (assertion.called, assertion.call_count) {
+        (true, Some(count)) => builder.evaluate_exactly(count as usize),
+        (true, None) => builder.evaluate(),
+        (false, _) => builder.evaluate_not_called(),
     };
 
     result.into()
 }
 
+/// Evaluate one `param_counts` entry: the count of calls matching its params.
+fn evaluate_param_count(
+    tool: &Tool,
+    tool_calls: &[ToolCall],
+    param_count: &ParamCount,
+    match_mode: MatchMode,
+) -> AssertionResult {
+    let mut builder = expect_tools(tool_calls)
+        .tool(*tool)
+        .with_params_matching(param_count.params.clone(), match_mode);
+    if let Some(count) = param_count.count {
+        builder = builder.times(count as usize);
+    }
+    if let Some(min) = param_count.min_calls {
+        builder = builder.at_least(min as usize);
+    }
+    if let Some(max) = param_count.max_calls {
+        builder = builder.at_most(max as usize);
+    }
+    builder.evaluate_count()
+}
+
 /// Evaluate nth call parameters using the fluent API.
 fn evaluate_nth_params(
     tool: &Tool,
     tool_calls: &[ToolCall],
     n: u32,
     expected_params: &std::collections::HashMap<String, String>,
+    match_mode: MatchMode,
 ) -> TestResult {
     // Check if there are enough calls
     let call_count = tool_calls.iter().filter(|c| c.name == tool.as_str()).count();
@@ -226,7 +626,7 @@ fn evaluate_nth_params(
     let result = expect_tools(tool_calls)
         .tool(*tool)
         .nth_call(n as usize)
-        .evaluate_params(expected_params.clone());
+        .evaluate_params_with(expected_params.clone(), match_mode);
 
     result.into()
 }
@@ -236,6 +636,7 @@ fn evaluate_last_params(
     tool: &Tool,
     tool_calls: &[ToolCall],
     expected_params: &std::collections::HashMap<String, String>,
+    match_mode: MatchMode,
 ) -> TestResult {
     // Check if there are any calls
     let call_count = tool_calls.iter().filter(|c| c.name == tool.as_str()).count();
@@ -249,37 +650,122 @@ fn evaluate_last_params(
     let result = expect_tools(tool_calls)
         .tool(*tool)
         .last_call()
-        .evaluate_params(expected_params.clone());
+        .evaluate_params_with(expected_params.clone(), match_mode);
 
     result.into()
 }
 
-/// Evaluate stdout assertion using LLM-powered review.
-fn evaluate_stdout_assertion(
+/// Outcome of the local part of a stdout assertion.
+enum StdoutCheck {
+    /// Decided without grading: a content check failed, there is nothing to
+    /// review, or no grader is available.
+    Done(TestResult),
+    /// Content checks passed and the review still needs grading.
+    Review(ReviewConfig),
+}
+
+/// Check a stdout assertion locally: content checks first, then decide
+/// whether an LLM-powered review is needed.
+fn check_stdout_assertion(
     constraints: &StdoutConstraints,
     stdout: &Option<String>,
+    test_model: Option<&str>,
     grader: Option<&Arc<dyn Agent>>,
-) -> TestResult {
-    let grader = match grader {
-        Some(g) => g,
-        None => {
-            return TestResult::Fail {
-                reason: "No grading agent available for stdout review".to_string(),
-            }
+) -> StdoutCheck {
+    let content = stdout_content_assertion(constraints, stdout);
+    if let Some(assertion) = &content {
+        let result = assertion.evaluate_exists();
+        if !result.passed {
+            return StdoutCheck::Done(TestResult::Fail {
+                reason: format!(
+                    "{} (stdout: {})",
+                    result.reason.unwrap_or_default(),
+                    assertion.format_stdout_preview()
+                ),
+            });
         }
-    };
+    }
+
+    if constraints.review.is_empty() {
+        return StdoutCheck::Done(match content {
+            Some(_) => TestResult::Pass,
+            None => TestResult::Fail {
+                reason: "stdout assertion must set 'review', 'contains', 'not_contains' or 'matches'"
+                    .to_string(),
+            },
+        });
+    }
+
+    if grader.is_none() {
+        return StdoutCheck::Done(TestResult::Fail {
+            reason: "No grading agent available for stdout review".to_string(),
+        });
+    }
 
-    let config = ReviewConfig {
+    StdoutCheck::Review(ReviewConfig {
         criteria: constraints.review.clone(),
         threshold: constraints.threshold,
-        model: constraints.model.clone(),
-    };
+        model: constraints
+            .model
+            .clone()
+            .or_else(|| test_model.map(str::to_string)),
+        prompt_template: constraints.template.clone(),
+    })
+}
+
+/// Grade every review of the same stdout concurrently.
+///
+/// Cached grades are reused; the rest go to the grader in one batch, each
+/// call on its own thread since [`Agent::grade`] blocks on a CLI process.
+/// Results are returned in the order of `configs`.
+fn grade_reviews(
+    stdout: &Option<String>,
+    configs: &[ReviewConfig],
+    grader: &Arc<dyn Agent>,
+    cache: Option<&GradeCache>,
+) -> Vec<anyhow::Result<ReviewResult>> {
+    let mut graded: Vec<Option<anyhow::Result<ReviewResult>>> = Vec::new();
+    let mut misses = Vec::new();
+    for (i, config) in configs.iter().enumerate() {
+        match cache.and_then(|cache| cache.get(grader.name(), stdout, config)) {
+            Some(hit) => graded.push(Some(Ok(hit))),
+            None => {
+                graded.push(None);
+                misses.push(i);
+            }
+        }
+    }
 
-    let grader_ref = grader.clone();
-    let result = review::grade_stdout(stdout, &config, |prompt, model| {
-        grader_ref.grade(prompt, model)
-    });
+    let requests: Vec<(Option<String>, ReviewConfig)> = misses
+        .iter()
+        .map(|&i| (stdout.clone(), configs[i].clone()))
+        .collect();
+    let fresh = futures::executor::block_on(review::grade_stdout_batch_each_async(
+        &requests,
+        |prompt, model| {
+            let grader = grader.clone();
+            let (tx, rx) = oneshot::channel();
+            thread::spawn(move || {
+                let _ = tx.send(grader.grade(&prompt, model.as_deref()));
+            });
+            async move { rx.await.unwrap_or_else(|_| Err(anyhow!("grading thread panicked"))) }
+        },
+    ));
+
+    for (&i, result) in misses.iter().zip(fresh) {
+        if let (Some(cache), Ok(review_result)) = (cache, &result) {
+            if let Err(e) = cache.put(grader.name(), stdout, &configs[i], review_result) {
+                eprintln!("Warning: {:#}", e);
+            }
+        }
+        graded[i] = Some(result);
+    }
+
+    graded.into_iter().flatten().collect()
+}
 
+/// Turn a graded review into a test result.
+fn review_test_result(threshold: u32, result: anyhow::Result<ReviewResult>) -> TestResult {
     match result {
         Ok(review_result) => {
             if review_result.passed {
@@ -288,7 +774,7 @@ fn evaluate_stdout_assertion(
                 TestResult::Fail {
                     reason: format!(
                         "score {}/10 below threshold {} — {}",
-                        review_result.score, constraints.threshold, review_result.reasoning
+                        review_result.score, threshold, review_result.reasoning
                     ),
                 }
             }
@@ -303,9 +789,87 @@ fn evaluate_stdout_assertion(
 // Validation and formatting helpers
 // =========================================================================
 
+fn evaluate_sequence_assertion(
+    names: &[String],
+    consecutive: bool,
+    tool_calls: &[ToolCall],
+) -> (String, TestResult) {
+    let tools: Result<Vec<Tool>, _> = names.iter().map(|name| parse_tool_name(name)).collect();
+    let tools = match tools {
+        Ok(tools) => tools,
+        Err(e) => {
+            return (
+                format!("[{}] (invalid)", names.join(", ")),
+                TestResult::Fail {
+                    reason: e.to_string(),
+                },
+            )
+        }
+    };
+
+    let assertion = SequenceAssertion::new(tool_calls.to_vec(), tools);
+    let result = if consecutive {
+        assertion.evaluate_consecutive()
+    } else {
+        assertion.evaluate()
+    };
+    (result.description.clone(), result.into())
+}
+
+fn evaluate_only_tools_assertion(names: &[String], tool_calls: &[ToolCall]) -> (String, TestResult) {
+    let tools: Result<Vec<Tool>, _> = names.iter().map(|name| parse_tool_name(name)).collect();
+    match tools {
+        Ok(tools) => {
+            let result = expect_tools(tool_calls).evaluate_only_tools_called(&tools);
+            (result.description.clone(), result.into())
+        }
+        Err(e) => (
+            format!("only [{}] (invalid)", names.join(", ")),
+            TestResult::Fail {
+                reason: e.to_string(),
+            },
+        ),
+    }
+}
+
 fn validate_assertion(assertion: &Assertion) -> Result<(), String> {
+    // no_tools checks the whole run, so a tool alongside it would go unchecked
+    if assertion.no_tools && assertion.tool_name().is_some() {
+        return Err("'no_tools' cannot be combined with 'tool' or 'bash'".to_string());
+    }
+    if let Some(bash) = &assertion.bash {
+        if bash.ran.is_none() && bash.contains.is_none() {
+            return Err("'bash' needs 'ran' or 'contains'".to_string());
+        }
+        if assertion.tool_name().is_some_and(|name| parse_tool_name(name).is_ok_and(|t| t != Tool::Bash)) {
+            return Err("'bash' can only be used with the Bash tool".to_string());
+        }
+    }
+    // not_called_between is its own check, not a modifier of called
+    if assertion.not_called_between.is_some() {
+        let conflicts = [
+            ("called: false", !assertion.called),
+            ("call_count", assertion.call_count.is_some()),
+            ("min_calls", assertion.min_calls.is_some()),
+            ("max_calls", assertion.max_calls.is_some()),
+            ("called_after", assertion.called_after.is_some()),
+            ("called_before", assertion.called_before.is_some()),
+            ("immediately_after", assertion.immediately_after.is_some()),
+            ("immediately_before", assertion.immediately_before.is_some()),
+        ];
+        if let Some((key, _)) = conflicts.iter().find(|(_, set)| *set) {
+            return Err(format!("'not_called_between' cannot be combined with '{}'", key));
+        }
+    }
     // called: false is mutually exclusive with count assertions
     if !assertion.called {
+        // Negative params are checked on the calls made, so there are none to check
+        if assertion.params_absent.is_some() {
+            return Err("'called: false' cannot be combined with 'params_absent'".to_string());
+        }
+        if assertion.params_not_matching.is_some() {
+            return Err("'called: false' cannot be combined with 'params_not_matching'".to_string());
+        }
         if assertion.call_count.is_some() {
             return Err("'called: false' cannot be combined with 'call_count'".to_string());
         }
@@ -319,14 +883,43 @@ fn validate_assertion(assertion: &Assertion) -> Result<(), String> {
             );
         }
     }
+    if let (Some(min), Some(max)) = (assertion.min_calls, assertion.max_calls) {
+        if min > max {
+            return Err(format!(
+                "'min_calls' ({}) cannot be greater than 'max_calls' ({})",
+                min, max
+            ));
+        }
+    }
+    if let Some(count) = assertion.call_count {
+        if assertion.min_calls.is_some_and(|min| count < min)
+            || assertion.max_calls.is_some_and(|max| count > max)
+        {
+            return Err(format!(
+                "'call_count' ({}) is outside the 'min_calls'/'max_calls' range",
+                count
+            ));
+        }
+    }
+    for (i, param_count) in assertion.param_counts.iter().flatten().enumerate() {
+        let entry = format!("'param_counts' entry {}", i + 1);
+        if param_count.count.is_none() && param_count.min_calls.is_none() && param_count.max_calls.is_none() {
+            return Err(format!("{} needs 'count', 'min_calls' or 'max_calls'", entry));
+        }
+        if let (Some(min), Some(max)) = (param_count.min_calls, param_count.max_calls) {
+            if min > max {
+                return Err(format!(
+                    "{}: 'min_calls' ({}) cannot be greater than 'max_calls' ({})",
+                    entry, min, max
+                ));
+            }
+        }
+    }
     Ok(())
 }
 
 fn format_assertion_description(assertion: &Assertion) -> String {
-    let mut desc = assertion
-        .tool
-        .clone()
-        .unwrap_or_else(|| "unknown".to_string());
+    let mut desc = assertion.tool_name().unwrap_or("unknown").to_string();
 
     if let Some(params) = &assertion.params {
         let param_str: Vec<String> = params
@@ -335,12 +928,42 @@ fn format_assertion_description(assertion: &Assertion) -> String {
             .collect();
         desc = format!("{} with {}", desc, param_str.join(", "));
     }
+    if let Some(keys) = &assertion.params_absent {
+        desc = format!("{} without {}", desc, keys.join(", "));
+    }
+    if let Some(params) = &assertion.params_not_matching {
+        let param_str: Vec<String> = params
+            .iter()
+            .map(|(k, v)| format!("{} not matching '{}'", k, v))
+            .collect();
+        desc = format!("{} with {}", desc, param_str.join(", "));
+    }
+    if let Some(index) = assertion.cell_index {
+        desc = format!("{} on cell {}", desc, index);
+    }
+    if let Some(key) = &assertion.distinct_param {
+        desc = format!("{} with distinct {}", desc, key);
+    }
+    if let Some(bash) = &assertion.bash {
+        if let Some(command) = &bash.ran {
+            desc = format!("{} {}", desc, CommandFilter::Exact(command.clone()));
+        }
+        if let Some(text) = &bash.contains {
+            desc = format!("{} {}", desc, CommandFilter::Containing(text.clone()));
+        }
+    }
 
-    if assertion.called {
+    if let Some([start, end]) = &assertion.not_called_between {
+        format!("{} not called between {} and {}", desc, start, end)
+    } else if assertion.called {
         if let Some(after) = &assertion.called_after {
             format!("{} called after {}", desc, after)
         } else if let Some(before) = &assertion.called_before {
             format!("{} called before {}", desc, before)
+        } else if let Some(after) = &assertion.immediately_after {
+            format!("{} called immediately after {}", desc, after)
+        } else if let Some(before) = &assertion.immediately_before {
+            format!("{} called immediately before {}", desc, before)
         } else {
             format!("{} called", desc)
         }
@@ -349,24 +972,70 @@ fn format_assertion_description(assertion: &Assertion) -> String {
     }
 }
 
+/// Build a fluent assertion for the local content checks, if any are set.
+fn stdout_content_assertion(
+    constraints: &StdoutConstraints,
+    stdout: &Option<String>,
+) -> Option<StdoutAssertion> {
+    if constraints.contains.is_none()
+        && constraints.not_contains.is_none()
+        && constraints.matches.is_none()
+    {
+        return None;
+    }
+
+    let mut assertion = StdoutAssertion::new(stdout.clone());
+    if let Some(needle) = &constraints.contains {
+        assertion = assertion.contains(needle);
+    }
+    if let Some(needle) = &constraints.not_contains {
+        assertion = assertion.not_contains(needle);
+    }
+    if let Some(pattern) = &constraints.matches {
+        assertion = assertion.matches_regex(pattern);
+    }
+    Some(assertion)
+}
+
 fn format_stdout_description(constraints: &StdoutConstraints) -> String {
-    format!(
-        "stdout review: \"{}\" (threshold: {}/10)",
-        constraints.review, constraints.threshold
-    )
+    let mut parts: Vec<String> = Vec::new();
+    if let Some(needle) = &constraints.contains {
+        parts.push(format!("contains \"{}\"", needle));
+    }
+    if let Some(needle) = &constraints.not_contains {
+        parts.push(format!("does not contain \"{}\"", needle));
+    }
+    if let Some(pattern) = &constraints.matches {
+        parts.push(format!("matches /{}/", pattern));
+    }
+    if !constraints.review.is_empty() {
+        parts.push(format!(
+            "review: \"{}\" (threshold: {}/10)",
+            constraints.review, constraints.threshold
+        ));
+    }
+
+    if parts.is_empty() {
+        "stdout (invalid)".to_string()
+    } else {
+        format!("stdout {}", parts.join(", "))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
-    use crate::agents::{ExecutionConfig, RawExecutionResult, ToolNameMapping};
+    use crate::test_util::MockAgent;
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
 
     fn make_call(name: &str, params: serde_json::Value) -> ToolCall {
         ToolCall {
             name: name.to_string(),
             params,
             timestamp: String::new(),
+            result: None,
         }
     }
 
@@ -375,61 +1044,64 @@ mod tests {
             tool: Some(tool.to_string()),
             called: true,
             params: None,
+            match_mode: MatchMode::Regex,
+            params_absent: None,
+            params_not_matching: None,
+            cell_index: None,
+            bash: None,
             called_after: None,
             called_before: None,
+            immediately_after: None,
+            immediately_before: None,
+            not_called_between: None,
+            strict_order: None,
             call_count: None,
             max_calls: None,
             min_calls: None,
+            distinct_param: None,
+            param_counts: None,
             nth_call_params: None,
             first_call_params: None,
             last_call_params: None,
             stdout: None,
+            exit_code: None,
+            sequence: None,
+            consecutive: false,
+            no_tools: false,
+            only_tools: None,
+            files_read: None,
+            files_written: None,
         }
     }
 
-    /// Mock agent for grading tests.
-    struct MockGrader {
-        response: String,
-    }
-
-    impl MockGrader {
-        fn passing() -> Arc<dyn Agent> {
-            Arc::new(Self {
-                response: r#"{"score": 9, "reasoning": "Meets criteria"}"#.to_string(),
-            })
+    fn make_test(assertions: Vec<Assertion>) -> Test {
+        Test {
+            name: "Test".to_string(),
+            prompt: "Test prompt".to_string(),
+            agent: None,
+            workdir: None,
+            model: None,
+            env: HashMap::new(),
+            args: Vec::new(),
+            setup: Vec::new(),
+            teardown: Vec::new(),
+            normalize_paths: false,
+            timeout_secs: None,
+            assertions,
         }
+    }
 
-        fn failing() -> Arc<dyn Agent> {
-            Arc::new(Self {
-                response: r#"{"score": 3, "reasoning": "Does not meet criteria"}"#.to_string(),
-            })
-        }
+    fn passing_grader() -> Arc<dyn Agent> {
+        Arc::new(MockAgent::new().with_grade(9, "Meets criteria"))
     }
 
-    #[async_trait::async_trait]
-    impl Agent for MockGrader {
-        fn name(&self) -> &'static str { "mock" }
-        fn execute(&self, _: &str, _: &ExecutionConfig) -> anyhow::Result<RawExecutionResult> {
-            unimplemented!()
-        }
-        fn parse_session(&self, _: &RawExecutionResult) -> anyhow::Result<Vec<ToolCall>> {
-            unimplemented!()
-        }
-        fn tool_mapping(&self) -> &ToolNameMapping { unimplemented!() }
-        fn is_available(&self) -> bool { true }
-        fn grade(&self, _: &str, _: Option<&str>) -> anyhow::Result<String> {
-            Ok(self.response.clone())
-        }
+    fn failing_grader() -> Arc<dyn Agent> {
+        Arc::new(MockAgent::new().with_grade(3, "Does not meet criteria"))
     }
 
     #[test]
     fn test_run_yaml_test_basic() {
-        let test = Test {
-            name: "Test".to_string(),
-            prompt: "Test prompt".to_string(),
-            agent: None,
-            assertions: vec![make_assertion("Read")],
-        };
+        let test = make_test(vec![make_assertion("Read")]);
 
         let calls = vec![make_call("Read", json!({"file_path": "/test.txt"}))];
         let results = run_yaml_test(&test, &calls, &None, None);
@@ -440,15 +1112,10 @@ mod tests {
 
     #[test]
     fn test_run_yaml_test_not_called() {
-        let test = Test {
-            name: "Test".to_string(),
-            prompt: "Test prompt".to_string(),
-            agent: None,
-            assertions: vec![Assertion {
-                called: false,
-                ..make_assertion("Bash")
-            }],
-        };
+        let test = make_test(vec![Assertion {
+            called: false,
+            ..make_assertion("Bash")
+        }]);
 
         let calls = vec![make_call("Read", json!({"file_path": "/test.txt"}))];
         let results = run_yaml_test(&test, &calls, &None, None);
@@ -459,12 +1126,7 @@ mod tests {
 
     #[test]
     fn test_run_yaml_test_case_insensitive() {
-        let test = Test {
-            name: "Test".to_string(),
-            prompt: "Test prompt".to_string(),
-            agent: None,
-            assertions: vec![make_assertion("read")],
-        };
+        let test = make_test(vec![make_assertion("read")]);
 
         let calls = vec![make_call("Read", json!({"file_path": "/test.txt"}))];
         let results = run_yaml_test(&test, &calls, &None, None);
@@ -475,12 +1137,7 @@ mod tests {
 
     #[test]
     fn test_run_yaml_test_alias() {
-        let test = Test {
-            name: "Test".to_string(),
-            prompt: "Test prompt".to_string(),
-            agent: None,
-            assertions: vec![make_assertion("read_file")],
-        };
+        let test = make_test(vec![make_assertion("read_file")]);
 
         let calls = vec![make_call("Read", json!({"file_path": "/test.txt"}))];
         let results = run_yaml_test(&test, &calls, &None, None);
@@ -491,15 +1148,10 @@ mod tests {
 
     #[test]
     fn test_run_yaml_test_with_count() {
-        let test = Test {
-            name: "Test".to_string(),
-            prompt: "Test prompt".to_string(),
-            agent: None,
-            assertions: vec![Assertion {
-                call_count: Some(2),
-                ..make_assertion("Read")
-            }],
-        };
+        let test = make_test(vec![Assertion {
+            call_count: Some(2),
+            ..make_assertion("Read")
+        }]);
 
         let calls = vec![
             make_call("Read", json!({"file_path": "/a.txt"})),
@@ -511,17 +1163,60 @@ mod tests {
         assert!(results[0].1.is_pass());
     }
 
+    #[test]
+    fn test_run_yaml_test_with_count_mismatch() {
+        let test = make_test(vec![Assertion {
+            call_count: Some(1),
+            ..make_assertion("Read")
+        }]);
+
+        let calls = vec![
+            make_call("Read", json!({"file_path": "/a.txt"})),
+            make_call("Read", json!({"file_path": "/b.txt"})),
+        ];
+        let results = run_yaml_test(&test, &calls, &None, None);
+
+        match &results[0].1 {
+            TestResult::Fail { reason } => {
+                assert_eq!(reason, "expected Read to be called exactly once, got 2");
+            }
+            TestResult::Pass => panic!("expected failure"),
+        }
+    }
+
+    #[test]
+    fn test_run_yaml_test_immediately_after() {
+        let test = make_test(vec![
+            Assertion {
+                immediately_after: Some("Read".to_string()),
+                ..make_assertion("Edit")
+            },
+            Assertion {
+                immediately_before: Some("Edit".to_string()),
+                not_called_between: None,
+                ..make_assertion("Bash")
+            },
+        ]);
+
+        let calls = vec![
+            make_call("Read", json!({"file_path": "/a.txt"})),
+            make_call("Bash", json!({"command": "ls"})),
+            make_call("Edit", json!({"file_path": "/a.txt"})),
+        ];
+        let results = run_yaml_test(&test, &calls, &None, None);
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].1.is_fail());
+        assert_eq!(results[0].0, "Edit called immediately after Read");
+        assert!(results[1].1.is_pass());
+    }
+
     #[test]
     fn test_run_yaml_test_ordering() {
-        let test = Test {
-            name: "Test".to_string(),
-            prompt: "Test prompt".to_string(),
-            agent: None,
-            assertions: vec![Assertion {
-                called_after: Some("Read".to_string()),
-                ..make_assertion("Write")
-            }],
-        };
+        let test = make_test(vec![Assertion {
+            called_after: Some("Read".to_string()),
+            ..make_assertion("Write")
+        }]);
 
         let calls = vec![
             make_call("Read", json!({"file_path": "/input.txt"})),
@@ -534,23 +1229,324 @@ mod tests {
     }
 
     #[test]
-    fn test_run_yaml_test_nth_call_params() {
-        let test = Test {
-            name: "Test".to_string(),
-            prompt: "Test prompt".to_string(),
-            agent: None,
-            assertions: vec![Assertion {
-                nth_call_params: Some({
-                    let mut map = std::collections::HashMap::new();
-                    let mut params = std::collections::HashMap::new();
-                    params.insert("file_path".to_string(), "/second.txt".to_string());
-                    map.insert(2, params);
-                    map
-                }),
-                ..make_assertion("Read")
-            }],
+    fn test_run_yaml_test_param_counts() {
+        let yaml = r#"
+name: Param counts
+prompt: Summarize the config
+assertions:
+  - tool: Read
+    match: glob
+    param_counts:
+      - params: { file_path: "*.json" }
+        count: 2
+      - params: { file_path: "*.yaml" }
+        max_calls: 1
+      - params: { file_path: "*.toml" }
+        max_calls: 0
+  - tool: Read
+    match: glob
+    param_counts:
+      - params: { file_path: "*.json" }
+        min_calls: 3
+      - params: { file_path: "*.md" }
+        count: 1
+  - tool: Read
+    param_counts:
+      - params: { file_path: "json" }
+"#;
+        let test: Test = serde_yaml::from_str(yaml).unwrap();
+        let calls = vec![
+            make_call("Read", json!({"file_path": "package.json"})),
+            make_call("Read", json!({"file_path": "config.yaml"})),
+            make_call("Read", json!({"file_path": "tsconfig.json"})),
+            make_call("Read", json!({"file_path": "README.md"})),
+        ];
+
+        let results = run_yaml_test(&test, &calls, &None, None);
+        let summary: Vec<(&str, bool)> = results.iter().map(|(d, r)| (d.as_str(), r.is_pass())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Read called", true),
+                ("Read with file_path='*.json' called 2 times", true),
+                ("Read with file_path='*.yaml' called at most 1 times", true),
+                ("Read with file_path='*.toml' called at most 0 times", true),
+                ("Read called", true),
+                ("Read with file_path='*.json' called at least 3 times", false),
+                ("Read with file_path='*.md' called 1 times", true),
+                ("Read (invalid)", false),
+            ]
+        );
+        assert!(matches!(
+            &results[5].1,
+            TestResult::Fail { reason } if reason == "expected at least 3 calls, got 2"
+        ));
+        assert!(matches!(
+            &results[7].1,
+            TestResult::Fail { reason } if reason == "'param_counts' entry 1 needs 'count', 'min_calls' or 'max_calls'"
+        ));
+    }
+
+    #[test]
+    fn test_run_yaml_test_not_called_between() {
+        let yaml = r#"
+name: Between
+prompt: Fix the bug
+assertions:
+  - tool: Bash
+    not_called_between: [Read, Edit]
+  - tool: Grep
+    not_called_between: [Read, Edit]
+  - tool: Grep
+    not_called_between: [Read, Edit]
+    call_count: 1
+"#;
+        let test: Test = serde_yaml::from_str(yaml).unwrap();
+        let calls = vec![
+            make_call("Bash", json!({"command": "ls"})),
+            make_call("Read", json!({"file_path": "/a.txt"})),
+            make_call("Grep", json!({"pattern": "bug"})),
+            make_call("Edit", json!({"file_path": "/a.txt"})),
+        ];
+        let results = run_yaml_test(&test, &calls, &None, None);
+
+        assert_eq!(results[0].0, "Bash not called between Read and Edit");
+        assert!(results[0].1.is_pass());
+        assert!(!results[1].1.is_pass());
+        assert!(matches!(
+            &results[2].1,
+            TestResult::Fail { reason } if reason == "'not_called_between' cannot be combined with 'call_count'"
+        ));
+    }
+
+    #[test]
+    fn test_run_yaml_test_report_counts() {
+        let yaml = r#"
+name: Report
+prompt: Fix the bug
+agent: gemini
+assertions:
+  - tool: Read
+  - tool: Edit
+  - tool: Bash
+    called: false
+"#;
+        let test: Test = serde_yaml::from_str(yaml).unwrap();
+        let calls = vec![
+            make_call("Read", json!({"file_path": "/a.txt"})),
+            make_call("Bash", json!({"command": "ls"})),
+        ];
+        let report = run_yaml_test_report(&test, &calls, &None, None, None, None);
+
+        assert_eq!(report.name, "Report");
+        assert_eq!(report.prompt, "Fix the bug");
+        assert_eq!(report.agent, "gemini");
+        assert_eq!(report.assertions.len(), 3);
+        assert_eq!((report.passed, report.failed), (1, 2));
+        assert!(!report.is_pass());
+
+        // The thin wrapper returns the same results
+        let results = run_yaml_test(&test, &calls, &None, None);
+        let passed: Vec<bool> = results.iter().map(|(_, r)| r.is_pass()).collect();
+        assert_eq!(passed, vec![true, false, false]);
+    }
+
+    #[test]
+    fn test_run_yaml_test_ordering_strict() {
+        let yaml = r#"
+name: Ordering
+prompt: Edit the file
+assertions:
+  - tool: Edit
+    called_after: Read
+  - tool: Edit
+    called_after: Read
+    strict_order: false
+  - tool: Read
+    called_before: Edit
+    strict_order: true
+"#;
+        let calls = vec![
+            make_call("Read", json!({"file_path": "/a.txt"})),
+            make_call("Bash", json!({"command": "ls"})),
+            make_call("Edit", json!({"file_path": "/a.txt"})),
+        ];
+        let run = |strict: bool| {
+            let mut test: Test = serde_yaml::from_str(yaml).unwrap();
+            test.resolve_ordering(strict);
+            run_yaml_test(&test, &calls, &None, None)
         };
 
+        // Loose by default: only the assertion asking for strict order fails
+        let passed: Vec<bool> = run(false).iter().map(|(_, r)| r.is_pass()).collect();
+        assert_eq!(passed, vec![true, true, false]);
+
+        // Strict config flips the first, the explicit opt-out still wins
+        let results = run(true);
+        let passed: Vec<bool> = results.iter().map(|(_, r)| r.is_pass()).collect();
+        assert_eq!(passed, vec![false, true, false]);
+        assert_eq!(results[0].0, "Edit called immediately after Read");
+        assert_eq!(results[1].0, "Edit called after Read");
+    }
+
+    #[test]
+    fn test_validate_negative_params_need_called() {
+        let test: Test = serde_yaml::from_str(
+            "name: Invalid\nprompt: Run it\nassertions:\n  - tool: Bash\n    called: false\n    params_absent: [command]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            validate_test(&test),
+            vec!["assertion 1: 'called: false' cannot be combined with 'params_absent'"]
+        );
+    }
+
+    #[test]
+    fn test_run_yaml_test_negative_params() {
+        let yaml = r#"
+name: Negative params
+prompt: Clean up
+assertions:
+  - tool: Bash
+    params_not_matching:
+      command: "rm -rf"
+  - tool: Bash
+    params:
+      command: "^rm"
+    params_not_matching:
+      command: "-rf"
+  - tool: Bash
+    params_absent: [timeout]
+  - tool: Read
+    match: glob
+    params_not_matching:
+      file_path: "*.env"
+"#;
+        let test: Test = serde_yaml::from_str(yaml).unwrap();
+        let calls = vec![
+            make_call("Bash", json!({"command": "rm -rf build", "timeout": 1000})),
+            make_call("Read", json!({"file_path": "/app/.env"})),
+        ];
+
+        let results = run_yaml_test(&test, &calls, &None, None);
+        let passed: Vec<bool> = results.iter().map(|(_, r)| r.is_pass()).collect();
+        assert_eq!(passed, vec![false, false, false, false]);
+        assert_eq!(results[0].0, "Bash with command not matching 'rm -rf' called");
+        assert_eq!(results[2].0, "Bash without timeout called");
+        match &results[2].1 {
+            TestResult::Fail { reason } => assert_eq!(reason, "Bash call #1 has timeout 1000"),
+            TestResult::Pass => panic!("expected failure"),
+        }
+
+        // A good call doesn't excuse a bad one
+        let calls = vec![
+            make_call("Bash", json!({"command": "rm build"})),
+            make_call("Bash", json!({"command": "rm -rf build"})),
+        ];
+        let results = run_yaml_test(&test, &calls, &None, None);
+        assert!(results[0].1.is_fail(), "{:?}", results);
+
+        // A present-but-nonmatching call satisfies them
+        let calls = vec![
+            make_call("Bash", json!({"command": "rm build"})),
+            make_call("Read", json!({"file_path": "/app/config.json"})),
+        ];
+        let results = run_yaml_test(&test, &calls, &None, None);
+        assert!(results.iter().all(|(_, r)| r.is_pass()), "{:?}", results);
+    }
+
+    #[test]
+    fn test_run_yaml_test_cell_index() {
+        let yaml = r#"
+name: Notebook
+prompt: Fix the plot
+assertions:
+  - tool: NotebookEdit
+    cell_index: 3
+    params:
+      new_source: "plt\\.show"
+  - tool: notebook_edit
+    cell_index: 0
+    called: false
+  - tool: NotebookEdit
+    cell_index: 4
+"#;
+        let test: Test = serde_yaml::from_str(yaml).unwrap();
+        let calls = vec![
+            make_call(
+                "NotebookEdit",
+                json!({"notebook_path": "/app/plots.ipynb", "cell_number": 3, "new_source": "plt.show()"}),
+            ),
+            make_call(
+                "NotebookEdit",
+                json!({"notebook_path": "/app/plots.ipynb", "cell_id": "1", "new_source": "import pandas"}),
+            ),
+        ];
+
+        let results = run_yaml_test(&test, &calls, &None, None);
+        let passed: Vec<bool> = results.iter().map(|(_, r)| r.is_pass()).collect();
+        assert_eq!(passed, vec![true, true, false]);
+        assert_eq!(results[2].0, "NotebookEdit on cell 4 called");
+    }
+
+    #[test]
+    fn test_run_yaml_test_bash_commands() {
+        let yaml = r#"
+name: Bash
+prompt: Build and test
+assertions:
+  - bash:
+      ran: "cargo build && cargo test"
+  - bash:
+      contains: "| tee"
+    called_after: Bash
+  - tool: Bash
+    called: false
+    bash:
+      contains: "rm -rf"
+  - bash:
+      ran: "cargo test"
+  - tool: Read
+    bash:
+      contains: "cargo"
+  - bash: {}
+"#;
+        let test: Test = serde_yaml::from_str(yaml).unwrap();
+        let calls = vec![
+            make_call("Bash", json!({"command": "cargo build && cargo test"})),
+            make_call("Bash", json!({"command": "cargo test 2>&1 | tee test.log"})),
+        ];
+
+        let results = run_yaml_test(&test, &calls, &None, None);
+        let passed: Vec<bool> = results.iter().map(|(_, r)| r.is_pass()).collect();
+        assert_eq!(passed, vec![true, true, true, false, false, false]);
+        assert_eq!(results[0].0, "Bash running 'cargo build && cargo test' called");
+        assert_eq!(results[1].0, "Bash with command containing '| tee' called after Bash");
+        assert_eq!(results[4].0, "Read (invalid)");
+
+        let problems = validate_test(&test);
+        assert_eq!(
+            problems,
+            vec![
+                "assertion 5: 'bash' can only be used with the Bash tool",
+                "assertion 6: 'bash' needs 'ran' or 'contains'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_yaml_test_nth_call_params() {
+        let test = make_test(vec![Assertion {
+            nth_call_params: Some({
+                let mut map = std::collections::HashMap::new();
+                let mut params = std::collections::HashMap::new();
+                params.insert("file_path".to_string(), "/second.txt".to_string());
+                map.insert(2, params);
+                map
+            }),
+            ..make_assertion("Read")
+        }]);
+
         let calls = vec![
             make_call("Read", json!({"file_path": "/first.txt"})),
             make_call("Read", json!({"file_path": "/second.txt"})),
@@ -563,32 +1559,111 @@ mod tests {
     }
 
     #[test]
-    fn test_run_yaml_test_stdout_review_pass() {
-        let grader = MockGrader::passing();
-        let test = Test {
-            name: "Test".to_string(),
-            prompt: "Test prompt".to_string(),
-            agent: None,
-            assertions: vec![Assertion {
-                tool: None,
-                called: true,
-                params: None,
-                called_after: None,
-                called_before: None,
-                call_count: None,
-                max_calls: None,
-                min_calls: None,
-                nth_call_params: None,
-                first_call_params: None,
-                last_call_params: None,
-                stdout: Some(StdoutConstraints {
-                    review: "should confirm success".to_string(),
-                    threshold: 7,
-                    model: None,
-                    agent: None,
-                }),
-            }],
+    fn test_run_yaml_test_sequence() {
+        let sequence = |names: &[&str], consecutive: bool| Assertion {
+            tool: None,
+            sequence: Some(names.iter().map(|n| n.to_string()).collect()),
+            consecutive,
+            ..make_assertion("Read")
         };
+        let test = make_test(vec![
+            sequence(&["glob", "Read", "Edit"], false),
+            sequence(&["Glob", "Read", "Edit"], true),
+            sequence(&["Read", "Nope"], false),
+        ]);
+
+        let calls = vec![
+            make_call("Glob", json!({"pattern": "*.rs"})),
+            make_call("Read", json!({"file_path": "/a.rs"})),
+            make_call("Bash", json!({"command": "cargo check"})),
+            make_call("Edit", json!({"file_path": "/a.rs"})),
+        ];
+        let results = run_yaml_test(&test, &calls, &None, None);
+
+        assert_eq!(results[0].0, "[Glob, Read, Edit] in order");
+        assert!(results[0].1.is_pass());
+        assert_eq!(results[1].0, "[Glob, Read, Edit] consecutively");
+        assert!(results[1].1.is_fail());
+        assert_eq!(results[2].0, "[Read, Nope] (invalid)");
+        assert!(results[2].1.is_fail());
+    }
+
+    #[test]
+    fn test_run_yaml_test_files_max() {
+        let test: Test = serde_yaml::from_str(
+            r#"
+name: Test
+prompt: Test prompt
+assertions:
+  - files_read:
+      max: 1
+  - files_written:
+      max: 1
+"#,
+        )
+        .unwrap();
+
+        let calls = vec![
+            make_call("Read", json!({"file_path": "/a.txt"})),
+            make_call("Read", json!({"file_path": "/a.txt"})),
+            make_call("Edit", json!({"file_path": "/a.txt"})),
+            make_call("Write", json!({"file_path": "/b.txt"})),
+        ];
+        let results = run_yaml_test(&test, &calls, &None, None);
+
+        assert_eq!(results[0].0, "at most 1 file read");
+        assert!(results[0].1.is_pass());
+        assert_eq!(results[1].0, "at most 1 file written");
+        match &results[1].1 {
+            TestResult::Fail { reason } => assert_eq!(reason, "2 files written: /a.txt, /b.txt"),
+            TestResult::Pass => panic!("expected failure"),
+        }
+    }
+
+    #[test]
+    fn test_run_yaml_test_stdout_review_pass() {
+        let grader = passing_grader();
+        let test = make_test(vec![Assertion {
+            tool: None,
+            called: true,
+            params: None,
+            match_mode: MatchMode::Regex,
+            params_absent: None,
+            params_not_matching: None,
+            cell_index: None,
+            bash: None,
+            called_after: None,
+            called_before: None,
+            immediately_after: None,
+            immediately_before: None,
+            not_called_between: None,
+            strict_order: None,
+            call_count: None,
+            max_calls: None,
+            min_calls: None,
+            distinct_param: None,
+            param_counts: None,
+            nth_call_params: None,
+            first_call_params: None,
+            last_call_params: None,
+            stdout: Some(StdoutConstraints {
+                review: "should confirm success".to_string(),
+                contains: None,
+                not_contains: None,
+                matches: None,
+                threshold: 7,
+                model: None,
+                agent: None,
+                template: None,
+            }),
+            exit_code: None,
+            sequence: None,
+            consecutive: false,
+            no_tools: false,
+            only_tools: None,
+            files_read: None,
+            files_written: None,
+        }]);
 
         let stdout = Some("Operation completed successfully".to_string());
         let results = run_yaml_test(&test, &[], &stdout, Some(&grader));
@@ -599,31 +1674,48 @@ mod tests {
 
     #[test]
     fn test_run_yaml_test_stdout_review_fail() {
-        let grader = MockGrader::failing();
-        let test = Test {
-            name: "Test".to_string(),
-            prompt: "Test prompt".to_string(),
-            agent: None,
-            assertions: vec![Assertion {
-                tool: None,
-                called: true,
-                params: None,
-                called_after: None,
-                called_before: None,
-                call_count: None,
-                max_calls: None,
-                min_calls: None,
-                nth_call_params: None,
-                first_call_params: None,
-                last_call_params: None,
-                stdout: Some(StdoutConstraints {
-                    review: "should confirm success".to_string(),
-                    threshold: 7,
-                    model: None,
-                    agent: None,
-                }),
-            }],
-        };
+        let grader = failing_grader();
+        let test = make_test(vec![Assertion {
+            tool: None,
+            called: true,
+            params: None,
+            match_mode: MatchMode::Regex,
+            params_absent: None,
+            params_not_matching: None,
+            cell_index: None,
+            bash: None,
+            called_after: None,
+            called_before: None,
+            immediately_after: None,
+            immediately_before: None,
+            not_called_between: None,
+            strict_order: None,
+            call_count: None,
+            max_calls: None,
+            min_calls: None,
+            distinct_param: None,
+            param_counts: None,
+            nth_call_params: None,
+            first_call_params: None,
+            last_call_params: None,
+            stdout: Some(StdoutConstraints {
+                review: "should confirm success".to_string(),
+                contains: None,
+                not_contains: None,
+                matches: None,
+                threshold: 7,
+                model: None,
+                agent: None,
+                template: None,
+            }),
+            exit_code: None,
+            sequence: None,
+            consecutive: false,
+            no_tools: false,
+            only_tools: None,
+            files_read: None,
+            files_written: None,
+        }]);
 
         let stdout = Some("Operation failed with error".to_string());
         let results = run_yaml_test(&test, &[], &stdout, Some(&grader));
@@ -633,31 +1725,484 @@ mod tests {
     }
 
     #[test]
-    fn test_run_yaml_test_stdout_no_grader() {
+    fn test_run_yaml_test_review_uses_test_model() {
+        let mock = MockAgent::new();
+        let grader: Arc<dyn Agent> = Arc::new(mock.clone());
+        let review = |model: Option<&str>| Assertion {
+            tool: None,
+            stdout: Some(StdoutConstraints {
+                review: "should confirm success".to_string(),
+                contains: None,
+                not_contains: None,
+                matches: None,
+                threshold: 7,
+                model: model.map(str::to_string),
+                agent: None,
+                template: None,
+            }),
+            ..make_assertion("Read")
+        };
         let test = Test {
-            name: "Test".to_string(),
-            prompt: "Test prompt".to_string(),
+            model: Some("claude-haiku-4-5".to_string()),
+            ..make_test(vec![review(None), review(Some("claude-opus-4-1"))])
+        };
+
+        let stdout = Some("Done".to_string());
+        run_yaml_test(&test, &[], &stdout, Some(&grader));
+
+        // An assertion's own model wins over the test-level model. Reviews are
+        // graded concurrently, so calls can be recorded in either order.
+        let mut models = mock.grade_models();
+        models.sort();
+        assert_eq!(
+            models,
+            vec![
+                Some("claude-haiku-4-5".to_string()),
+                Some("claude-opus-4-1".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_yaml_test_grades_reviews_in_batch() {
+        let mock = MockAgent::new()
+            .with_grade(8, "Looks right")
+            .with_grade_delay(Duration::from_millis(300));
+        let grader: Arc<dyn Agent> = Arc::new(mock.clone());
+        let review = |criteria: &str, threshold: u32| Assertion {
+            tool: None,
+            stdout: Some(StdoutConstraints {
+                review: criteria.to_string(),
+                contains: None,
+                not_contains: None,
+                matches: None,
+                threshold,
+                model: None,
+                agent: None,
+                template: None,
+            }),
+            ..make_assertion("Read")
+        };
+        let test = make_test(vec![
+            review("should confirm success", 7),
+            make_assertion("Read"),
+            review("should be concise", 9),
+        ]);
+
+        let stdout = Some("Done".to_string());
+        let start = Instant::now();
+        let results = run_yaml_test(&test, &[], &stdout, Some(&grader));
+        let elapsed = start.elapsed();
+
+        // Both reviews were graded, concurrently rather than one after another
+        assert_eq!(mock.grade_models().len(), 2);
+        assert!(elapsed < Duration::from_millis(550), "took {:?}", elapsed);
+
+        // Results stay in assertion order
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0].0,
+            "stdout review: \"should confirm success\" (threshold: 7/10)"
+        );
+        assert!(results[0].1.is_pass());
+        assert!(results[1].1.is_fail());
+        match &results[2].1 {
+            TestResult::Fail { reason } => {
+                assert_eq!(reason, "score 8/10 below threshold 9 — Looks right")
+            }
+            TestResult::Pass => panic!("expected the stricter review to fail"),
+        }
+    }
+
+    #[test]
+    fn test_run_yaml_test_not_contains_empty_stdout() {
+        let test: Test = serde_yaml::from_str(
+            "name: Quiet\nprompt: Fix it\nassertions:\n  - stdout:\n      not_contains: ERROR\n",
+        )
+        .unwrap();
+
+        let results = run_yaml_test(&test, &[], &None, None);
+        assert!(results[0].1.is_pass(), "{:?}", results);
+        let results = run_yaml_test(&test, &[], &Some(String::new()), None);
+        assert!(results[0].1.is_pass(), "{:?}", results);
+    }
+
+    #[test]
+    fn test_run_yaml_test_stdout_content_checks() {
+        let constraints = |contains: &str| StdoutConstraints {
+            review: String::new(),
+            contains: Some(contains.to_string()),
+            not_contains: Some("ERROR".to_string()),
+            matches: Some(r"\d+ files".to_string()),
+            threshold: 7,
+            model: None,
             agent: None,
-            assertions: vec![Assertion {
+            template: None,
+        };
+        let test = make_test(vec![
+            Assertion {
+                tool: None,
+                stdout: Some(constraints("Updated")),
+                ..make_assertion("Read")
+            },
+            Assertion {
                 tool: None,
-                called: true,
-                params: None,
-                called_after: None,
-                called_before: None,
-                call_count: None,
-                max_calls: None,
-                min_calls: None,
-                nth_call_params: None,
-                first_call_params: None,
-                last_call_params: None,
-                stdout: Some(StdoutConstraints {
-                    review: "should confirm success".to_string(),
-                    threshold: 7,
-                    model: None,
-                    agent: None,
-                }),
-            }],
+                stdout: Some(constraints("Deleted")),
+                ..make_assertion("Read")
+            },
+        ]);
+
+        // No grader needed for local checks
+        let stdout = Some("Updated 3 files".to_string());
+        let results = run_yaml_test(&test, &[], &stdout, None);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].0,
+            r#"stdout contains "Updated", does not contain "ERROR", matches /\d+ files/"#
+        );
+        assert!(results[0].1.is_pass());
+        match &results[1].1 {
+            TestResult::Fail { reason } => assert_eq!(
+                reason,
+                r#"stdout does not contain "Deleted" (stdout: "Updated 3 files")"#
+            ),
+            TestResult::Pass => panic!("expected failure"),
+        }
+    }
+
+    fn retry_test() -> Test {
+        Test {
+            prompt: "Read a file".to_string(),
+            ..make_test(vec![make_assertion("Read")])
+        }
+    }
+
+    fn run_attempts(
+        harness: &crate::agents::AgentHarness,
+        config: &crate::agents::ExecutionConfig,
+    ) -> anyhow::Result<(Vec<(String, TestResult)>, u32)> {
+        let test = retry_test();
+        run_with_retries(config.retries, |_| {
+            let output = harness.execute(None, &test.prompt, config.clone())?;
+            Ok(run_yaml_test_with_cache(&test, &output.result.tool_calls, &output.stdout, output.exit_code, None, None))
+        })
+    }
+
+    #[test]
+    fn test_run_with_retries_passes_on_second_attempt() {
+        use crate::agents::{AgentHarness, AgentType, ExecutionConfig};
+
+        let mock = Arc::new(
+            MockAgent::new()
+                .with_tool_call("Read", json!({"file_path": "/a.txt"}))
+                .with_failed_runs(1),
+        );
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, mock.clone());
+
+        let config = ExecutionConfig::new().with_retries(2);
+        let (results, attempt) = run_attempts(&harness, &config).unwrap();
+
+        assert_eq!(attempt, 2);
+        assert!(results.iter().all(|(_, r)| r.is_pass()));
+        // Stops as soon as the assertions pass
+        assert_eq!(mock.runs(), 2);
+    }
+
+    #[test]
+    fn test_run_with_retries_reports_final_failure() {
+        use crate::agents::{AgentHarness, AgentType, ExecutionConfig};
+
+        let mock = Arc::new(MockAgent::new().with_failed_runs(5));
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, mock.clone());
+
+        let config = ExecutionConfig::new().with_retries(2);
+        let (results, attempt) = run_attempts(&harness, &config).unwrap();
+
+        assert_eq!(attempt, 3);
+        assert!(results[0].1.is_fail());
+        assert_eq!(mock.runs(), 3);
+    }
+
+    #[test]
+    fn test_run_with_retries_does_not_retry_errors() {
+        use crate::agents::{AgentHarness, AgentType, ExecutionConfig};
+
+        let mut harness = AgentHarness::new();
+        harness.register(AgentType::Claude, Arc::new(MockAgent::new().unavailable()));
+
+        let config = ExecutionConfig::new().with_retries(3);
+        let mut attempts = 0;
+        let err = run_with_retries(config.retries, |_| {
+            attempts += 1;
+            harness.execute(None, "prompt", config.clone())?;
+            Ok(Vec::new())
+        })
+        .unwrap_err();
+
+        assert!(err.to_string().contains("not available"));
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_run_with_budget_fails_on_overrun() {
+        use crate::agents::{AgentHarness, AgentType, ExecutionConfig};
+
+        let mut harness = AgentHarness::new();
+        let mock = MockAgent::new()
+            .with_tool_call("Read", json!({"file_path": "/a.txt"}))
+            .with_delay(Duration::from_millis(200));
+        harness.register(AgentType::Claude, Arc::new(mock));
+        let test: Test =
+            serde_yaml::from_str("name: Slow\nprompt: Read a.txt\nassertions:\n  - tool: Read\n").unwrap();
+
+        let results = run_with_budget(Some(Duration::from_millis(20)), |deadline| {
+            assert!(deadline.is_some());
+            let output = harness.execute(None, &test.prompt, ExecutionConfig::new())?;
+            Ok(run_yaml_test_with_cache(&test, &output.result.tool_calls, &output.stdout, output.exit_code, None, None))
+        })
+        .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].1.is_pass());
+        assert_eq!(results[1].0, "completes within 20ms");
+        match &results[1].1 {
+            TestResult::Fail { reason } => assert!(reason.starts_with("took "), "{}", reason),
+            TestResult::Pass => panic!("expected failure"),
+        }
+    }
+
+    #[test]
+    fn test_run_with_budget_within_budget() {
+        let pass = || vec![("Read called".to_string(), TestResult::Pass)];
+
+        let results = run_with_budget(None, |deadline| {
+            assert!(deadline.is_none());
+            Ok(pass())
+        })
+        .unwrap();
+        assert_eq!(results.len(), 1);
+
+        let results = run_with_budget(Some(Duration::from_secs(60)), |_| Ok(pass())).unwrap();
+        assert_eq!(results.len(), 1);
+
+        // Errors within the budget are still errors
+        let err = run_with_budget(Some(Duration::from_secs(60)), |_| Err(anyhow!("not installed")));
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_run_with_budget_overrun_error_is_failure() {
+        // An agent killed at the deadline errors; the overrun is what's reported
+        let results = run_with_budget(Some(Duration::from_millis(10)), |deadline| {
+            thread::sleep(deadline.unwrap().saturating_duration_since(Instant::now()) * 2);
+            Err(anyhow!("claude timed out and was killed"))
+        })
+        .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_fail());
+    }
+
+    #[test]
+    fn test_run_yaml_test_exit_code() {
+        let test = make_test(vec![Assertion {
+            tool: None,
+            exit_code: Some(0),
+            ..make_assertion("Read")
+        }]);
+
+        let results = run_yaml_test_with_cache(&test, &[], &None, Some(0), None, None);
+        assert_eq!(results[0].0, "exit code 0");
+        assert!(results[0].1.is_pass());
+
+        let results = run_yaml_test_with_cache(&test, &[], &None, Some(2), None, None);
+        match &results[0].1 {
+            TestResult::Fail { reason } => assert_eq!(reason, "got exit code 2"),
+            TestResult::Pass => panic!("expected failure"),
+        }
+    }
+
+    #[test]
+    fn test_run_yaml_test_no_tools() {
+        let test: Test =
+            serde_yaml::from_str("name: Answer\nprompt: What is 2 + 2?\nassertions:\n  - no_tools: true\n")
+                .unwrap();
+        assert!(validate_test(&test).is_empty());
+
+        let results = run_yaml_test(&test, &[], &None, None);
+        assert_eq!(results[0].0, "no tools called");
+        assert!(results[0].1.is_pass());
+
+        let calls = vec![make_call("Read", json!({"file_path": "/a.txt"}))];
+        let results = run_yaml_test(&test, &calls, &None, None);
+        match &results[0].1 {
+            TestResult::Fail { reason } => assert_eq!(reason, "1 tool call made: Read"),
+            TestResult::Pass => panic!("expected failure"),
+        }
+
+        // A tool alongside no_tools is rejected rather than ignored
+        let test: Test = serde_yaml::from_str(
+            "name: Answer\nprompt: What is 2 + 2?\nassertions:\n  - no_tools: true\n    tool: Read\n",
+        )
+        .unwrap();
+        assert_eq!(
+            validate_test(&test),
+            vec!["assertion 1: 'no_tools' cannot be combined with 'tool' or 'bash'"]
+        );
+        let results = run_yaml_test(&test, &[], &None, None);
+        assert_eq!(results[0].0, "no_tools (invalid)");
+        assert!(!results[0].1.is_pass());
+    }
+
+    #[test]
+    fn test_run_yaml_test_only_tools() {
+        let test: Test = serde_yaml::from_str(
+            "name: Sandbox\nprompt: Summarize the repo\nassertions:\n  - only_tools: [Read, glob]\n",
+        )
+        .unwrap();
+        assert!(validate_test(&test).is_empty());
+
+        let calls = vec![
+            make_call("Glob", json!({"pattern": "**/*.rs"})),
+            make_call("Read", json!({"file_path": "/a.rs"})),
+        ];
+        let results = run_yaml_test(&test, &calls, &None, None);
+        assert_eq!(results[0].0, "only [Read, Glob] called");
+        assert!(results[0].1.is_pass());
+
+        let calls = vec![
+            make_call("Read", json!({"file_path": "/a.rs"})),
+            make_call("Bash", json!({"command": "ls"})),
+        ];
+        let results = run_yaml_test(&test, &calls, &None, None);
+        match &results[0].1 {
+            TestResult::Fail { reason } => {
+                assert_eq!(reason, r#"call #2 used 'Bash' with {"command":"ls"}"#)
+            }
+            TestResult::Pass => panic!("expected failure"),
+        }
+
+        let test: Test =
+            serde_yaml::from_str("name: Bad\nprompt: Hi\nassertions:\n  - only_tools: [Read, Nope]\n")
+                .unwrap();
+        assert_eq!(validate_test(&test).len(), 1);
+    }
+
+    #[test]
+    fn test_run_yaml_test_distinct_param() {
+        let test: Test = serde_yaml::from_str(
+            "name: Reads\nprompt: Review the code\nassertions:\n  - tool: Read\n    distinct_param: file_path\n",
+        )
+        .unwrap();
+
+        let calls = vec![
+            make_call("Read", json!({"file_path": "/a.rs"})),
+            make_call("Read", json!({"file_path": "/b.rs"})),
+        ];
+        let results = run_yaml_test(&test, &calls, &None, None);
+        assert_eq!(results[0].0, "Read with distinct file_path called");
+        assert!(results[0].1.is_pass());
+
+        let calls = vec![
+            make_call("Read", json!({"file_path": "/a.rs"})),
+            make_call("Read", json!({"file_path": "/b.rs"})),
+            make_call("Read", json!({"file_path": "/a.rs"})),
+        ];
+        let results = run_yaml_test(&test, &calls, &None, None);
+        match &results[0].1 {
+            TestResult::Fail { reason } => assert!(reason.contains("'/a.rs' was already used")),
+            TestResult::Pass => panic!("expected failure"),
+        }
+    }
+
+    #[test]
+    fn test_run_yaml_test_exact_match() {
+        let exact_params = || {
+            let mut params = std::collections::HashMap::new();
+            params.insert("file_path".to_string(), "/tmp/a.txt".to_string());
+            params
         };
+        let test = make_test(vec![
+            Assertion {
+                params: Some(exact_params()),
+                ..make_assertion("Read")
+            },
+            Assertion {
+                params: Some(exact_params()),
+                match_mode: MatchMode::Exact,
+                ..make_assertion("Read")
+            },
+        ]);
+
+        let calls = vec![make_call("Read", json!({"file_path": "/tmp/a.txt.bak"}))];
+        let results = run_yaml_test(&test, &calls, &None, None);
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].1.is_pass(), "regex mode matches substrings");
+        assert!(results[1].1.is_fail(), "exact mode rejects substrings");
+    }
+
+    #[test]
+    fn test_assert_no_failures_with_yaml_results() {
+        let test = make_test(vec![
+            make_assertion("Read"),
+            Assertion { called: false, ..make_assertion("Read") },
+        ]);
+
+        let calls = vec![make_call("Read", json!({"file_path": "/test.txt"}))];
+        let results = run_yaml_test(&test, &calls, &None, None);
+
+        let failures = crate::fluent::assert_no_failures(results).unwrap_err();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].description, "Read not called");
+    }
+
+    #[test]
+    fn test_run_yaml_test_stdout_no_grader() {
+        let test = make_test(vec![Assertion {
+            tool: None,
+            called: true,
+            params: None,
+            match_mode: MatchMode::Regex,
+            params_absent: None,
+            params_not_matching: None,
+            cell_index: None,
+            bash: None,
+            called_after: None,
+            called_before: None,
+            immediately_after: None,
+            immediately_before: None,
+            not_called_between: None,
+            strict_order: None,
+            call_count: None,
+            max_calls: None,
+            min_calls: None,
+            distinct_param: None,
+            param_counts: None,
+            nth_call_params: None,
+            first_call_params: None,
+            last_call_params: None,
+            stdout: Some(StdoutConstraints {
+                review: "should confirm success".to_string(),
+                contains: None,
+                not_contains: None,
+                matches: None,
+                threshold: 7,
+                model: None,
+                agent: None,
+                template: None,
+            }),
+            exit_code: None,
+            sequence: None,
+            consecutive: false,
+            no_tools: false,
+            only_tools: None,
+            files_read: None,
+            files_written: None,
+        }]);
 
         let stdout = Some("test".to_string());
         let results = run_yaml_test(&test, &[], &stdout, None);
@@ -671,4 +2216,98 @@ mod tests {
             _ => panic!("Expected failure"),
         }
     }
+
+    #[test]
+    fn test_validate_count_constraints() {
+        let mut assertion = make_assertion("Read");
+        assertion.min_calls = Some(3);
+        assertion.max_calls = Some(1);
+        assert_eq!(
+            validate_assertion(&assertion).unwrap_err(),
+            "'min_calls' (3) cannot be greater than 'max_calls' (1)"
+        );
+
+        let mut assertion = make_assertion("Read");
+        assertion.call_count = Some(5);
+        assertion.max_calls = Some(2);
+        assert!(validate_assertion(&assertion).unwrap_err().contains("'call_count' (5)"));
+
+        let mut assertion = make_assertion("Read");
+        assertion.call_count = Some(2);
+        assertion.min_calls = Some(1);
+        assertion.max_calls = Some(2);
+        assert!(validate_assertion(&assertion).is_ok());
+    }
+
+    #[test]
+    fn test_validate_args() {
+        let yaml = r#"
+name: "Args"
+prompt: "Run the tests"
+model: claude-haiku-4-5
+args: ["--dangerously-skip-permissions", "--print", "--output-format=json", "--model", "other"]
+assertions:
+  - tool: Bash
+"#;
+        let test: Test = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            validate_test(&test),
+            vec![
+                "args: '--print' is already passed by aptitude",
+                "args: '--output-format' is already passed by aptitude",
+                "args: '--model' conflicts with 'model'; set one or the other",
+            ]
+        );
+
+        // Without `model`, `--model` in args is fine
+        let yaml = r#"
+name: "Args"
+prompt: "Run the tests"
+args: ["--dangerously-skip-permissions", "--model", "claude-haiku-4-5"]
+assertions:
+  - tool: Bash
+"#;
+        let test: Test = serde_yaml::from_str(yaml).unwrap();
+        assert!(check_test_args(&test).is_empty());
+        assert!(validate_test(&test).is_empty());
+    }
+
+    #[test]
+    fn test_validate_test_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("valid.aptitude.yaml"),
+            "name: Valid\nprompt: Read it\nassertions:\n  - tool: read\n    called_after: Glob\n  - sequence: [Read, Edit]\n  - stdout:\n      contains: done\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("invalid.aptitude.yaml"),
+            "name: Invalid\nprompt: Read it\nassertions:\n  - tool: Reed\n  - tool: Bash\n    called: false\n    call_count: 1\n  - tool: Write\n    immediately_before: Nope\n  - sequence: [Read, Nope]\n  - called: true\n",
+        )
+        .unwrap();
+
+        let paths = crate::discovery::discover_tests(dir.path(), &crate::config::Config::default()).unwrap();
+        assert_eq!(paths.len(), 2);
+
+        let problems: Vec<(String, Vec<String>)> = paths
+            .iter()
+            .map(|path| {
+                let test = crate::yaml::load_test(path).unwrap();
+                (test.name.clone(), validate_test(&test))
+            })
+            .collect();
+        let problems: HashMap<_, _> = problems.into_iter().collect();
+
+        assert!(problems["Valid"].is_empty());
+        let invalid = &problems["Invalid"];
+        assert_eq!(invalid.len(), 5);
+        assert!(invalid[0].starts_with("assertion 1: Unknown tool: 'Reed'"));
+        assert_eq!(
+            invalid[1],
+            "assertion 2: 'called: false' cannot be combined with 'call_count'"
+        );
+        assert!(invalid[2].starts_with("assertion 3: Unknown tool: 'Nope'"));
+        assert!(invalid[3].starts_with("assertion 4: Unknown tool: 'Nope'"));
+        assert!(invalid[4].starts_with("assertion 5: Assertion must have 'tool'"));
+    }
 }
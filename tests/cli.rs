@@ -0,0 +1,120 @@
+//! End-to-end tests of the `aptitude` binary, replaying recorded agent runs.
+
+#![cfg(feature = "yaml")]
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use aptitude::agents::{recording_path, ExecutionConfig, Recording};
+
+const PROMPT: &str = "Read a.txt";
+
+/// Write a test file asserting that `Read` is called, and a recording of a
+/// run that made `tool_calls`, then run the file against the recording.
+fn run_replayed(dir: &Path, tool_calls: Vec<aptitude::ToolCall>, args: &[&str]) -> Output {
+    let test = dir.join("read.yaml");
+    std::fs::write(
+        &test,
+        format!(
+            "name: Reads a.txt\nprompt: {PROMPT}\nagent: claude\nassertions:\n  - tool: Read\n    called: true\n"
+        ),
+    )
+    .unwrap();
+
+    let recordings = dir.join("recordings");
+    Recording {
+        agent: "claude".to_string(),
+        prompt: PROMPT.to_string(),
+        history: Vec::new(),
+        stdout: Some(String::new()),
+        stderr: None,
+        exit_code: Some(0),
+        tool_calls,
+        usage: None,
+    }
+    .save(&recording_path(&recordings, "claude", PROMPT, &ExecutionConfig::new()))
+    .unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_aptitude"))
+        .arg("run")
+        .arg(&test)
+        .arg("--replay")
+        .arg(&recordings)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn read_call() -> aptitude::ToolCall {
+    aptitude::ToolCall {
+        name: "Read".to_string(),
+        params: serde_json::json!({"file_path": "a.txt"}),
+        timestamp: String::new(),
+        result: None,
+    }
+}
+
+#[test]
+fn test_single_file_exit_status() {
+    let dir = tempfile::tempdir().unwrap();
+
+    for args in [&[][..], &["--summary"], &["--format", "json"]] {
+        let passing = run_replayed(dir.path(), vec![read_call()], args);
+        assert!(passing.status.success(), "{:?}: {:?}", args, passing);
+
+        let failing = run_replayed(dir.path(), Vec::new(), args);
+        assert_eq!(failing.status.code(), Some(1), "{:?}: {:?}", args, failing);
+    }
+}
+
+#[test]
+fn test_cli_model_conflicts_with_args() {
+    let dir = tempfile::tempdir().unwrap();
+    let test = dir.path().join("model.yaml");
+    std::fs::write(
+        &test,
+        "name: Model\nprompt: Read a.txt\nargs: [\"--model=claude-haiku-4-5\"]\nassertions:\n  - tool: Read\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_aptitude"))
+        .arg("run")
+        .arg(&test)
+        .args(["--model", "claude-opus-4-1"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--model conflicts with '--model' in the test file's args"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("Invalid test file"), "{}", stderr);
+}
+
+#[test]
+fn test_analyze_multibyte_timestamp() {
+    let dir = tempfile::tempdir().unwrap();
+    let test = dir.path().join("read.yaml");
+    std::fs::write(&test, format!("name: Reads a.txt\nprompt: {PROMPT}\nassertions:\n  - tool: Read\n")).unwrap();
+    // 'é' straddles byte 11, where the time would start
+    let session = dir.path().join("session.jsonl");
+    std::fs::write(
+        &session,
+        r#"{"type":"assistant","timestamp":"2024-01-19é12:00:00Z","message":{"content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"a.txt"}}]}}"#,
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_aptitude"))
+        .arg("analyze")
+        .arg(&test)
+        .arg(&session)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[??:??:??] Read: a.txt"), "{}", stdout);
+}
@@ -22,12 +22,14 @@ fn test_streaming_events_arrive() {
             StreamEvent::ToolCall(tc) => {
                 tool_calls.push(tc);
             }
+            StreamEvent::AssistantText(_) => {}
             StreamEvent::SessionDetected(_) => {
                 got_session = true;
             }
             StreamEvent::Error(msg) => {
                 eprintln!("Stream error: {}", msg);
             }
+            StreamEvent::Completed { .. } => {}
         }
     }
 